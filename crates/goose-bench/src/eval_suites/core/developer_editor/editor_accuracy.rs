@@ -0,0 +1,141 @@
+use crate::bench_session::BenchAgent;
+use crate::bench_work_dir::BenchmarkWorkDir;
+use crate::eval_suites::{EvalMetricValue, Evaluation, ExtensionRequirements};
+use crate::register_evaluation;
+use async_trait::async_trait;
+use goose_mcp::developer::{create_editor_model, EditorChain};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A recorded edit: the file before, what the model asked for, and the file it meant
+#[derive(Debug, Deserialize)]
+struct EditFixture {
+    name: String,
+    path: String,
+    original: String,
+    old_str: String,
+    new_str: String,
+    expected: String,
+}
+
+#[derive(Debug, Default)]
+struct EditorScore {
+    correct: usize,
+    failed: usize,
+    latency: Duration,
+}
+
+impl EditorScore {
+    fn record(&mut self, result: Result<String, String>, expected: &str, latency: Duration) {
+        self.latency += latency;
+        match result {
+            Ok(content) if content.trim_end() == expected.trim_end() => self.correct += 1,
+            Ok(_) => {}
+            Err(_) => self.failed += 1,
+        }
+    }
+
+    fn metrics(&self, editor: &str, total: usize) -> Vec<(String, EvalMetricValue)> {
+        vec![
+            (
+                format!("{}_accuracy", editor),
+                EvalMetricValue::Float(self.correct as f64 / total as f64),
+            ),
+            (
+                format!("{}_failures", editor),
+                EvalMetricValue::Integer(self.failed as i64),
+            ),
+            (
+                format!("{}_mean_latency_ms", editor),
+                EvalMetricValue::Float(self.latency.as_secs_f64() * 1000.0 / total as f64),
+            ),
+        ]
+    }
+}
+
+/// Compares the str_replace editors on recorded edits: the fast-apply model configured
+/// through GOOSE_EDITOR_* if any, the local fallbacks on their own, and the whole chain.
+/// No agent is involved, so results only depend on the editors.
+#[derive(Debug)]
+pub struct DeveloperEditorAccuracy {}
+
+impl DeveloperEditorAccuracy {
+    pub fn new() -> Self {
+        DeveloperEditorAccuracy {}
+    }
+}
+
+#[async_trait]
+impl Evaluation for DeveloperEditorAccuracy {
+    async fn run(
+        &self,
+        _agent: &mut BenchAgent,
+        run_loc: &mut BenchmarkWorkDir,
+    ) -> anyhow::Result<Vec<(String, EvalMetricValue)>> {
+        let fixtures_path = run_loc
+            .fs_get("./assets/editor_fixtures.json".to_string())
+            .map_err(|_| anyhow::anyhow!("Could not find editor_fixtures.json file"))?;
+        let fixtures: Vec<EditFixture> = serde_json::from_str(&fs::read_to_string(fixtures_path)?)?;
+        if fixtures.is_empty() {
+            return Err(anyhow::anyhow!("editor_fixtures.json has no edits"));
+        }
+
+        let chain = EditorChain::new(create_editor_model());
+        let mut metrics = Vec::new();
+
+        for editor in chain.editors() {
+            let mut score = EditorScore::default();
+            for fixture in &fixtures {
+                let start = Instant::now();
+                let result = if editor.accepts(&fixture.old_str, &fixture.new_str) {
+                    editor
+                        .edit_code(&fixture.original, &fixture.old_str, &fixture.new_str)
+                        .await
+                } else {
+                    Err("not applicable".to_string())
+                };
+                score.record(result, &fixture.expected, start.elapsed());
+            }
+            metrics.extend(score.metrics(editor.name(), fixtures.len()));
+        }
+
+        let mut score = EditorScore::default();
+        for fixture in &fixtures {
+            let start = Instant::now();
+            let result = chain
+                .edit(
+                    Path::new(&fixture.path),
+                    &fixture.original,
+                    &fixture.old_str,
+                    &fixture.new_str,
+                )
+                .await;
+            let elapsed = start.elapsed();
+            match &result {
+                Ok(edit) => println!("{}: applied with {}", fixture.name, edit.editor),
+                Err(e) => println!("{}: not applied: {}", fixture.name, e),
+            }
+            score.record(result.map(|edit| edit.content), &fixture.expected, elapsed);
+        }
+        metrics.extend(score.metrics("chain", fixtures.len()));
+
+        metrics.push((
+            "score".to_string(),
+            EvalMetricValue::Float(score.correct as f64 / fixtures.len() as f64),
+        ));
+
+        Ok(metrics)
+    }
+
+    fn name(&self) -> &str {
+        "developer_editor_accuracy"
+    }
+
+    fn required_extensions(&self) -> ExtensionRequirements {
+        ExtensionRequirements::default()
+    }
+}
+
+register_evaluation!(DeveloperEditorAccuracy);
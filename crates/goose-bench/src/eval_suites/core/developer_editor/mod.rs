@@ -0,0 +1 @@
+mod editor_accuracy;
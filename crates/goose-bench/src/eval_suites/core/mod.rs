@@ -1,5 +1,6 @@
 mod computercontroller;
 mod developer;
+mod developer_editor;
 mod developer_image;
 mod developer_search_replace;
 mod example;
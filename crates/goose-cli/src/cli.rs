@@ -12,20 +12,25 @@ use crate::commands::bench::agent_generator;
 use crate::commands::configure::handle_configure;
 use crate::commands::info::handle_info;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
-use crate::commands::recipe::{handle_deeplink, handle_list, handle_open, handle_validate};
+use crate::commands::recipe::{
+    handle_deeplink, handle_install, handle_list, handle_open, handle_remove, handle_update,
+    handle_validate,
+};
 use crate::commands::term::{
     handle_term_info, handle_term_init, handle_term_log, handle_term_run, Shell,
 };
 
 use crate::commands::schedule::{
-    handle_schedule_add, handle_schedule_cron_help, handle_schedule_list, handle_schedule_remove,
-    handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
-    handle_schedule_sessions,
+    handle_schedule_add, handle_schedule_cron_help, handle_schedule_history, handle_schedule_list,
+    handle_schedule_remove, handle_schedule_run_now, handle_schedule_services_status,
+    handle_schedule_services_stop, handle_schedule_sessions, handle_schedule_validate,
 };
 use crate::commands::session::{handle_session_list, handle_session_remove};
 use crate::recipes::extract_from_cli::extract_recipe_info_from_cli;
 use crate::recipes::recipe::{explain_recipe, render_recipe_as_yaml};
 use crate::session::{build_session, SessionBuilderConfig, SessionSettings};
+use goose::scheduler_expression::DEFAULT_PREVIEW_COUNT;
+use goose::scheduler_runs::{CatchUpPolicy, ConcurrencyPolicy, FailureNotification, RetryPolicy};
 use goose::session::session_manager::SessionType;
 use goose::session::SessionManager;
 use goose_bench::bench_config::BenchRunConfig;
@@ -35,6 +40,7 @@ use goose_bench::runners::metric_aggregator::MetricAggregator;
 use goose_bench::runners::model_runner::ModelRunner;
 use std::io::Read;
 use std::path::PathBuf;
+use std::str::FromStr;
 use tracing::warn;
 
 #[derive(Parser)]
@@ -224,14 +230,15 @@ enum SessionCommand {
             short,
             long,
             help = "Output file path (default: stdout)",
-            long_help = "Path to save the exported Markdown. If not provided, output will be sent to stdout"
+            long_help = "Path to save the exported session. If not provided, output will be sent to stdout"
         )]
         output: Option<PathBuf>,
 
         #[arg(
             long = "format",
             value_name = "FORMAT",
-            help = "Output format (markdown, json, yaml)",
+            help = "Output format (markdown, json, yaml, html, openai-jsonl, anthropic-jsonl, otlp)",
+            long_help = "Output format:\n  'markdown'         - Readable transcript\n  'json' / 'yaml'    - The full stored session\n  'html'             - Standalone HTML transcript with collapsible tool calls and images\n  'openai-jsonl'     - OpenAI chat fine-tuning record\n  'anthropic-jsonl'  - Anthropic fine-tuning record\n  'otlp'             - OpenTelemetry (OTLP/JSON) trace with spans for turns and tool calls",
             default_value = "markdown"
         )]
         format: String,
@@ -260,15 +267,64 @@ enum SchedulerCommand {
         schedule_id: String,
         #[arg(
             long,
-            help = "Cron expression for the schedule",
-            long_help = "Cron expression for when to run the job. Examples:\n  '0 * * * *'     - Every hour at minute 0\n  '0 */2 * * *'   - Every 2 hours\n  '@hourly'       - Every hour (shorthand)\n  '0 9 * * *'     - Every day at 9:00 AM\n  '0 9 * * 1'     - Every Monday at 9:00 AM\n  '0 0 1 * *'     - First day of every month at midnight"
+            help = "Cron expression or plain-English schedule",
+            long_help = "Cron expression or plain-English schedule for when to run the job. Examples:\n  '0 * * * *'     - Every hour at minute 0\n  '0 */2 * * *'   - Every 2 hours\n  '@hourly'       - Every hour (shorthand)\n  '0 9 * * *'     - Every day at 9:00 AM\n  '0 9 * * 1'     - Every Monday at 9:00 AM\n  '0 0 1 * *'     - First day of every month at midnight\n  'every weekday at 9am'\n  'every 15 minutes'",
+            required_unless_present = "trigger",
+            conflicts_with = "trigger"
         )]
-        cron: String,
+        cron: Option<String>,
+        #[arg(
+            long,
+            help = "Event trigger to use instead of a cron expression",
+            long_help = "Run the job on an event instead of a cron schedule:\n  'watch:<glob>'                              - When a matching file is created, modified or removed\n  'webhook[:<token>]'                         - When POST /hooks/schedule/<id> is called on goosed\n  'after:<job-id>[:success|failure|always]'   - When another job finishes"
+        )]
+        trigger: Option<String>,
+        #[arg(
+            long,
+            value_name = "IANA_NAME",
+            help = "Time zone the cron schedule is evaluated in, e.g. Europe/Berlin (defaults to local time)"
+        )]
+        timezone: Option<String>,
         #[arg(
             long,
             help = "Recipe source (path to file, or base64 encoded recipe string)"
         )]
         recipe_source: String,
+        #[arg(
+            long,
+            default_value_t = 0,
+            help = "Number of times to retry a failed run"
+        )]
+        retries: u32,
+        #[arg(
+            long = "retry-backoff",
+            value_name = "SECONDS",
+            default_value_t = 60,
+            help = "Delay before the first retry, doubled for each further retry"
+        )]
+        retry_backoff: u64,
+        #[arg(
+            long,
+            default_value = "skip",
+            value_parser = ConcurrencyPolicy::from_str,
+            help = "What to do when the job is triggered while still running: skip, queue or allow"
+        )]
+        concurrency: ConcurrencyPolicy,
+        #[arg(
+            long = "catch-up",
+            default_value = "skip",
+            value_parser = CatchUpPolicy::from_str,
+            help = "What to do about runs missed while goose was stopped or asleep: skip, run-once or run-all"
+        )]
+        catch_up: CatchUpPolicy,
+        #[arg(
+            long = "notify",
+            value_name = "TARGET",
+            value_parser = FailureNotification::from_str,
+            help = "Notify when a run fails after its retries (repeatable)",
+            long_help = "Notify when a run fails after all of its retries. Can be given more than once:\n  'desktop'         - Desktop notification\n  'command:<cmd>'   - Run a shell command with GOOSE_SCHEDULE_ID, GOOSE_RUN_ID, GOOSE_RUN_ERROR and GOOSE_SESSION_ID set\n  'webhook:<url>'   - POST the failed run as JSON"
+        )]
+        notifications: Vec<FailureNotification>,
     },
     #[command(about = "List all scheduled jobs")]
     List {},
@@ -290,6 +346,14 @@ enum SchedulerCommand {
         #[arg(short = 'l', long, help = "Maximum number of sessions to return")]
         limit: Option<usize>,
     },
+    /// Show the run history of a specific schedule
+    #[command(about = "Show the run history of a specific schedule")]
+    History {
+        #[arg(long = "schedule-id", alias = "id", help = "ID of the schedule")]
+        schedule_id: String,
+        #[arg(short = 'l', long, help = "Maximum number of runs to show")]
+        limit: Option<usize>,
+    },
     #[command(about = "Run a scheduled job immediately")]
     RunNow {
         /// ID of the schedule to run
@@ -302,6 +366,25 @@ enum SchedulerCommand {
     /// Stop scheduler services (deprecated - no external services needed)
     #[command(about = "[Deprecated] Stop scheduler services")]
     ServicesStop {},
+    /// Validate a schedule and preview its next run times
+    #[command(about = "Validate a schedule and preview its next run times")]
+    Validate {
+        #[arg(long, help = "Cron expression or plain-English schedule to check")]
+        cron: String,
+        #[arg(
+            long,
+            value_name = "IANA_NAME",
+            help = "Time zone to evaluate the schedule in (defaults to local time)"
+        )]
+        timezone: Option<String>,
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = DEFAULT_PREVIEW_COUNT,
+            help = "Number of upcoming run times to show"
+        )]
+        count: usize,
+    },
     /// Show cron expression examples and help
     #[command(about = "Show cron expression examples and help")]
     CronHelp {},
@@ -424,6 +507,73 @@ enum RecipeCommand {
             help = "Show verbose information including recipe descriptions"
         )]
         verbose: bool,
+
+        /// Only list recipes installed from git remotes
+        #[arg(long, help = "Only list recipes installed from git remotes")]
+        installed: bool,
+    },
+
+    /// Install a recipe from a git repository
+    #[command(about = "Install a recipe from a git repository")]
+    Install {
+        /// Git repository URL or local path
+        #[arg(help = "Git repository URL or local path to clone the recipe from")]
+        repo: String,
+
+        /// Branch, tag or commit to install from
+        #[arg(
+            long = "ref",
+            value_name = "REF",
+            default_value = crate::recipes::registry::DEFAULT_RECIPE_REF,
+            help = "Branch, tag or commit to install from"
+        )]
+        git_ref: String,
+
+        /// Path of the recipe file or directory inside the repository
+        #[arg(
+            long,
+            default_value = ".",
+            help = "Path of the recipe file, or of a directory containing recipe.yaml, inside the repository"
+        )]
+        path: String,
+
+        /// Name to install the recipe under
+        #[arg(
+            long,
+            help = "Name to install the recipe under (defaults to the recipe directory or file name)"
+        )]
+        name: Option<String>,
+
+        /// Replace an installed recipe with the same name
+        #[arg(long, help = "Replace an installed recipe with the same name")]
+        force: bool,
+    },
+
+    /// Update installed recipes
+    #[command(about = "Update installed recipes to newer versions")]
+    Update {
+        /// Recipe to update, all installed recipes if omitted
+        #[arg(help = "Name of the installed recipe to update (updates all if omitted)")]
+        name: Option<String>,
+
+        /// Allow updates to versions that are not semver-compatible
+        #[arg(long, help = "Allow updates across major versions")]
+        major: bool,
+
+        /// Reinstall from the pinned ref even if the version did not increase
+        #[arg(
+            long,
+            help = "Reinstall from the pinned ref even if the version did not increase"
+        )]
+        force: bool,
+    },
+
+    /// Remove an installed recipe
+    #[command(about = "Remove an installed recipe")]
+    Remove {
+        /// Name of the installed recipe
+        #[arg(help = "Name of the installed recipe to remove")]
+        name: String,
     },
 }
 
@@ -782,8 +932,8 @@ enum Command {
         model: Option<String>,
     },
 
-    /// Recipe utilities for validation and deeplinking
-    #[command(about = "Recipe utilities for validation and deeplinking")]
+    /// Recipe utilities for validation, deeplinking and installation
+    #[command(about = "Recipe utilities for validation, deeplinking and installation")]
     Recipe {
         #[command(subcommand)]
         command: RecipeCommand,
@@ -1402,9 +1552,31 @@ pub async fn cli() -> anyhow::Result<()> {
                 SchedulerCommand::Add {
                     schedule_id,
                     cron,
+                    trigger,
+                    timezone,
                     recipe_source,
+                    retries,
+                    retry_backoff,
+                    concurrency,
+                    catch_up,
+                    notifications,
                 } => {
-                    handle_schedule_add(schedule_id, cron, recipe_source).await?;
+                    let retry = RetryPolicy {
+                        max_retries: retries,
+                        backoff_seconds: retry_backoff,
+                    };
+                    handle_schedule_add(
+                        schedule_id,
+                        cron,
+                        trigger,
+                        recipe_source,
+                        timezone,
+                        retry,
+                        concurrency,
+                        catch_up,
+                        notifications,
+                    )
+                    .await?;
                 }
                 SchedulerCommand::List {} => {
                     handle_schedule_list().await?;
@@ -1416,6 +1588,9 @@ pub async fn cli() -> anyhow::Result<()> {
                     // New arm
                     handle_schedule_sessions(schedule_id, limit).await?;
                 }
+                SchedulerCommand::History { schedule_id, limit } => {
+                    handle_schedule_history(schedule_id, limit).await?;
+                }
                 SchedulerCommand::RunNow { schedule_id } => {
                     // New arm
                     handle_schedule_run_now(schedule_id).await?;
@@ -1426,6 +1601,13 @@ pub async fn cli() -> anyhow::Result<()> {
                 SchedulerCommand::ServicesStop {} => {
                     handle_schedule_services_stop().await?;
                 }
+                SchedulerCommand::Validate {
+                    cron,
+                    timezone,
+                    count,
+                } => {
+                    handle_schedule_validate(cron, timezone, count).await?;
+                }
                 SchedulerCommand::CronHelp {} => {
                     handle_schedule_cron_help().await?;
                 }
@@ -1476,8 +1658,27 @@ pub async fn cli() -> anyhow::Result<()> {
                 } => {
                     handle_open(&recipe_name, &params)?;
                 }
-                RecipeCommand::List { format, verbose } => {
-                    handle_list(&format, verbose)?;
+                RecipeCommand::List {
+                    format,
+                    verbose,
+                    installed,
+                } => {
+                    handle_list(&format, verbose, installed)?;
+                }
+                RecipeCommand::Install {
+                    repo,
+                    git_ref,
+                    path,
+                    name,
+                    force,
+                } => {
+                    handle_install(&repo, &git_ref, &path, name.as_deref(), force)?;
+                }
+                RecipeCommand::Update { name, major, force } => {
+                    handle_update(name.as_deref(), major, force)?;
+                }
+                RecipeCommand::Remove { name } => {
+                    handle_remove(&name)?;
                 }
             }
             return Ok(());
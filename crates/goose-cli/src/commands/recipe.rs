@@ -4,7 +4,12 @@ use goose::recipe::validate_recipe::validate_recipe_template_from_file;
 use std::collections::HashMap;
 
 use crate::recipes::github_recipe::RecipeSource;
-use crate::recipes::search_recipe::{list_available_recipes, load_recipe_file};
+use crate::recipes::registry::{
+    short_commit, RecipeGitSource, RecipeRegistry, UpdateOptions, UpdateOutcome,
+};
+use crate::recipes::search_recipe::{
+    list_available_recipes, list_installed_recipes, load_recipe_file,
+};
 use goose::recipe_deeplink;
 
 pub fn handle_validate(recipe_name: &str) -> Result<()> {
@@ -83,8 +88,13 @@ pub fn handle_open(recipe_name: &str, params: &[String]) -> Result<()> {
     }
 }
 
-pub fn handle_list(format: &str, verbose: bool) -> Result<()> {
-    let recipes = match list_available_recipes() {
+pub fn handle_list(format: &str, verbose: bool, installed: bool) -> Result<()> {
+    let recipes = if installed {
+        list_installed_recipes()
+    } else {
+        list_available_recipes()
+    };
+    let recipes = match recipes {
         Ok(recipes) => recipes,
         Err(e) => {
             return Err(anyhow::anyhow!("Failed to list recipes: {}", e));
@@ -105,6 +115,7 @@ pub fn handle_list(format: &str, verbose: bool) -> Result<()> {
                     let source_info = match recipe.source {
                         RecipeSource::Local => format!("local: {}", recipe.path),
                         RecipeSource::GitHub => format!("github: {}", recipe.path),
+                        RecipeSource::Installed => format!("installed: {}", recipe.path),
                     };
 
                     let description = if let Some(desc) = &recipe.description {
@@ -134,6 +145,75 @@ pub fn handle_list(format: &str, verbose: bool) -> Result<()> {
     Ok(())
 }
 
+pub fn handle_install(
+    repo: &str,
+    git_ref: &str,
+    path: &str,
+    name: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    // Local repositories are cloned from the cache directory, so relative paths must be resolved
+    let repo = match std::path::Path::new(repo).canonicalize() {
+        Ok(local_path) => local_path.display().to_string(),
+        Err(_) => repo.to_string(),
+    };
+    let source = RecipeGitSource {
+        repo,
+        git_ref: git_ref.to_string(),
+        path: path.to_string(),
+    };
+    let (name, locked) = RecipeRegistry::new().install(name, source, force)?;
+    println!(
+        "{} Installed recipe '{}' v{} from {}@{} ({})",
+        style("✓").green().bold(),
+        name,
+        locked.version,
+        locked.source.repo,
+        locked.source.git_ref,
+        short_commit(&locked.commit)
+    );
+    Ok(())
+}
+
+pub fn handle_update(name: Option<&str>, allow_major: bool, force: bool) -> Result<()> {
+    let outcomes = RecipeRegistry::new().update(name, UpdateOptions { allow_major, force })?;
+    if outcomes.is_empty() {
+        println!("No installed recipes");
+        return Ok(());
+    }
+    for (name, outcome) in outcomes {
+        match outcome {
+            UpdateOutcome::Updated { from, to } => println!(
+                "{} {} updated {} -> {}",
+                style("✓").green().bold(),
+                name,
+                from,
+                to
+            ),
+            UpdateOutcome::UpToDate { version } => {
+                println!("  {} is up to date (v{})", name, version)
+            }
+            UpdateOutcome::SkippedIncompatible {
+                installed,
+                available,
+            } => println!(
+                "{} {} v{} is available but not compatible with v{}; rerun with --major to update",
+                style("!").yellow().bold(),
+                name,
+                available,
+                installed
+            ),
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_remove(name: &str) -> Result<()> {
+    RecipeRegistry::new().remove(name)?;
+    println!("{} Removed recipe '{}'", style("✓").green().bold(), name);
+    Ok(())
+}
+
 fn parse_params(params: &[String]) -> Result<HashMap<String, String>> {
     let mut params_map = HashMap::new();
     for param in params {
@@ -1,79 +1,90 @@
 use anyhow::{bail, Context, Result};
+use chrono::Utc;
 use goose::scheduler::{
     get_default_scheduled_recipes_dir, get_default_scheduler_storage_path, ScheduledJob, Scheduler,
     SchedulerError,
 };
+use goose::scheduler_expression::{next_fire_times, parse_schedule_expression};
+use goose::scheduler_runs::{
+    CatchUpPolicy, ConcurrencyPolicy, FailureNotification, JobRunStatus, RetryPolicy,
+};
+use goose::scheduler_trigger::ScheduleTrigger;
 use std::path::Path;
+use std::str::FromStr;
 
-fn validate_cron_expression(cron: &str) -> Result<()> {
-    // Basic validation and helpful suggestions
-    if cron.trim().is_empty() {
-        bail!("Cron expression cannot be empty");
+fn print_next_fire_times(cron: &str, timezone: Option<&str>, count: usize) -> Result<()> {
+    let fire_times =
+        next_fire_times(cron, timezone, Utc::now(), count).map_err(|e| anyhow::anyhow!(e))?;
+    println!(
+        "   Next {} run(s) ({}):",
+        fire_times.len(),
+        timezone.unwrap_or("local time")
+    );
+    for fire_time in fire_times {
+        println!("   - {}", fire_time.format("%a %Y-%m-%d %H:%M:%S %:z"));
     }
+    Ok(())
+}
 
-    // Check for common mistakes and provide helpful suggestions
-    let parts: Vec<&str> = cron.split_whitespace().collect();
+fn validate_cron_expression(expression: &str, timezone: Option<&str>) -> Result<String> {
+    if expression.trim().is_empty() {
+        bail!("Cron expression cannot be empty");
+    }
 
-    match parts.len() {
-        5 => {
-            // Standard 5-field cron (minute hour day month weekday)
-            println!("✅ Using standard 5-field cron format: {}", cron);
-        }
-        6 => {
-            // 6-field cron with seconds (second minute hour day month weekday)
-            println!("✅ Using 6-field cron format with seconds: {}", cron);
-        }
-        1 if cron.starts_with('@') => {
-            // Shorthand expressions like @hourly, @daily, etc.
-            let valid_shorthands = [
-                "@yearly",
-                "@annually",
-                "@monthly",
-                "@weekly",
-                "@daily",
-                "@midnight",
-                "@hourly",
-            ];
-            if valid_shorthands.contains(&cron) {
-                println!("✅ Using cron shorthand: {}", cron);
-            } else {
-                println!(
-                    "⚠️  Unknown cron shorthand '{}'. Valid options: {}",
-                    cron,
-                    valid_shorthands.join(", ")
-                );
-            }
-        }
-        _ => {
-            println!("⚠️  Unusual cron format detected: '{}'", cron);
-            println!("   Common formats:");
-            println!("   - 5 fields: '0 * * * *' (minute hour day month weekday)");
-            println!("   - 6 fields: '0 0 * * * *' (second minute hour day month weekday)");
-            println!("   - Shorthand: '@hourly', '@daily', '@weekly', '@monthly'");
-        }
+    let cron = parse_schedule_expression(expression).map_err(|e| anyhow::anyhow!(e))?;
+    if cron == expression.trim() {
+        println!("✅ Using cron expression: {}", cron);
+    } else {
+        println!(
+            "✅ Interpreted '{}' as cron expression: {}",
+            expression, cron
+        );
     }
 
     // Provide examples for common scheduling needs
-    if cron == "* * * * *" {
+    if cron == "0 * * * * *" {
         println!("⚠️  This will run every minute! Did you mean:");
         println!("   - '0 * * * *' for every hour?");
         println!("   - '0 0 * * *' for every day?");
     }
 
-    Ok(())
+    print_next_fire_times(&cron, timezone, 3)?;
+    Ok(cron)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_schedule_add(
     schedule_id: String,
-    cron: String,
+    cron: Option<String>,
+    trigger: Option<String>,
     recipe_source_arg: String, // This is expected to be a file path by the Scheduler
+    timezone: Option<String>,
+    retry: RetryPolicy,
+    concurrency: ConcurrencyPolicy,
+    catch_up: CatchUpPolicy,
+    notifications: Vec<FailureNotification>,
 ) -> Result<()> {
+    let trigger = match trigger {
+        Some(trigger) => ScheduleTrigger::from_str(&trigger).map_err(|e| anyhow::anyhow!(e))?,
+        None => ScheduleTrigger::Cron,
+    };
+    let mut cron = cron.unwrap_or_default();
     println!(
-        "[CLI Debug] Scheduling job ID: {}, Cron: {}, Recipe Source Path: {}",
-        schedule_id, cron, recipe_source_arg
+        "[CLI Debug] Scheduling job ID: {}, Trigger: {}, Recipe Source Path: {}",
+        schedule_id,
+        if trigger.is_cron() {
+            cron.clone()
+        } else {
+            trigger.to_string()
+        },
+        recipe_source_arg
     );
 
-    validate_cron_expression(&cron)?;
+    if trigger.is_cron() {
+        cron = validate_cron_expression(&cron, timezone.as_deref())?;
+    } else if timezone.is_some() {
+        println!("⚠️  --timezone only applies to cron schedules and will be ignored");
+    }
 
     // The Scheduler's add_scheduled_job will handle copying the recipe from recipe_source_arg
     // to its internal storage and validating the path.
@@ -81,11 +92,17 @@ pub async fn handle_schedule_add(
         id: schedule_id.clone(),
         source: recipe_source_arg.clone(), // Pass the original user-provided path
         cron,
+        trigger: trigger.clone(),
+        timezone,
         last_run: None,
         currently_running: false,
         paused: false,
         current_session_id: None,
         process_start_time: None,
+        retry,
+        concurrency,
+        catch_up,
+        notifications,
     };
 
     let scheduler_storage_path =
@@ -111,6 +128,12 @@ pub async fn handle_schedule_add(
                 "Scheduled job '{}' added. Recipe expected at {:?}",
                 schedule_id, final_recipe_path
             );
+            if let ScheduleTrigger::Webhook { token } = &trigger {
+                println!(
+                    "Trigger it with: curl -X POST -H 'X-Goose-Webhook-Token: {}' -d '{{}}' http://<goosed-host>/hooks/schedule/{}",
+                    token, schedule_id
+                );
+            }
             Ok(())
         }
         Err(e) => {
@@ -126,6 +149,9 @@ pub async fn handle_schedule_add(
                         recipe_source_arg
                     );
                 }
+                SchedulerError::InvalidTrigger(msg) => {
+                    bail!("Error: {}", msg);
+                }
                 _ => Err(anyhow::Error::new(e))
                     .context(format!("Failed to add job '{}' to scheduler", schedule_id)),
             }
@@ -154,11 +180,20 @@ pub async fn handle_schedule_list() -> Result<()> {
                 "⏹️  IDLE"
             };
 
+            let schedule = if job.trigger.is_cron() {
+                match &job.timezone {
+                    Some(timezone) => format!("Cron: {} ({})", job.cron, timezone),
+                    None => format!("Cron: {}", job.cron),
+                }
+            } else {
+                format!("Trigger: {}", job.trigger)
+            };
+
             println!(
-                "- ID: {}\n  Status: {}\n  Cron: {}\n  Recipe Source (in store): {}\n  Last Run: {}",
+                "- ID: {}\n  Status: {}\n  {}\n  Recipe Source (in store): {}\n  Last Run: {}",
                 job.id,
                 status,
-                job.cron,
+                schedule,
                 job.source, // This source is now the path within scheduled_recipes_dir
                 job.last_run
                     .map_or_else(|| "Never".to_string(), |dt| dt.to_rfc3339())
@@ -231,6 +266,60 @@ pub async fn handle_schedule_sessions(schedule_id: String, limit: Option<usize>)
     Ok(())
 }
 
+pub async fn handle_schedule_history(schedule_id: String, limit: Option<usize>) -> Result<()> {
+    let scheduler_storage_path =
+        get_default_scheduler_storage_path().context("Failed to get scheduler storage path")?;
+    let scheduler = Scheduler::new(scheduler_storage_path)
+        .await
+        .context("Failed to initialize scheduler")?;
+
+    let runs = match scheduler.runs(&schedule_id, limit.unwrap_or(20)).await {
+        Ok(runs) => runs,
+        Err(SchedulerError::JobNotFound(job_id)) => {
+            bail!("Error: Job with ID '{}' not found.", job_id);
+        }
+        Err(e) => bail!("Failed to get runs for schedule '{}': {:?}", schedule_id, e),
+    };
+
+    if runs.is_empty() {
+        println!("No runs recorded for schedule ID '{}'.", schedule_id);
+        return Ok(());
+    }
+
+    println!("Runs for schedule ID '{}' (newest first):", schedule_id);
+    for run in runs {
+        let status = match run.status {
+            JobRunStatus::Succeeded => "✅ succeeded",
+            JobRunStatus::Failed => "❌ failed",
+            JobRunStatus::Cancelled => "⏹️  cancelled",
+            JobRunStatus::Skipped => "⏭️  skipped",
+        };
+        let duration = run.finished_at.signed_duration_since(run.started_at);
+        println!(
+            "- {} {} (attempt {}, {}s)",
+            run.started_at.to_rfc3339(),
+            status,
+            run.attempt,
+            duration.num_seconds()
+        );
+        if let Some(session_id) = &run.session_id {
+            println!("  Session: {}", session_id);
+        }
+        if let Some(total_tokens) = run.total_tokens {
+            println!(
+                "  Tokens: {} (input {}, output {})",
+                total_tokens,
+                run.input_tokens.unwrap_or_default(),
+                run.output_tokens.unwrap_or_default()
+            );
+        }
+        if let Some(error) = &run.error {
+            println!("  Error: {}", error);
+        }
+    }
+    Ok(())
+}
+
 pub async fn handle_schedule_run_now(schedule_id: String) -> Result<()> {
     let scheduler_storage_path =
         get_default_scheduler_storage_path().context("Failed to get scheduler storage path")?;
@@ -271,6 +360,17 @@ pub async fn handle_schedule_services_stop() -> Result<()> {
     Ok(())
 }
 
+pub async fn handle_schedule_validate(
+    expression: String,
+    timezone: Option<String>,
+    count: usize,
+) -> Result<()> {
+    let cron = parse_schedule_expression(&expression).map_err(|e| anyhow::anyhow!(e))?;
+    println!("✅ '{}' is a valid schedule", expression);
+    println!("   Cron expression: {}", cron);
+    print_next_fire_times(&cron, timezone.as_deref(), count)
+}
+
 pub async fn handle_schedule_cron_help() -> Result<()> {
     println!("📅 Cron Expression Guide for goose Scheduler");
     println!("===========================================\\n");
@@ -322,6 +422,20 @@ pub async fn handle_schedule_cron_help() -> Result<()> {
     println!("  @daily    - Once a day (0 0 * * *)");
     println!("  @hourly   - Once an hour (0 * * * *)\\n");
 
+    println!("🗣️  PLAIN ENGLISH:");
+    println!("  every 15 minutes                 - Every 15 minutes");
+    println!("  every day at 9am                 - Every day at 9:00 AM");
+    println!("  every weekday at 17:30           - Monday to Friday at 5:30 PM");
+    println!("  every monday and friday at noon  - Mondays and Fridays at 12:00 PM");
+    println!("  every month on the 15th at 8am   - 15th of every month at 8:00 AM\\n");
+
+    println!("🌍 TIME ZONES:");
+    println!("  Schedules run in local time unless --timezone is given, e.g.");
+    println!("  --timezone Europe/Berlin or --timezone America/New_York\\n");
+
+    println!("🔍 PREVIEW:");
+    println!("  goose schedule validate --cron \"every weekday at 9am\" --timezone UTC -n 5\\n");
+
     println!("💡 EXAMPLES:");
     println!(
         "  goose schedule add --schedule-id hourly-report --cron \"0 * * * *\" --recipe-source report.yaml"
@@ -2,12 +2,14 @@ use crate::session::message_to_markdown;
 use anyhow::{Context, Result};
 
 use cliclack::{confirm, multiselect, select};
+use goose::session::export::{export_session, ExportFormat};
 use goose::session::{generate_diagnostics, Session, SessionManager};
 use goose::utils::safe_truncate;
 use regex::Regex;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 const TRUNCATED_DESC_LENGTH: usize = 60;
 
@@ -193,7 +195,6 @@ pub async fn handle_session_export(
     };
 
     let output = match format.as_str() {
-        "json" => serde_json::to_string_pretty(&session)?,
         "yaml" => serde_yaml::to_string(&session)?,
         "markdown" => {
             let conversation = session
@@ -201,7 +202,11 @@ pub async fn handle_session_export(
                 .ok_or_else(|| anyhow::anyhow!("Session has no messages"))?;
             export_session_to_markdown(conversation.messages().to_vec(), &session.name)
         }
-        _ => return Err(anyhow::anyhow!("Unsupported format: {}", format)),
+        other => {
+            let export_format = ExportFormat::from_str(other)
+                .map_err(|_| anyhow::anyhow!("Unsupported format: {}", format))?;
+            export_session(&session, export_format)?
+        }
     };
 
     if let Some(output_path) = output_path {
@@ -13,28 +13,161 @@ use axum::{
 };
 use base64::Engine;
 use futures::{sink::SinkExt, stream::StreamExt};
-use goose::agents::{Agent, AgentEvent};
-use goose::conversation::message::Message as GooseMessage;
+use goose::agents::{Agent, AgentEvent, ExtensionConfig, SessionConfig};
+use goose::conversation::message::{ActionRequiredData, Message as GooseMessage, MessageContent};
+use goose::model::ModelConfig;
+use goose::permission::permission_confirmation::PrincipalType;
+use goose::permission::{Permission, PermissionConfirmation};
+use goose::recipe::build_recipe::{build_recipe_from_template, RecipeError};
+use goose::recipe::template_recipe::parse_recipe_content;
+use goose::recipe::{Recipe, RecipeParameter};
 use goose::session::session_manager::SessionType;
 use goose::session::SessionManager;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use std::{net::SocketAddr, sync::Arc};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{broadcast, Mutex};
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
-use tracing::error;
+use tracing::{error, warn};
 use webbrowser;
 
-type CancellationStore = Arc<RwLock<std::collections::HashMap<String, tokio::task::AbortHandle>>>;
+use crate::recipes::search_recipe::{list_available_recipes, load_recipe_file};
+
+type Sender = Arc<Mutex<futures::stream::SplitSink<WebSocket, Message>>>;
+
+/// How long a session's agent is kept once nothing uses it. The conversation is stored with
+/// the session, so the next message just creates a new agent.
+const AGENT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 
 #[derive(Clone)]
 struct AppState {
-    agent: Arc<Agent>,
-    cancellations: CancellationStore,
+    provider_name: String,
+    model_config: ModelConfig,
+    /// One agent per session, created on first use
+    agents: Arc<Mutex<SessionAgents>>,
+    /// Replies in progress, by session
+    turns: Arc<Mutex<HashMap<String, Arc<Turn>>>>,
     auth_token: Option<String>,
     ws_token: String,
 }
 
+/// The agents of the sessions used recently. Agents idle for longer than
+/// `AGENT_IDLE_TIMEOUT` are dropped whenever another one is added.
+#[derive(Default)]
+struct SessionAgents {
+    agents: HashMap<String, (Arc<Agent>, Instant)>,
+}
+
+impl SessionAgents {
+    fn get(&mut self, session_id: &str) -> Option<Arc<Agent>> {
+        let (agent, last_used) = self.agents.get_mut(session_id)?;
+        *last_used = Instant::now();
+        Some(agent.clone())
+    }
+
+    /// Sets the session's agent, keeping the current one if there is one and `replace` is
+    /// false, and returns the session's agent.
+    fn insert(&mut self, session_id: &str, agent: Arc<Agent>, replace: bool) -> Arc<Agent> {
+        self.evict_idle(AGENT_IDLE_TIMEOUT);
+        let now = Instant::now();
+        let entry = self
+            .agents
+            .entry(session_id.to_string())
+            .and_modify(|current| {
+                if replace {
+                    current.0 = agent.clone();
+                }
+                current.1 = now;
+            })
+            .or_insert_with(|| (agent, now));
+        entry.0.clone()
+    }
+
+    fn remove(&mut self, session_id: &str) {
+        self.agents.remove(session_id);
+    }
+
+    /// Drops the agents unused for longer than `timeout`. Agents still held elsewhere, by a
+    /// reply in progress, are kept whatever their age.
+    fn evict_idle(&mut self, timeout: Duration) {
+        self.agents.retain(|_, (agent, last_used)| {
+            Arc::strong_count(agent) > 1 || last_used.elapsed() < timeout
+        });
+    }
+}
+
+/// A reply being produced for a session. It is not tied to the socket that started it: it
+/// keeps running when the browser disconnects, and a reconnecting client catches up with
+/// `resume`, which replays everything sent so far.
+struct Turn {
+    cancel_token: CancellationToken,
+    events: std::sync::Mutex<Vec<TurnEvent>>,
+    broadcast: broadcast::Sender<TurnEvent>,
+}
+
+#[derive(Clone)]
+struct TurnEvent {
+    text: String,
+    last: bool,
+}
+
+impl Turn {
+    fn new() -> Self {
+        Self {
+            cancel_token: CancellationToken::new(),
+            events: std::sync::Mutex::new(Vec::new()),
+            broadcast: broadcast::channel(256).0,
+        }
+    }
+
+    fn publish(&self, message: &WebSocketMessage) {
+        let last = matches!(
+            message,
+            WebSocketMessage::Complete { .. } | WebSocketMessage::Cancelled { .. }
+        );
+        let event = TurnEvent {
+            text: serde_json::to_string(message).unwrap(),
+            last,
+        };
+        let mut events = self.events.lock().unwrap();
+        events.push(event.clone());
+        let _ = self.broadcast.send(event);
+    }
+
+    /// Sends the events published so far to the socket, then follows the turn until it ends
+    /// or the socket goes away.
+    fn follow(self: &Arc<Self>, sender: Sender) {
+        let (backlog, mut receiver) = {
+            let events = self.events.lock().unwrap();
+            (events.clone(), self.broadcast.subscribe())
+        };
+        tokio::spawn(async move {
+            for event in backlog {
+                if send_text(&sender, event.text).await.is_err() || event.last {
+                    return;
+                }
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if send_text(&sender, event.text).await.is_err() || event.last {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Web client fell behind, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+}
+
+/// Messages exchanged over `/ws`, see WEB_INTERFACE.md for the protocol.
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type")]
 enum WebSocketMessage {
@@ -46,6 +179,35 @@ enum WebSocketMessage {
     },
     #[serde(rename = "cancel")]
     Cancel { session_id: String },
+    #[serde(rename = "resume")]
+    Resume { session_id: String },
+    #[serde(rename = "tool_confirmation_response")]
+    ToolConfirmationResponse {
+        session_id: String,
+        id: String,
+        action: ConfirmationAction,
+    },
+    #[serde(rename = "elicitation_response")]
+    ElicitationResponse {
+        session_id: String,
+        id: String,
+        /// Omitted or null when the user declined to answer
+        #[serde(default)]
+        user_data: Option<Value>,
+    },
+    #[serde(rename = "start_recipe")]
+    StartRecipe {
+        session_id: String,
+        recipe: String,
+        #[serde(default)]
+        parameters: HashMap<String, String>,
+    },
+    #[serde(rename = "set_extension")]
+    SetExtension {
+        session_id: String,
+        name: String,
+        enabled: bool,
+    },
     #[serde(rename = "response")]
     Response {
         content: String,
@@ -70,7 +232,28 @@ enum WebSocketMessage {
         tool_name: String,
         arguments: serde_json::Value,
         needs_confirmation: bool,
+        #[serde(default)]
+        prompt: Option<String>,
+    },
+    #[serde(rename = "elicitation")]
+    Elicitation {
+        id: String,
+        message: String,
+        requested_schema: Value,
+    },
+    #[serde(rename = "recipe_parameters")]
+    RecipeParameters {
+        recipe: String,
+        parameters: Vec<RecipeParameter>,
     },
+    #[serde(rename = "recipe_started")]
+    RecipeStarted {
+        title: String,
+        description: String,
+        prompt: Option<String>,
+    },
+    #[serde(rename = "extensions")]
+    Extensions { enabled: Vec<String> },
     #[serde(rename = "error")]
     Error { message: String },
     #[serde(rename = "thinking")]
@@ -83,6 +266,24 @@ enum WebSocketMessage {
     Complete { message: String },
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ConfirmationAction {
+    AllowOnce,
+    AlwaysAllow,
+    Deny,
+}
+
+impl From<ConfirmationAction> for Permission {
+    fn from(action: ConfirmationAction) -> Self {
+        match action {
+            ConfirmationAction::AllowOnce => Permission::AllowOnce,
+            ConfirmationAction::AlwaysAllow => Permission::AlwaysAllow,
+            ConfirmationAction::Deny => Permission::DenyOnce,
+        }
+    }
+}
+
 async fn auth_middleware(
     State(state): State<AppState>,
     req: Request,
@@ -151,25 +352,10 @@ pub async fn handle_web(
         }
     };
 
-    let model_config = goose::model::ModelConfig::new(&model)?;
-
-    let init_session = SessionManager::create_session(
-        std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")),
-        "Web Agent Initialization".to_string(),
-        SessionType::Hidden,
-    )
-    .await?;
-
-    let agent = Agent::new();
-    let provider = goose::providers::create(&provider_name, model_config).await?;
-    agent.update_provider(provider, &init_session.id).await?;
+    let model_config = ModelConfig::new(&model)?;
 
-    let enabled_configs = goose::config::get_enabled_extensions();
-    for config in enabled_configs {
-        if let Err(e) = agent.add_extension(config.clone()).await {
-            eprintln!("Warning: Failed to load extension {}: {}", config.name(), e);
-        }
-    }
+    // Fail early on a broken provider setup rather than on the first message
+    goose::providers::create(&provider_name, model_config.clone()).await?;
 
     let ws_token = if auth_token.is_none() {
         uuid::Uuid::new_v4().to_string()
@@ -178,8 +364,10 @@ pub async fn handle_web(
     };
 
     let state = AppState {
-        agent: Arc::new(agent),
-        cancellations: Arc::new(RwLock::new(std::collections::HashMap::new())),
+        provider_name: provider_name.clone(),
+        model_config,
+        agents: Arc::new(Mutex::new(SessionAgents::default())),
+        turns: Arc::new(Mutex::new(HashMap::new())),
         auth_token: auth_token.clone(),
         ws_token,
     };
@@ -207,7 +395,15 @@ pub async fn handle_web(
         .route("/ws", get(websocket_handler))
         .route("/api/health", get(health_check))
         .route("/api/sessions", get(list_sessions))
-        .route("/api/sessions/{session_id}", get(get_session))
+        .route(
+            "/api/sessions/{session_id}",
+            get(get_session)
+                .patch(rename_session)
+                .delete(delete_session),
+        )
+        .route("/api/recipes", get(list_recipes))
+        .route("/api/recipes/{name}", get(get_recipe))
+        .route("/api/extensions", get(list_extensions))
         .route("/static/{*path}", get(serve_static))
         .layer(middleware::from_fn_with_state(
             state.clone(),
@@ -344,6 +540,106 @@ async fn get_session(
     }
 }
 
+#[derive(Deserialize)]
+struct RenameSessionRequest {
+    name: String,
+}
+
+async fn rename_session(
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+    Json(request): Json<RenameSessionRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Name cannot be empty".to_string()));
+    }
+    SessionManager::update_session(&session_id)
+        .user_provided_name(name)
+        .apply()
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(Json(serde_json::json!({ "name": name })))
+}
+
+async fn delete_session(
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if let Some(turn) = state.turns.lock().await.remove(&session_id) {
+        turn.cancel_token.cancel();
+    }
+    state.agents.lock().await.remove(&session_id);
+    SessionManager::delete_session(&session_id)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_recipes() -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let recipes = tokio::task::spawn_blocking(list_available_recipes)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(serde_json::json!({ "recipes": recipes })))
+}
+
+/// Describes a recipe and its parameters, so the client can show a form before starting it.
+async fn get_recipe(
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let recipe = tokio::task::spawn_blocking(move || {
+        let recipe_file = load_recipe_file(&name)?;
+        let (recipe, _) = parse_recipe_content(
+            &recipe_file.content,
+            Some(recipe_file.parent_dir.display().to_string()),
+        )?;
+        Ok::<_, anyhow::Error>(recipe)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "title": recipe.title,
+        "description": recipe.description,
+        "parameters": recipe.parameters.unwrap_or_default(),
+    })))
+}
+
+#[derive(Deserialize)]
+struct ExtensionsQuery {
+    session_id: Option<String>,
+}
+
+/// Configured extensions and whether they are enabled, for the session's agent when it has
+/// one and otherwise as configured.
+async fn list_extensions(
+    State(state): State<AppState>,
+    Query(query): Query<ExtensionsQuery>,
+) -> Json<serde_json::Value> {
+    let agent = match &query.session_id {
+        Some(session_id) => state.agents.lock().await.get(session_id),
+        None => None,
+    };
+    let running = match agent {
+        Some(agent) => Some(agent.list_extensions().await),
+        None => None,
+    };
+
+    let extensions: Vec<_> = goose::config::get_all_extensions()
+        .into_iter()
+        .map(|entry| {
+            let name = entry.config.name();
+            let enabled = match &running {
+                Some(running) => running.contains(&name),
+                None => entry.enabled,
+            };
+            serde_json::json!({ "name": name, "enabled": enabled })
+        })
+        .collect();
+    Json(serde_json::json!({ "extensions": extensions }))
+}
+
 #[derive(Deserialize)]
 struct WsQuery {
     token: Option<String>,
@@ -365,296 +661,679 @@ async fn websocket_handler(
     Ok(ws.on_upgrade(|socket| handle_socket(socket, state)))
 }
 
+async fn send_text(sender: &Sender, text: String) -> Result<(), axum::Error> {
+    sender.lock().await.send(Message::Text(text.into())).await
+}
+
+async fn send_message(sender: &Sender, message: &WebSocketMessage) {
+    let _ = send_text(sender, serde_json::to_string(message).unwrap()).await;
+}
+
+async fn send_error(sender: &Sender, message: impl Into<String>) {
+    send_message(
+        sender,
+        &WebSocketMessage::Error {
+            message: message.into(),
+        },
+    )
+    .await;
+}
+
 async fn handle_socket(socket: WebSocket, state: AppState) {
     let (sender, mut receiver) = socket.split();
-    let sender = Arc::new(Mutex::new(sender));
+    let sender: Sender = Arc::new(Mutex::new(sender));
 
     while let Some(msg) = receiver.next().await {
-        if let Ok(msg) = msg {
-            match msg {
-                Message::Text(text) => {
-                    match serde_json::from_str::<WebSocketMessage>(&text.to_string()) {
-                        Ok(WebSocketMessage::Message {
-                            content,
-                            session_id,
-                            ..
-                        }) => {
-                            let sender_clone = sender.clone();
-                            let agent = state.agent.clone();
-                            let session_id_clone = session_id.clone();
-
-                            let task_handle = tokio::spawn(async move {
-                                let result = process_message_streaming(
-                                    &agent,
-                                    session_id_clone,
-                                    content,
-                                    sender_clone,
-                                )
-                                .await;
-
-                                if let Err(e) = result {
-                                    error!("Error processing message: {}", e);
-                                }
-                            });
-
-                            {
-                                let mut cancellations = state.cancellations.write().await;
-                                cancellations
-                                    .insert(session_id.clone(), task_handle.abort_handle());
-                            }
-
-                            // Handle task completion and cleanup
-                            let sender_for_abort = sender.clone();
-                            let session_id_for_cleanup = session_id.clone();
-                            let cancellations_for_cleanup = state.cancellations.clone();
-
-                            tokio::spawn(async move {
-                                match task_handle.await {
-                                    Ok(_) => {}
-                                    Err(e) if e.is_cancelled() => {
-                                        let mut sender = sender_for_abort.lock().await;
-                                        let _ = sender
-                                            .send(Message::Text(
-                                                serde_json::to_string(
-                                                    &WebSocketMessage::Cancelled {
-                                                        message: "Operation cancelled by user"
-                                                            .to_string(),
-                                                    },
-                                                )
-                                                .unwrap()
-                                                .into(),
-                                            ))
-                                            .await;
-                                    }
-                                    Err(e) => {
-                                        error!("Task error: {}", e);
-                                    }
-                                }
-
-                                let mut cancellations = cancellations_for_cleanup.write().await;
-                                cancellations.remove(&session_id_for_cleanup);
-                            });
-                        }
-                        Ok(WebSocketMessage::Cancel { session_id }) => {
-                            // Cancel the active operation for this session
-                            let abort_handle = {
-                                let mut cancellations = state.cancellations.write().await;
-                                cancellations.remove(&session_id)
-                            };
-
-                            if let Some(handle) = abort_handle {
-                                handle.abort();
-
-                                // Send cancellation confirmation
-                                let mut sender = sender.lock().await;
-                                let _ = sender
-                                    .send(Message::Text(
-                                        serde_json::to_string(&WebSocketMessage::Cancelled {
-                                            message: "Operation cancelled".to_string(),
-                                        })
-                                        .unwrap()
-                                        .into(),
-                                    ))
-                                    .await;
-                            }
-                        }
-                        Ok(_) => {
-                            // Ignore other message types
-                        }
-                        Err(e) => {
-                            error!("Failed to parse WebSocket message: {}", e);
-                        }
-                    }
+        let Ok(msg) = msg else {
+            break;
+        };
+        match msg {
+            Message::Text(text) => match serde_json::from_str::<WebSocketMessage>(text.as_str()) {
+                Ok(message) => handle_client_message(&state, &sender, message).await,
+                Err(e) => {
+                    error!("Failed to parse WebSocket message: {}", e);
                 }
-                Message::Close(_) => break,
-                _ => {}
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    // Replies started from this socket keep running; a new socket picks them up with `resume`
+}
+
+async fn handle_client_message(state: &AppState, sender: &Sender, message: WebSocketMessage) {
+    match message {
+        WebSocketMessage::Message {
+            content,
+            session_id,
+            ..
+        } => {
+            let message = GooseMessage::user().with_text(content);
+            start_turn(state, sender, session_id, None, message).await;
+        }
+        WebSocketMessage::Cancel { session_id } => {
+            if let Some(turn) = state.turns.lock().await.get(&session_id) {
+                turn.cancel_token.cancel();
             }
-        } else {
-            break;
+        }
+        WebSocketMessage::Resume { session_id } => {
+            let turn = state.turns.lock().await.get(&session_id).cloned();
+            match turn {
+                Some(turn) => turn.follow(sender.clone()),
+                None => {
+                    send_message(
+                        sender,
+                        &WebSocketMessage::Complete {
+                            message: "No reply in progress".to_string(),
+                        },
+                    )
+                    .await
+                }
+            }
+        }
+        WebSocketMessage::ToolConfirmationResponse {
+            session_id,
+            id,
+            action,
+        } => match existing_agent(state, &session_id).await {
+            Some(agent) => {
+                agent
+                    .handle_confirmation(
+                        id,
+                        PermissionConfirmation {
+                            principal_type: PrincipalType::Tool,
+                            permission: action.into(),
+                        },
+                    )
+                    .await
+            }
+            None => send_error(sender, format!("No agent for session {}", session_id)).await,
+        },
+        WebSocketMessage::ElicitationResponse {
+            session_id,
+            id,
+            user_data,
+        } => answer_elicitation(state, sender, session_id, id, user_data).await,
+        WebSocketMessage::StartRecipe {
+            session_id,
+            recipe,
+            parameters,
+        } => start_recipe(state, sender, session_id, recipe, parameters).await,
+        WebSocketMessage::SetExtension {
+            session_id,
+            name,
+            enabled,
+        } => {
+            if let Err(e) = set_extension(state, &session_id, &name, enabled).await {
+                send_error(sender, format!("Failed to update {}: {}", name, e)).await;
+            }
+            if let Some(agent) = existing_agent(state, &session_id).await {
+                send_message(
+                    sender,
+                    &WebSocketMessage::Extensions {
+                        enabled: agent.list_extensions().await,
+                    },
+                )
+                .await;
+            }
+        }
+        _ => {
+            // Ignore server-to-client message types
         }
     }
 }
 
-async fn process_message_streaming(
-    agent: &Agent,
-    session_id: String,
-    content: String,
-    sender: Arc<Mutex<futures::stream::SplitSink<WebSocket, Message>>>,
+async fn existing_agent(state: &AppState, session_id: &str) -> Option<Arc<Agent>> {
+    state.agents.lock().await.get(session_id)
+}
+
+/// Returns the session's agent, creating one the first time the session is used or after
+/// its agent was dropped for being idle: set up from the session's recipe if it was started
+/// with one, and otherwise with the configured provider and enabled extensions.
+async fn agent_for(state: &AppState, session_id: &str) -> Result<Arc<Agent>> {
+    if let Some(agent) = existing_agent(state, session_id).await {
+        return Ok(agent);
+    }
+
+    let recipe = SessionManager::get_session(session_id, false)
+        .await
+        .ok()
+        .and_then(|session| session.recipe);
+    let agent = match recipe {
+        Some(recipe) => create_recipe_agent(state, session_id, &recipe).await?,
+        None => {
+            create_agent(
+                session_id,
+                &state.provider_name,
+                state.model_config.clone(),
+                goose::config::get_enabled_extensions(),
+            )
+            .await?
+        }
+    };
+    Ok(state.agents.lock().await.insert(session_id, agent, false))
+}
+
+async fn create_agent(
+    session_id: &str,
+    provider_name: &str,
+    model_config: ModelConfig,
+    extensions: Vec<ExtensionConfig>,
+) -> Result<Arc<Agent>> {
+    let agent = Agent::new();
+    let provider = goose::providers::create(provider_name, model_config).await?;
+    agent.update_provider(provider, session_id).await?;
+
+    for config in extensions {
+        if let Err(e) = agent.add_extension(config.clone()).await {
+            warn!("Failed to load extension {}: {}", config.name(), e);
+        }
+    }
+    Ok(Arc::new(agent))
+}
+
+async fn set_extension(
+    state: &AppState,
+    session_id: &str,
+    name: &str,
+    enabled: bool,
 ) -> Result<()> {
-    use futures::StreamExt;
-    use goose::agents::SessionConfig;
-    use goose::conversation::message::MessageContent;
-
-    let user_message = GooseMessage::user().with_text(content.clone());
-
-    let provider = agent.provider().await;
-    if provider.is_err() {
-        let error_msg = "I'm not properly configured yet. Please configure a provider through the CLI first using `goose configure`.".to_string();
-        let mut sender = sender.lock().await;
-        let _ = sender
-            .send(Message::Text(
-                serde_json::to_string(&WebSocketMessage::Response {
-                    content: error_msg,
-                    role: "assistant".to_string(),
-                    timestamp: chrono::Utc::now().timestamp_millis(),
-                })
-                .unwrap()
-                .into(),
-            ))
+    let agent = agent_for(state, session_id).await?;
+    if enabled {
+        let config = goose::config::get_extension_by_name(name)
+            .ok_or_else(|| anyhow::anyhow!("Extension {} is not configured", name))?;
+        agent.add_extension(config).await?;
+    } else {
+        agent.remove_extension(name).await?;
+    }
+    Ok(())
+}
+
+/// Starts a reply for the session, unless one is already running, and streams it to the
+/// socket. `agent` is used instead of the session's agent when given.
+async fn start_turn(
+    state: &AppState,
+    sender: &Sender,
+    session_id: String,
+    agent: Option<Arc<Agent>>,
+    message: GooseMessage,
+) {
+    let agent = match agent {
+        Some(agent) => agent,
+        None => match agent_for(state, &session_id).await {
+            Ok(agent) => agent,
+            Err(e) => {
+                send_error(sender, format!("Error: {}", e)).await;
+                send_message(
+                    sender,
+                    &WebSocketMessage::Complete {
+                        message: "Response complete".to_string(),
+                    },
+                )
+                .await;
+                return;
+            }
+        },
+    };
+
+    let turn = {
+        let mut turns = state.turns.lock().await;
+        if turns.contains_key(&session_id) {
+            drop(turns);
+            send_error(sender, "A reply is already in progress for this session").await;
+            return;
+        }
+        let turn = Arc::new(Turn::new());
+        turns.insert(session_id.clone(), turn.clone());
+        turn
+    };
+    turn.follow(sender.clone());
+
+    let turns = state.turns.clone();
+    tokio::spawn(async move {
+        if let Err(e) = process_message_streaming(&agent, &session_id, message, &turn).await {
+            error!("Error processing message: {}", e);
+        }
+
+        let mut turns = turns.lock().await;
+        if turns
+            .get(&session_id)
+            .is_some_and(|current| Arc::ptr_eq(current, &turn))
+        {
+            turns.remove(&session_id);
+        }
+    });
+}
+
+async fn answer_elicitation(
+    state: &AppState,
+    sender: &Sender,
+    session_id: String,
+    id: String,
+    user_data: Option<Value>,
+) {
+    let Some(user_data) = user_data.filter(|data| !data.is_null()) else {
+        // Declining ends the turn, like the CLI does
+        if let Some(turn) = state.turns.lock().await.get(&session_id) {
+            turn.cancel_token.cancel();
+        }
+        return;
+    };
+
+    let Some(agent) = existing_agent(state, &session_id).await else {
+        send_error(sender, format!("No agent for session {}", session_id)).await;
+        return;
+    };
+
+    let response = GooseMessage::user()
+        .with_content(MessageContent::action_required_elicitation_response(
+            id, user_data,
+        ))
+        .with_visibility(false, true);
+    let session_config = SessionConfig {
+        id: session_id,
+        schedule_id: None,
+        max_turns: None,
+        retry_config: None,
+    };
+    // The response unblocks the waiting tool call; the returned stream is empty
+    if let Err(e) = agent.reply(response, session_config, None).await {
+        send_error(sender, format!("Failed to submit response: {}", e)).await;
+    };
+}
+
+enum LoadedRecipe {
+    Ready(Box<Recipe>),
+    MissingParameters(Vec<RecipeParameter>),
+}
+
+fn load_recipe_with_parameters(
+    name: &str,
+    parameters: &HashMap<String, String>,
+) -> Result<LoadedRecipe> {
+    let recipe_file = load_recipe_file(name)?;
+    let params = parameters
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    match build_recipe_from_template(
+        recipe_file.content.clone(),
+        &recipe_file.parent_dir,
+        params,
+        None::<fn(&str, &str) -> Result<String, anyhow::Error>>,
+    ) {
+        Ok(recipe) => Ok(LoadedRecipe::Ready(Box::new(recipe))),
+        Err(RecipeError::MissingParams {
+            parameters: missing,
+        }) => {
+            let (recipe, _) = parse_recipe_content(
+                &recipe_file.content,
+                Some(recipe_file.parent_dir.display().to_string()),
+            )?;
+            Ok(LoadedRecipe::MissingParameters(missing_parameters(
+                recipe.parameters.unwrap_or_default(),
+                &missing,
+            )))
+        }
+        Err(e) => Err(anyhow::anyhow!(e.to_string())),
+    }
+}
+
+fn missing_parameters(
+    parameters: Vec<RecipeParameter>,
+    missing: &[String],
+) -> Vec<RecipeParameter> {
+    parameters
+        .into_iter()
+        .filter(|parameter| missing.contains(&parameter.key))
+        .collect()
+}
+
+/// Starts the session over with a recipe: a fresh agent with the recipe's extensions,
+/// instructions, sub-recipes and settings, then the recipe's prompt if it has one. Asks the
+/// client for parameter values first when some are missing.
+async fn start_recipe(
+    state: &AppState,
+    sender: &Sender,
+    session_id: String,
+    name: String,
+    parameters: HashMap<String, String>,
+) {
+    if state.turns.lock().await.contains_key(&session_id) {
+        send_error(sender, "A reply is already in progress for this session").await;
+        return;
+    }
+
+    let loaded = {
+        let name = name.clone();
+        let parameters = parameters.clone();
+        tokio::task::spawn_blocking(move || load_recipe_with_parameters(&name, &parameters)).await
+    };
+    let recipe = match loaded {
+        Ok(Ok(LoadedRecipe::Ready(recipe))) => *recipe,
+        Ok(Ok(LoadedRecipe::MissingParameters(missing))) => {
+            send_message(
+                sender,
+                &WebSocketMessage::RecipeParameters {
+                    recipe: name,
+                    parameters: missing,
+                },
+            )
             .await;
-        return Ok(());
+            return;
+        }
+        Ok(Err(e)) => {
+            send_error(sender, format!("Failed to load recipe {}: {}", name, e)).await;
+            return;
+        }
+        Err(e) => {
+            send_error(sender, format!("Failed to load recipe {}: {}", name, e)).await;
+            return;
+        }
+    };
+
+    let agent = match create_recipe_agent(state, &session_id, &recipe).await {
+        Ok(agent) => agent,
+        Err(e) => {
+            send_error(sender, format!("Failed to start recipe: {}", e)).await;
+            return;
+        }
+    };
+    state
+        .agents
+        .lock()
+        .await
+        .insert(&session_id, agent.clone(), true);
+
+    if let Err(e) = SessionManager::update_session(&session_id)
+        .user_provided_name(recipe.title.clone())
+        .recipe(Some(recipe.clone()))
+        .user_recipe_values(Some(parameters))
+        .apply()
+        .await
+    {
+        warn!("Failed to store recipe on session {}: {}", session_id, e);
+    }
+
+    let prompt = recipe.prompt.clone().filter(|p| !p.trim().is_empty());
+    send_message(
+        sender,
+        &WebSocketMessage::RecipeStarted {
+            title: recipe.title.clone(),
+            description: recipe.description.clone(),
+            prompt: prompt.clone(),
+        },
+    )
+    .await;
+
+    if let Some(prompt) = prompt {
+        let message = GooseMessage::user().with_text(prompt);
+        start_turn(state, sender, session_id, Some(agent), message).await;
     }
+}
+
+/// Creates an agent set up for a recipe: its provider, model and extensions, instructions and
+/// sub-recipes.
+async fn create_recipe_agent(
+    state: &AppState,
+    session_id: &str,
+    recipe: &Recipe,
+) -> Result<Arc<Agent>> {
+    let settings = recipe.settings.as_ref();
+    let provider_name = settings
+        .and_then(|s| s.goose_provider.clone())
+        .unwrap_or_else(|| state.provider_name.clone());
+    let model_config = match settings.and_then(|s| s.goose_model.clone()) {
+        Some(model) => ModelConfig::new(&model)
+            .map_err(|e| anyhow::anyhow!("Invalid model in recipe: {}", e))?,
+        None => state.model_config.clone(),
+    };
+    let model_config = match settings.and_then(|s| s.temperature) {
+        Some(temperature) => model_config.with_temperature(Some(temperature)),
+        None => model_config,
+    };
 
-    let session = SessionManager::get_session(&session_id, true).await?;
-    let mut messages = session.conversation.unwrap_or_default();
-    messages.push(user_message.clone());
+    let extensions = recipe
+        .extensions
+        .clone()
+        .unwrap_or_else(goose::config::get_enabled_extensions);
+    let agent = create_agent(session_id, &provider_name, model_config, extensions).await?;
+    agent
+        .apply_recipe_components(recipe.sub_recipes.clone(), recipe.response.clone(), true)
+        .await;
+    if let Some(instructions) = &recipe.instructions {
+        agent.extend_system_prompt(instructions.clone()).await;
+    }
+    Ok(agent)
+}
 
+async fn process_message_streaming(
+    agent: &Agent,
+    session_id: &str,
+    user_message: GooseMessage,
+    turn: &Turn,
+) -> Result<()> {
     let session_config = SessionConfig {
-        id: session.id.clone(),
+        id: session_id.to_string(),
         schedule_id: None,
         max_turns: None,
         retry_config: None,
     };
 
-    match agent.reply(user_message, session_config, None).await {
-        Ok(mut stream) => {
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(AgentEvent::Message(message)) => {
-                        for content in &message.content {
-                            match content {
-                                MessageContent::Text(text) => {
-                                    let mut sender = sender.lock().await;
-                                    let _ = sender
-                                        .send(Message::Text(
-                                            serde_json::to_string(&WebSocketMessage::Response {
-                                                content: text.text.clone(),
-                                                role: "assistant".to_string(),
-                                                timestamp: chrono::Utc::now().timestamp_millis(),
-                                            })
-                                            .unwrap()
-                                            .into(),
-                                        ))
-                                        .await;
-                                }
-                                MessageContent::ToolRequest(req) => {
-                                    let mut sender = sender.lock().await;
-                                    if let Ok(tool_call) = &req.tool_call {
-                                        let _ = sender
-                                            .send(Message::Text(
-                                                serde_json::to_string(
-                                                    &WebSocketMessage::ToolRequest {
-                                                        id: req.id.clone(),
-                                                        tool_name: tool_call.name.to_string(),
-                                                        arguments: Value::from(
-                                                            tool_call.arguments.clone(),
-                                                        ),
-                                                    },
-                                                )
-                                                .unwrap()
-                                                .into(),
-                                            ))
-                                            .await;
-                                    }
-                                }
-                                MessageContent::ToolResponse(_resp) => {}
-                                MessageContent::ToolConfirmationRequest(confirmation) => {
-                                    let mut sender = sender.lock().await;
-                                    let _ = sender
-                                        .send(Message::Text(
-                                            serde_json::to_string(
-                                                &WebSocketMessage::ToolConfirmation {
-                                                    id: confirmation.id.clone(),
-                                                    tool_name: confirmation
-                                                        .tool_name
-                                                        .to_string()
-                                                        .clone(),
-                                                    arguments: Value::from(
-                                                        confirmation.arguments.clone(),
-                                                    ),
-                                                    needs_confirmation: true,
-                                                },
-                                            )
-                                            .unwrap()
-                                            .into(),
-                                        ))
-                                        .await;
-
-                                    agent.handle_confirmation(
-                                        confirmation.id.clone(),
-                                        goose::permission::PermissionConfirmation {
-                                            principal_type: goose::permission::permission_confirmation::PrincipalType::Tool,
-                                            permission: goose::permission::Permission::AllowOnce,
-                                        }
-                                    ).await;
-                                }
-                                MessageContent::Thinking(thinking) => {
-                                    let mut sender = sender.lock().await;
-                                    let _ = sender
-                                        .send(Message::Text(
-                                            serde_json::to_string(&WebSocketMessage::Thinking {
-                                                message: thinking.thinking.clone(),
-                                            })
-                                            .unwrap()
-                                            .into(),
-                                        ))
-                                        .await;
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    Ok(AgentEvent::HistoryReplaced(_new_messages)) => {
-                        tracing::info!("History replaced, compacting happened in reply");
-                    }
-                    Ok(AgentEvent::McpNotification(_notification)) => {
-                        tracing::info!("Received MCP notification in web interface");
-                    }
-                    Ok(AgentEvent::ModelChange { model, mode }) => {
-                        tracing::info!("Model changed to {} in {} mode", model, mode);
-                    }
-                    Err(e) => {
-                        error!("Error in message stream: {}", e);
-                        let mut sender = sender.lock().await;
-                        let _ = sender
-                            .send(Message::Text(
-                                serde_json::to_string(&WebSocketMessage::Error {
-                                    message: format!("Error: {}", e),
-                                })
-                                .unwrap()
-                                .into(),
-                            ))
-                            .await;
-                        break;
+    let cancel_token = turn.cancel_token.clone();
+    match agent
+        .reply(user_message, session_config, Some(cancel_token.clone()))
+        .await
+    {
+        Ok(mut stream) => loop {
+            let result = tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                result = stream.next() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+            };
+            match result {
+                Ok(AgentEvent::Message(message)) => {
+                    for content in &message.content {
+                        publish_content(turn, content);
                     }
                 }
+                Ok(AgentEvent::HistoryReplaced(_new_messages)) => {
+                    tracing::info!("History replaced, compacting happened in reply");
+                }
+                Ok(AgentEvent::McpNotification(_notification)) => {
+                    tracing::info!("Received MCP notification in web interface");
+                }
+                Ok(AgentEvent::ModelChange { model, mode }) => {
+                    tracing::info!("Model changed to {} in {} mode", model, mode);
+                }
+                Err(e) => {
+                    error!("Error in message stream: {}", e);
+                    turn.publish(&WebSocketMessage::Error {
+                        message: format!("Error: {}", e),
+                    });
+                    break;
+                }
             }
-        }
+        },
         Err(e) => {
             error!("Error calling agent: {}", e);
-            let mut sender = sender.lock().await;
-            let _ = sender
-                .send(Message::Text(
-                    serde_json::to_string(&WebSocketMessage::Error {
-                        message: format!("Error: {}", e),
-                    })
-                    .unwrap()
-                    .into(),
-                ))
-                .await;
+            turn.publish(&WebSocketMessage::Error {
+                message: format!("Error: {}", e),
+            });
         }
     }
 
-    let mut sender = sender.lock().await;
-    let _ = sender
-        .send(Message::Text(
-            serde_json::to_string(&WebSocketMessage::Complete {
-                message: "Response complete".to_string(),
-            })
-            .unwrap()
-            .into(),
-        ))
-        .await;
+    if cancel_token.is_cancelled() {
+        turn.publish(&WebSocketMessage::Cancelled {
+            message: "Operation cancelled by user".to_string(),
+        });
+    } else {
+        turn.publish(&WebSocketMessage::Complete {
+            message: "Response complete".to_string(),
+        });
+    }
 
     Ok(())
 }
+
+fn publish_content(turn: &Turn, content: &MessageContent) {
+    match content {
+        MessageContent::Text(text) => {
+            turn.publish(&WebSocketMessage::Response {
+                content: text.text.clone(),
+                role: "assistant".to_string(),
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            });
+        }
+        MessageContent::ToolRequest(req) => {
+            if let Ok(tool_call) = &req.tool_call {
+                turn.publish(&WebSocketMessage::ToolRequest {
+                    id: req.id.clone(),
+                    tool_name: tool_call.name.to_string(),
+                    arguments: Value::from(tool_call.arguments.clone()),
+                });
+            }
+        }
+        MessageContent::ActionRequired(action) => match &action.data {
+            ActionRequiredData::ToolConfirmation {
+                id,
+                tool_name,
+                arguments,
+                prompt,
+            } => {
+                turn.publish(&WebSocketMessage::ToolConfirmation {
+                    id: id.clone(),
+                    tool_name: tool_name.clone(),
+                    arguments: Value::Object(arguments.clone()),
+                    needs_confirmation: true,
+                    prompt: prompt.clone(),
+                });
+            }
+            ActionRequiredData::Elicitation {
+                id,
+                message,
+                requested_schema,
+            } => {
+                turn.publish(&WebSocketMessage::Elicitation {
+                    id: id.clone(),
+                    message: message.clone(),
+                    requested_schema: requested_schema.clone(),
+                });
+            }
+            ActionRequiredData::ElicitationResponse { .. } => {}
+        },
+        MessageContent::Thinking(thinking) => {
+            turn.publish(&WebSocketMessage::Thinking {
+                message: thinking.thinking.clone(),
+            });
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goose::recipe::{RecipeParameterInputType, RecipeParameterRequirement};
+
+    #[test]
+    fn test_parse_client_messages() {
+        let message: WebSocketMessage = serde_json::from_str(
+            r#"{"type": "tool_confirmation_response", "session_id": "s1", "id": "t1", "action": "always_allow"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            message,
+            WebSocketMessage::ToolConfirmationResponse {
+                action: ConfirmationAction::AlwaysAllow,
+                ..
+            }
+        ));
+
+        let message: WebSocketMessage = serde_json::from_str(
+            r#"{"type": "start_recipe", "session_id": "s1", "recipe": "release-notes"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            message,
+            WebSocketMessage::StartRecipe { parameters, .. } if parameters.is_empty()
+        ));
+
+        let message: WebSocketMessage = serde_json::from_str(
+            r#"{"type": "elicitation_response", "session_id": "s1", "id": "e1"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            message,
+            WebSocketMessage::ElicitationResponse {
+                user_data: None,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_session_agents() {
+        let mut agents = SessionAgents::default();
+        let busy = agents.insert("busy", Arc::new(Agent::new()), false);
+        agents.insert("idle", Arc::new(Agent::new()), false);
+
+        // Only replaced when asked to
+        let other = Arc::new(Agent::new());
+        assert!(!Arc::ptr_eq(
+            &agents.insert("idle", other.clone(), false),
+            &other
+        ));
+        assert!(Arc::ptr_eq(
+            &agents.insert("idle", other.clone(), true),
+            &other
+        ));
+        drop(other);
+
+        agents.evict_idle(Duration::ZERO);
+        assert!(agents.get("idle").is_none());
+        assert!(Arc::ptr_eq(&agents.get("busy").unwrap(), &busy));
+
+        drop(busy);
+        agents.evict_idle(Duration::ZERO);
+        assert!(agents.get("busy").is_none());
+    }
+
+    #[test]
+    fn test_turn_keeps_events_until_the_last() {
+        let turn = Arc::new(Turn::new());
+        turn.publish(&WebSocketMessage::Thinking {
+            message: "one".to_string(),
+        });
+        turn.publish(&WebSocketMessage::Complete {
+            message: "done".to_string(),
+        });
+
+        let events = turn.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 2);
+        assert!(!events[0].last);
+        assert!(events[1].last);
+        assert!(events[1].text.contains("\"type\":\"complete\""));
+    }
+
+    #[test]
+    fn test_missing_parameters_keeps_definitions() {
+        let parameter = |key: &str| RecipeParameter {
+            key: key.to_string(),
+            input_type: RecipeParameterInputType::String,
+            requirement: RecipeParameterRequirement::Required,
+            description: format!("The {}", key),
+            default: None,
+            options: None,
+        };
+        let missing = missing_parameters(
+            vec![parameter("repo"), parameter("branch")],
+            &["branch".to_string()],
+        );
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].key, "branch");
+    }
+}
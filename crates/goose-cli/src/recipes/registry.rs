@@ -0,0 +1,780 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use goose::config::paths::Paths;
+use goose::recipe::read_recipe_file_content::{read_recipe_file, RecipeFile};
+use goose::recipe::template_recipe::parse_recipe_content;
+use goose::recipe::RECIPE_FILE_EXTENSIONS;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tar::Archive;
+
+const REGISTRY_DIR_NAME: &str = "recipe_registry";
+const LOCKFILE_NAME: &str = "recipes.lock.yaml";
+pub const DEFAULT_RECIPE_REF: &str = "HEAD";
+
+/// Where an installed recipe comes from: a git remote, a ref in it, and a path inside the tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeGitSource {
+    pub repo: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    #[serde(default)]
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedRecipe {
+    pub source: RecipeGitSource,
+    /// Commit the installed files were taken from
+    pub commit: String,
+    /// `Recipe.version` at `commit`
+    pub version: String,
+    /// Recipe file name relative to the install directory
+    pub file: String,
+    pub installed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecipeLockfile {
+    #[serde(default)]
+    pub recipes: BTreeMap<String, LockedRecipe>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpdateOptions {
+    /// Accept versions that are not semver-compatible with the installed one
+    pub allow_major: bool,
+    /// Reinstall whatever the ref points to, even if the version did not increase
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    Updated {
+        from: String,
+        to: String,
+    },
+    UpToDate {
+        version: String,
+    },
+    SkippedIncompatible {
+        installed: String,
+        available: String,
+    },
+}
+
+/// Recipes installed from git remotes, pinned in a lockfile.
+///
+/// Remotes are mirrored into a local cache so installed recipes can be loaded, and
+/// reinstalled from the cache, without network access.
+pub struct RecipeRegistry {
+    root: PathBuf,
+}
+
+impl Default for RecipeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecipeRegistry {
+    pub fn new() -> Self {
+        Self::with_root(Paths::in_data_dir(REGISTRY_DIR_NAME))
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.root.join(LOCKFILE_NAME)
+    }
+
+    fn cache_dir(&self, repo: &str) -> PathBuf {
+        self.root.join("cache").join(cache_key(repo))
+    }
+
+    fn install_dir(&self, name: &str) -> PathBuf {
+        self.root.join("installed").join(name)
+    }
+
+    pub fn load_lockfile(&self) -> Result<RecipeLockfile> {
+        let path = self.lockfile_path();
+        if !path.exists() {
+            return Ok(RecipeLockfile::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse recipe lockfile {}", path.display()))
+    }
+
+    fn save_lockfile(&self, lockfile: &RecipeLockfile) -> Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let path = self.lockfile_path();
+        let tmp_path = path.with_extension("yaml.tmp");
+        fs::write(&tmp_path, serde_yaml::to_string(lockfile)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn list_installed(&self) -> Result<Vec<(String, LockedRecipe)>> {
+        Ok(self.load_lockfile()?.recipes.into_iter().collect())
+    }
+
+    pub fn install(
+        &self,
+        name: Option<&str>,
+        source: RecipeGitSource,
+        force: bool,
+    ) -> Result<(String, LockedRecipe)> {
+        let source = RecipeGitSource {
+            path: normalize_tree_path(&source.path),
+            ..source
+        };
+        validate_source(&source)?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => default_recipe_name(&source),
+        };
+        validate_recipe_name(&name)?;
+
+        let mut lockfile = self.load_lockfile()?;
+        if !force {
+            if let Some(existing) = lockfile.recipes.get(&name) {
+                return Err(anyhow!(
+                    "Recipe '{}' is already installed from {} (version {}). Use update, or install with --force to replace it",
+                    name,
+                    existing.source.repo,
+                    existing.version
+                ));
+            }
+        }
+
+        let cache = self.sync_cache(&source.repo)?;
+        let commit = resolve_commit(&cache, &source.git_ref)?;
+        let locked = self.install_commit(&name, &cache, &source, &commit)?;
+
+        lockfile.recipes.insert(name.clone(), locked.clone());
+        self.save_lockfile(&lockfile)?;
+        Ok((name, locked))
+    }
+
+    /// Updates one installed recipe, or all of them when `name` is `None`.
+    ///
+    /// A recipe is only updated when the `version` found at its ref is newer than the locked
+    /// one and compatible with it under caret rules, unless `options` relax that.
+    pub fn update(
+        &self,
+        name: Option<&str>,
+        options: UpdateOptions,
+    ) -> Result<Vec<(String, UpdateOutcome)>> {
+        let mut lockfile = self.load_lockfile()?;
+        let names: Vec<String> = match name {
+            Some(name) => {
+                if !lockfile.recipes.contains_key(name) {
+                    return Err(anyhow!("Recipe '{}' is not installed", name));
+                }
+                vec![name.to_string()]
+            }
+            None => lockfile.recipes.keys().cloned().collect(),
+        };
+
+        // Recipes often share a repository, which only needs fetching once
+        let mut caches = HashMap::new();
+        for name in &names {
+            let repo = &lockfile.recipes[name].source.repo;
+            if !caches.contains_key(repo) {
+                caches.insert(repo.clone(), self.sync_cache(repo)?);
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        for name in names {
+            let locked = lockfile.recipes[&name].clone();
+            let cache = caches[&locked.source.repo].clone();
+            let commit = resolve_commit(&cache, &locked.source.git_ref)?;
+
+            if commit == locked.commit && !options.force {
+                outcomes.push((
+                    name,
+                    UpdateOutcome::UpToDate {
+                        version: locked.version,
+                    },
+                ));
+                continue;
+            }
+
+            let (_, _, available) = locate_recipe(&cache, &commit, &locked.source.path)?;
+            let installed_version = parse_version(&locked.version)?;
+            let available_version = parse_version(&available)?;
+
+            if !options.force {
+                if available_version <= installed_version {
+                    outcomes.push((
+                        name,
+                        UpdateOutcome::UpToDate {
+                            version: locked.version,
+                        },
+                    ));
+                    continue;
+                }
+                let compatible = VersionReq::parse(&format!("^{}", installed_version))?;
+                if !options.allow_major && !compatible.matches(&available_version) {
+                    outcomes.push((
+                        name,
+                        UpdateOutcome::SkippedIncompatible {
+                            installed: locked.version,
+                            available,
+                        },
+                    ));
+                    continue;
+                }
+            }
+
+            let updated = self.install_commit(&name, &cache, &locked.source, &commit)?;
+            outcomes.push((
+                name.clone(),
+                UpdateOutcome::Updated {
+                    from: locked.version,
+                    to: updated.version.clone(),
+                },
+            ));
+            lockfile.recipes.insert(name, updated);
+            self.save_lockfile(&lockfile)?;
+        }
+
+        Ok(outcomes)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let mut lockfile = self.load_lockfile()?;
+        if lockfile.recipes.remove(name).is_none() {
+            return Err(anyhow!("Recipe '{}' is not installed", name));
+        }
+        let install_dir = self.install_dir(name);
+        if install_dir.exists() {
+            fs::remove_dir_all(&install_dir)?;
+        }
+        self.save_lockfile(&lockfile)
+    }
+
+    pub fn load_installed_recipe_file(&self, name: &str) -> Result<RecipeFile> {
+        let lockfile = self.load_lockfile()?;
+        let locked = lockfile
+            .recipes
+            .get(name)
+            .ok_or_else(|| anyhow!("Recipe '{}' is not installed", name))?;
+        read_recipe_file(self.install_dir(name).join(&locked.file))
+    }
+
+    /// Mirrors `repo` into the cache, or refreshes an existing mirror. A failed refresh is not
+    /// fatal so that installs keep working offline from what was fetched before.
+    fn sync_cache(&self, repo: &str) -> Result<PathBuf> {
+        let cache = self.cache_dir(repo);
+        if cache.exists() {
+            if let Err(e) = run_git(&cache, &["fetch", "--prune", "origin"]) {
+                tracing::warn!(
+                    "Could not fetch {}, using cached copy: {}",
+                    repo,
+                    e.to_string().trim()
+                );
+            }
+            return Ok(cache);
+        }
+
+        let parent = cache
+            .parent()
+            .ok_or_else(|| anyhow!("Invalid cache directory {}", cache.display()))?;
+        fs::create_dir_all(parent)?;
+        run_git(
+            parent,
+            &[
+                "clone",
+                "--mirror",
+                "--quiet",
+                "--",
+                repo,
+                &cache.to_string_lossy(),
+            ],
+        )
+        .with_context(|| format!("Failed to clone recipe repository {}", repo))?;
+        Ok(cache)
+    }
+
+    fn install_commit(
+        &self,
+        name: &str,
+        cache: &Path,
+        source: &RecipeGitSource,
+        commit: &str,
+    ) -> Result<LockedRecipe> {
+        let (dir, file, version) = locate_recipe(cache, commit, &source.path)?;
+
+        let install_dir = self.install_dir(name);
+        let parent = install_dir
+            .parent()
+            .ok_or_else(|| anyhow!("Invalid install directory {}", install_dir.display()))?;
+        fs::create_dir_all(parent)?;
+        // Recipe names can't start with '.', so the staging directory never clashes with one
+        let staging_dir = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(parent)?;
+        extract_tree(cache, commit, &dir, staging_dir.path())?;
+
+        if install_dir.exists() {
+            fs::remove_dir_all(&install_dir)?;
+        }
+        fs::rename(staging_dir.path(), &install_dir)?;
+
+        Ok(LockedRecipe {
+            source: source.clone(),
+            commit: commit.to_string(),
+            version,
+            file,
+            installed_at: Utc::now(),
+        })
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn resolve_commit(cache: &Path, git_ref: &str) -> Result<String> {
+    run_git(
+        cache,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", git_ref)],
+    )
+    .map(|out| out.trim().to_string())
+    .map_err(|_| anyhow!("Could not resolve ref '{}' in recipe repository", git_ref))
+}
+
+fn object_type(cache: &Path, commit: &str, path: &str) -> Option<String> {
+    run_git(cache, &["cat-file", "-t", &format!("{}:{}", commit, path)])
+        .ok()
+        .map(|out| out.trim().to_string())
+}
+
+/// Finds the recipe at `path` in `commit`: either the file itself or a `recipe.<ext>` inside
+/// the directory. Returns the directory to install, the file name in it, and the recipe version.
+fn locate_recipe(cache: &Path, commit: &str, path: &str) -> Result<(String, String, String)> {
+    let (dir, file) = match object_type(cache, commit, path).as_deref() {
+        Some("blob") => match path.rsplit_once('/') {
+            Some((dir, file)) => (dir.to_string(), file.to_string()),
+            None => (String::new(), path.to_string()),
+        },
+        Some("tree") => {
+            let file = RECIPE_FILE_EXTENSIONS
+                .iter()
+                .map(|ext| format!("recipe.{}", ext))
+                .find(|candidate| {
+                    object_type(cache, commit, &join_tree_path(path, candidate)).as_deref()
+                        == Some("blob")
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No recipe file found in '{}' (looked for extensions: {:?})",
+                        display_tree_path(path),
+                        RECIPE_FILE_EXTENSIONS
+                    )
+                })?;
+            (path.to_string(), file)
+        }
+        _ => {
+            return Err(anyhow!(
+                "Path '{}' does not exist at commit {}",
+                display_tree_path(path),
+                short_commit(commit)
+            ))
+        }
+    };
+
+    let content = run_git(
+        cache,
+        &[
+            "show",
+            &format!("{}:{}", commit, join_tree_path(&dir, &file)),
+        ],
+    )?;
+    let (recipe, _) = parse_recipe_content(&content, None)?;
+    parse_version(&recipe.version)?;
+    Ok((dir, file, recipe.version))
+}
+
+fn extract_tree(cache: &Path, commit: &str, dir: &str, target: &Path) -> Result<()> {
+    let mut child = Command::new("git")
+        .args(["archive", "--format=tar", &format!("{}:{}", commit, dir)])
+        .current_dir(cache)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to capture stdout from git archive"))?;
+    Archive::new(stdout).unpack(target)?;
+    if !child.wait()?.success() {
+        return Err(anyhow!(
+            "git archive failed for '{}'",
+            display_tree_path(dir)
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a recipe version, accepting the short `1` and `1.2` forms recipes sometimes use.
+pub fn parse_version(version: &str) -> Result<Version> {
+    let version = version.trim().trim_start_matches('v');
+    let padded = match version.matches('.').count() {
+        0 => format!("{}.0.0", version),
+        1 => format!("{}.0", version),
+        _ => version.to_string(),
+    };
+    Version::parse(&padded).map_err(|e| anyhow!("Invalid recipe version '{}': {}", version, e))
+}
+
+fn normalize_tree_path(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn join_tree_path(dir: &str, file: &str) -> String {
+    if dir.is_empty() {
+        file.to_string()
+    } else {
+        format!("{}/{}", dir, file)
+    }
+}
+
+fn display_tree_path(path: &str) -> &str {
+    if path.is_empty() {
+        "."
+    } else {
+        path
+    }
+}
+
+pub fn short_commit(commit: &str) -> &str {
+    commit.get(..8).unwrap_or(commit)
+}
+
+fn default_recipe_name(source: &RecipeGitSource) -> String {
+    let last_path_segment = source.path.rsplit('/').next().unwrap_or_default();
+    let candidate = if last_path_segment.is_empty() {
+        source
+            .repo
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".git")
+    } else {
+        let stem = Path::new(last_path_segment)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(last_path_segment);
+        if stem == "recipe" {
+            source.path.rsplit('/').nth(1).unwrap_or(stem)
+        } else {
+            stem
+        }
+    };
+    candidate.to_string()
+}
+
+fn validate_recipe_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Invalid recipe name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        ))
+    }
+}
+
+/// Rejects sources git would read as options rather than a repository or ref.
+fn validate_source(source: &RecipeGitSource) -> Result<()> {
+    if source.repo.is_empty() || source.repo.starts_with('-') {
+        return Err(anyhow!("Invalid recipe repository '{}'", source.repo));
+    }
+    if source.git_ref.is_empty() || source.git_ref.starts_with('-') {
+        return Err(anyhow!("Invalid recipe ref '{}'", source.git_ref));
+    }
+    Ok(())
+}
+
+fn cache_key(repo: &str) -> String {
+    repo.trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .expect("failed to run git");
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    struct Remote {
+        work: PathBuf,
+        bare: PathBuf,
+    }
+
+    impl Remote {
+        fn new(tmp: &TempDir) -> Self {
+            let work = tmp.path().join("work");
+            let bare = tmp.path().join("remote.git");
+            fs::create_dir_all(&work).unwrap();
+            git(&work, &["init", "--quiet", "-b", "main"]);
+            let remote = Self { work, bare };
+            remote.commit_version("1.0.0");
+            git(
+                tmp.path(),
+                &["clone", "--bare", "--quiet", "work", "remote.git"],
+            );
+            remote
+        }
+
+        fn commit_version(&self, version: &str) {
+            let dir = self.work.join("recipes/hello");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("recipe.yaml"),
+                format!(
+                    "version: \"{}\"\ntitle: Hello\ndescription: Says hello\nprompt: say hello\n",
+                    version
+                ),
+            )
+            .unwrap();
+            git(&self.work, &["add", "-A"]);
+            git(&self.work, &["commit", "--quiet", "-m", version]);
+        }
+
+        fn publish(&self, version: &str) {
+            self.commit_version(version);
+            git(
+                &self.work,
+                &["push", "--quiet", &self.bare.to_string_lossy(), "main"],
+            );
+        }
+
+        fn source(&self) -> RecipeGitSource {
+            RecipeGitSource {
+                repo: self.bare.to_string_lossy().into_owned(),
+                git_ref: "main".to_string(),
+                path: "recipes/hello".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_install_pins_commit_and_version() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new(&tmp);
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+
+        let (name, locked) = registry.install(None, remote.source(), false).unwrap();
+        assert_eq!(name, "hello");
+        assert_eq!(locked.version, "1.0.0");
+        assert_eq!(locked.file, "recipe.yaml");
+        assert_eq!(locked.commit.len(), 40);
+
+        let lockfile = registry.load_lockfile().unwrap();
+        assert_eq!(lockfile.recipes["hello"].commit, locked.commit);
+
+        let recipe_file = registry.load_installed_recipe_file("hello").unwrap();
+        assert!(recipe_file.content.contains("title: Hello"));
+
+        let err = registry.install(None, remote.source(), false).unwrap_err();
+        assert!(err.to_string().contains("already installed"));
+    }
+
+    #[test]
+    fn test_update_respects_semver() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new(&tmp);
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+        registry.install(None, remote.source(), false).unwrap();
+
+        remote.publish("1.1.0");
+        let outcomes = registry.update(None, UpdateOptions::default()).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![(
+                "hello".to_string(),
+                UpdateOutcome::Updated {
+                    from: "1.0.0".to_string(),
+                    to: "1.1.0".to_string()
+                }
+            )]
+        );
+
+        remote.publish("2.0.0");
+        let outcomes = registry
+            .update(Some("hello"), UpdateOptions::default())
+            .unwrap();
+        assert_eq!(
+            outcomes[0].1,
+            UpdateOutcome::SkippedIncompatible {
+                installed: "1.1.0".to_string(),
+                available: "2.0.0".to_string()
+            }
+        );
+
+        let options = UpdateOptions {
+            allow_major: true,
+            force: false,
+        };
+        let outcomes = registry.update(Some("hello"), options).unwrap();
+        assert!(matches!(outcomes[0].1, UpdateOutcome::Updated { .. }));
+        assert_eq!(
+            registry.load_lockfile().unwrap().recipes["hello"].version,
+            "2.0.0"
+        );
+
+        remote.publish("1.5.0");
+        let outcomes = registry.update(None, options).unwrap();
+        assert_eq!(
+            outcomes[0].1,
+            UpdateOutcome::UpToDate {
+                version: "2.0.0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_works_offline_from_cache() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new(&tmp);
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+        registry.install(None, remote.source(), false).unwrap();
+
+        fs::rename(&remote.bare, tmp.path().join("gone.git")).unwrap();
+
+        let outcomes = registry.update(None, UpdateOptions::default()).unwrap();
+        assert!(matches!(outcomes[0].1, UpdateOutcome::UpToDate { .. }));
+        registry
+            .install(Some("hello-copy"), remote.source(), false)
+            .unwrap();
+        assert!(registry.load_installed_recipe_file("hello-copy").is_ok());
+    }
+
+    #[test]
+    fn test_remove() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new(&tmp);
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+        registry.install(None, remote.source(), false).unwrap();
+
+        registry.remove("hello").unwrap();
+        assert!(registry.list_installed().unwrap().is_empty());
+        assert!(registry.load_installed_recipe_file("hello").is_err());
+        assert!(registry.remove("hello").is_err());
+    }
+
+    #[test]
+    fn test_dotted_names_install_side_by_side() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new(&tmp);
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+
+        registry
+            .install(Some("hello.one"), remote.source(), false)
+            .unwrap();
+        registry
+            .install(Some("hello.two"), remote.source(), false)
+            .unwrap();
+        assert!(registry.load_installed_recipe_file("hello.one").is_ok());
+        assert!(registry.load_installed_recipe_file("hello.two").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_option_like_sources() {
+        let tmp = TempDir::new().unwrap();
+        let registry = RecipeRegistry::with_root(tmp.path().join("registry"));
+        let marker = tmp.path().join("pwned");
+        let source = RecipeGitSource {
+            repo: format!("--upload-pack=touch {}", marker.display()),
+            git_ref: DEFAULT_RECIPE_REF.to_string(),
+            path: String::new(),
+        };
+
+        let err = registry.install(Some("evil"), source, false).unwrap_err();
+        assert!(err.to_string().contains("Invalid recipe repository"));
+        assert!(!marker.exists());
+
+        let source = RecipeGitSource {
+            repo: "/tmp/repo".to_string(),
+            git_ref: "--output=/tmp/x".to_string(),
+            path: String::new(),
+        };
+        assert!(registry.install(Some("evil"), source, false).is_err());
+    }
+
+    #[test]
+    fn test_default_recipe_name() {
+        let source = |repo: &str, path: &str| RecipeGitSource {
+            repo: repo.to_string(),
+            git_ref: DEFAULT_RECIPE_REF.to_string(),
+            path: path.to_string(),
+        };
+        assert_eq!(
+            default_recipe_name(&source("https://github.com/org/recipes.git", "")),
+            "recipes"
+        );
+        assert_eq!(
+            default_recipe_name(&source("git@github.com:org/repo.git", "ops/triage.yaml")),
+            "triage"
+        );
+        assert_eq!(
+            default_recipe_name(&source("/tmp/repo", "ops/triage/recipe.yaml")),
+            "triage"
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1").unwrap(), Version::new(1, 0, 0));
+        assert_eq!(parse_version("1.2").unwrap(), Version::new(1, 2, 0));
+        assert_eq!(parse_version("v1.2.3").unwrap(), Version::new(1, 2, 3));
+        assert!(parse_version("latest").is_err());
+    }
+}
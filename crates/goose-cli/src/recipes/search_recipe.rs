@@ -1,21 +1,29 @@
 use anyhow::Result;
 use goose::config::Config;
 use goose::recipe::read_recipe_file_content::RecipeFile;
+use goose::recipe::template_recipe::parse_recipe_content;
 
 use super::github_recipe::{
     list_github_recipes, retrieve_recipe_from_github, RecipeInfo, RecipeSource,
     GOOSE_RECIPE_GITHUB_REPO_CONFIG_KEY,
 };
+use super::registry::{short_commit, RecipeRegistry};
 use goose::recipe::local_recipes::{list_local_recipes, load_local_recipe_file};
 
 pub fn load_recipe_file(recipe_name: &str) -> Result<RecipeFile> {
-    load_local_recipe_file(recipe_name).or_else(|e| {
-        if let Some(recipe_repo_full_name) = configured_github_recipe_repo() {
-            retrieve_recipe_from_github(recipe_name, &recipe_repo_full_name)
-        } else {
-            Err(e)
-        }
-    })
+    load_local_recipe_file(recipe_name)
+        .or_else(|e| {
+            RecipeRegistry::new()
+                .load_installed_recipe_file(recipe_name)
+                .map_err(|_| e)
+        })
+        .or_else(|e| {
+            if let Some(recipe_repo_full_name) = configured_github_recipe_repo() {
+                retrieve_recipe_from_github(recipe_name, &recipe_repo_full_name)
+            } else {
+                Err(e)
+            }
+        })
 }
 
 fn configured_github_recipe_repo() -> Option<String> {
@@ -49,6 +57,11 @@ pub fn list_available_recipes() -> Result<Vec<RecipeInfo>> {
         }));
     }
 
+    // Search recipes installed from git remotes
+    if let Ok(installed_recipes) = list_installed_recipes() {
+        recipes.extend(installed_recipes);
+    }
+
     // Search GitHub recipes if configured
     if let Some(repo) = configured_github_recipe_repo() {
         if let Ok(github_recipes) = list_github_recipes(&repo) {
@@ -58,3 +71,35 @@ pub fn list_available_recipes() -> Result<Vec<RecipeInfo>> {
 
     Ok(recipes)
 }
+
+/// Lists recipes installed with `goose recipe install`
+pub fn list_installed_recipes() -> Result<Vec<RecipeInfo>> {
+    let registry = RecipeRegistry::new();
+    let mut recipes = Vec::new();
+    for (name, locked) in registry.list_installed()? {
+        let (title, description) = match registry.load_installed_recipe_file(&name) {
+            Ok(recipe_file) => match parse_recipe_content(
+                &recipe_file.content,
+                Some(recipe_file.parent_dir.display().to_string()),
+            ) {
+                Ok((recipe, _)) => (Some(recipe.title), Some(recipe.description)),
+                Err(_) => (None, None),
+            },
+            Err(_) => (None, None),
+        };
+        recipes.push(RecipeInfo {
+            name,
+            source: RecipeSource::Installed,
+            path: format!(
+                "{}@{} ({}) v{}",
+                locked.source.repo,
+                locked.source.git_ref,
+                short_commit(&locked.commit),
+                locked.version
+            ),
+            title,
+            description,
+        });
+    }
+    Ok(recipes)
+}
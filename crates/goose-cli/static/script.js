@@ -152,6 +152,16 @@ function connectWebSocket() {
         
         // Check if this session exists and load history if it does
         loadSessionIfExists();
+
+        // Pick up a reply that kept running while we were disconnected
+        if (isProcessing) {
+            socket.send(JSON.stringify({
+                type: 'resume',
+                session_id: sessionId
+            }));
+        } else {
+            startRecipeFromUrl();
+        }
     };
     
     socket.onmessage = (event) => {
@@ -195,6 +205,18 @@ function handleServerMessage(data) {
         case 'tool_confirmation':
             handleToolConfirmation(data);
             break;
+        case 'elicitation':
+            handleElicitation(data);
+            break;
+        case 'recipe_parameters':
+            handleRecipeParameters(data);
+            break;
+        case 'recipe_started':
+            handleRecipeStarted(data);
+            break;
+        case 'extensions':
+            console.log('Enabled extensions:', data.enabled);
+            break;
         case 'thinking':
             handleThinking(data);
             break;
@@ -341,20 +363,220 @@ function handleToolResponse(data) {
 
 // Handle tool confirmations
 function handleToolConfirmation(data) {
+    removeThinkingIndicator();
+    // A resumed reply replays confirmations that may already be answered
+    if (document.getElementById(`confirm-${data.id}`)) {
+        return;
+    }
+
     const confirmDiv = document.createElement('div');
+    confirmDiv.id = `confirm-${data.id}`;
     confirmDiv.className = 'message tool-confirmation';
     confirmDiv.innerHTML = `
         <div class="tool-confirm-header">⚠️ Tool Confirmation Required</div>
         <div class="tool-confirm-content">
-            <strong>${data.tool_name}</strong> wants to execute with:
-            <pre><code>${JSON.stringify(data.arguments, null, 2)}</code></pre>
+            ${data.prompt ? `<p>${escapeHtml(data.prompt)}</p>` : ''}
+            <strong>${escapeHtml(data.tool_name)}</strong> wants to execute with:
+            <pre><code>${escapeHtml(JSON.stringify(data.arguments, null, 2))}</code></pre>
+        </div>
+        <div class="tool-confirm-actions">
+            <button type="button" data-action="allow_once">Allow</button>
+            <button type="button" data-action="always_allow">Always allow</button>
+            <button type="button" data-action="deny">Deny</button>
         </div>
-        <div class="tool-confirm-note">Auto-approved in web mode (UI coming soon)</div>
     `;
+    confirmDiv.querySelectorAll('button').forEach(button => {
+        button.addEventListener('click', () => {
+            socket.send(JSON.stringify({
+                type: 'tool_confirmation_response',
+                session_id: sessionId,
+                id: data.id,
+                action: button.dataset.action
+            }));
+            confirmDiv.querySelector('.tool-confirm-actions').innerHTML =
+                `<div class="tool-confirm-note">${escapeHtml(button.textContent)}</div>`;
+            addThinkingIndicator();
+        });
+    });
     messagesContainer.appendChild(confirmDiv);
     messagesContainer.scrollTop = messagesContainer.scrollHeight;
 }
 
+// Build a form with one input per field that calls onSubmit with the entered values
+function createFieldsForm(fields, submitLabel, onSubmit, onDecline) {
+    const form = document.createElement('form');
+    form.className = 'input-form';
+    fields.forEach(field => {
+        const label = document.createElement('label');
+        label.textContent = field.label;
+        let input;
+        if (field.options) {
+            input = document.createElement('select');
+            field.options.forEach(option => {
+                const optionElement = document.createElement('option');
+                optionElement.value = option;
+                optionElement.textContent = option;
+                input.appendChild(optionElement);
+            });
+        } else {
+            input = document.createElement('input');
+            input.type = field.type === 'boolean' ? 'checkbox' : 'text';
+        }
+        input.name = field.name;
+        if (field.value !== undefined && field.value !== null) {
+            input.value = field.value;
+        }
+        input.required = !!field.required && field.type !== 'boolean';
+        label.appendChild(input);
+        form.appendChild(label);
+    });
+
+    const actions = document.createElement('div');
+    actions.className = 'tool-confirm-actions';
+    const submit = document.createElement('button');
+    submit.type = 'submit';
+    submit.textContent = submitLabel;
+    actions.appendChild(submit);
+    if (onDecline) {
+        const decline = document.createElement('button');
+        decline.type = 'button';
+        decline.textContent = 'Decline';
+        decline.addEventListener('click', () => {
+            form.remove();
+            onDecline();
+        });
+        actions.appendChild(decline);
+    }
+    form.appendChild(actions);
+
+    form.addEventListener('submit', (e) => {
+        e.preventDefault();
+        const values = {};
+        fields.forEach(field => {
+            const input = form.elements[field.name];
+            if (field.type === 'boolean') {
+                values[field.name] = input.checked;
+            } else if (field.type === 'number' || field.type === 'integer') {
+                values[field.name] = input.value === '' ? null : Number(input.value);
+            } else {
+                values[field.name] = input.value;
+            }
+        });
+        form.remove();
+        onSubmit(values);
+    });
+    return form;
+}
+
+// Handle requests for structured input from an extension
+function handleElicitation(data) {
+    removeThinkingIndicator();
+    if (document.getElementById(`elicit-${data.id}`)) {
+        return;
+    }
+
+    const schema = data.requested_schema || {};
+    const required = schema.required || [];
+    const fields = Object.entries(schema.properties || {}).map(([name, property]) => ({
+        name,
+        label: property.title || property.description || name,
+        type: property.type,
+        options: property.enum,
+        value: property.default,
+        required: required.includes(name)
+    }));
+
+    const elicitDiv = document.createElement('div');
+    elicitDiv.id = `elicit-${data.id}`;
+    elicitDiv.className = 'message tool-confirmation';
+    elicitDiv.innerHTML = `<div class="tool-confirm-header">${escapeHtml(data.message)}</div>`;
+    elicitDiv.appendChild(createFieldsForm(fields, 'Submit', (values) => {
+        socket.send(JSON.stringify({
+            type: 'elicitation_response',
+            session_id: sessionId,
+            id: data.id,
+            user_data: values
+        }));
+        addThinkingIndicator();
+    }, () => {
+        socket.send(JSON.stringify({
+            type: 'elicitation_response',
+            session_id: sessionId,
+            id: data.id,
+            user_data: null
+        }));
+    }));
+    messagesContainer.appendChild(elicitDiv);
+    messagesContainer.scrollTop = messagesContainer.scrollHeight;
+}
+
+// Start the recipe named in the URL (?recipe=name), once per page load
+let recipeFromUrl = new URLSearchParams(window.location.search).get('recipe');
+
+function startRecipeFromUrl() {
+    if (!recipeFromUrl) {
+        return;
+    }
+    startRecipe(recipeFromUrl, {});
+    recipeFromUrl = null;
+}
+
+function startRecipe(recipe, parameters) {
+    socket.send(JSON.stringify({
+        type: 'start_recipe',
+        session_id: sessionId,
+        recipe,
+        parameters
+    }));
+}
+
+// Ask for the parameters a recipe still needs, then start it again with them
+function handleRecipeParameters(data) {
+    const fields = data.parameters.map(parameter => ({
+        name: parameter.key,
+        label: parameter.description || parameter.key,
+        type: parameter.input_type,
+        options: parameter.options,
+        value: parameter.default,
+        required: parameter.requirement === 'required'
+    }));
+
+    const formDiv = document.createElement('div');
+    formDiv.className = 'message tool-confirmation';
+    formDiv.innerHTML = `<div class="tool-confirm-header">📋 Recipe <strong>${escapeHtml(data.recipe)}</strong> needs some values</div>`;
+    formDiv.appendChild(createFieldsForm(fields, 'Start recipe', (values) => {
+        const parameters = {};
+        Object.entries(values).forEach(([key, value]) => {
+            parameters[key] = String(value);
+        });
+        formDiv.remove();
+        startRecipe(data.recipe, parameters);
+    }));
+    messagesContainer.appendChild(formDiv);
+    messagesContainer.scrollTop = messagesContainer.scrollHeight;
+}
+
+function handleRecipeStarted(data) {
+    const welcomeMessage = messagesContainer.querySelector('.welcome-message');
+    if (welcomeMessage) {
+        welcomeMessage.remove();
+    }
+    document.title = `goose chat - ${data.title}`;
+
+    const recipeDiv = document.createElement('div');
+    recipeDiv.className = 'message system-message';
+    recipeDiv.innerHTML = `<em>Recipe started: ${escapeHtml(data.title)}</em>`;
+    messagesContainer.appendChild(recipeDiv);
+
+    if (data.prompt) {
+        addMessage(data.prompt, 'user', Date.now());
+        addThinkingIndicator();
+        isProcessing = true;
+        sendButton.textContent = 'Cancel';
+        sendButton.classList.add('cancel-mode');
+    }
+}
+
 // Handle thinking messages
 function handleThinking(data) {
     // For now, just log thinking messages
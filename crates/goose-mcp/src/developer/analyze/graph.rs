@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 
@@ -7,7 +8,7 @@ use crate::developer::analyze::types::{AnalysisResult, CallChain};
 /// as callers in the call graph, since they don't have an actual caller function.
 const REFERENCE_CALLER: &str = "<reference>";
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CallGraph {
     callers: HashMap<String, Vec<(PathBuf, usize, String)>>,
     callees: HashMap<String, Vec<(PathBuf, usize, String)>>,
@@ -0,0 +1,439 @@
+use etcetera::{choose_app_strategy, AppStrategy};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use super::lock_or_recover;
+use crate::developer::analyze::graph::CallGraph;
+use crate::developer::analyze::types::AnalysisResult;
+
+/// Bumped whenever the stored format or the extraction output changes, so that
+/// indexes written by older versions are rebuilt rather than misread
+const INDEX_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const GRAPH_FILE: &str = "graph.json";
+const OBJECTS_DIR: &str = "objects";
+
+/// Size and modification time of a file, used to skip re-hashing unchanged files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: u128,
+}
+
+impl FileStamp {
+    pub fn from_metadata(metadata: &fs::Metadata) -> Option<Self> {
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(Self {
+            size: metadata.len(),
+            modified,
+        })
+    }
+
+    fn of(path: &Path) -> Option<Self> {
+        Self::from_metadata(&fs::metadata(path).ok()?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    stamp: FileStamp,
+    key: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    root: PathBuf,
+    files: HashMap<PathBuf, FileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredGraph {
+    version: u32,
+    fingerprint: String,
+    graph: CallGraph,
+}
+
+/// In-memory view of one workspace's index directory
+struct Workspace {
+    dir: PathBuf,
+    manifest: Manifest,
+    /// Paths whose entries changed since the manifest was last written
+    dirty: HashSet<PathBuf>,
+    graph: Option<(String, Arc<CallGraph>)>,
+}
+
+impl Workspace {
+    fn open(dir: PathBuf, root: &Path) -> Self {
+        let manifest = read_json::<Manifest>(&dir.join(MANIFEST_FILE))
+            .filter(|m| m.version == INDEX_VERSION && m.root == root)
+            .unwrap_or_else(|| Manifest {
+                version: INDEX_VERSION,
+                root: root.to_path_buf(),
+                files: HashMap::new(),
+            });
+
+        let mut workspace = Self {
+            dir,
+            manifest,
+            dirty: HashSet::new(),
+            graph: None,
+        };
+        workspace.prune();
+        workspace
+    }
+
+    /// Drop entries for files that no longer exist, and objects nothing refers to. Objects
+    /// newer than the manifest are kept, as other sessions may have written them for entries
+    /// they haven't flushed yet.
+    fn prune(&mut self) {
+        let before = self.manifest.files.len();
+        self.manifest.files.retain(|path, _| path.is_file());
+        if self.manifest.files.len() != before {
+            tracing::debug!(
+                "Pruned {} deleted files from analysis index",
+                before - self.manifest.files.len()
+            );
+            self.write_manifest();
+        }
+
+        let Some(manifest_written) = fs::metadata(self.dir.join(MANIFEST_FILE))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+        else {
+            return;
+        };
+        let live: HashSet<&str> = self
+            .manifest
+            .files
+            .values()
+            .map(|entry| entry.key.as_str())
+            .collect();
+        let Ok(objects) = fs::read_dir(self.dir.join(OBJECTS_DIR)) else {
+            return;
+        };
+        for object in objects.flatten() {
+            let name = object.file_name();
+            let Some(key) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            let written = object.metadata().and_then(|metadata| metadata.modified());
+            if !live.contains(key) && written.is_ok_and(|written| written < manifest_written) {
+                let _ = fs::remove_file(object.path());
+            }
+        }
+    }
+
+    /// The indexed entries of `files`, or None if any of them isn't indexed
+    fn entries(&self, files: &[PathBuf]) -> Option<Vec<FileEntry>> {
+        files
+            .iter()
+            .map(|file| self.manifest.files.get(file).cloned())
+            .collect()
+    }
+
+    fn record(&mut self, path: &Path, stamp: FileStamp, key: &str) {
+        self.manifest.files.insert(
+            path.to_path_buf(),
+            FileEntry {
+                stamp,
+                key: key.to_string(),
+            },
+        );
+        self.dirty.insert(path.to_path_buf());
+    }
+
+    /// Write the manifest, merging in entries other sessions have written since we
+    /// loaded it so that concurrent sessions don't discard each other's work
+    fn write_manifest(&mut self) {
+        if let Some(on_disk) = read_json::<Manifest>(&self.dir.join(MANIFEST_FILE))
+            .filter(|m| m.version == INDEX_VERSION && m.root == self.manifest.root)
+        {
+            for (path, entry) in on_disk.files {
+                if !self.dirty.contains(&path) && path.is_file() {
+                    self.manifest.files.insert(path, entry);
+                }
+            }
+        }
+
+        if write_json_atomic(&self.dir, MANIFEST_FILE, &self.manifest) {
+            self.dirty.clear();
+        }
+    }
+}
+
+fn object_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(OBJECTS_DIR).join(format!("{}.json", key))
+}
+
+/// Fingerprint of the indexed contents of `files`, given their `entries`, or None if any
+/// file has changed on disk since it was indexed
+fn fingerprint(files: &[PathBuf], entries: &[FileEntry]) -> Option<String> {
+    let mut hasher = Sha256::new();
+    for (file, entry) in files.iter().zip(entries) {
+        if FileStamp::of(file)? != entry.stamp {
+            return None;
+        }
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(entry.key.as_bytes());
+        hasher.update([0]);
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Persistent, content-addressed index of semantic analysis results and call graphs
+///
+/// Each workspace (the nearest ancestor containing `.git`) gets its own directory
+/// under the goose cache dir. Analysis results are stored by a hash of the file's
+/// language and content, so an entry stays valid until the content changes and is
+/// reused when a file is touched without being modified. A manifest maps paths to
+/// their size, mtime and content hash, which lets unchanged files be recognized
+/// with a single stat. The call graph of the last focused analysis is stored next
+/// to it and reused as long as none of its files changed.
+///
+/// Writes are atomic renames, so several sessions can share one index. Files are read and
+/// written outside the in-memory lock, so parallel analyses don't wait on each other's I/O.
+#[derive(Clone)]
+pub struct AnalysisIndex {
+    base_dir: Option<PathBuf>,
+    workspaces: Arc<Mutex<HashMap<PathBuf, Workspace>>>,
+}
+
+impl Default for AnalysisIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalysisIndex {
+    /// Open the index in the goose cache dir, unless disabled with
+    /// `GOOSE_ANALYZE_INDEX=off`
+    pub fn new() -> Self {
+        let disabled = std::env::var("GOOSE_ANALYZE_INDEX")
+            .map(|v| matches!(v.to_lowercase().as_str(), "off" | "false" | "0"))
+            .unwrap_or(false);
+
+        let base_dir = if disabled {
+            tracing::info!("Persistent analysis index disabled");
+            None
+        } else {
+            choose_app_strategy(crate::APP_STRATEGY.clone())
+                .map(|strategy| strategy.in_cache_dir("analyze_index"))
+                .ok()
+        };
+
+        Self::with_base_dir(base_dir)
+    }
+
+    /// Open an index rooted at `base_dir`, or a disabled index if None
+    pub fn with_base_dir(base_dir: Option<PathBuf>) -> Self {
+        Self {
+            base_dir,
+            workspaces: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.base_dir.is_some()
+    }
+
+    /// The workspace a path belongs to: the nearest ancestor containing `.git`,
+    /// falling back to the path itself (or its directory, for a file)
+    pub fn workspace_root(path: &Path) -> PathBuf {
+        let start = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        start
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .unwrap_or(start)
+            .to_path_buf()
+    }
+
+    /// Key under which the analysis of `content` is stored
+    pub fn content_key(language: &str, content: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(language.as_bytes());
+        hasher.update([0]);
+        hasher.update(content.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn with_workspace<T>(&self, root: &Path, f: impl FnOnce(&mut Workspace) -> T) -> Option<T> {
+        let base_dir = self.base_dir.as_ref()?;
+        {
+            let mut workspaces = lock_or_recover(&self.workspaces, |w| w.clear());
+            if let Some(workspace) = workspaces.get_mut(root) {
+                return Some(f(workspace));
+            }
+        }
+
+        // Opening reads the manifest and prunes, which is done outside the lock. Should
+        // another thread open the workspace meanwhile, the first one opened is kept.
+        let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+        let name: String = format!("{:x}", digest).chars().take(16).collect();
+        let dir = base_dir.join(name);
+        tracing::debug!("Opening analysis index for {:?} at {:?}", root, dir);
+        let opened = Workspace::open(dir, root);
+
+        let mut workspaces = lock_or_recover(&self.workspaces, |w| w.clear());
+        let workspace = workspaces.entry(root.to_path_buf()).or_insert(opened);
+        Some(f(workspace))
+    }
+
+    /// Look up a file whose size and mtime match what was indexed
+    pub fn get(&self, root: &Path, path: &Path, stamp: FileStamp) -> Option<AnalysisResult> {
+        let object = self.with_workspace(root, |ws| {
+            let entry = ws.manifest.files.get(path)?;
+            (entry.stamp == stamp).then(|| object_path(&ws.dir, &entry.key))
+        })??;
+        let result = read_json(&object);
+        if result.is_some() {
+            tracing::trace!("Analysis index hit for {:?}", path);
+        }
+        result
+    }
+
+    /// Look up a file by content, recording its new stamp on a hit so the next
+    /// lookup doesn't need to read it
+    pub fn get_by_key(
+        &self,
+        root: &Path,
+        path: &Path,
+        stamp: FileStamp,
+        key: &str,
+    ) -> Option<AnalysisResult> {
+        let dir = self.with_workspace(root, |ws| ws.dir.clone())?;
+        let result = read_json(&object_path(&dir, key))?;
+        tracing::trace!("Analysis index content hit for {:?}", path);
+        self.with_workspace(root, |ws| ws.record(path, stamp, key));
+        Some(result)
+    }
+
+    pub fn put(
+        &self,
+        root: &Path,
+        path: &Path,
+        stamp: FileStamp,
+        key: &str,
+        result: &AnalysisResult,
+    ) {
+        let Some(dir) = self.with_workspace(root, |ws| ws.dir.clone()) else {
+            return;
+        };
+        if object_path(&dir, key).exists()
+            || write_json_atomic(&dir.join(OBJECTS_DIR), &format!("{}.json", key), result)
+        {
+            self.with_workspace(root, |ws| ws.record(path, stamp, key));
+        }
+    }
+
+    /// The call graph for `files`, if one was built from exactly their current contents
+    pub fn get_graph(&self, root: &Path, files: &[PathBuf]) -> Option<Arc<CallGraph>> {
+        let (dir, entries, cached) = self.with_workspace(root, |ws| {
+            (ws.dir.clone(), ws.entries(files), ws.graph.clone())
+        })?;
+        let fingerprint = fingerprint(files, &entries?)?;
+        if let Some((cached, graph)) = cached {
+            if cached == fingerprint {
+                return Some(graph);
+            }
+        }
+
+        let stored = read_json::<StoredGraph>(&dir.join(GRAPH_FILE))
+            .filter(|g| g.version == INDEX_VERSION && g.fingerprint == fingerprint)?;
+        tracing::debug!("Loaded call graph for {} files from index", files.len());
+        let graph = Arc::new(stored.graph);
+        self.with_workspace(root, |ws| ws.graph = Some((fingerprint, graph.clone())));
+        Some(graph)
+    }
+
+    /// Store the call graph built from the current contents of `files`
+    pub fn put_graph(&self, root: &Path, files: &[PathBuf], graph: Arc<CallGraph>) {
+        let Some((dir, Some(entries))) =
+            self.with_workspace(root, |ws| (ws.dir.clone(), ws.entries(files)))
+        else {
+            return;
+        };
+        let Some(fingerprint) = fingerprint(files, &entries) else {
+            return;
+        };
+        let stored = StoredGraph {
+            version: INDEX_VERSION,
+            fingerprint: fingerprint.clone(),
+            graph: (*graph).clone(),
+        };
+        write_json_atomic(&dir, GRAPH_FILE, &stored);
+        self.with_workspace(root, |ws| ws.graph = Some((fingerprint, graph)));
+    }
+
+    /// Persist manifest changes made since the last flush
+    pub fn flush(&self) {
+        if self.base_dir.is_none() {
+            return;
+        }
+        let mut workspaces = lock_or_recover(&self.workspaces, |w| w.clear());
+        for workspace in workspaces.values_mut() {
+            if !workspace.dirty.is_empty() {
+                workspace.write_manifest();
+            }
+        }
+    }
+
+    /// Number of files indexed for a workspace
+    pub fn len(&self, root: &Path) -> usize {
+        self.with_workspace(root, |ws| ws.manifest.files.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self, root: &Path) -> bool {
+        self.len(root) == 0
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let content = fs::read(path).ok()?;
+    serde_json::from_slice(&content)
+        .map_err(|e| tracing::debug!("Ignoring unreadable index file {:?}: {}", path, e))
+        .ok()
+}
+
+/// Write `value` to `dir/name` through a temporary file and rename, so readers in
+/// other sessions never see a partial write
+fn write_json_atomic<T: Serialize>(dir: &Path, name: &str, value: &T) -> bool {
+    let result = (|| -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        serde_json::to_writer(&mut file, value)?;
+        file.flush()?;
+        file.persist(dir.join(name)).map_err(|e| e.error)?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to write analysis index file {:?}: {}",
+                dir.join(name),
+                e
+            );
+            false
+        }
+    }
+}
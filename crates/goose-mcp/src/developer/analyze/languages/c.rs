@@ -0,0 +1,105 @@
+/// Tree-sitter query for extracting C code elements.
+///
+/// This query captures:
+/// - Function definitions, including ones returning pointers
+/// - Struct, union and enum definitions with a body
+/// - Typedef names
+/// - Macro constants
+/// - Include directives
+pub const ELEMENT_QUERY: &str = r#"
+    ; Function definitions
+    (function_definition
+      declarator: (function_declarator declarator: (identifier) @func))
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator declarator: (identifier) @func)))
+
+    ; Struct, union and enum definitions
+    (struct_specifier name: (type_identifier) @struct body: (field_declaration_list))
+    (union_specifier name: (type_identifier) @struct body: (field_declaration_list))
+    (enum_specifier name: (type_identifier) @struct body: (enumerator_list))
+
+    ; Typedefs
+    (type_definition declarator: (type_identifier) @struct)
+
+    ; Macro constants
+    (preproc_def name: (identifier) @const)
+
+    ; Includes
+    (preproc_include path: (_) @import)
+"#;
+
+/// Tree-sitter query for extracting C function calls and identifier references
+pub const CALL_QUERY: &str = r#"
+    ; Function calls
+    (call_expression
+      function: (identifier) @function.call)
+
+    ; Calls through struct members (function pointers)
+    (call_expression
+      function: (field_expression
+        field: (field_identifier) @method.call))
+
+    ; Identifier references in various expression contexts
+    (argument_list (identifier) @identifier.reference)
+    (binary_expression left: (identifier) @identifier.reference)
+    (binary_expression right: (identifier) @identifier.reference)
+    (return_statement (identifier) @identifier.reference)
+    (assignment_expression right: (identifier) @identifier.reference)
+"#;
+
+/// Tree-sitter query for extracting C type references
+pub const REFERENCE_QUERY: &str = r#"
+    ; Compound literals - (Point){1, 2}
+    (compound_literal_expression
+      type: (type_descriptor type: (type_identifier) @struct.literal))
+
+    ; Struct field types - typedef names and struct tags
+    (field_declaration
+      type: (type_identifier) @field.type)
+    (field_declaration
+      type: (struct_specifier name: (type_identifier) @field.type))
+
+    ; Variable types
+    (declaration
+      type: (type_identifier) @var.type)
+    (declaration
+      type: (struct_specifier name: (type_identifier) @var.type))
+
+    ; Parameter types
+    (parameter_declaration
+      type: (type_identifier) @param.type)
+    (parameter_declaration
+      type: (struct_specifier name: (type_identifier) @param.type))
+"#;
+
+/// Extract the function name from a C or C++ function definition
+///
+/// The name of a `function_definition` is nested inside its declarator chain
+/// (pointers, references, the function declarator itself), so it is not a direct
+/// child of the definition. For C++ out-of-line methods like `Widget::draw` this
+/// returns just the method name.
+pub fn extract_function_name_for_kind(
+    node: &tree_sitter::Node,
+    source: &str,
+    kind: &str,
+) -> Option<String> {
+    if kind != "function_definition" {
+        return None;
+    }
+
+    let mut current = node.child_by_field_name("declarator")?;
+    loop {
+        match current.kind() {
+            "identifier" | "field_identifier" | "destructor_name" | "operator_name" => {
+                return source.get(current.byte_range()).map(|s| s.to_string());
+            }
+            "qualified_identifier" => {
+                current = current.child_by_field_name("name")?;
+            }
+            _ => {
+                current = current.child_by_field_name("declarator")?;
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/// Tree-sitter query for extracting C++ code elements.
+///
+/// This query captures:
+/// - Free function and method definitions, inline or out-of-line (Type::method)
+/// - Class, struct, union and enum definitions with a body
+/// - Include directives
+pub const ELEMENT_QUERY: &str = r#"
+    ; Functions and inline methods
+    (function_definition
+      declarator: (function_declarator
+        declarator: [(identifier) (field_identifier) (destructor_name)] @func))
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: [(identifier) (field_identifier)] @func)))
+    (function_definition
+      declarator: (reference_declarator
+        (function_declarator
+          declarator: [(identifier) (field_identifier)] @func)))
+
+    ; Out-of-line methods, constructors and destructors (Type::method)
+    (function_definition
+      declarator: (function_declarator
+        declarator: (qualified_identifier
+          name: [(identifier) (destructor_name)] @func)))
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (qualified_identifier name: (identifier) @func))))
+    (function_definition
+      declarator: (reference_declarator
+        (function_declarator
+          declarator: (qualified_identifier name: (identifier) @func))))
+
+    ; Type definitions
+    (class_specifier name: (type_identifier) @class body: (field_declaration_list))
+    (struct_specifier name: (type_identifier) @struct body: (field_declaration_list))
+    (union_specifier name: (type_identifier) @struct body: (field_declaration_list))
+    (enum_specifier name: (type_identifier) @struct body: (enumerator_list))
+
+    ; Includes
+    (preproc_include path: (_) @import)
+"#;
+
+/// Tree-sitter query for extracting C++ function calls and identifier references
+pub const CALL_QUERY: &str = r#"
+    ; Function calls
+    (call_expression
+      function: (identifier) @function.call)
+
+    ; Template function calls - max_of<int>(...)
+    (call_expression
+      function: (template_function
+        name: (identifier) @function.call))
+
+    ; Method calls - obj.method() and ptr->method()
+    (call_expression
+      function: (field_expression
+        field: (field_identifier) @method.call))
+
+    ; Qualified calls - ns::func() and Type::static_method()
+    (call_expression
+      function: (qualified_identifier) @scoped.call)
+
+    ; Constructor calls with new
+    (new_expression
+      type: (type_identifier) @constructor.call)
+
+    ; Identifier references in various expression contexts
+    (argument_list (identifier) @identifier.reference)
+    (binary_expression left: (identifier) @identifier.reference)
+    (binary_expression right: (identifier) @identifier.reference)
+    (return_statement (identifier) @identifier.reference)
+    (assignment_expression right: (identifier) @identifier.reference)
+"#;
+
+/// Tree-sitter query for extracting C++ type references and usage patterns
+pub const REFERENCE_QUERY: &str = r#"
+    ; Out-of-line method definitions - capture the class scope of Type::method
+    (function_definition
+      declarator: (function_declarator
+        declarator: (qualified_identifier
+          scope: (namespace_identifier) @method.receiver)))
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (qualified_identifier
+            scope: (namespace_identifier) @method.receiver))))
+    (function_definition
+      declarator: (reference_declarator
+        (function_declarator
+          declarator: (qualified_identifier
+            scope: (namespace_identifier) @method.receiver))))
+
+    ; Methods defined inside a class body - capture the method name
+    (field_declaration_list
+      (function_definition
+        declarator: (function_declarator
+          declarator: (field_identifier) @method.receiver)))
+
+    ; Object creation with new
+    (new_expression
+      type: (type_identifier) @struct.literal)
+
+    ; Field types
+    (field_declaration
+      type: (type_identifier) @field.type)
+    (field_declaration
+      type: (qualified_identifier name: (type_identifier) @field.type))
+
+    ; Variable types
+    (declaration
+      type: (type_identifier) @var.type)
+    (declaration
+      type: (qualified_identifier name: (type_identifier) @var.type))
+
+    ; Parameter types
+    (parameter_declaration
+      type: (type_identifier) @param.type)
+    (parameter_declaration
+      type: (qualified_identifier name: (type_identifier) @param.type))
+"#;
+
+/// Find the method name for a method receiver node in C++
+///
+/// The receiver_node is either the class scope of an out-of-line definition
+/// (`Widget` in `Widget::draw`) or the name of a method defined inside a class
+/// body. Either way the method is the enclosing function_definition.
+pub fn find_method_for_receiver(
+    receiver_node: &tree_sitter::Node,
+    source: &str,
+    _ast_recursion_limit: Option<usize>,
+) -> Option<String> {
+    let mut current = *receiver_node;
+    while let Some(parent) = current.parent() {
+        if parent.kind() == "function_definition" {
+            return super::c::extract_function_name_for_kind(&parent, source, parent.kind());
+        }
+        current = parent;
+    }
+    None
+}
+
+/// Find the receiver type for a method receiver node in C++
+///
+/// Out-of-line definitions name their class in the scope, which is the receiver
+/// node itself. Methods defined inside a class body belong to the nearest
+/// enclosing class or struct.
+pub fn find_receiver_type(node: &tree_sitter::Node, source: &str) -> Option<String> {
+    if node.kind() == "namespace_identifier" {
+        return source.get(node.byte_range()).map(|s| s.to_string());
+    }
+
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(parent.kind(), "class_specifier" | "struct_specifier") {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+        current = parent;
+    }
+    None
+}
@@ -0,0 +1,124 @@
+/// Tree-sitter query for extracting C# code elements.
+///
+/// This query captures:
+/// - Method, constructor and local function declarations
+/// - Class, interface, record, struct and enum declarations
+/// - Using directives (the imported namespace)
+pub const ELEMENT_QUERY: &str = r#"
+    ; Methods and constructors
+    (method_declaration name: (identifier) @func)
+    (constructor_declaration name: (identifier) @func)
+    (local_function_statement name: (identifier) @func)
+
+    ; Type declarations
+    (class_declaration name: (identifier) @class)
+    (interface_declaration name: (identifier) @class)
+    (record_declaration name: (identifier) @class)
+    (struct_declaration name: (identifier) @struct)
+    (enum_declaration name: (identifier) @struct)
+
+    ; Using directives
+    (using_directive [(identifier) (qualified_name)] @import)
+"#;
+
+/// Tree-sitter query for extracting C# function calls and identifier references
+pub const CALL_QUERY: &str = r#"
+    ; Function calls
+    (invocation_expression
+      function: (identifier) @function.call)
+
+    ; Generic function calls - Create<Widget>()
+    (invocation_expression
+      function: (generic_name (identifier) @function.call))
+
+    ; Method calls
+    (invocation_expression
+      function: (member_access_expression
+        name: (identifier) @method.call))
+
+    ; Constructor calls
+    (object_creation_expression
+      type: (identifier) @constructor.call)
+
+    ; Identifier references in various expression contexts
+    (argument (identifier) @identifier.reference)
+    (binary_expression left: (identifier) @identifier.reference)
+    (binary_expression right: (identifier) @identifier.reference)
+    (return_statement (identifier) @identifier.reference)
+    (assignment_expression right: (identifier) @identifier.reference)
+"#;
+
+/// Tree-sitter query for extracting C# type references and usage patterns
+pub const REFERENCE_QUERY: &str = r#"
+    ; Methods declared in a type body - capture the method name
+    (declaration_list
+      (method_declaration name: (identifier) @method.receiver))
+
+    ; Object creation
+    (object_creation_expression
+      type: (identifier) @struct.literal)
+
+    ; Field and property types
+    (field_declaration
+      (variable_declaration type: (identifier) @field.type))
+    (property_declaration
+      type: (identifier) @field.type)
+    (property_declaration
+      type: (generic_name (type_argument_list (identifier) @field.type)))
+
+    ; Local variable types
+    (local_declaration_statement
+      (variable_declaration type: (identifier) @var.type))
+
+    ; Parameter types
+    (parameter
+      type: (identifier) @param.type)
+"#;
+
+/// Extract function name for C# declarations
+///
+/// A method's return type can be an identifier that precedes its name, so the
+/// name is read from the `name` field rather than the first identifier child.
+pub fn extract_function_name_for_kind(
+    node: &tree_sitter::Node,
+    source: &str,
+    _kind: &str,
+) -> Option<String> {
+    let name = node.child_by_field_name("name")?;
+    source.get(name.byte_range()).map(|s| s.to_string())
+}
+
+/// Find the method name for a method receiver node in C#
+///
+/// The receiver_node is the name identifier of the method declaration itself.
+pub fn find_method_for_receiver(
+    receiver_node: &tree_sitter::Node,
+    source: &str,
+    _ast_recursion_limit: Option<usize>,
+) -> Option<String> {
+    source
+        .get(receiver_node.byte_range())
+        .map(|s| s.to_string())
+}
+
+/// Find the receiver type for a method name node in C#
+///
+/// Walks up to the nearest enclosing class, struct, record or interface and
+/// returns its name.
+pub fn find_receiver_type(node: &tree_sitter::Node, source: &str) -> Option<String> {
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(
+            parent.kind(),
+            "class_declaration"
+                | "struct_declaration"
+                | "record_declaration"
+                | "interface_declaration"
+        ) {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+        current = parent;
+    }
+    None
+}
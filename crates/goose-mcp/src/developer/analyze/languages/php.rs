@@ -0,0 +1,109 @@
+/// Tree-sitter query for extracting PHP code elements.
+///
+/// This query captures:
+/// - Function and method declarations
+/// - Class, interface, trait and enum declarations
+/// - Class constants
+/// - Use imports (the imported name, or each name of a grouped use)
+pub const ELEMENT_QUERY: &str = r#"
+    ; Functions and methods
+    (function_definition name: (name) @func)
+    (method_declaration name: (name) @func)
+
+    ; Type declarations
+    (class_declaration name: (name) @class)
+    (interface_declaration name: (name) @class)
+    (trait_declaration name: (name) @class)
+    (enum_declaration name: (name) @struct)
+
+    ; Class constants
+    (const_element (name) @const)
+
+    ; Use imports, including each name of a grouped use
+    (namespace_use_clause [(name) (qualified_name)] @import)
+    (namespace_use_group_clause (namespace_name) @import)
+"#;
+
+/// Tree-sitter query for extracting PHP function calls
+pub const CALL_QUERY: &str = r#"
+    ; Function calls
+    (function_call_expression
+      function: [(name) (qualified_name)] @function.call)
+
+    ; Method calls - $obj->method() and $obj?->method()
+    (member_call_expression
+      name: (name) @method.call)
+    (nullsafe_member_call_expression
+      name: (name) @method.call)
+
+    ; Static calls - Type::method(), self::method()
+    (scoped_call_expression
+      name: (name) @method.call)
+
+    ; Constructor calls
+    (object_creation_expression
+      [(name) (qualified_name)] @constructor.call)
+"#;
+
+/// Tree-sitter query for extracting PHP type references and usage patterns
+pub const REFERENCE_QUERY: &str = r#"
+    ; Methods declared in a class, trait, interface or enum body - capture the method name
+    (declaration_list
+      (method_declaration name: (name) @method.receiver))
+    (enum_declaration_list
+      (method_declaration name: (name) @method.receiver))
+
+    ; Object creation
+    (object_creation_expression
+      (name) @struct.literal)
+
+    ; Typed properties, including promoted constructor parameters
+    (property_declaration
+      type: (named_type (name) @field.type))
+    (property_declaration
+      type: (union_type (named_type (name) @field.type)))
+    (property_promotion_parameter
+      type: (named_type (name) @field.type))
+    (property_promotion_parameter
+      type: (union_type (named_type (name) @field.type)))
+
+    ; Parameter types
+    (simple_parameter
+      type: (named_type (name) @param.type))
+    (simple_parameter
+      type: (union_type (named_type (name) @param.type)))
+"#;
+
+/// Find the method name for a method receiver node in PHP
+///
+/// The receiver_node is the name of the method declaration itself.
+pub fn find_method_for_receiver(
+    receiver_node: &tree_sitter::Node,
+    source: &str,
+    _ast_recursion_limit: Option<usize>,
+) -> Option<String> {
+    source
+        .get(receiver_node.byte_range())
+        .map(|s| s.to_string())
+}
+
+/// Find the receiver type for a method name node in PHP
+///
+/// Walks up to the enclosing class, trait, interface or enum and returns its name.
+pub fn find_receiver_type(node: &tree_sitter::Node, source: &str) -> Option<String> {
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(
+            parent.kind(),
+            "class_declaration"
+                | "trait_declaration"
+                | "interface_declaration"
+                | "enum_declaration"
+        ) {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+        current = parent;
+    }
+    None
+}
@@ -0,0 +1,100 @@
+/// Tree-sitter query for extracting Scala code elements.
+///
+/// This query captures:
+/// - Method definitions and abstract declarations
+/// - Class, object, trait and enum definitions
+/// - Import declarations
+pub const ELEMENT_QUERY: &str = r#"
+    ; Methods
+    (function_definition name: (identifier) @func)
+    (function_declaration name: (identifier) @func)
+
+    ; Type definitions
+    (class_definition name: (identifier) @class)
+    (object_definition name: (identifier) @class)
+    (trait_definition name: (identifier) @class)
+    (enum_definition name: (identifier) @struct)
+
+    ; Imports
+    (import_declaration) @import
+"#;
+
+/// Tree-sitter query for extracting Scala function calls
+pub const CALL_QUERY: &str = r#"
+    ; Function calls, including case class apply - Widget(1)
+    (call_expression
+      function: (identifier) @function.call)
+
+    ; Method calls - obj.method()
+    (call_expression
+      function: (field_expression
+        field: (identifier) @method.call))
+
+    ; Generic calls - create[Widget]()
+    (call_expression
+      function: (generic_function
+        function: (identifier) @function.call))
+
+    ; Constructor calls with new
+    (instance_expression
+      (type_identifier) @constructor.call)
+
+    ; Identifier references in arguments
+    (arguments (identifier) @identifier.reference)
+"#;
+
+/// Tree-sitter query for extracting Scala type references and usage patterns
+pub const REFERENCE_QUERY: &str = r#"
+    ; Methods defined in a class, object or trait body - capture the method name
+    (template_body
+      (function_definition name: (identifier) @method.receiver))
+
+    ; Object creation with new
+    (instance_expression
+      (type_identifier) @struct.literal)
+
+    ; Class parameter types (fields)
+    (class_parameter
+      type: (type_identifier) @field.type)
+
+    ; Value and variable types
+    (val_definition
+      type: (type_identifier) @var.type)
+    (var_definition
+      type: (type_identifier) @var.type)
+
+    ; Method parameter types
+    (parameter
+      type: (type_identifier) @param.type)
+"#;
+
+/// Find the method name for a method receiver node in Scala
+///
+/// The receiver_node is the name of the method definition itself.
+pub fn find_method_for_receiver(
+    receiver_node: &tree_sitter::Node,
+    source: &str,
+    _ast_recursion_limit: Option<usize>,
+) -> Option<String> {
+    source
+        .get(receiver_node.byte_range())
+        .map(|s| s.to_string())
+}
+
+/// Find the receiver type for a method name node in Scala
+///
+/// Walks up to the enclosing class, object, trait or enum and returns its name.
+pub fn find_receiver_type(node: &tree_sitter::Node, source: &str) -> Option<String> {
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(
+            parent.kind(),
+            "class_definition" | "object_definition" | "trait_definition" | "enum_definition"
+        ) {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+        current = parent;
+    }
+    None
+}
@@ -0,0 +1,145 @@
+/// Tree-sitter query for extracting TypeScript/TSX code elements.
+///
+/// This query captures:
+/// - Function declarations, class methods and arrow functions bound to a name
+/// - Class, abstract class and interface declarations
+/// - Type aliases and enums
+/// - Import statements
+pub const ELEMENT_QUERY: &str = r#"
+    ; Functions and methods
+    (function_declaration name: (identifier) @func)
+    (generator_function_declaration name: (identifier) @func)
+    (method_definition name: (property_identifier) @func)
+    (variable_declarator name: (identifier) @func value: (arrow_function))
+
+    ; Classes and interfaces
+    (class_declaration name: (type_identifier) @class)
+    (abstract_class_declaration name: (type_identifier) @class)
+    (interface_declaration name: (type_identifier) @class)
+
+    ; Type aliases and enums
+    (type_alias_declaration name: (type_identifier) @struct)
+    (enum_declaration name: (identifier) @struct)
+
+    ; Imports
+    (import_statement) @import
+"#;
+
+/// Call patterns shared by the TypeScript and TSX grammars
+macro_rules! call_query {
+    () => {
+        r#"
+    ; Function calls
+    (call_expression
+      function: (identifier) @function.call)
+
+    ; Method calls
+    (call_expression
+      function: (member_expression
+        property: (property_identifier) @method.call))
+
+    ; Constructor calls
+    (new_expression
+      constructor: (identifier) @constructor.call)
+
+    ; Identifier references in arguments and returns
+    (arguments (identifier) @identifier.reference)
+    (return_statement (identifier) @identifier.reference)
+"#
+    };
+}
+
+/// Tree-sitter query for extracting TypeScript function calls
+pub const CALL_QUERY: &str = call_query!();
+
+/// Tree-sitter query for extracting TSX function calls, including components
+/// used as JSX elements
+pub const TSX_CALL_QUERY: &str = concat!(
+    call_query!(),
+    r#"
+    ; Components rendered as JSX elements
+    (jsx_opening_element
+      name: (identifier) @function.call (#match? @function.call "^[A-Z]"))
+    (jsx_self_closing_element
+      name: (identifier) @function.call (#match? @function.call "^[A-Z]"))
+"#
+);
+
+/// Tree-sitter query for extracting TypeScript type references and usage patterns
+pub const REFERENCE_QUERY: &str = r#"
+    ; Methods in a class body - capture the method name
+    (class_body
+      (method_definition name: (property_identifier) @method.receiver))
+
+    ; Object creation
+    (new_expression
+      constructor: (identifier) @struct.literal)
+
+    ; Class field and interface property types
+    (public_field_definition
+      type: (type_annotation (type_identifier) @field.type))
+    (public_field_definition
+      type: (type_annotation (array_type (type_identifier) @field.type)))
+    (property_signature
+      type: (type_annotation (type_identifier) @field.type))
+
+    ; Variable types
+    (variable_declarator
+      type: (type_annotation (type_identifier) @var.type))
+
+    ; Parameter types
+    (required_parameter
+      type: (type_annotation (type_identifier) @param.type))
+    (optional_parameter
+      type: (type_annotation (type_identifier) @param.type))
+"#;
+
+/// Extract function name for TypeScript-specific node kinds
+///
+/// Arrow functions have no name of their own, so they take the name of the
+/// variable they are assigned to.
+pub fn extract_function_name_for_kind(
+    node: &tree_sitter::Node,
+    source: &str,
+    kind: &str,
+) -> Option<String> {
+    if kind == "arrow_function" {
+        let parent = node.parent()?;
+        if parent.kind() == "variable_declarator" {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+    }
+    None
+}
+
+/// Find the method name for a method receiver node in TypeScript
+///
+/// The receiver_node is the name of the method definition itself.
+pub fn find_method_for_receiver(
+    receiver_node: &tree_sitter::Node,
+    source: &str,
+    _ast_recursion_limit: Option<usize>,
+) -> Option<String> {
+    source
+        .get(receiver_node.byte_range())
+        .map(|s| s.to_string())
+}
+
+/// Find the receiver type for a method name node in TypeScript
+///
+/// Walks up to the enclosing class declaration and returns its name.
+pub fn find_receiver_type(node: &tree_sitter::Node, source: &str) -> Option<String> {
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(
+            parent.kind(),
+            "class_declaration" | "abstract_class_declaration" | "class"
+        ) {
+            let name = parent.child_by_field_name("name")?;
+            return source.get(name.byte_range()).map(|s| s.to_string());
+        }
+        current = parent;
+    }
+    None
+}
@@ -1,8 +1,10 @@
 pub mod cache;
 pub mod formatter;
 pub mod graph;
+pub mod index;
 pub mod languages;
 pub mod parser;
+pub mod syntax;
 pub mod traversal;
 pub mod types;
 
@@ -12,12 +14,14 @@ mod tests;
 use ignore::gitignore::Gitignore;
 use rmcp::model::{CallToolResult, ErrorCode, ErrorData};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::developer::lang;
 
 use self::cache::AnalysisCache;
 use self::formatter::Formatter;
 use self::graph::CallGraph;
+use self::index::{AnalysisIndex, FileStamp};
 use self::parser::{ElementExtractor, ParserManager};
 use self::traversal::FileTraverser;
 use self::types::{AnalysisMode, AnalysisResult, AnalyzeParams, FocusedAnalysisData};
@@ -44,6 +48,7 @@ where
 pub struct CodeAnalyzer {
     parser_manager: ParserManager,
     cache: AnalysisCache,
+    index: AnalysisIndex,
 }
 
 impl Default for CodeAnalyzer {
@@ -55,9 +60,15 @@ impl Default for CodeAnalyzer {
 impl CodeAnalyzer {
     pub fn new() -> Self {
         tracing::debug!("Initializing CodeAnalyzer");
+        Self::with_index(AnalysisIndex::with_base_dir(None))
+    }
+
+    /// Create an analyzer backed by a persistent index shared across sessions
+    pub fn with_index(index: AnalysisIndex) -> Self {
         Self {
             parser_manager: ParserManager::new(),
             cache: AnalysisCache::new(100),
+            index,
         }
     }
 
@@ -77,25 +88,32 @@ impl CodeAnalyzer {
 
         tracing::debug!("Using analysis mode: {:?}", mode);
 
-        let mut output = match mode {
-            AnalysisMode::Focused => self.analyze_focused(&path, &params, &traverser)?,
+        // Results depend on the recursion limit, so only default runs use the index
+        let root = (self.index.is_enabled() && params.ast_recursion_limit.is_none())
+            .then(|| AnalysisIndex::workspace_root(&path));
+        let root = root.as_deref();
+
+        let output = match mode {
+            AnalysisMode::Focused => self.analyze_focused(&path, &params, &traverser, root),
             AnalysisMode::Semantic => {
                 if path.is_file() {
-                    let result = self.analyze_file(&path, &mode, &params)?;
-                    Formatter::format_analysis_result(&path, &result, &mode)
+                    self.analyze_file(&path, &mode, &params, root)
+                        .map(|result| Formatter::format_analysis_result(&path, &result, &mode))
                 } else {
-                    self.analyze_directory(&path, &params, &traverser, &mode)?
+                    self.analyze_directory(&path, &params, &traverser, &mode, root)
                 }
             }
             AnalysisMode::Structure => {
                 if path.is_file() {
-                    let result = self.analyze_file(&path, &mode, &params)?;
-                    Formatter::format_analysis_result(&path, &result, &mode)
+                    self.analyze_file(&path, &mode, &params, root)
+                        .map(|result| Formatter::format_analysis_result(&path, &result, &mode))
                 } else {
-                    self.analyze_directory(&path, &params, &traverser, &mode)?
+                    self.analyze_directory(&path, &params, &traverser, &mode, root)
                 }
             }
         };
+        self.index.flush();
+        let mut output = output?;
 
         // If focus is specified with non-focused mode, filter results
         if let Some(focus) = &params.focus {
@@ -150,11 +168,31 @@ impl CodeAnalyzer {
         }
     }
 
+    /// Whether files at this path are parsed, judging by their extension
+    fn is_parsed(path: &Path) -> bool {
+        languages::get_language_info(lang::get_language_identifier(path))
+            .map(|info| !info.element_query.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Reduce a semantic result to what a structure analysis reports
+    fn for_mode(mut result: AnalysisResult, mode: &AnalysisMode) -> AnalysisResult {
+        if *mode == AnalysisMode::Structure {
+            result.functions.clear();
+            result.classes.clear();
+            result.imports.clear();
+            result.calls.clear();
+            result.references.clear();
+        }
+        result
+    }
+
     fn analyze_file(
         &self,
         path: &Path,
         mode: &AnalysisMode,
         params: &AnalyzeParams,
+        root: Option<&Path>,
     ) -> Result<AnalysisResult, ErrorData> {
         tracing::debug!("Analyzing file {:?} in {:?} mode", path, mode);
 
@@ -184,6 +222,17 @@ impl CodeAnalyzer {
             return Ok(cached);
         }
 
+        // The index holds semantic results, which also answer structure queries
+        let indexed = root.zip(FileStamp::from_metadata(&metadata));
+        if let Some((root, stamp)) = indexed {
+            if let Some(result) = self.index.get(root, path, stamp) {
+                let result = Self::for_mode(result, mode);
+                self.cache
+                    .put(path.to_path_buf(), modified, mode, result.clone());
+                return Ok(result);
+            }
+        }
+
         let content = match std::fs::read_to_string(path) {
             Ok(content) => content,
             Err(e) => {
@@ -202,15 +251,23 @@ impl CodeAnalyzer {
 
         // Check if we support this language for parsing
         // A language is supported if it has query definitions
-        let language_supported = languages::get_language_info(language)
-            .map(|info| !info.element_query.is_empty())
-            .unwrap_or(false);
-
-        if !language_supported {
+        if !Self::is_parsed(path) {
             tracing::trace!("Language {} not supported for parsing", language);
             return Ok(AnalysisResult::empty(line_count));
         }
 
+        // A touched but unmodified file, or a copy of one, is still in the index
+        let indexed = indexed
+            .map(|(root, stamp)| (root, stamp, AnalysisIndex::content_key(language, &content)));
+        if let Some((root, stamp, key)) = &indexed {
+            if let Some(result) = self.index.get_by_key(root, path, *stamp, key) {
+                let result = Self::for_mode(result, mode);
+                self.cache
+                    .put(path.to_path_buf(), modified, mode, result.clone());
+                return Ok(result);
+            }
+        }
+
         let tree = self.parser_manager.parse(&content, language)?;
 
         let depth = mode.as_str();
@@ -224,6 +281,12 @@ impl CodeAnalyzer {
 
         result.line_count = line_count;
 
+        if *mode == AnalysisMode::Semantic {
+            if let Some((root, stamp, key)) = &indexed {
+                self.index.put(root, path, *stamp, key, &result);
+            }
+        }
+
         self.cache
             .put(path.to_path_buf(), modified, mode, result.clone());
 
@@ -236,13 +299,14 @@ impl CodeAnalyzer {
         params: &AnalyzeParams,
         traverser: &FileTraverser<'_>,
         mode: &AnalysisMode,
+        root: Option<&Path>,
     ) -> Result<String, ErrorData> {
         tracing::debug!("Analyzing directory {:?} in {:?} mode", path, mode);
 
         let mode = *mode;
 
         let results = traverser.collect_directory_results(path, params.max_depth, |file_path| {
-            self.analyze_file(file_path, &mode, params)
+            self.analyze_file(file_path, &mode, params, root)
         })?;
 
         Ok(Formatter::format_directory_structure(
@@ -257,6 +321,7 @@ impl CodeAnalyzer {
         path: &Path,
         params: &AnalyzeParams,
         traverser: &FileTraverser<'_>,
+        root: Option<&Path>,
     ) -> Result<String, ErrorData> {
         let focus_symbol = params.focus.as_ref().ok_or_else(|| {
             ErrorData::new(
@@ -280,17 +345,35 @@ impl CodeAnalyzer {
             files_to_analyze.len()
         );
 
-        use rayon::prelude::*;
-        let all_results: Result<Vec<_>, _> = files_to_analyze
-            .par_iter()
-            .map(|file_path| {
-                self.analyze_file(file_path, &AnalysisMode::Semantic, params)
-                    .map(|result| (file_path.clone(), result))
-            })
+        // Only parsed files contribute to the graph, so they alone decide whether
+        // the indexed graph is still current
+        let mut parsed_files: Vec<PathBuf> = files_to_analyze
+            .iter()
+            .filter(|file_path| Self::is_parsed(file_path))
+            .cloned()
             .collect();
-        let all_results = all_results?;
-
-        let graph = CallGraph::build_from_results(&all_results);
+        parsed_files.sort();
+
+        let graph = match root.and_then(|root| self.index.get_graph(root, &parsed_files)) {
+            Some(graph) => graph,
+            None => {
+                use rayon::prelude::*;
+                let all_results: Result<Vec<_>, _> = files_to_analyze
+                    .par_iter()
+                    .map(|file_path| {
+                        self.analyze_file(file_path, &AnalysisMode::Semantic, params, root)
+                            .map(|result| (file_path.clone(), result))
+                    })
+                    .collect();
+                let all_results = all_results?;
+
+                let graph = Arc::new(CallGraph::build_from_results(&all_results));
+                if let Some(root) = root {
+                    self.index.put_graph(root, &parsed_files, graph.clone());
+                }
+                graph
+            }
+        };
 
         let incoming_chains = if params.follow_depth > 0 {
             graph.find_incoming_chains(focus_symbol, params.follow_depth)
@@ -35,12 +35,19 @@ impl ParserManager {
         let language_config: Language = match language {
             "python" => tree_sitter_python::language(),
             "rust" => tree_sitter_rust::language(),
-            "javascript" | "typescript" => tree_sitter_javascript::language(),
+            "javascript" => tree_sitter_javascript::language(),
+            "typescript" => tree_sitter_typescript::language_typescript(),
+            "tsx" => tree_sitter_typescript::language_tsx(),
             "go" => tree_sitter_go::language(),
             "java" => tree_sitter_java::language(),
             "kotlin" => tree_sitter_kotlin::language(),
             "swift" => devgen_tree_sitter_swift::language(),
             "ruby" => tree_sitter_ruby::language(),
+            "c" => tree_sitter_c::language(),
+            "cpp" => tree_sitter_cpp::language(),
+            "csharp" => tree_sitter_c_sharp::language(),
+            "php" => tree_sitter_php::language_php(),
+            "scala" => tree_sitter_scala::language(),
             _ => {
                 tracing::warn!("Unsupported language: {}", language);
                 return Err(ErrorData::new(
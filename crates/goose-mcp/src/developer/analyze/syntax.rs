@@ -0,0 +1,66 @@
+use once_cell::sync::Lazy;
+use std::path::Path;
+use tree_sitter::Node;
+
+use super::languages::get_language_info;
+use super::parser::ParserManager;
+use crate::developer::lang;
+
+/// Longest stretch of offending source quoted in a syntax error
+const MAX_QUOTED_CHARS: usize = 40;
+
+static PARSERS: Lazy<ParserManager> = Lazy::new(ParserManager::new);
+
+/// Describe the first syntax error in `content`, parsed as the language of `path`.
+///
+/// Returns `None` when the content parses cleanly, and also when there is no parser
+/// for the language, so callers can treat it as "nothing known to be wrong".
+pub fn check_syntax(path: &Path, content: &str) -> Option<String> {
+    let language = lang::get_language_identifier(path);
+    get_language_info(language)?;
+
+    let tree = PARSERS.parse(content, language).ok()?;
+    let root = tree.root_node();
+    if !root.has_error() {
+        return None;
+    }
+
+    let node = first_error(root)?;
+    let position = node.start_position();
+    let problem = if node.is_missing() {
+        format!("missing `{}`", node.kind())
+    } else {
+        let text = content
+            .get(node.byte_range())
+            .and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()))
+            .unwrap_or_default();
+        if text.is_empty() {
+            "syntax error".to_string()
+        } else {
+            format!(
+                "unexpected `{}`",
+                text.chars().take(MAX_QUOTED_CHARS).collect::<String>()
+            )
+        }
+    };
+
+    Some(format!(
+        "line {}, column {}: {}",
+        position.row + 1,
+        position.column + 1,
+        problem
+    ))
+}
+
+fn first_error(node: Node) -> Option<Node> {
+    if node.is_error() || node.is_missing() {
+        return Some(node);
+    }
+
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.children(&mut cursor).collect();
+    children
+        .into_iter()
+        .filter(|child| child.has_error())
+        .find_map(first_error)
+}
@@ -0,0 +1,81 @@
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+
+fn parse_and_extract(code: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, "c").unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, "c", "semantic", None).unwrap()
+}
+
+#[test]
+fn test_c_elements_and_calls() {
+    let code = r#"
+#include <stdio.h>
+#include "widget.h"
+
+#define MAX_SIZE 10
+
+typedef struct {
+    int x;
+    int y;
+} Point;
+
+struct Widget {
+    Point origin;
+    struct Widget *next;
+};
+
+static int add(int a, int b) {
+    return a + b;
+}
+
+char *widget_name(struct Widget *w) {
+    return "widget";
+}
+
+int main(void) {
+    int sum = add(1, MAX_SIZE);
+    printf("%d\n", sum);
+    return 0;
+}
+"#;
+
+    let result = parse_and_extract(code);
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("add"));
+    assert!(function_names.contains("widget_name"));
+    assert!(function_names.contains("main"));
+    assert!(function_names.contains("MAX_SIZE"));
+    assert!(result.main_line.is_some());
+
+    let type_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(type_names.contains("Point"));
+    assert!(type_names.contains("Widget"));
+
+    assert_eq!(result.import_count, 2);
+    assert!(result.imports.contains(&"<stdio.h>".to_string()));
+
+    let add_call = result
+        .calls
+        .iter()
+        .find(|c| c.callee_name == "add")
+        .expect("Expected a call to add");
+    assert_eq!(add_call.caller_name.as_deref(), Some("main"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "printf"));
+
+    let field_types: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| r.ref_type == ReferenceType::FieldType)
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(field_types.contains("Point"));
+    assert!(field_types.contains("Widget"));
+
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::ParameterType && r.symbol == "Widget"));
+}
@@ -0,0 +1,133 @@
+use crate::developer::analyze::graph::CallGraph;
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+fn parse_and_extract(code: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, "cpp").unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, "cpp", "semantic", None).unwrap()
+}
+
+fn build_test_graph(files: Vec<(&str, &str)>) -> CallGraph {
+    let manager = ParserManager::new();
+    let results: Vec<_> = files
+        .iter()
+        .map(|(path, code)| {
+            let tree = manager.parse(code, "cpp").unwrap();
+            let result =
+                ElementExtractor::extract_with_depth(&tree, code, "cpp", "semantic", None).unwrap();
+            (PathBuf::from(*path), result)
+        })
+        .collect();
+    CallGraph::build_from_results(&results)
+}
+
+const WIDGET_CODE: &str = r#"
+#include <vector>
+#include "config.h"
+
+namespace app {
+
+class Widget {
+public:
+    Widget(int size);
+    void draw() const;
+    int size() const { return size_; }
+private:
+    int size_;
+    Config config_;
+};
+
+struct Point {
+    int x;
+    int y;
+};
+
+Widget::Widget(int size) : size_(size) {}
+
+void Widget::draw() const {
+    render(size_);
+    config_.apply();
+    logging::info("draw");
+}
+
+Widget *make_widget(const Config &config) {
+    Widget *widget = new Widget(3);
+    widget->draw();
+    return widget;
+}
+
+}  // namespace app
+
+int main() {
+    app::Widget widget(2);
+    widget.draw();
+    return 0;
+}
+"#;
+
+#[test]
+fn test_cpp_elements_and_calls() {
+    let result = parse_and_extract(WIDGET_CODE);
+
+    let class_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(class_names.contains("Widget"));
+    assert!(class_names.contains("Point"));
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("size"));
+    assert!(function_names.contains("Widget"));
+    assert!(function_names.contains("draw"));
+    assert!(function_names.contains("make_widget"));
+    assert!(function_names.contains("main"));
+
+    assert_eq!(result.import_count, 2);
+
+    // Calls inside an out-of-line method are attributed to the method name
+    let render_call = result
+        .calls
+        .iter()
+        .find(|c| c.callee_name == "render")
+        .expect("Expected a call to render");
+    assert_eq!(render_call.caller_name.as_deref(), Some("draw"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "apply"));
+    assert!(result
+        .calls
+        .iter()
+        .any(|c| c.callee_name == "logging::info"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "Widget"));
+}
+
+#[test]
+fn test_cpp_method_receiver_resolution() {
+    let result = parse_and_extract(WIDGET_CODE);
+
+    let widget_methods: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| {
+            r.ref_type == ReferenceType::MethodDefinition
+                && r.associated_type.as_deref() == Some("Widget")
+        })
+        .map(|r| r.symbol.as_str())
+        .collect();
+    // Out-of-line definitions (Widget::draw) and inline ones (size) both resolve to Widget
+    assert!(widget_methods.contains("draw"));
+    assert!(widget_methods.contains("size"));
+    assert!(widget_methods.contains("Widget"));
+
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::TypeInstantiation && r.symbol == "Widget"));
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::FieldType && r.symbol == "Config"));
+
+    let graph = build_test_graph(vec![("widget.cpp", WIDGET_CODE)]);
+    let outgoing = graph.find_outgoing_chains("Widget", 1);
+    assert!(!outgoing.is_empty(), "Expected to find methods on Widget");
+}
@@ -0,0 +1,130 @@
+use crate::developer::analyze::graph::CallGraph;
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+fn parse_and_extract(code: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, "csharp").unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, "csharp", "semantic", None).unwrap()
+}
+
+const SERVICE_CODE: &str = r#"
+using System;
+using System.Collections.Generic;
+
+namespace App.Services
+{
+    public interface IRenderer
+    {
+        void Render(Widget widget);
+    }
+
+    public class Widget : IRenderer
+    {
+        private Config config;
+        public List<Item> Items { get; set; }
+
+        public Widget(Config config)
+        {
+            this.config = config;
+        }
+
+        public void Render(Widget widget)
+        {
+            Console.WriteLine(widget.Name());
+            Helper(config);
+        }
+
+        private static Widget Helper(Config c)
+        {
+            return new Widget(c);
+        }
+    }
+
+    public struct Point
+    {
+        public int X;
+    }
+
+    public static class Program
+    {
+        public static void Main(string[] args)
+        {
+            Widget widget = new Widget(new Config());
+            widget.Render(widget);
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_csharp_elements_and_calls() {
+    let result = parse_and_extract(SERVICE_CODE);
+
+    let class_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(class_names.contains("IRenderer"));
+    assert!(class_names.contains("Widget"));
+    assert!(class_names.contains("Point"));
+    assert!(class_names.contains("Program"));
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("Render"));
+    assert!(function_names.contains("Helper"));
+    assert!(function_names.contains("Main"));
+
+    assert_eq!(result.import_count, 2);
+    assert!(result
+        .imports
+        .contains(&"System.Collections.Generic".to_string()));
+
+    // The caller is the method name even when the return type is a class
+    let creation = result
+        .calls
+        .iter()
+        .find(|c| c.callee_name == "Widget" && c.caller_name.as_deref() == Some("Helper"));
+    assert!(creation.is_some(), "Expected new Widget() inside Helper");
+    assert!(result.calls.iter().any(|c| c.callee_name == "WriteLine"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "Helper"));
+}
+
+#[test]
+fn test_csharp_method_receiver_resolution() {
+    let result = parse_and_extract(SERVICE_CODE);
+
+    let widget_methods: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| {
+            r.ref_type == ReferenceType::MethodDefinition
+                && r.associated_type.as_deref() == Some("Widget")
+        })
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(widget_methods.contains("Render"));
+    assert!(widget_methods.contains("Helper"));
+
+    assert!(result.references.iter().any(|r| {
+        r.ref_type == ReferenceType::MethodDefinition
+            && r.symbol == "Main"
+            && r.associated_type.as_deref() == Some("Program")
+    }));
+
+    let field_types: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| r.ref_type == ReferenceType::FieldType)
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(field_types.contains("Config"));
+    assert!(field_types.contains("Item"));
+
+    let manager = ParserManager::new();
+    let tree = manager.parse(SERVICE_CODE, "csharp").unwrap();
+    let result =
+        ElementExtractor::extract_with_depth(&tree, SERVICE_CODE, "csharp", "semantic", None)
+            .unwrap();
+    let graph = CallGraph::build_from_results(&[(PathBuf::from("Widget.cs"), result)]);
+    assert!(!graph.find_outgoing_chains("Widget", 1).is_empty());
+}
@@ -0,0 +1,244 @@
+// Tests for the persistent analysis index
+
+use crate::developer::analyze::graph::CallGraph;
+use crate::developer::analyze::index::{AnalysisIndex, FileStamp};
+use crate::developer::analyze::tests::fixtures::create_test_gitignore;
+use crate::developer::analyze::types::{AnalysisResult, AnalyzeParams, FunctionInfo};
+use crate::developer::analyze::CodeAnalyzer;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn result_with_function(name: &str) -> AnalysisResult {
+    AnalysisResult {
+        functions: vec![FunctionInfo {
+            name: name.to_string(),
+            line: 1,
+            params: vec![],
+        }],
+        function_count: 1,
+        ..AnalysisResult::empty(3)
+    }
+}
+
+fn stamp(path: &Path) -> FileStamp {
+    FileStamp::from_metadata(&fs::metadata(path).unwrap()).unwrap()
+}
+
+fn focus_params(path: &Path, focus: &str) -> AnalyzeParams {
+    AnalyzeParams {
+        path: path.to_string_lossy().to_string(),
+        focus: Some(focus.to_string()),
+        follow_depth: 2,
+        max_depth: 3,
+        ast_recursion_limit: None,
+        force: false,
+    }
+}
+
+fn output_text(analyzer: &CodeAnalyzer, path: &Path, focus: &str) -> String {
+    let ignore = create_test_gitignore();
+    let result = analyzer
+        .analyze(focus_params(path, focus), path.to_path_buf(), &ignore)
+        .unwrap();
+    result.content[0].as_text().unwrap().text.clone()
+}
+
+#[test]
+fn test_index_lookup_by_stamp_and_content() {
+    let cache = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let root = workspace.path();
+    let file = root.join("lib.py");
+    fs::write(&file, "def helper():\n    pass\n").unwrap();
+
+    let index = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    let key = AnalysisIndex::content_key("python", "def helper():\n    pass\n");
+    assert!(index.get(root, &file, stamp(&file)).is_none());
+
+    index.put(
+        root,
+        &file,
+        stamp(&file),
+        &key,
+        &result_with_function("helper"),
+    );
+    let hit = index.get(root, &file, stamp(&file)).unwrap();
+    assert_eq!(hit.functions[0].name, "helper");
+
+    // A changed stamp misses, but the same content is found by its key
+    let touched = FileStamp {
+        modified: stamp(&file).modified + 1,
+        ..stamp(&file)
+    };
+    assert!(index.get(root, &file, touched).is_none());
+    assert!(index.get_by_key(root, &file, touched, &key).is_some());
+    assert!(index.get(root, &file, touched).is_some());
+
+    let other = AnalysisIndex::content_key("python", "def other():\n    pass\n");
+    assert!(index.get_by_key(root, &file, touched, &other).is_none());
+    assert_ne!(
+        key,
+        AnalysisIndex::content_key("ruby", "def helper():\n    pass\n")
+    );
+}
+
+#[test]
+fn test_index_shared_across_instances() {
+    let cache = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let root = workspace.path();
+    let kept = root.join("kept.py");
+    let deleted = root.join("deleted.py");
+    fs::write(&kept, "def kept(): pass").unwrap();
+    fs::write(&deleted, "def deleted(): pass").unwrap();
+
+    let first = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    first.put(
+        root,
+        &kept,
+        stamp(&kept),
+        "a",
+        &result_with_function("kept"),
+    );
+    first.put(
+        root,
+        &deleted,
+        stamp(&deleted),
+        "b",
+        &result_with_function("deleted"),
+    );
+
+    let files = vec![deleted.clone(), kept.clone()];
+    let graph = Arc::new(CallGraph::build_from_results(&[(
+        kept.clone(),
+        result_with_function("kept"),
+    )]));
+    first.put_graph(root, &files, graph);
+    first.flush();
+
+    // A second session sees the results, but not entries or graphs for deleted files
+    fs::remove_file(&deleted).unwrap();
+    let second = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    assert_eq!(second.len(root), 1);
+    assert!(second.get(root, &kept, stamp(&kept)).is_some());
+    assert!(second.get_graph(root, &files).is_none());
+
+    let third = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    assert!(third.get_graph(root, &files[1..]).is_none());
+}
+
+#[test]
+fn test_graph_reused_until_file_changes() {
+    let cache = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let root = workspace.path();
+    let main = root.join("main.py");
+    let lib = root.join("lib.py");
+    fs::write(&main, "def main():\n    helper()\n").unwrap();
+    fs::write(&lib, "def helper():\n    pass\n").unwrap();
+
+    let index = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    let analyzer = CodeAnalyzer::with_index(index.clone());
+    let output = output_text(&analyzer, root, "helper");
+    assert!(output.contains("main"));
+
+    let mut files = vec![main.clone(), lib.clone()];
+    files.sort();
+    assert_eq!(index.len(root), 2);
+    assert!(index.get_graph(root, &files).is_some());
+
+    // A new session answers from the index without parsing anything
+    let fresh = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    assert!(fresh.get_graph(root, &files).is_some());
+
+    // Editing a file invalidates the graph and the new caller shows up
+    fs::write(
+        &lib,
+        "def helper():\n    pass\n\ndef extra():\n    helper()\n",
+    )
+    .unwrap();
+    assert!(index.get_graph(root, &files).is_none());
+    let output = output_text(&CodeAnalyzer::with_index(fresh.clone()), root, "helper");
+    assert!(output.contains("extra"));
+    assert!(fresh.get_graph(root, &files).is_some());
+}
+
+#[test]
+fn test_disabled_index_stores_nothing() {
+    let workspace = TempDir::new().unwrap();
+    let root = workspace.path();
+    let file = root.join("lib.py");
+    fs::write(&file, "def helper(): pass").unwrap();
+
+    let index = AnalysisIndex::with_base_dir(None);
+    assert!(!index.is_enabled());
+    index.put(
+        root,
+        &file,
+        stamp(&file),
+        "a",
+        &result_with_function("helper"),
+    );
+    assert!(index.get(root, &file, stamp(&file)).is_none());
+    assert!(index.is_empty(root));
+
+    let analyzer = CodeAnalyzer::with_index(index);
+    assert!(output_text(&analyzer, root, "helper").contains("helper"));
+}
+
+#[test]
+fn test_workspace_root_finds_git_dir() {
+    let workspace = TempDir::new().unwrap();
+    let nested = workspace.path().join("src/module");
+    fs::create_dir_all(&nested).unwrap();
+    let file = nested.join("lib.py");
+    fs::write(&file, "").unwrap();
+
+    assert_eq!(AnalysisIndex::workspace_root(&file), nested);
+
+    fs::create_dir(workspace.path().join(".git")).unwrap();
+    assert_eq!(AnalysisIndex::workspace_root(&file), workspace.path());
+    assert_eq!(AnalysisIndex::workspace_root(&nested), workspace.path());
+}
+
+#[test]
+fn test_opening_keeps_objects_other_sessions_have_not_flushed() {
+    let cache = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let root = workspace.path();
+    let first = root.join("first.py");
+    let second = root.join("second.py");
+    fs::write(&first, "def first():\n    pass\n").unwrap();
+    fs::write(&second, "def second():\n    pass\n").unwrap();
+    let first_key = AnalysisIndex::content_key("python", "def first():\n    pass\n");
+    let second_key = AnalysisIndex::content_key("python", "def second():\n    pass\n");
+
+    let flushed = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    flushed.put(
+        root,
+        &first,
+        stamp(&first),
+        &first_key,
+        &result_with_function("first"),
+    );
+    flushed.flush();
+
+    // Another live session indexes a file but hasn't written its manifest yet
+    let live = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    live.put(
+        root,
+        &second,
+        stamp(&second),
+        &second_key,
+        &result_with_function("second"),
+    );
+
+    // A new session opening the index must not prune that object
+    let opened = AnalysisIndex::with_base_dir(Some(cache.path().to_path_buf()));
+    assert!(opened.get(root, &first, stamp(&first)).is_some());
+    assert!(opened
+        .get_by_key(root, &second, stamp(&second), &second_key)
+        .is_some());
+}
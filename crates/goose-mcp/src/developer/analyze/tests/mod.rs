@@ -1,13 +1,21 @@
 // Test modules for the analyze tool
 
+pub mod c_test;
 pub mod cache_tests;
+pub mod cpp_test;
+pub mod csharp_test;
 pub mod fixtures;
 pub mod formatter_tests;
 pub mod go_test;
 pub mod graph_tests;
+pub mod index_tests;
 pub mod integration_tests;
 pub mod large_output_tests;
 pub mod parser_tests;
+pub mod php_test;
 pub mod ruby_test;
 pub mod rust_test;
+pub mod scala_test;
+pub mod syntax_tests;
 pub mod traversal_tests;
+pub mod typescript_test;
@@ -241,6 +241,12 @@ fn test_language_registry() {
         "kotlin",
         "swift",
         "ruby",
+        "tsx",
+        "c",
+        "cpp",
+        "csharp",
+        "php",
+        "scala",
     ];
 
     for lang in supported {
@@ -266,12 +272,30 @@ fn test_language_registry() {
         );
     }
 
-    let js = languages::get_language_info("javascript").unwrap();
     let ts = languages::get_language_info("typescript").unwrap();
+    let tsx = languages::get_language_info("tsx").unwrap();
     assert_eq!(
-        js.element_query, ts.element_query,
-        "JS/TS should share config"
+        ts.element_query, tsx.element_query,
+        "TS/TSX should share element queries"
     );
+    assert_ne!(
+        ts.call_query, tsx.call_query,
+        "TSX should also track JSX components"
+    );
+
+    for lang in ["cpp", "csharp", "typescript", "php", "scala"] {
+        let info = languages::get_language_info(lang).unwrap();
+        assert!(
+            !info.reference_query.is_empty(),
+            "{} should have reference tracking",
+            lang
+        );
+        assert!(
+            info.find_method_for_receiver_handler.is_some(),
+            "{} should resolve method receivers",
+            lang
+        );
+    }
 
     let go = languages::get_language_info("go").unwrap();
     assert!(
@@ -0,0 +1,114 @@
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+
+fn parse_and_extract(code: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, "php").unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, "php", "semantic", None).unwrap()
+}
+
+#[test]
+fn test_php_elements_and_receivers() {
+    let code = r#"<?php
+
+namespace App\Services;
+
+use App\Models\Config;
+use App\Models\{Item, User};
+
+interface Renderer
+{
+    public function render(Widget $widget): void;
+}
+
+trait Loggable
+{
+    public function log(string $message): void
+    {
+        error_log($message);
+    }
+}
+
+class Widget implements Renderer
+{
+    use Loggable;
+
+    const MAX_SIZE = 10;
+    private Config $config;
+
+    public function __construct(Config $config)
+    {
+        $this->config = $config;
+    }
+
+    public function render(Widget $widget): void
+    {
+        $widget->log(strtoupper("x"));
+        self::helper($this->config);
+    }
+
+    private static function helper(Config $config): string
+    {
+        return (string) $config;
+    }
+}
+
+function make_widget(): Widget
+{
+    $widget = new Widget(new Config());
+    $widget->render($widget);
+    return $widget;
+}
+"#;
+
+    let result = parse_and_extract(code);
+
+    let class_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(class_names.contains("Renderer"));
+    assert!(class_names.contains("Loggable"));
+    assert!(class_names.contains("Widget"));
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("__construct"));
+    assert!(function_names.contains("render"));
+    assert!(function_names.contains("make_widget"));
+    assert!(function_names.contains("MAX_SIZE"));
+
+    assert_eq!(result.import_count, 3);
+    assert!(result.imports.contains(&"App\\Models\\Config".to_string()));
+
+    let render_calls: HashSet<_> = result
+        .calls
+        .iter()
+        .filter(|c| c.caller_name.as_deref() == Some("render"))
+        .map(|c| c.callee_name.as_str())
+        .collect();
+    assert!(render_calls.contains("log"));
+    assert!(render_calls.contains("strtoupper"));
+    assert!(render_calls.contains("helper"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "Config"));
+
+    let widget_methods: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| {
+            r.ref_type == ReferenceType::MethodDefinition
+                && r.associated_type.as_deref() == Some("Widget")
+        })
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(widget_methods.contains("__construct"));
+    assert!(widget_methods.contains("render"));
+    assert!(widget_methods.contains("helper"));
+
+    assert!(result.references.iter().any(|r| {
+        r.ref_type == ReferenceType::MethodDefinition
+            && r.symbol == "log"
+            && r.associated_type.as_deref() == Some("Loggable")
+    }));
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::ParameterType && r.symbol == "Config"));
+}
@@ -0,0 +1,87 @@
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+
+fn parse_and_extract(code: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, "scala").unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, "scala", "semantic", None).unwrap()
+}
+
+#[test]
+fn test_scala_elements_and_receivers() {
+    let code = r#"
+package app
+
+import scala.collection.mutable
+import app.config.Config
+
+trait Renderer {
+  def render(widget: Widget): Unit
+}
+
+case class Point(x: Int, y: Int)
+
+class Widget(config: Config) extends Renderer {
+  def render(widget: Widget): Unit = {
+    println(widget.name())
+    helper(config)
+  }
+
+  private def helper(c: Config): String = c.toString
+}
+
+object Main {
+  def main(args: Array[String]): Unit = {
+    val widget: Widget = new Widget(Config.load())
+    widget.render(widget)
+  }
+}
+"#;
+
+    let result = parse_and_extract(code);
+
+    let class_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(class_names.contains("Renderer"));
+    assert!(class_names.contains("Point"));
+    assert!(class_names.contains("Widget"));
+    assert!(class_names.contains("Main"));
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("render"));
+    assert!(function_names.contains("helper"));
+    assert!(function_names.contains("main"));
+
+    assert_eq!(result.import_count, 2);
+
+    let render_calls: HashSet<_> = result
+        .calls
+        .iter()
+        .filter(|c| c.caller_name.as_deref() == Some("render"))
+        .map(|c| c.callee_name.as_str())
+        .collect();
+    assert!(render_calls.contains("println"));
+    assert!(render_calls.contains("helper"));
+
+    let widget_methods: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| {
+            r.ref_type == ReferenceType::MethodDefinition
+                && r.associated_type.as_deref() == Some("Widget")
+        })
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(widget_methods.contains("render"));
+    assert!(widget_methods.contains("helper"));
+
+    assert!(result.references.iter().any(|r| {
+        r.ref_type == ReferenceType::MethodDefinition
+            && r.symbol == "main"
+            && r.associated_type.as_deref() == Some("Main")
+    }));
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::FieldType && r.symbol == "Config"));
+}
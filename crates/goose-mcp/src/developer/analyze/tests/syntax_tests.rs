@@ -0,0 +1,37 @@
+// Tests for syntax checking of edited files
+
+use crate::developer::analyze::syntax::check_syntax;
+use std::path::Path;
+
+#[test]
+fn test_valid_source_has_no_error() {
+    assert_eq!(
+        check_syntax(Path::new("main.rs"), "fn main() {\n    let x = 1;\n}\n"),
+        None
+    );
+    assert_eq!(
+        check_syntax(Path::new("app.py"), "def f(x):\n    return x\n"),
+        None
+    );
+}
+
+#[test]
+fn test_reports_first_error_location() {
+    let error = check_syntax(Path::new("main.rs"), "fn main() {\n    let x = ;\n}\n").unwrap();
+    assert!(error.starts_with("line 2,"), "{}", error);
+
+    let error = check_syntax(Path::new("app.py"), "def f(x:\n    return x\n").unwrap();
+    assert!(error.starts_with("line 1,"), "{}", error);
+}
+
+#[test]
+fn test_reports_missing_tokens() {
+    let error = check_syntax(Path::new("main.rs"), "fn main() {\n    let x = 1\n}\n").unwrap();
+    assert!(error.contains("missing `;`"), "{}", error);
+}
+
+#[test]
+fn test_unsupported_languages_are_not_checked() {
+    assert_eq!(check_syntax(Path::new("notes.md"), "# {{{ not code"), None);
+    assert_eq!(check_syntax(Path::new("Makefile"), "all: ((("), None);
+}
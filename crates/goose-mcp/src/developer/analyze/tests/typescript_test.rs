@@ -0,0 +1,126 @@
+use crate::developer::analyze::parser::{ElementExtractor, ParserManager};
+use crate::developer::analyze::types::{AnalysisResult, ReferenceType};
+use std::collections::HashSet;
+
+fn parse_and_extract(code: &str, language: &str) -> AnalysisResult {
+    let manager = ParserManager::new();
+    let tree = manager.parse(code, language).unwrap();
+    ElementExtractor::extract_with_depth(&tree, code, language, "semantic", None).unwrap()
+}
+
+#[test]
+fn test_typescript_elements_and_receivers() {
+    let code = r#"
+import { Config } from "./config";
+
+export interface Renderer {
+  render(widget: Widget): void;
+}
+
+export type Size = { width: number };
+
+export enum Color { Red, Green }
+
+export class Widget implements Renderer {
+  private config: Config;
+
+  constructor(config: Config) {
+    this.config = config;
+  }
+
+  render(widget: Widget): void {
+    console.log(widget.name());
+    helper(this.config);
+  }
+}
+
+export function helper(config: Config): string {
+  const widget: Widget = new Widget(config);
+  return widget.toString();
+}
+
+export const makeWidget = (config: Config): Widget => new Widget(config);
+"#;
+
+    let result = parse_and_extract(code, "typescript");
+
+    let class_names: HashSet<_> = result.classes.iter().map(|c| c.name.as_str()).collect();
+    assert!(class_names.contains("Renderer"));
+    assert!(class_names.contains("Widget"));
+    assert!(class_names.contains("Size"));
+    assert!(class_names.contains("Color"));
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("render"));
+    assert!(function_names.contains("helper"));
+    assert!(function_names.contains("makeWidget"));
+    assert_eq!(result.import_count, 1);
+
+    // Arrow functions take the name of the variable they are bound to
+    let arrow_call = result
+        .calls
+        .iter()
+        .find(|c| c.callee_name == "Widget" && c.context.starts_with("export const makeWidget"))
+        .expect("Expected new Widget() in makeWidget");
+    assert_eq!(arrow_call.caller_name.as_deref(), Some("makeWidget"));
+
+    let widget_methods: HashSet<_> = result
+        .references
+        .iter()
+        .filter(|r| {
+            r.ref_type == ReferenceType::MethodDefinition
+                && r.associated_type.as_deref() == Some("Widget")
+        })
+        .map(|r| r.symbol.as_str())
+        .collect();
+    assert!(widget_methods.contains("constructor"));
+    assert!(widget_methods.contains("render"));
+
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::FieldType && r.symbol == "Config"));
+    assert!(result
+        .references
+        .iter()
+        .any(|r| r.ref_type == ReferenceType::VariableType && r.symbol == "Widget"));
+}
+
+#[test]
+fn test_tsx_components() {
+    let code = r#"
+import React from "react";
+
+interface Props { title: string }
+
+export function Header({ title }: Props) {
+  return <h1>{format(title)}</h1>;
+}
+
+export function App() {
+  return (
+    <Layout>
+      <Header title="home" />
+    </Layout>
+  );
+}
+"#;
+
+    let result = parse_and_extract(code, "tsx");
+
+    let function_names: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+    assert!(function_names.contains("Header"));
+    assert!(function_names.contains("App"));
+
+    let app_calls: HashSet<_> = result
+        .calls
+        .iter()
+        .filter(|c| c.caller_name.as_deref() == Some("App"))
+        .map(|c| c.callee_name.as_str())
+        .collect();
+    assert!(app_calls.contains("Header"));
+    assert!(app_calls.contains("Layout"));
+    // Intrinsic elements are not components
+    assert!(!result.calls.iter().any(|c| c.callee_name == "h1"));
+    assert!(result.calls.iter().any(|c| c.callee_name == "format"));
+}
@@ -1,12 +1,19 @@
 mod morphllm_editor;
 mod openai_compatible_editor;
+mod patch_editor;
 mod relace_editor;
+mod replace_editor;
 
 use anyhow::Result;
+use std::path::Path;
+
+use super::analyze::syntax::check_syntax;
 
 pub use morphllm_editor::MorphLLMEditor;
 pub use openai_compatible_editor::OpenAICompatibleEditor;
+pub use patch_editor::PatchEditor;
 pub use relace_editor::RelaceEditor;
+pub use replace_editor::ReplaceEditor;
 
 /// Enum for different editor models that can perform intelligent code editing
 #[derive(Debug, Clone)]
@@ -14,6 +21,8 @@ pub enum EditorModel {
     MorphLLM(MorphLLMEditor),
     OpenAICompatible(OpenAICompatibleEditor),
     Relace(RelaceEditor),
+    Patch(PatchEditor),
+    Replace(ReplaceEditor),
 }
 
 impl EditorModel {
@@ -40,6 +49,38 @@ impl EditorModel {
                     .edit_code(original_code, old_str, update_snippet)
                     .await
             }
+            EditorModel::Patch(editor) => {
+                editor
+                    .edit_code(original_code, old_str, update_snippet)
+                    .await
+            }
+            EditorModel::Replace(editor) => {
+                editor
+                    .edit_code(original_code, old_str, update_snippet)
+                    .await
+            }
+        }
+    }
+
+    /// Short name of the editor, used when reporting which editor applied an edit
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorModel::MorphLLM(editor) => editor.name(),
+            EditorModel::OpenAICompatible(editor) => editor.name(),
+            EditorModel::Relace(editor) => editor.name(),
+            EditorModel::Patch(editor) => editor.name(),
+            EditorModel::Replace(editor) => editor.name(),
+        }
+    }
+
+    /// Whether the editor can make sense of this kind of update snippet
+    pub fn accepts(&self, old_str: &str, update_snippet: &str) -> bool {
+        match self {
+            EditorModel::MorphLLM(editor) => editor.accepts(old_str, update_snippet),
+            EditorModel::OpenAICompatible(editor) => editor.accepts(old_str, update_snippet),
+            EditorModel::Relace(editor) => editor.accepts(old_str, update_snippet),
+            EditorModel::Patch(editor) => editor.accepts(old_str, update_snippet),
+            EditorModel::Replace(editor) => editor.accepts(old_str, update_snippet),
         }
     }
 
@@ -49,6 +90,8 @@ impl EditorModel {
             EditorModel::MorphLLM(editor) => editor.get_str_replace_description(),
             EditorModel::OpenAICompatible(editor) => editor.get_str_replace_description(),
             EditorModel::Relace(editor) => editor.get_str_replace_description(),
+            EditorModel::Patch(editor) => editor.get_str_replace_description(),
+            EditorModel::Replace(editor) => editor.get_str_replace_description(),
         }
     }
 }
@@ -63,10 +106,122 @@ pub trait EditorModelImpl {
         update_snippet: &str,
     ) -> Result<String, String>;
 
+    /// Short name of the editor, used when reporting which editor applied an edit
+    fn name(&self) -> &'static str;
+
+    /// Whether the editor can make sense of this kind of update snippet
+    fn accepts(&self, _old_str: &str, _update_snippet: &str) -> bool {
+        true
+    }
+
     /// Get the description for the str_replace command when this editor is active
     fn get_str_replace_description(&self) -> &'static str;
 }
 
+/// What an [`EditorChain`] did with an edit
+#[derive(Debug)]
+pub struct ChainedEdit {
+    /// The edited file content
+    pub content: String,
+    /// The editor whose result was kept
+    pub editor: &'static str,
+    /// The other editors that were tried, with why their result was not kept
+    pub fallbacks: Vec<(&'static str, String)>,
+    /// Set when the plain replacement was kept even though the file no longer parses
+    pub syntax_error: Option<String>,
+}
+
+/// Editors tried in order until one applies an edit: the configured fast-apply model, then
+/// applying the snippet locally as a unified diff when `old_str` is empty, then exact string
+/// replacement.
+///
+/// When the original file parses, a result that no longer does counts as a failure and the
+/// next editor is tried, so a fast-apply model mangling the file doesn't go unnoticed. Only
+/// the exact replacement is applied regardless, with the syntax error reported.
+#[derive(Debug, Clone)]
+pub struct EditorChain {
+    editors: Vec<EditorModel>,
+}
+
+impl EditorChain {
+    pub fn new(fast_apply: Option<EditorModel>) -> Self {
+        let mut editors: Vec<EditorModel> = fast_apply.into_iter().collect();
+        editors.push(EditorModel::Patch(PatchEditor::new()));
+        editors.push(EditorModel::Replace(ReplaceEditor::new()));
+        Self { editors }
+    }
+
+    /// The editors in the order they are tried
+    pub fn editors(&self) -> &[EditorModel] {
+        &self.editors
+    }
+
+    /// Apply an edit to `original_code`, the content of `path`, with the first editor that
+    /// manages it. Fails with the reason of every editor tried when none does.
+    pub async fn edit(
+        &self,
+        path: &Path,
+        original_code: &str,
+        old_str: &str,
+        update_snippet: &str,
+    ) -> Result<ChainedEdit, String> {
+        // Files that are already broken can't tell a bad edit from a good one
+        let verify = check_syntax(path, original_code).is_none();
+
+        let mut fallbacks = Vec::new();
+        for editor in &self.editors {
+            if !editor.accepts(old_str, update_snippet) {
+                continue;
+            }
+
+            let content = match editor
+                .edit_code(original_code, old_str, update_snippet)
+                .await
+            {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::debug!("Editor {} failed: {}", editor.name(), e);
+                    fallbacks.push((editor.name(), e));
+                    continue;
+                }
+            };
+
+            let syntax_error = verify.then(|| check_syntax(path, &content)).flatten();
+            match syntax_error {
+                // Only the literal replacement is the model's own text, so it's the only
+                // result worth keeping when it doesn't parse, for the model to fix up
+                Some(error) if !matches!(editor, EditorModel::Replace(_)) => {
+                    tracing::debug!("Editor {} broke the syntax: {}", editor.name(), error);
+                    fallbacks.push((
+                        editor.name(),
+                        format!("the result does not parse, {}", error),
+                    ));
+                }
+                syntax_error => {
+                    return Ok(ChainedEdit {
+                        content,
+                        editor: editor.name(),
+                        fallbacks,
+                        syntax_error,
+                    })
+                }
+            }
+        }
+
+        match fallbacks.as_slice() {
+            [(_, error)] => Err(error.clone()),
+            _ => Err(format!(
+                "No editor could apply the edit:\n{}",
+                fallbacks
+                    .iter()
+                    .map(|(name, error)| format!("- {}: {}", name, error))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )),
+        }
+    }
+}
+
 /// Factory function to create the appropriate editor model based on environment variables
 pub fn create_editor_model() -> Option<EditorModel> {
     // Don't use Editor API during tests
@@ -96,3 +251,104 @@ pub fn create_editor_model() -> Option<EditorModel> {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chain_falls_back_to_str_replace() {
+        let chain = EditorChain::new(None);
+        let edit = chain
+            .edit(
+                Path::new("main.rs"),
+                "fn main() {\n    let x = 1;\n}\n",
+                "let x = 1;",
+                "let x = 2;",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(edit.content, "fn main() {\n    let x = 2;\n}\n");
+        assert_eq!(edit.editor, "str_replace");
+        assert!(edit.fallbacks.is_empty());
+        assert!(edit.syntax_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chain_applies_diff_snippets() {
+        let chain = EditorChain::new(None);
+        let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n }\n";
+        let edit = chain
+            .edit(
+                Path::new("main.rs"),
+                "fn main() {\n    let x = 1;\n}\n",
+                "",
+                diff,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(edit.content, "fn main() {\n    let x = 2;\n}\n");
+        assert_eq!(edit.editor, "patch");
+
+        // With an old_str, diff text is replaced literally, as when editing a patch file
+        let edit = chain
+            .edit(Path::new("fix.patch"), "TODO\n", "TODO", diff.trim_end())
+            .await
+            .unwrap();
+        assert_eq!(edit.content, format!("{}\n", diff.trim_end()));
+        assert_eq!(edit.editor, "str_replace");
+    }
+
+    #[tokio::test]
+    async fn test_chain_reports_edits_that_break_syntax() {
+        let chain = EditorChain::new(None);
+        let edit = chain
+            .edit(
+                Path::new("main.rs"),
+                "fn main() {\n    let x = 1;\n}\n",
+                "let x = 1;",
+                "let x = ;",
+            )
+            .await
+            .unwrap();
+
+        // The edit is still made, but the model is told the file no longer parses
+        assert_eq!(edit.editor, "str_replace");
+        assert!(edit.syntax_error.unwrap().starts_with("line 2,"));
+    }
+
+    #[tokio::test]
+    async fn test_chain_errors() {
+        let chain = EditorChain::new(None);
+        let error = chain
+            .edit(Path::new("notes.txt"), "hello\n", "goodbye", "hi")
+            .await
+            .unwrap_err();
+        assert!(error.contains("does not appear in the file"));
+
+        // A diff that doesn't apply falls through to str_replace, and both reasons are given
+        let diff = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1 +1 @@\n-something else\n+hi\n";
+        let error = chain
+            .edit(Path::new("notes.txt"), "hello\n", "", diff)
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("No editor could apply the edit:"));
+        assert!(error.contains("- patch: "));
+        assert!(error.contains("- str_replace: "));
+
+        // A result that breaks the syntax is never kept from anything but str_replace
+        let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    let x = 1;\n+    let x = ;\n }\n";
+        let error = chain
+            .edit(
+                Path::new("main.rs"),
+                "fn main() {\n    let x = 1;\n}\n",
+                "",
+                diff,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("- patch: the result does not parse, line 2,"));
+    }
+}
@@ -130,6 +130,10 @@ impl EditorModelImpl for MorphLLMEditor {
         Ok(content.to_string())
     }
 
+    fn name(&self) -> &'static str {
+        "morphllm"
+    }
+
     fn get_str_replace_description(&self) -> &'static str {
         "Use the edit_file to propose an edit to an existing file.
         This will be read by a less intelligent model, which will quickly apply the edit. You should make it clear what the edit is, while also minimizing the unchanged code you write.
@@ -96,6 +96,10 @@ impl EditorModelImpl for OpenAICompatibleEditor {
         Ok(content.to_string())
     }
 
+    fn name(&self) -> &'static str {
+        "openai_compatible"
+    }
+
     fn get_str_replace_description(&self) -> &'static str {
         "Edit the file with the new content."
     }
@@ -0,0 +1,90 @@
+use super::EditorModelImpl;
+use crate::developer::text_editor::parse_diff_content;
+use anyhow::Result;
+use mpatch::{apply_patch, PatchError};
+use std::path::Component;
+
+/// Local editor that applies a unified diff of the file, with the same fuzzy matching
+/// as the `diff` parameter of str_replace
+#[derive(Debug, Clone, Default)]
+pub struct PatchEditor;
+
+impl PatchEditor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Whether an update snippet is a unified diff rather than code
+pub fn is_unified_diff(snippet: &str) -> bool {
+    let has_line = |prefix: &str| snippet.lines().any(|line| line.starts_with(prefix));
+    has_line("--- ") && has_line("+++ ") && has_line("@@")
+}
+
+impl EditorModelImpl for PatchEditor {
+    async fn edit_code(
+        &self,
+        original_code: &str,
+        _old_str: &str,
+        update_snippet: &str,
+    ) -> Result<String, String> {
+        let patches = parse_diff_content(update_snippet).map_err(|e| e.message.to_string())?;
+        let [patch] = patches.as_slice() else {
+            return Err("The diff must change exactly one file".to_string());
+        };
+        if !patch
+            .file_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!(
+                "The diff path '{}' must be relative",
+                patch.file_path.display()
+            ));
+        }
+
+        // mpatch only applies to files on disk, so patch a scratch copy of the content
+        let dir = tempfile::tempdir()
+            .map_err(|e| format!("Failed to create a scratch directory: {}", e))?;
+        let file = dir.path().join(&patch.file_path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create a scratch directory: {}", e))?;
+        }
+        std::fs::write(&file, original_code)
+            .map_err(|e| format!("Failed to write a scratch copy: {}", e))?;
+
+        let applied = apply_patch(patch, dir.path(), false, 0.7).map_err(|e| match e {
+            PatchError::Io { source, .. } => format!("Failed to apply the diff: {}", source),
+            PatchError::PathTraversal(path) => {
+                format!("The diff path '{}' must be relative", path.display())
+            }
+            PatchError::TargetNotFound(path) => {
+                format!("The diff does not apply to '{}'", path.display())
+            }
+            PatchError::MissingFileHeader => "Invalid patch format".to_string(),
+        })?;
+        if !applied {
+            return Err(format!(
+                "Some of the {} hunks of the diff do not match the file",
+                patch.hunks.len()
+            ));
+        }
+
+        std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read the patched copy: {}", e))
+    }
+
+    fn name(&self) -> &'static str {
+        "patch"
+    }
+
+    fn accepts(&self, old_str: &str, update_snippet: &str) -> bool {
+        // Opt-in through an empty old_str, so diff text can still be inserted literally
+        old_str.is_empty() && is_unified_diff(update_snippet)
+    }
+
+    fn get_str_replace_description(&self) -> &'static str {
+        "With an empty old_str, new_str may be a unified diff of the file, which is applied with fuzzy matching."
+    }
+}
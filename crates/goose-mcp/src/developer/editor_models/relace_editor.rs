@@ -96,6 +96,10 @@ impl EditorModelImpl for RelaceEditor {
         Ok(content.to_string())
     }
 
+    fn name(&self) -> &'static str {
+        "relace"
+    }
+
     fn get_str_replace_description(&self) -> &'static str {
         "edit_file will take the new_str and work out how to place old_str with it intelligently."
     }
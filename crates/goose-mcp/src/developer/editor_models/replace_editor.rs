@@ -0,0 +1,36 @@
+use super::EditorModelImpl;
+use anyhow::Result;
+
+/// Local editor that swaps `old_str` for the update snippet verbatim, the plain
+/// str_replace behavior
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceEditor;
+
+impl ReplaceEditor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl EditorModelImpl for ReplaceEditor {
+    async fn edit_code(
+        &self,
+        original_code: &str,
+        old_str: &str,
+        update_snippet: &str,
+    ) -> Result<String, String> {
+        match original_code.matches(old_str).count() {
+            1 => Ok(original_code.replacen(old_str, update_snippet, 1)),
+            0 => Err("'old_str' must appear exactly once in the file, but it does not appear in the file. Make sure the string exactly matches existing file content, including whitespace!".to_string()),
+            _ => Err("'old_str' must appear exactly once in the file, but it appears multiple times".to_string()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "str_replace"
+    }
+
+    fn get_str_replace_description(&self) -> &'static str {
+        "The `old_str` needs to exactly match one unique section of the original file, including any whitespace."
+    }
+}
@@ -0,0 +1,1285 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use git2::{
+    build::CheckoutBuilder, ApplyLocation, ApplyOptions, BlameOptions, Branch, BranchType, Diff,
+    DiffOptions, ErrorCode as GitErrorCode, IndexAddOption, ObjectType, Oid, Patch, Repository,
+    ResetType, Sort, Status, StatusOptions,
+};
+use ignore::gitignore::Gitignore;
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    schemars::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Commits listed by `log` when `max_count` is not given.
+const DEFAULT_LOG_COUNT: usize = 20;
+
+/// Most commits listed by one `log`.
+const MAX_LOG_COUNT: usize = 200;
+
+/// Characters of diff shown before it is cut, far below the shell tool's output limit.
+const MAX_DIFF_CHARS: usize = 50_000;
+
+/// Parameters for the git tool
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GitParams {
+    /// The operation to perform. Allowed options are: `status`, `diff`, `log`, `blame`,
+    /// `branch`, `switch`, `stage`, `unstage`, `commit`, `reset`, `push`, `delete_branch`.
+    pub command: String,
+
+    /// Absolute path of the repository or a directory inside it. Defaults to the working
+    /// directory.
+    pub repo: Option<String>,
+
+    /// Files to limit `diff`, `log`, `stage` and `unstage` to, absolute or relative to the
+    /// repository root. The file to annotate for `blame`.
+    pub paths: Option<Vec<String>>,
+
+    /// For `diff`, show staged changes instead of unstaged ones.
+    #[serde(default)]
+    pub staged: bool,
+
+    /// For `stage` and `unstage` of a single file, the hunks to apply, numbered as `diff`
+    /// shows them (unstaged hunks for `stage`, staged hunks for `unstage`).
+    pub hunks: Option<Vec<usize>>,
+
+    /// For `log`, the most commits to list, 20 by default and at most 200.
+    pub max_count: Option<usize>,
+
+    /// For `log`, only commits whose author name or email contains this text.
+    pub author: Option<String>,
+
+    /// For `log`, only commits whose message contains this text.
+    pub grep: Option<String>,
+
+    /// For `log`, only commits made on or after this date, as YYYY-MM-DD.
+    pub since: Option<String>,
+
+    /// For `log`, only commits made on or before this date, as YYYY-MM-DD.
+    pub until: Option<String>,
+
+    /// For `blame`, the first 1-indexed line to annotate.
+    pub start_line: Option<usize>,
+
+    /// For `blame`, the last 1-indexed line to annotate.
+    pub end_line: Option<usize>,
+
+    /// Branch name for `branch`, `switch`, `delete_branch` and `push`.
+    pub branch: Option<String>,
+
+    /// Commit, branch or tag to start `log` from, create a `branch` at or `reset` to.
+    /// Defaults to HEAD.
+    pub revision: Option<String>,
+
+    /// For `commit`, the commit message.
+    pub message: Option<String>,
+
+    /// For `reset`: `soft`, `mixed` (the default) or `hard`.
+    pub mode: Option<String>,
+
+    /// For `push`, the remote to push to. Defaults to `origin`.
+    pub remote: Option<String>,
+
+    /// For `push`, overwrite the remote branch even if it has commits the local one lacks.
+    #[serde(default)]
+    pub force: bool,
+
+    /// Must be true for operations that can lose work: `reset` with mode `hard`, `push` with
+    /// `force`, and `delete_branch`. The user is always asked to approve these.
+    #[serde(default)]
+    pub destructive: bool,
+}
+
+fn invalid_params(message: impl Into<String>) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message.into(), None)
+}
+
+fn git_error(e: git2::Error) -> ErrorData {
+    ErrorData::new(ErrorCode::INTERNAL_ERROR, e.message().to_string(), None)
+}
+
+/// Why an operation can lose work, if it can.
+fn destructive_reason(params: &GitParams) -> Option<&'static str> {
+    match params.command.as_str() {
+        "reset" if params.mode.as_deref() == Some("hard") => {
+            Some("reset with mode hard discards uncommitted changes")
+        }
+        "push" if params.force => Some("force push can overwrite commits on the remote"),
+        "delete_branch" => Some("deleting a branch loses commits that are not merged elsewhere"),
+        _ => None,
+    }
+}
+
+/// Run a git operation in the repository containing `path`. Files matched by
+/// `ignore_patterns` are left out of diffs and can't be blamed or staged.
+pub fn run(
+    params: &GitParams,
+    path: &Path,
+    ignore_patterns: &Gitignore,
+) -> Result<String, ErrorData> {
+    if let Some(reason) = destructive_reason(params) {
+        if !params.destructive {
+            return Err(invalid_params(format!(
+                "Refusing to run {}: {}. Call again with destructive: true to confirm, the user \
+                 will be asked to approve it.",
+                params.command, reason
+            )));
+        }
+    }
+
+    let repo = Repository::discover(path).map_err(|_| {
+        invalid_params(format!(
+            "'{}' is not inside a git repository",
+            path.display()
+        ))
+    })?;
+    let git = Git {
+        repo,
+        ignore_patterns,
+    };
+
+    match params.command.as_str() {
+        "status" => git.status(),
+        "diff" => git.diff(params),
+        "log" => git.log(params),
+        "blame" => git.blame(params),
+        "branch" => git.create_branch(params),
+        "switch" => git.switch(params),
+        "stage" => git.stage(params),
+        "unstage" => git.unstage(params),
+        "commit" => git.commit(params),
+        "reset" => git.reset(params),
+        "push" => git.push(params),
+        "delete_branch" => git.delete_branch(params),
+        _ => Err(invalid_params(format!(
+            "Unknown command '{}'. Allowed options are: status, diff, log, blame, branch, \
+             switch, stage, unstage, commit, reset, push, delete_branch",
+            params.command
+        ))),
+    }
+}
+
+struct Git<'a> {
+    repo: Repository,
+    ignore_patterns: &'a Gitignore,
+}
+
+impl Git<'_> {
+    fn workdir(&self) -> Result<&Path, ErrorData> {
+        self.repo
+            .workdir()
+            .ok_or_else(|| invalid_params("Bare repositories are not supported"))
+    }
+
+    fn is_ignored(&self, relative: &Path) -> bool {
+        self.repo.workdir().is_some_and(|workdir| {
+            self.ignore_patterns
+                .matched(workdir.join(relative), false)
+                .is_ignore()
+        })
+    }
+
+    /// Paths relative to the repository root, which is what libgit2 expects.
+    fn relative_paths(&self, params: &GitParams) -> Result<Vec<PathBuf>, ErrorData> {
+        let workdir = self.workdir()?;
+        let workdir = workdir
+            .canonicalize()
+            .unwrap_or_else(|_| workdir.to_path_buf());
+        let mut paths = Vec::new();
+        for path in params.paths.iter().flatten() {
+            let path = Path::new(path);
+            let relative = if path.is_absolute() {
+                let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                absolute
+                    .strip_prefix(&workdir)
+                    .map(Path::to_path_buf)
+                    .map_err(|_| {
+                        invalid_params(format!(
+                            "'{}' is outside the repository at {}",
+                            path.display(),
+                            workdir.display()
+                        ))
+                    })?
+            } else {
+                path.to_path_buf()
+            };
+            if self.is_ignored(&relative) {
+                return Err(ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!(
+                        "Access to '{}' is restricted by .gooseignore",
+                        relative.display()
+                    ),
+                    None,
+                ));
+            }
+            paths.push(relative);
+        }
+        Ok(paths)
+    }
+
+    fn current_branch(&self) -> Option<String> {
+        let head = self.repo.head().ok()?;
+        head.is_branch()
+            .then(|| head.shorthand().map(str::to_string))
+            .flatten()
+    }
+
+    fn head_tree(&self) -> Option<git2::Tree<'_>> {
+        self.repo.head().ok()?.peel_to_tree().ok()
+    }
+
+    fn resolve(&self, revision: Option<&str>) -> Result<git2::Object<'_>, ErrorData> {
+        let revision = revision.unwrap_or("HEAD");
+        self.repo
+            .revparse_single(revision)
+            .map_err(|_| invalid_params(format!("Unknown revision '{}'", revision)))
+    }
+
+    fn status(&self) -> Result<String, ErrorData> {
+        let mut output = String::new();
+        match self.repo.head() {
+            Ok(head) if head.is_branch() => {
+                let name = head.shorthand().unwrap_or("HEAD");
+                let _ = write!(output, "On branch {}", name);
+                if let Some((upstream, ahead, behind)) = self.upstream_distance(name) {
+                    let _ = write!(
+                        output,
+                        " (ahead {}, behind {} of {})",
+                        ahead, behind, upstream
+                    );
+                }
+                output.push('\n');
+            }
+            Ok(head) => {
+                let id = head.target().map(short_id).unwrap_or_default();
+                let _ = writeln!(output, "HEAD detached at {}", id);
+            }
+            Err(e) if e.code() == GitErrorCode::UnbornBranch => {
+                let name = self
+                    .repo
+                    .find_reference("HEAD")
+                    .ok()
+                    .and_then(|r| r.symbolic_target().map(str::to_string))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    output,
+                    "No commits yet on branch {}",
+                    name.trim_start_matches("refs/heads/")
+                );
+            }
+            Err(e) => return Err(git_error(e)),
+        }
+
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true);
+        let statuses = self.repo.statuses(Some(&mut options)).map_err(git_error)?;
+
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        let mut untracked = Vec::new();
+        let mut conflicted = Vec::new();
+        for entry in statuses.iter() {
+            let path = entry.path().unwrap_or_default().to_string();
+            let status = entry.status();
+            if status.is_conflicted() {
+                conflicted.push(path);
+                continue;
+            }
+            if status.is_wt_new() {
+                untracked.push(path.clone());
+            }
+            let index_change = [
+                (Status::INDEX_NEW, "added"),
+                (Status::INDEX_MODIFIED, "modified"),
+                (Status::INDEX_DELETED, "deleted"),
+                (Status::INDEX_RENAMED, "renamed"),
+                (Status::INDEX_TYPECHANGE, "typechange"),
+            ]
+            .into_iter()
+            .find(|(flag, _)| status.contains(*flag));
+            if let Some((_, change)) = index_change {
+                staged.push(format!("{}: {}", change, path));
+            }
+            let worktree_change = [
+                (Status::WT_MODIFIED, "modified"),
+                (Status::WT_DELETED, "deleted"),
+                (Status::WT_RENAMED, "renamed"),
+                (Status::WT_TYPECHANGE, "typechange"),
+            ]
+            .into_iter()
+            .find(|(flag, _)| status.contains(*flag));
+            if let Some((_, change)) = worktree_change {
+                unstaged.push(format!("{}: {}", change, path));
+            }
+        }
+
+        for (title, entries) in [
+            ("Conflicts", conflicted),
+            ("Staged", staged),
+            ("Unstaged", unstaged),
+            ("Untracked", untracked),
+        ] {
+            if !entries.is_empty() {
+                let _ = writeln!(output, "{}:", title);
+                for entry in entries {
+                    let _ = writeln!(output, "  {}", entry);
+                }
+            }
+        }
+        if statuses.is_empty() {
+            output.push_str("Nothing to commit, working tree clean\n");
+        }
+        Ok(output)
+    }
+
+    /// The upstream of a local branch and how far ahead and behind of it the branch is.
+    fn upstream_distance(&self, branch: &str) -> Option<(String, usize, usize)> {
+        let local = self.repo.find_branch(branch, BranchType::Local).ok()?;
+        let upstream = local.upstream().ok()?;
+        let name = upstream.name().ok()??.to_string();
+        let (ahead, behind) = self
+            .repo
+            .graph_ahead_behind(local.get().target()?, upstream.get().target()?)
+            .ok()?;
+        Some((name, ahead, behind))
+    }
+
+    fn changes(
+        &self,
+        paths: &[PathBuf],
+        staged: bool,
+        reverse: bool,
+    ) -> Result<Diff<'_>, ErrorData> {
+        let mut options = DiffOptions::new();
+        options.reverse(reverse);
+        for path in paths {
+            options.pathspec(path);
+        }
+        let diff = if staged {
+            self.repo
+                .diff_tree_to_index(self.head_tree().as_ref(), None, Some(&mut options))
+        } else {
+            self.repo.diff_index_to_workdir(None, Some(&mut options))
+        };
+        diff.map_err(git_error)
+    }
+
+    fn diff(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let paths = self.relative_paths(params)?;
+        let diff = self.changes(&paths, params.staged, false)?;
+
+        let mut output = String::new();
+        let mut omitted = 0;
+        for index in 0..diff.deltas().len() {
+            let Some(patch) = Patch::from_diff(&diff, index).map_err(git_error)? else {
+                continue;
+            };
+            let delta = patch.delta();
+            let old = delta.old_file().path().unwrap_or(Path::new(""));
+            let new = delta.new_file().path().unwrap_or(old);
+            if self.is_ignored(old) || self.is_ignored(new) {
+                omitted += 1;
+                continue;
+            }
+            if output.len() > MAX_DIFF_CHARS {
+                let _ = writeln!(
+                    output,
+                    "\nDiff truncated at {} files of {}, pass paths to see the rest.",
+                    index,
+                    diff.deltas().len()
+                );
+                break;
+            }
+
+            let _ = writeln!(output, "--- a/{}\n+++ b/{}", old.display(), new.display());
+            if delta.flags().is_binary() {
+                output.push_str("Binary file changed\n");
+                continue;
+            }
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, lines) = patch.hunk(hunk_index).map_err(git_error)?;
+                let header = String::from_utf8_lossy(hunk.header());
+                let _ = writeln!(output, "{} [hunk {}]", header.trim_end(), hunk_index + 1);
+                for line_index in 0..lines {
+                    let line = patch
+                        .line_in_hunk(hunk_index, line_index)
+                        .map_err(git_error)?;
+                    let origin = match line.origin() {
+                        origin @ ('+' | '-' | ' ') => origin,
+                        _ => continue,
+                    };
+                    let content = String::from_utf8_lossy(line.content());
+                    let _ = writeln!(output, "{}{}", origin, content.trim_end_matches('\n'));
+                }
+            }
+        }
+
+        if output.is_empty() {
+            output = format!(
+                "No {} changes",
+                if params.staged { "staged" } else { "unstaged" }
+            );
+        }
+        if omitted > 0 {
+            let _ = write!(
+                output,
+                "\n{} files restricted by .gooseignore omitted",
+                omitted
+            );
+        }
+        Ok(output)
+    }
+
+    fn log(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let paths = self.relative_paths(params)?;
+        let parse_date = |date: &Option<String>| -> Result<Option<NaiveDate>, ErrorData> {
+            date.as_deref()
+                .map(|date| {
+                    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                        invalid_params(format!("Invalid date '{}', use YYYY-MM-DD", date))
+                    })
+                })
+                .transpose()
+        };
+        let since = parse_date(&params.since)?
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc().timestamp());
+        let until = parse_date(&params.until)?
+            .and_then(|d| d.and_hms_opt(23, 59, 59))
+            .map(|d| d.and_utc().timestamp());
+        let author = params.author.as_deref().map(str::to_lowercase);
+        let grep = params.grep.as_deref().map(str::to_lowercase);
+        let max_count = params
+            .max_count
+            .unwrap_or(DEFAULT_LOG_COUNT)
+            .clamp(1, MAX_LOG_COUNT);
+
+        let start = self.resolve(params.revision.as_deref())?;
+        let mut walk = self.repo.revwalk().map_err(git_error)?;
+        walk.set_sorting(Sort::TIME).map_err(git_error)?;
+        walk.push(start.peel_to_commit().map_err(git_error)?.id())
+            .map_err(git_error)?;
+
+        let mut output = String::new();
+        let mut listed = 0;
+        for id in walk {
+            let commit = self
+                .repo
+                .find_commit(id.map_err(git_error)?)
+                .map_err(git_error)?;
+            let time = commit.time().seconds();
+            if since.is_some_and(|since| time < since) || until.is_some_and(|until| time > until) {
+                continue;
+            }
+            let signature = commit.author();
+            if let Some(author) = &author {
+                let name = signature.name().unwrap_or_default().to_lowercase();
+                let email = signature.email().unwrap_or_default().to_lowercase();
+                if !name.contains(author) && !email.contains(author) {
+                    continue;
+                }
+            }
+            let message = commit.message().unwrap_or_default();
+            if grep
+                .as_ref()
+                .is_some_and(|grep| !message.to_lowercase().contains(grep))
+            {
+                continue;
+            }
+            if !paths.is_empty() && !self.touches(&commit, &paths)? {
+                continue;
+            }
+
+            let date = DateTime::<Utc>::from_timestamp(time, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                output,
+                "{} {} {} {}",
+                short_id(commit.id()),
+                date,
+                signature.name().unwrap_or_default(),
+                commit.summary().unwrap_or_default()
+            );
+            listed += 1;
+            if listed == max_count {
+                break;
+            }
+        }
+
+        if output.is_empty() {
+            output = "No matching commits".to_string();
+        }
+        Ok(output)
+    }
+
+    /// Whether a commit changed any of `paths` compared to its first parent.
+    fn touches(&self, commit: &git2::Commit<'_>, paths: &[PathBuf]) -> Result<bool, ErrorData> {
+        let tree = commit.tree().map_err(git_error)?;
+        let parent = commit
+            .parent(0)
+            .ok()
+            .map(|p| p.tree())
+            .transpose()
+            .map_err(git_error)?;
+        let mut options = DiffOptions::new();
+        for path in paths {
+            options.pathspec(path);
+        }
+        let diff = self
+            .repo
+            .diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut options))
+            .map_err(git_error)?;
+        Ok(diff.deltas().len() > 0)
+    }
+
+    fn blame(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let paths = self.relative_paths(params)?;
+        let [path] = paths.as_slice() else {
+            return Err(invalid_params("blame needs exactly one file in paths"));
+        };
+
+        let tree = self
+            .head_tree()
+            .ok_or_else(|| invalid_params("The repository has no commits yet"))?;
+        let blob = tree
+            .get_path(path)
+            .and_then(|entry| entry.to_object(&self.repo))
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|_| invalid_params(format!("'{}' is not in HEAD", path.display())))?;
+        let content = String::from_utf8_lossy(blob.content()).into_owned();
+        let lines: Vec<&str> = content.lines().collect();
+
+        let start = params.start_line.unwrap_or(1).max(1);
+        let end = params.end_line.unwrap_or(lines.len()).min(lines.len());
+        if start > end {
+            return Err(invalid_params(format!(
+                "Invalid line range {}-{}, the file has {} lines",
+                start,
+                end,
+                lines.len()
+            )));
+        }
+
+        let mut options = BlameOptions::new();
+        options.min_line(start).max_line(end);
+        let blame = self
+            .repo
+            .blame_file(path, Some(&mut options))
+            .map_err(git_error)?;
+
+        let mut output = format!(
+            "Blame of {} at HEAD, uncommitted changes are not shown\n",
+            path.display()
+        );
+        for line in start..=end {
+            let Some(hunk) = blame.get_line(line) else {
+                continue;
+            };
+            let signature = hunk.final_signature();
+            let date = DateTime::<Utc>::from_timestamp(signature.when().seconds(), 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                output,
+                "{} {} {} {}: {}",
+                short_id(hunk.final_commit_id()),
+                date,
+                signature.name().unwrap_or_default(),
+                line,
+                lines[line - 1]
+            );
+        }
+        Ok(output)
+    }
+
+    fn required_branch<'p>(&self, params: &'p GitParams) -> Result<&'p str, ErrorData> {
+        params
+            .branch
+            .as_deref()
+            .ok_or_else(|| invalid_params(format!("{} needs a branch", params.command)))
+    }
+
+    fn create_branch(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let name = self.required_branch(params)?;
+        let commit = self
+            .resolve(params.revision.as_deref())?
+            .peel_to_commit()
+            .map_err(git_error)?;
+        self.repo.branch(name, &commit, false).map_err(git_error)?;
+        Ok(format!(
+            "Created branch {} at {}, use switch to check it out",
+            name,
+            short_id(commit.id())
+        ))
+    }
+
+    fn switch(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let name = self.required_branch(params)?;
+        let branch = self
+            .repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|_| invalid_params(format!("No local branch named '{}'", name)))?;
+        let reference = branch.get();
+        let target = reference.peel(ObjectType::Commit).map_err(git_error)?;
+
+        // A safe checkout refuses to overwrite local changes instead of discarding them
+        self.repo
+            .checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+            .map_err(git_error)?;
+        self.repo
+            .set_head(reference.name().unwrap_or_default())
+            .map_err(git_error)?;
+        Ok(format!("Switched to branch {}", name))
+    }
+
+    /// Apply the chosen hunks of a single file's diff to the index.
+    fn apply_hunks(&self, diff: &Diff<'_>, hunks: &[usize]) -> Result<usize, ErrorData> {
+        let total = diff
+            .deltas()
+            .enumerate()
+            .map(|(index, _)| {
+                Patch::from_diff(diff, index)
+                    .ok()
+                    .flatten()
+                    .map_or(0, |p| p.num_hunks())
+            })
+            .sum::<usize>();
+        if let Some(missing) = hunks.iter().find(|&&h| h == 0 || h > total) {
+            return Err(invalid_params(format!(
+                "No hunk {}, the file has {} hunks",
+                missing, total
+            )));
+        }
+
+        let current = Cell::new(0);
+        let mut options = ApplyOptions::new();
+        options.hunk_callback(|_| {
+            current.set(current.get() + 1);
+            hunks.contains(&current.get())
+        });
+        self.repo
+            .apply(diff, ApplyLocation::Index, Some(&mut options))
+            .map_err(git_error)?;
+        Ok(hunks.len())
+    }
+
+    fn stage(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let paths = self.relative_paths(params)?;
+        if let Some(hunks) = &params.hunks {
+            let [_] = paths.as_slice() else {
+                return Err(invalid_params(
+                    "Staging hunks needs exactly one file in paths",
+                ));
+            };
+            let diff = self.changes(&paths, false, false)?;
+            let staged = self.apply_hunks(&diff, hunks)?;
+            return Ok(format!("Staged {} hunks of {}", staged, paths[0].display()));
+        }
+
+        let mut index = self.repo.index().map_err(git_error)?;
+        let specs: Vec<&Path> = if paths.is_empty() {
+            vec![Path::new(".")]
+        } else {
+            paths.iter().map(PathBuf::as_path).collect()
+        };
+        let mut skip_ignored = |path: &Path, _: &[u8]| -> i32 {
+            if self.is_ignored(path) {
+                1
+            } else {
+                0
+            }
+        };
+        index
+            .add_all(
+                specs.iter(),
+                IndexAddOption::DEFAULT,
+                Some(&mut skip_ignored),
+            )
+            .map_err(git_error)?;
+        // add_all doesn't see deleted files, update_all stages their removal
+        index
+            .update_all(specs.iter(), Some(&mut skip_ignored))
+            .map_err(git_error)?;
+        index.write().map_err(git_error)?;
+
+        Ok(if paths.is_empty() {
+            "Staged all changes".to_string()
+        } else {
+            format!("Staged {}", display_paths(&paths))
+        })
+    }
+
+    fn unstage(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let paths = self.relative_paths(params)?;
+        if let Some(hunks) = &params.hunks {
+            let [_] = paths.as_slice() else {
+                return Err(invalid_params(
+                    "Unstaging hunks needs exactly one file in paths",
+                ));
+            };
+            let diff = self.changes(&paths, true, true)?;
+            let unstaged = self.apply_hunks(&diff, hunks)?;
+            return Ok(format!(
+                "Unstaged {} hunks of {}",
+                unstaged,
+                paths[0].display()
+            ));
+        }
+
+        let specs: Vec<PathBuf> = if paths.is_empty() {
+            let diff = self.changes(&[], true, false)?;
+            diff.deltas()
+                .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+                .map(Path::to_path_buf)
+                .collect()
+        } else {
+            paths.clone()
+        };
+        if specs.is_empty() {
+            return Ok("Nothing is staged".to_string());
+        }
+
+        match self.repo.head() {
+            Ok(head) => {
+                let commit = head.peel(ObjectType::Commit).map_err(git_error)?;
+                self.repo
+                    .reset_default(Some(&commit), specs.iter())
+                    .map_err(git_error)?;
+            }
+            // Before the first commit, unstaging removes the files from the index
+            Err(_) => {
+                let mut index = self.repo.index().map_err(git_error)?;
+                for spec in &specs {
+                    index.remove_path(spec).map_err(git_error)?;
+                }
+                index.write().map_err(git_error)?;
+            }
+        }
+
+        Ok(if paths.is_empty() {
+            "Unstaged all changes".to_string()
+        } else {
+            format!("Unstaged {}", display_paths(&paths))
+        })
+    }
+
+    fn commit(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let message = params
+            .message
+            .as_deref()
+            .filter(|m| !m.trim().is_empty())
+            .ok_or_else(|| invalid_params("commit needs a message"))?;
+
+        let mut index = self.repo.index().map_err(git_error)?;
+        let tree_id = index.write_tree().map_err(git_error)?;
+        let tree = self.repo.find_tree(tree_id).map_err(git_error)?;
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+            return Err(invalid_params("Nothing is staged, use stage first"));
+        }
+
+        let signature = self.repo.signature().map_err(|_| {
+            invalid_params("No git identity is configured, set user.name and user.email")
+        })?;
+        let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+        let id = self
+            .repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .map_err(git_error)?;
+
+        Ok(format!(
+            "[{} {}] {}",
+            self.current_branch().unwrap_or_else(|| "HEAD".to_string()),
+            short_id(id),
+            message.lines().next().unwrap_or_default()
+        ))
+    }
+
+    fn reset(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let kind = match params.mode.as_deref().unwrap_or("mixed") {
+            "soft" => ResetType::Soft,
+            "mixed" => ResetType::Mixed,
+            "hard" => ResetType::Hard,
+            mode => {
+                return Err(invalid_params(format!(
+                    "Unknown reset mode '{}', use soft, mixed or hard",
+                    mode
+                )))
+            }
+        };
+        let target = self.resolve(params.revision.as_deref())?;
+        let commit = target.peel_to_commit().map_err(git_error)?;
+        self.repo
+            .reset(commit.as_object(), kind, None)
+            .map_err(git_error)?;
+        Ok(format!(
+            "Reset ({}) to {} {}",
+            params.mode.as_deref().unwrap_or("mixed"),
+            short_id(commit.id()),
+            commit.summary().unwrap_or_default()
+        ))
+    }
+
+    /// Push with the git command line, so the user's credential helpers and ssh setup apply.
+    fn push(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let branch = match &params.branch {
+            Some(branch) => branch.clone(),
+            None => self
+                .current_branch()
+                .ok_or_else(|| invalid_params("HEAD is not on a branch, pass a branch to push"))?,
+        };
+        let remote = params.remote.as_deref().unwrap_or("origin");
+        // A leading + forces, a leading : deletes and a leading - is read as an option,
+        // none of which should get past the destructive check
+        for (what, name) in [("branch", branch.as_str()), ("remote", remote)] {
+            if name.is_empty() || name.starts_with(['-', '+', ':']) {
+                return Err(invalid_params(format!("Invalid {} name '{}'", what, name)));
+            }
+        }
+        if !Branch::name_is_valid(&branch).unwrap_or(false) {
+            return Err(invalid_params(format!("Invalid branch name '{}'", branch)));
+        }
+
+        let mut command = Command::new("git");
+        command.current_dir(self.workdir()?).arg("push");
+        if params.force {
+            command.arg("--force-with-lease");
+        }
+        let output = command
+            .arg("--")
+            .arg(remote)
+            .arg(format!("refs/heads/{0}:refs/heads/{0}", branch))
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to run git push: {}", e),
+                    None,
+                )
+            })?;
+
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if output.status.success() {
+            Ok(format!(
+                "Pushed {} to {}\n{}",
+                branch,
+                remote,
+                text.trim_end()
+            ))
+        } else {
+            Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("git push failed:\n{}", text.trim_end()),
+                None,
+            ))
+        }
+    }
+
+    fn delete_branch(&self, params: &GitParams) -> Result<String, ErrorData> {
+        let name = self.required_branch(params)?;
+        if self.current_branch().as_deref() == Some(name) {
+            return Err(invalid_params(format!(
+                "'{}' is the current branch, switch to another one first",
+                name
+            )));
+        }
+        let mut branch = self
+            .repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|_| invalid_params(format!("No local branch named '{}'", name)))?;
+        let id = branch.get().target().map(short_id).unwrap_or_default();
+        branch.delete().map_err(git_error)?;
+        Ok(format!("Deleted branch {} (was {})", name, id))
+    }
+}
+
+fn short_id(id: Oid) -> String {
+    id.to_string().chars().take(8).collect()
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ignore::gitignore::GitignoreBuilder;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn params(command: &str) -> GitParams {
+        GitParams {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn no_ignores(dir: &Path) -> Gitignore {
+        GitignoreBuilder::new(dir).build().unwrap()
+    }
+
+    /// A repository with one commit of `lib.rs`.
+    fn repository() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        let lines: Vec<String> = (1..=20).map(|i| format!("line {}", i)).collect();
+        fs::write(dir.path().join("lib.rs"), lines.join("\n") + "\n").unwrap();
+        let ignores = no_ignores(dir.path());
+        run(&params("stage"), dir.path(), &ignores).unwrap();
+        run(
+            &GitParams {
+                message: Some("Initial commit".to_string()),
+                ..params("commit")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        dir
+    }
+
+    fn edit_lines(dir: &Path, replacements: &[(usize, &str)]) {
+        let path = dir.join("lib.rs");
+        let content = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        for (line, text) in replacements {
+            lines[line - 1] = text.to_string();
+        }
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_status_diff_and_commit() {
+        let dir = repository();
+        let ignores = no_ignores(dir.path());
+        let status = run(&params("status"), dir.path(), &ignores).unwrap();
+        assert!(status.contains("working tree clean"));
+
+        edit_lines(dir.path(), &[(2, "changed")]);
+        fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+        let status = run(&params("status"), dir.path(), &ignores).unwrap();
+        assert!(status.contains("Unstaged:\n  modified: lib.rs"));
+        assert!(status.contains("Untracked:\n  new.txt"));
+
+        let diff = run(&params("diff"), dir.path(), &ignores).unwrap();
+        assert!(diff.contains("[hunk 1]"));
+        assert!(diff.contains("-line 2\n+changed"));
+
+        run(&params("stage"), dir.path(), &ignores).unwrap();
+        let staged = run(
+            &GitParams {
+                staged: true,
+                ..params("diff")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(staged.contains("+changed"));
+        assert!(staged.contains("+++ b/new.txt"));
+
+        let commit = run(
+            &GitParams {
+                message: Some("Change line 2".to_string()),
+                ..params("commit")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(commit.ends_with("Change line 2"));
+
+        let log = run(&params("log"), dir.path(), &ignores).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        let filtered = run(
+            &GitParams {
+                grep: Some("initial".to_string()),
+                ..params("log")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert_eq!(filtered.lines().count(), 1);
+        let by_path = run(
+            &GitParams {
+                paths: Some(vec!["new.txt".to_string()]),
+                ..params("log")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(by_path.contains("Change line 2") && !by_path.contains("Initial"));
+
+        let blame = run(
+            &GitParams {
+                paths: Some(vec!["lib.rs".to_string()]),
+                start_line: Some(2),
+                end_line: Some(3),
+                ..params("blame")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        let lines: Vec<&str> = blame.lines().skip(1).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("2: changed"));
+        assert!(lines[1].ends_with("3: line 3"));
+    }
+
+    #[test]
+    fn test_stage_and_unstage_single_hunk() {
+        let dir = repository();
+        let ignores = no_ignores(dir.path());
+        edit_lines(dir.path(), &[(2, "first"), (18, "second")]);
+        let file = |hunks: Vec<usize>, command: &str| GitParams {
+            paths: Some(vec!["lib.rs".to_string()]),
+            hunks: Some(hunks),
+            ..params(command)
+        };
+
+        run(&file(vec![2], "stage"), dir.path(), &ignores).unwrap();
+        let staged = run(
+            &GitParams {
+                staged: true,
+                ..params("diff")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(staged.contains("+second") && !staged.contains("+first"));
+        let unstaged = run(&params("diff"), dir.path(), &ignores).unwrap();
+        assert!(unstaged.contains("+first") && !unstaged.contains("+second"));
+
+        let err = run(&file(vec![3], "stage"), dir.path(), &ignores).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+
+        run(&file(vec![1], "unstage"), dir.path(), &ignores).unwrap();
+        let staged = run(
+            &GitParams {
+                staged: true,
+                ..params("diff")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert_eq!(staged, "No staged changes");
+        assert!(fs::read_to_string(dir.path().join("lib.rs"))
+            .unwrap()
+            .contains("second"));
+    }
+
+    #[test]
+    fn test_branches() {
+        let dir = repository();
+        let ignores = no_ignores(dir.path());
+        let branch = |command: &str| GitParams {
+            branch: Some("feature".to_string()),
+            ..params(command)
+        };
+        let original = Repository::open(dir.path())
+            .unwrap()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+
+        run(&branch("branch"), dir.path(), &ignores).unwrap();
+        run(&branch("switch"), dir.path(), &ignores).unwrap();
+        let status = run(&params("status"), dir.path(), &ignores).unwrap();
+        assert!(status.starts_with("On branch feature"));
+
+        let err = run(&branch("delete_branch"), dir.path(), &ignores).unwrap_err();
+        assert!(err.message.contains("destructive: true"));
+        let err = run(
+            &GitParams {
+                destructive: true,
+                ..branch("delete_branch")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap_err();
+        assert!(err.message.contains("current branch"));
+
+        run(
+            &GitParams {
+                branch: Some(original),
+                ..params("switch")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        run(
+            &GitParams {
+                destructive: true,
+                ..branch("delete_branch")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(Repository::open(dir.path())
+            .unwrap()
+            .find_branch("feature", BranchType::Local)
+            .is_err());
+    }
+
+    #[test]
+    fn test_hard_reset_requires_destructive() {
+        let dir = repository();
+        let ignores = no_ignores(dir.path());
+        edit_lines(dir.path(), &[(1, "uncommitted")]);
+        let reset = GitParams {
+            mode: Some("hard".to_string()),
+            ..params("reset")
+        };
+
+        let err = run(&reset, dir.path(), &ignores).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        assert!(fs::read_to_string(dir.path().join("lib.rs"))
+            .unwrap()
+            .contains("uncommitted"));
+
+        run(
+            &GitParams {
+                destructive: true,
+                ..reset
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(!fs::read_to_string(dir.path().join("lib.rs"))
+            .unwrap()
+            .contains("uncommitted"));
+
+        // Pushing isn't destructive unless it is forced
+        let push = GitParams {
+            force: true,
+            ..params("push")
+        };
+        assert!(destructive_reason(&push).is_some());
+        assert!(destructive_reason(&params("push")).is_none());
+    }
+
+    #[test]
+    fn test_push_rejects_refspec_tricks() {
+        let dir = repository();
+        let ignores = no_ignores(dir.path());
+        let remote = TempDir::new().unwrap();
+        Repository::init_bare(remote.path()).unwrap();
+        let remote_path = remote.path().to_string_lossy().to_string();
+        let push = |branch: &str| GitParams {
+            branch: Some(branch.to_string()),
+            remote: Some(remote_path.clone()),
+            ..params("push")
+        };
+
+        let current = Repository::open(dir.path())
+            .unwrap()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+
+        // A + would force and a : would delete the remote branch without approval
+        for name in [
+            format!("+{}", current),
+            format!(":{}", current),
+            "--all".to_string(),
+        ] {
+            let err = run(&push(&name), dir.path(), &ignores).unwrap_err();
+            assert_eq!(err.code, ErrorCode::INVALID_PARAMS, "{}", name);
+        }
+        let err = run(
+            &GitParams {
+                remote: Some("--receive-pack=evil".to_string()),
+                ..push(&current)
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+
+        run(&push(&current), dir.path(), &ignores).unwrap();
+        assert!(Repository::open_bare(remote.path())
+            .unwrap()
+            .find_branch(&current, BranchType::Local)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_ignored_files_are_hidden() {
+        let dir = repository();
+        let mut builder = GitignoreBuilder::new(dir.path());
+        builder.add_line(None, "secret.env").unwrap();
+        let ignores = builder.build().unwrap();
+
+        fs::write(dir.path().join("secret.env"), "TOKEN=1\n").unwrap();
+        edit_lines(dir.path(), &[(1, "visible")]);
+        run(&params("stage"), dir.path(), &ignores).unwrap();
+        let staged = run(
+            &GitParams {
+                staged: true,
+                ..params("diff")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap();
+        assert!(staged.contains("+visible"));
+        assert!(!staged.contains("TOKEN"));
+
+        let err = run(
+            &GitParams {
+                paths: Some(vec!["secret.env".to_string()]),
+                ..params("blame")
+            },
+            dir.path(),
+            &ignores,
+        )
+        .unwrap_err();
+        assert!(err.message.contains(".gooseignore"));
+    }
+}
@@ -8,7 +8,8 @@ pub fn get_language_identifier(path: &Path) -> &'static str {
         Some("rkt") | Some("scm") => "scheme",
         Some("py") => "python",
         Some("js") => "javascript",
-        Some("ts") => "typescript",
+        Some("ts") | Some("mts") | Some("cts") => "typescript",
+        Some("tsx") => "tsx",
         Some("json") => "json",
         Some("toml") => "toml",
         Some("yaml") | Some("yml") => "yaml",
@@ -24,7 +25,8 @@ pub fn get_language_identifier(path: &Path) -> &'static str {
         Some("java") => "java",
         Some("cpp") | Some("cc") | Some("cxx") => "cpp",
         Some("c") => "c",
-        Some("h") | Some("hpp") => "cpp",
+        Some("h") | Some("hpp") | Some("hh") | Some("hxx") => "cpp",
+        Some("cs") => "csharp",
         Some("rb") => "ruby",
         Some("php") => "php",
         Some("swift") => "swift",
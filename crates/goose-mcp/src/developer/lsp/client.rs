@@ -0,0 +1,416 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, Command},
+    sync::{oneshot, watch},
+};
+use url::Url;
+
+#[cfg(unix)]
+#[allow(unused_imports)] // False positive: trait is used for process_group method
+use std::os::unix::process::CommandExt;
+
+use super::ServerConfig;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Diagnostics most recently published by the server for one document.
+#[derive(Default)]
+struct PublishedDiagnostics {
+    generation: u64,
+    items: Vec<Value>,
+}
+
+/// State shared between the client and the task reading the server's stdout.
+struct Shared {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: Mutex<HashMap<i64, oneshot::Sender<Result<Value>>>>,
+    diagnostics: Mutex<HashMap<String, PublishedDiagnostics>>,
+    published: watch::Sender<u64>,
+}
+
+struct OpenDocument {
+    version: i64,
+    text: String,
+}
+
+/// A running language server spoken to over JSON-RPC on its stdin and stdout.
+pub struct LspClient {
+    config: ServerConfig,
+    child: Mutex<Child>,
+    pid: Option<u32>,
+    next_id: AtomicI64,
+    shared: Arc<Shared>,
+    documents: tokio::sync::Mutex<HashMap<String, OpenDocument>>,
+}
+
+impl LspClient {
+    /// Launches the server for `root` and completes the initialize handshake.
+    pub async fn start(config: &ServerConfig, root: &Path) -> Result<Self> {
+        let mut command = Command::new(&config.command);
+        command
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        {
+            command.process_group(0);
+        }
+
+        let mut child = command.spawn().with_context(|| {
+            format!(
+                "Failed to start {} language server '{}', is it installed and on PATH?",
+                config.language, config.command
+            )
+        })?;
+        let pid = child.id();
+        let stdin = child.stdin.take().context("Language server has no stdin")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("Language server has no stdout")?;
+
+        let shared = Arc::new(Shared {
+            stdin: tokio::sync::Mutex::new(stdin),
+            pending: Mutex::new(HashMap::new()),
+            diagnostics: Mutex::new(HashMap::new()),
+            published: watch::Sender::new(0),
+        });
+        tokio::spawn(read_messages(BufReader::new(stdout), shared.clone()));
+
+        let client = Self {
+            config: config.clone(),
+            child: Mutex::new(child),
+            pid,
+            next_id: AtomicI64::new(1),
+            shared,
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+        };
+
+        let root_uri = file_uri(root)?;
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root_uri.clone());
+        client
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": root_uri,
+                    "workspaceFolders": [{ "uri": root_uri, "name": name }],
+                    "capabilities": {
+                        "textDocument": {
+                            "synchronization": { "didSave": true, "dynamicRegistration": false },
+                            "definition": { "linkSupport": true },
+                            "references": {},
+                            "hover": { "contentFormat": ["markdown", "plaintext"] },
+                            "publishDiagnostics": { "versionSupport": true },
+                        },
+                        "workspace": {
+                            "symbol": {},
+                            "workspaceFolders": true,
+                            "configuration": true,
+                        },
+                        "general": { "positionEncodings": ["utf-16"] },
+                    },
+                }),
+            )
+            .await
+            .with_context(|| format!("Failed to initialize {}", config.command))?;
+        client.notify("initialized", json!({})).await?;
+        Ok(client)
+    }
+
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.shared.pending.lock().unwrap().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = write_message(&mut *self.shared.stdin.lock().await, &message).await {
+            self.shared.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => bail!("{} exited while handling {}", self.config.command, method),
+            Err(_) => {
+                self.shared.pending.lock().unwrap().remove(&id);
+                bail!(
+                    "{} did not answer {} within {}s",
+                    self.config.command,
+                    method,
+                    REQUEST_TIMEOUT.as_secs()
+                )
+            }
+        }
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut *self.shared.stdin.lock().await, &message).await
+    }
+
+    /// Sends the file's current contents to the server, opening it on first use, so answers
+    /// reflect edits made on disk since the last call. Returns the document uri and whether
+    /// anything was sent.
+    pub async fn sync(&self, path: &Path, text: &str) -> Result<(String, bool)> {
+        let uri = file_uri(path)?;
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(&uri) {
+            None => {
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": self.config.language_id(path),
+                            "version": 1,
+                            "text": text,
+                        }
+                    }),
+                )
+                .await?;
+                documents.insert(
+                    uri.clone(),
+                    OpenDocument {
+                        version: 1,
+                        text: text.to_string(),
+                    },
+                );
+            }
+            Some(document) if document.text != text => {
+                document.version += 1;
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": document.version },
+                        "contentChanges": [{ "text": text }],
+                    }),
+                )
+                .await?;
+                // Servers like rust-analyzer only re-check the workspace on save
+                self.notify(
+                    "textDocument/didSave",
+                    json!({ "textDocument": { "uri": uri } }),
+                )
+                .await?;
+                document.text = text.to_string();
+            }
+            Some(_) => return Ok((uri, false)),
+        }
+        Ok((uri, true))
+    }
+
+    /// Generation of the diagnostics last published for `uri`, to wait for newer ones.
+    pub fn diagnostics_generation(&self, uri: &str) -> u64 {
+        self.shared
+            .diagnostics
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map_or(0, |published| published.generation)
+    }
+
+    /// Waits up to `wait` for diagnostics newer than `after`, returning the latest known ones
+    /// and whether they are fresh.
+    pub async fn diagnostics(&self, uri: &str, after: u64, wait: Duration) -> (Vec<Value>, bool) {
+        let mut published = self.shared.published.subscribe();
+        let fresh = tokio::time::timeout(wait, async {
+            loop {
+                if self.diagnostics_generation(uri) > after {
+                    return;
+                }
+                if published.changed().await.is_err() {
+                    // The server is gone, nothing newer will arrive
+                    std::future::pending::<()>().await;
+                }
+            }
+        })
+        .await
+        .is_ok();
+
+        let items = self
+            .shared
+            .diagnostics
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map(|published| published.items.clone())
+            .unwrap_or_default();
+        (items, fresh)
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        // Servers such as tsserver start helpers of their own, take the whole group down
+        #[cfg(unix)]
+        if let Some(pid) = self.pid {
+            unsafe {
+                libc::kill(-(pid as i32), libc::SIGKILL);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = self.pid;
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.start_kill();
+        }
+    }
+}
+
+pub fn file_uri(path: &Path) -> Result<String> {
+    Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow!("'{}' is not an absolute path", path.display()))
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads one `Content-Length` framed message, `None` at end of stream.
+async fn read_message(reader: &mut (impl AsyncBufRead + Unpin)) -> Result<Option<Value>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let length = content_length.context("Message without Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+async fn read_messages(mut reader: impl AsyncBufRead + Unpin, shared: Arc<Shared>) {
+    loop {
+        let message = match read_message(&mut reader).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Failed to read language server message: {}", e);
+                break;
+            }
+        };
+
+        let method = message.get("method").and_then(Value::as_str);
+        match (message.get("id"), method) {
+            // A response to one of our requests
+            (Some(id), None) => {
+                let Some(sender) = id
+                    .as_i64()
+                    .and_then(|id| shared.pending.lock().unwrap().remove(&id))
+                else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(anyhow!(
+                        "{}",
+                        error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("Language server returned an error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            // A request from the server, which expects an answer before it carries on
+            (Some(id), Some(method)) => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                        Value::Array(vec![Value::Null; items])
+                    }
+                    _ => Value::Null,
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                if let Err(e) = write_message(&mut *shared.stdin.lock().await, &response).await {
+                    tracing::warn!("Failed to answer language server request: {}", e);
+                }
+            }
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let params = &message["params"];
+                let Some(uri) = params["uri"].as_str() else {
+                    continue;
+                };
+                let items = params["diagnostics"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                {
+                    let mut diagnostics = shared.diagnostics.lock().unwrap();
+                    let published = diagnostics.entry(uri.to_string()).or_default();
+                    published.generation += 1;
+                    published.items = items;
+                }
+                shared.published.send_modify(|count| *count += 1);
+            }
+            _ => {}
+        }
+    }
+
+    // Fail whatever is still waiting, the server is gone
+    shared.pending.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_message_framing_round_trip() {
+        let mut buffer = Vec::new();
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "result": { "name": "héllo" } });
+        let second = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+        write_message(&mut buffer, &first).await.unwrap();
+        write_message(&mut buffer, &second).await.unwrap();
+
+        let mut reader = BufReader::new(buffer.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+
+        let mut missing = BufReader::new(&b"Content-Type: json\r\n\r\n{}"[..]);
+        assert!(read_message(&mut missing).await.is_err());
+    }
+}
@@ -0,0 +1,739 @@
+mod client;
+
+use rmcp::{
+    model::{CallToolResult, Content, ErrorCode, ErrorData, Role},
+    schemars::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use self::client::{uri_to_path, LspClient};
+
+/// Most references or symbols listed in one answer.
+const MAX_RESULTS: usize = 200;
+
+/// How long `diagnostics` waits for the server to re-check a file that changed.
+const DIAGNOSTICS_WAIT: Duration = Duration::from_secs(15);
+
+/// Parameters for the lsp tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LspParams {
+    /// The operation to perform. Allowed options are: `definition`, `references`, `hover`,
+    /// `diagnostics`, `symbols`.
+    pub command: String,
+
+    /// Absolute path to the file to query. For `symbols`, any file or directory in the
+    /// workspace to search.
+    pub path: String,
+
+    /// 1-indexed line of the position to query. Required for `definition`, `references`
+    /// and `hover`.
+    pub line: Option<usize>,
+
+    /// Name of the symbol on `line` to query, e.g. `parse_config`. Preferred over `character`.
+    pub symbol: Option<String>,
+
+    /// 1-indexed column on `line` to query, used when `symbol` is not given.
+    pub character: Option<usize>,
+
+    /// Symbol name or prefix to search for. Required for `symbols`.
+    pub query: Option<String>,
+}
+
+/// How to launch the language server for one language and where its workspaces start.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub language: &'static str,
+    pub command: String,
+    pub args: Vec<String>,
+    extensions: &'static [&'static str],
+    root_markers: &'static [&'static str],
+    /// Use the outermost directory with a marker, e.g. the cargo workspace over a member crate
+    outermost_root: bool,
+}
+
+impl ServerConfig {
+    fn new(
+        language: &'static str,
+        command: &str,
+        extensions: &'static [&'static str],
+        root_markers: &'static [&'static str],
+    ) -> Self {
+        let mut parts = command.split_whitespace().map(str::to_string);
+        Self {
+            language,
+            command: parts.next().unwrap_or_default(),
+            args: parts.collect(),
+            extensions,
+            root_markers,
+            outermost_root: false,
+        }
+    }
+
+    /// The servers goose knows about. `GOOSE_LSP_<LANGUAGE>` replaces the command line of a
+    /// server, e.g. `GOOSE_LSP_PYTHON="basedpyright-langserver --stdio"`, and `off` disables it.
+    fn defaults() -> Vec<Self> {
+        let mut rust = Self::new("rust", "rust-analyzer", &["rs"], &["Cargo.toml"]);
+        rust.outermost_root = true;
+        let servers = vec![
+            rust,
+            Self::new(
+                "python",
+                "pyright-langserver --stdio",
+                &["py", "pyi"],
+                &[
+                    "pyproject.toml",
+                    "pyrightconfig.json",
+                    "setup.py",
+                    "setup.cfg",
+                    "requirements.txt",
+                ],
+            ),
+            Self::new("go", "gopls", &["go"], &["go.work", "go.mod"]),
+            Self::new(
+                "typescript",
+                "typescript-language-server --stdio",
+                &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"],
+                &["tsconfig.json", "jsconfig.json", "package.json"],
+            ),
+        ];
+
+        servers
+            .into_iter()
+            .filter_map(|server| {
+                let key = format!("GOOSE_LSP_{}", server.language.to_uppercase());
+                match std::env::var(&key) {
+                    Ok(value) if value.trim().is_empty() || value.trim() == "off" => None,
+                    Ok(value) => Some(Self {
+                        outermost_root: server.outermost_root,
+                        ..Self::new(
+                            server.language,
+                            &value,
+                            server.extensions,
+                            server.root_markers,
+                        )
+                    }),
+                    Err(_) => Some(server),
+                }
+            })
+            .collect()
+    }
+
+    fn handles(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext))
+    }
+
+    pub fn language_id(&self, path: &Path) -> &'static str {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tsx") => "typescriptreact",
+            Some("jsx") => "javascriptreact",
+            Some("js") | Some("mjs") | Some("cjs") => "javascript",
+            _ => self.language,
+        }
+    }
+
+    /// The workspace the server is started in for `path`.
+    fn find_root(&self, path: &Path, cwd: &Path) -> PathBuf {
+        let start = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let mut roots = start.ancestors().filter(|dir| {
+            self.root_markers
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+        });
+        let root = if self.outermost_root {
+            roots.last()
+        } else {
+            roots.next()
+        };
+
+        match root {
+            Some(root) => root.to_path_buf(),
+            None if path.starts_with(cwd) => cwd.to_path_buf(),
+            None => start.to_path_buf(),
+        }
+    }
+}
+
+/// Running servers by language and workspace root.
+type Clients = HashMap<(&'static str, PathBuf), Arc<LspClient>>;
+
+/// Language servers started on demand, one per language and workspace. They are stopped
+/// when the last handle goes away with the developer extension.
+#[derive(Clone)]
+pub struct LspManager {
+    servers: Arc<Vec<ServerConfig>>,
+    clients: Arc<tokio::sync::Mutex<Clients>>,
+}
+
+impl Default for LspManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LspManager {
+    pub fn new() -> Self {
+        Self {
+            servers: Arc::new(ServerConfig::defaults()),
+            clients: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn run(&self, params: LspParams, path: PathBuf) -> Result<CallToolResult, ErrorData> {
+        let text = match params.command.as_str() {
+            "definition" | "references" | "hover" => {
+                let line = params.line.ok_or_else(|| {
+                    invalid_params(format!("Missing 'line' parameter for {}", params.command))
+                })?;
+                let (client, text, uri) = self.open(&path).await?;
+                let position = position(&text, line, params.symbol.as_deref(), params.character)
+                    .map_err(invalid_params)?;
+                let document = json!({ "textDocument": { "uri": uri }, "position": position });
+
+                match params.command.as_str() {
+                    "definition" => {
+                        let result = request(&client, "textDocument/definition", document).await?;
+                        format_locations(&result, "No definition found")
+                    }
+                    "references" => {
+                        let mut document = document;
+                        document["context"] = json!({ "includeDeclaration": true });
+                        let result = request(&client, "textDocument/references", document).await?;
+                        format_locations(&result, "No references found")
+                    }
+                    _ => {
+                        let result = request(&client, "textDocument/hover", document).await?;
+                        let hover = hover_text(&result["contents"]);
+                        if hover.trim().is_empty() {
+                            "No hover information at this position".to_string()
+                        } else {
+                            hover
+                        }
+                    }
+                }
+            }
+            "diagnostics" => {
+                let (client, uri, changed) = self.sync(&path).await?;
+                let before = client.diagnostics_generation(&uri);
+                let wait = if changed || before == 0 {
+                    DIAGNOSTICS_WAIT
+                } else {
+                    Duration::ZERO
+                };
+                let (diagnostics, fresh) = client.diagnostics(&uri, before, wait).await;
+                let mut text = format_diagnostics(&path, &diagnostics);
+                if !fresh && changed {
+                    text.push_str(&format!(
+                        "\n(The language server did not report back within {}s, these may be out of date.)",
+                        DIAGNOSTICS_WAIT.as_secs()
+                    ));
+                }
+                text
+            }
+            "symbols" => {
+                let query = params
+                    .query
+                    .ok_or_else(|| invalid_params("Missing 'query' parameter for symbols"))?;
+                let client = self.client_for(&path).await?;
+                let result =
+                    request(&client, "workspace/symbol", json!({ "query": query })).await?;
+                format_symbols(&result)
+            }
+            command => {
+                return Err(invalid_params(format!(
+                    "Unknown command '{}'. Allowed commands are: definition, references, hover, diagnostics, symbols",
+                    command
+                )))
+            }
+        };
+
+        Ok(CallToolResult::success(vec![
+            Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(text)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Makes sure the server has the file as it is on disk, returning its text and uri.
+    async fn open(&self, path: &Path) -> Result<(Arc<LspClient>, String, String), ErrorData> {
+        let text = read_file(path).await?;
+        let client = self.client_for(path).await?;
+        let (uri, _) = client.sync(path, &text).await.map_err(internal_error)?;
+        Ok((client, text, uri))
+    }
+
+    async fn sync(&self, path: &Path) -> Result<(Arc<LspClient>, String, bool), ErrorData> {
+        let text = read_file(path).await?;
+        let client = self.client_for(path).await?;
+        let (uri, changed) = client.sync(path, &text).await.map_err(internal_error)?;
+        Ok((client, uri, changed))
+    }
+
+    async fn client_for(&self, path: &Path) -> Result<Arc<LspClient>, ErrorData> {
+        let config = if path.is_dir() {
+            path.ancestors().find_map(|dir| {
+                self.servers.iter().find(|server| {
+                    server
+                        .root_markers
+                        .iter()
+                        .any(|marker| dir.join(marker).exists())
+                })
+            })
+        } else {
+            self.servers.iter().find(|server| server.handles(path))
+        }
+        .ok_or_else(|| {
+            invalid_params(format!(
+                "No language server is configured for '{}'. Supported languages: {}",
+                path.display(),
+                self.servers
+                    .iter()
+                    .map(|server| server.language)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients
+            .iter()
+            .find(|((language, root), _)| *language == config.language && path.starts_with(root))
+            .map(|(_, client)| client.clone())
+        {
+            return Ok(client);
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let root = config.find_root(path, &cwd);
+        tracing::info!(
+            "Starting {} language server {} in {}",
+            config.language,
+            config.command,
+            root.display()
+        );
+        let client = Arc::new(
+            LspClient::start(config, &root)
+                .await
+                .map_err(internal_error)?,
+        );
+        clients.insert((config.language, root), client.clone());
+        Ok(client)
+    }
+}
+
+async fn read_file(path: &Path) -> Result<String, ErrorData> {
+    if !path.is_file() {
+        return Err(invalid_params(format!(
+            "'{}' is not a file",
+            path.display()
+        )));
+    }
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| internal_error(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+async fn request(client: &LspClient, method: &str, params: Value) -> Result<Value, ErrorData> {
+    client.request(method, params).await.map_err(internal_error)
+}
+
+fn invalid_params(message: impl ToString) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message.to_string(), None)
+}
+
+fn internal_error(message: impl ToString) -> ErrorData {
+    ErrorData::new(ErrorCode::INTERNAL_ERROR, message.to_string(), None)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Turns a 1-indexed line and a symbol name or 1-indexed column into an LSP position, which
+/// counts UTF-16 code units from 0.
+fn position(
+    text: &str,
+    line: usize,
+    symbol: Option<&str>,
+    character: Option<usize>,
+) -> Result<Value, String> {
+    let line_count = text.lines().count();
+    let line_text = line
+        .checked_sub(1)
+        .and_then(|index| text.lines().nth(index))
+        .ok_or_else(|| {
+            format!(
+                "Line {} is out of range, the file has {} lines",
+                line, line_count
+            )
+        })?;
+
+    let byte_offset = match (symbol, character) {
+        (Some(symbol), _) => {
+            let mut matches = line_text.match_indices(symbol).map(|(offset, _)| offset);
+            let whole_word = line_text.match_indices(symbol).find(|(offset, _)| {
+                let before = line_text.get(..*offset).and_then(|s| s.chars().next_back());
+                let after = line_text
+                    .get(offset + symbol.len()..)
+                    .and_then(|s| s.chars().next());
+                !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+            });
+            whole_word
+                .map(|(offset, _)| offset)
+                .or_else(|| matches.next())
+                .ok_or_else(|| format!("'{}' does not appear on line {}", symbol, line))?
+        }
+        (None, Some(character)) => line_text
+            .char_indices()
+            .nth(character.saturating_sub(1))
+            .map_or(line_text.len(), |(offset, _)| offset),
+        (None, None) => line_text.len() - line_text.trim_start().len(),
+    };
+
+    let utf16_column: usize = line_text
+        .get(..byte_offset)
+        .unwrap_or_default()
+        .chars()
+        .map(char::len_utf16)
+        .sum();
+    Ok(json!({ "line": line - 1, "character": utf16_column }))
+}
+
+/// Resolves a range start to a displayable path relative to the working directory, a
+/// 1-indexed line and column and the text of that line.
+fn locate(uri: &str, range: &Value) -> (String, usize, usize, String) {
+    let line = range["start"]["line"].as_u64().unwrap_or(0) as usize;
+    let utf16_column = range["start"]["character"].as_u64().unwrap_or(0) as usize;
+
+    let Some(path) = uri_to_path(uri) else {
+        return (uri.to_string(), line + 1, utf16_column + 1, String::new());
+    };
+    let line_text = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|source| source.lines().nth(line).map(str::to_string))
+        .unwrap_or_default();
+
+    let mut units = 0;
+    let column = line_text
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= utf16_column
+        })
+        .count();
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let display = path
+        .strip_prefix(&cwd)
+        .unwrap_or(&path)
+        .display()
+        .to_string();
+    (display, line + 1, column + 1, line_text)
+}
+
+/// Location as `path:line:column` followed by the source line.
+fn format_location(uri: &str, range: &Value) -> String {
+    let (path, line, column, line_text) = locate(uri, range);
+    if line_text.trim().is_empty() {
+        format!("{}:{}:{}", path, line, column)
+    } else {
+        format!("{}:{}:{}: {}", path, line, column, line_text.trim())
+    }
+}
+
+/// Formats a `Location`, `Location[]` or `LocationLink[]` result.
+fn format_locations(result: &Value, empty: &str) -> String {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        single => vec![single.clone()],
+    };
+
+    let mut lines: Vec<String> = items
+        .iter()
+        .filter_map(|item| {
+            if let Some(uri) = item["targetUri"].as_str() {
+                Some(format_location(uri, &item["targetSelectionRange"]))
+            } else {
+                item["uri"]
+                    .as_str()
+                    .map(|uri| format_location(uri, &item["range"]))
+            }
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return empty.to_string();
+    }
+    let total = lines.len();
+    lines.truncate(MAX_RESULTS);
+    let mut text = lines.join("\n");
+    if total > MAX_RESULTS {
+        text.push_str(&format!("\n... and {} more", total - MAX_RESULTS));
+    }
+    text
+}
+
+/// Flattens `MarkupContent`, `MarkedString` or a list of them.
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(fields) => match (fields.get("language"), fields.get("value")) {
+            (Some(language), Some(Value::String(value))) => format!(
+                "```{}\n{}\n```",
+                language.as_str().unwrap_or_default(),
+                value
+            ),
+            (_, Some(Value::String(value))) => value.clone(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+fn format_diagnostics(path: &Path, diagnostics: &[Value]) -> String {
+    if diagnostics.is_empty() {
+        return format!("No problems reported for {}", path.display());
+    }
+
+    let uri = client::file_uri(path).unwrap_or_default();
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let severity = match diagnostic["severity"].as_u64() {
+                Some(1) => "error",
+                Some(2) => "warning",
+                Some(3) => "info",
+                Some(4) => "hint",
+                _ => "problem",
+            };
+            let source = match (diagnostic["source"].as_str(), &diagnostic["code"]) {
+                (Some(source), Value::String(code)) => format!("[{} {}]", source, code),
+                (Some(source), Value::Number(code)) => format!("[{} {}]", source, code),
+                (Some(source), _) => format!("[{}]", source),
+                (None, Value::String(code)) => format!("[{}]", code),
+                _ => String::new(),
+            };
+            let (path, line, column, _) = locate(&uri, &diagnostic["range"]);
+            format!(
+                "{}:{}:{} {}{}: {}",
+                path,
+                line,
+                column,
+                severity,
+                source,
+                diagnostic["message"].as_str().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn symbol_kind(kind: u64) -> &'static str {
+    const KINDS: [&str; 26] = [
+        "file",
+        "module",
+        "namespace",
+        "package",
+        "class",
+        "method",
+        "property",
+        "field",
+        "constructor",
+        "enum",
+        "interface",
+        "function",
+        "variable",
+        "constant",
+        "string",
+        "number",
+        "boolean",
+        "array",
+        "object",
+        "key",
+        "null",
+        "enum member",
+        "struct",
+        "event",
+        "operator",
+        "type parameter",
+    ];
+    kind.checked_sub(1)
+        .and_then(|index| KINDS.get(index as usize))
+        .copied()
+        .unwrap_or("symbol")
+}
+
+/// Formats `SymbolInformation[]` or `WorkspaceSymbol[]`.
+fn format_symbols(result: &Value) -> String {
+    let symbols = result.as_array().cloned().unwrap_or_default();
+    if symbols.is_empty() {
+        return "No matching symbols found".to_string();
+    }
+
+    let mut lines: Vec<String> = symbols
+        .iter()
+        .take(MAX_RESULTS)
+        .map(|symbol| {
+            let name = symbol["name"].as_str().unwrap_or_default();
+            let kind = symbol_kind(symbol["kind"].as_u64().unwrap_or(0));
+            let container = symbol["containerName"]
+                .as_str()
+                .filter(|container| !container.is_empty())
+                .map(|container| format!(" in {}", container))
+                .unwrap_or_default();
+            let location = &symbol["location"];
+            let location = match location["uri"].as_str() {
+                Some(uri) if location.get("range").is_some() => {
+                    format_location(uri, &location["range"])
+                }
+                Some(uri) => uri_to_path(uri)
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| uri.to_string()),
+                None => String::new(),
+            };
+            format!("{} {}{} - {}", kind, name, container, location)
+        })
+        .collect();
+    if symbols.len() > MAX_RESULTS {
+        lines.push(format!("... and {} more", symbols.len() - MAX_RESULTS));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_position_from_symbol_or_column() {
+        let text = "fn main() {\n    let total = café_count + café;\n}\n";
+
+        // Whole words win over earlier partial matches
+        assert_eq!(
+            position(text, 2, Some("café"), None).unwrap(),
+            json!({ "line": 1, "character": 29 })
+        );
+        assert_eq!(
+            position(text, 2, Some("count"), None).unwrap(),
+            json!({ "line": 1, "character": 21 })
+        );
+        // Columns count UTF-16 code units
+        assert_eq!(
+            position("a 😀 b", 1, Some("b"), None).unwrap(),
+            json!({ "line": 0, "character": 5 })
+        );
+        assert_eq!(
+            position(text, 2, None, None).unwrap(),
+            json!({ "line": 1, "character": 4 })
+        );
+        assert_eq!(
+            position(text, 1, None, Some(4)).unwrap(),
+            json!({ "line": 0, "character": 3 })
+        );
+        assert!(position(text, 2, Some("missing"), None).is_err());
+        assert!(position(text, 9, None, None).is_err());
+    }
+
+    #[test]
+    fn test_find_root() {
+        let dir = TempDir::new().unwrap();
+        let member = dir.path().join("crates/core/src");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.path().join("crates/core/Cargo.toml"), "").unwrap();
+        std::fs::write(dir.path().join("crates/core/pyproject.toml"), "").unwrap();
+        let file = member.join("lib.rs");
+
+        let servers = ServerConfig::defaults();
+        let rust = servers.iter().find(|s| s.language == "rust").unwrap();
+        let python = servers.iter().find(|s| s.language == "python").unwrap();
+        assert!(rust.handles(&file));
+        assert_eq!(rust.find_root(&file, Path::new("/elsewhere")), dir.path());
+        assert_eq!(
+            python.find_root(&member.join("tool.py"), Path::new("/elsewhere")),
+            dir.path().join("crates/core")
+        );
+
+        let loose = TempDir::new().unwrap();
+        let script = loose.path().join("main.go");
+        let go = servers.iter().find(|s| s.language == "go").unwrap();
+        assert_eq!(go.find_root(&script, loose.path()), loose.path());
+    }
+
+    #[test]
+    fn test_format_results() {
+        assert_eq!(
+            hover_text(&json!([
+                { "language": "rust", "value": "fn main()" },
+                "Entry point"
+            ])),
+            "```rust\nfn main()\n```\n\nEntry point"
+        );
+        assert_eq!(
+            hover_text(&json!({ "kind": "markdown", "value": "**x**: i32" })),
+            "**x**: i32"
+        );
+
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "fn main() {\n    let x: i32 = \"a\";\n}\n").unwrap();
+        let uri = client::file_uri(&file).unwrap();
+        let range = json!({ "start": { "line": 1, "character": 17 }, "end": { "line": 1, "character": 20 } });
+
+        let diagnostics = format_diagnostics(
+            &file,
+            &[
+                json!({ "range": range, "severity": 1, "source": "rustc", "code": "E0308", "message": "mismatched types" }),
+            ],
+        );
+        assert_eq!(
+            diagnostics,
+            format!(
+                "{}:2:18 error[rustc E0308]: mismatched types",
+                file.display()
+            )
+        );
+
+        let locations = format_locations(
+            &json!([{ "uri": uri, "range": range }]),
+            "No references found",
+        );
+        assert_eq!(
+            locations,
+            format!("{}:2:18: let x: i32 = \"a\";", file.display())
+        );
+        assert_eq!(
+            format_locations(&Value::Null, "No definition found"),
+            "No definition found"
+        );
+
+        let symbols = format_symbols(&json!([{
+            "name": "main", "kind": 12, "location": { "uri": uri, "range": { "start": { "line": 0, "character": 3 } } }
+        }]));
+        assert_eq!(
+            symbols,
+            format!("function main - {}:1:4: fn main() {{", file.display())
+        );
+    }
+}
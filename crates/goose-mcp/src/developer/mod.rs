@@ -1,11 +1,19 @@
 pub mod analyze;
 mod editor_models;
+mod git;
 mod lang;
+mod lsp;
+mod notebook;
 pub mod paths;
+mod processes;
+mod search;
 mod shell;
+mod test_runner;
 mod text_editor;
 
 pub mod rmcp_developer;
 
+pub use editor_models::{create_editor_model, ChainedEdit, EditorChain, EditorModel};
+
 #[cfg(test)]
 mod tests;
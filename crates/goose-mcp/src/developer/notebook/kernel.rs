@@ -0,0 +1,285 @@
+use rmcp::model::{ErrorCode, ErrorData};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::Mutex,
+};
+use tokio_util::sync::CancellationToken;
+
+/// How long a cell may run before the kernel is interrupted.
+const EXECUTION_TIMEOUT_SECS: u64 = 300;
+
+/// How long a kernel may take to start.
+const STARTUP_TIMEOUT_SECS: u64 = 60;
+
+/// Bytes of the bridge's stderr kept to explain why it stopped.
+const MAX_STDERR_BYTES: usize = 4096;
+
+/// Talks to a Jupyter kernel through jupyter_client, one JSON request and reply per line.
+/// Outputs are collected in nbformat's shape so they can be stored in the notebook as-is.
+const BRIDGE: &str = r#"
+import json, sys
+from jupyter_client.manager import start_new_kernel
+
+km, kc = start_new_kernel(kernel_name=sys.argv[1] or "python3", cwd=sys.argv[2])
+print(json.dumps({"ready": True}), flush=True)
+
+for line in sys.stdin:
+    request = json.loads(line)
+    outputs = []
+
+    def hook(msg):
+        kind, content = msg["header"]["msg_type"], msg["content"]
+        if kind == "stream":
+            last = outputs[-1] if outputs else None
+            if last and last["output_type"] == "stream" and last["name"] == content["name"]:
+                last["text"] += content["text"]
+            else:
+                outputs.append({"name": content["name"], "output_type": "stream", "text": content["text"]})
+        elif kind in ("display_data", "execute_result"):
+            output = {"data": content["data"], "metadata": content.get("metadata", {}), "output_type": kind}
+            if kind == "execute_result":
+                output["execution_count"] = content.get("execution_count")
+            outputs.append(output)
+        elif kind == "error":
+            outputs.append({"ename": content["ename"], "evalue": content["evalue"], "output_type": "error", "traceback": content["traceback"]})
+        elif kind == "clear_output":
+            outputs.clear()
+
+    try:
+        reply = kc.execute_interactive(request["code"], output_hook=hook, allow_stdin=False, timeout=request["timeout"])
+        status, count = reply["content"]["status"], reply["content"].get("execution_count")
+    except TimeoutError:
+        km.interrupt_kernel()
+        status, count = "timeout", None
+    print(json.dumps({"status": status, "execution_count": count, "outputs": outputs}), flush=True)
+"#;
+
+/// The result of running one cell.
+#[derive(Debug, Deserialize)]
+pub struct Execution {
+    pub status: String,
+    pub execution_count: Option<i64>,
+    pub outputs: Vec<Value>,
+}
+
+struct KernelSession {
+    // Dropping the session kills the bridge, which shuts its kernel down
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    stderr: Arc<std::sync::Mutex<String>>,
+}
+
+impl KernelSession {
+    async fn start(kernel_name: Option<&str>, dir: &Path) -> Result<Self, ErrorData> {
+        let python = which::which("python3")
+            .or_else(|_| which::which("python"))
+            .map_err(|_| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    "Executing notebook cells needs Python with Jupyter installed".to_string(),
+                    None,
+                )
+            })?;
+
+        let mut command = Command::new(python);
+        command
+            .arg("-c")
+            .arg(BRIDGE)
+            .arg(kernel_name.unwrap_or_default())
+            .arg(dir)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command.spawn().map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to start Python for the notebook kernel: {}", e),
+                None,
+            )
+        })?;
+
+        // Drain stderr so a chatty kernel can't block, keeping the end to explain failures
+        let stderr = Arc::new(std::sync::Mutex::new(String::new()));
+        let mut pipe = child.stderr.take().unwrap();
+        let kept = stderr.clone();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1024];
+            while let Ok(read) = pipe.read(&mut buffer).await {
+                if read == 0 {
+                    break;
+                }
+                let mut kept = kept.lock().unwrap();
+                kept.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                if kept.len() > MAX_STDERR_BYTES {
+                    let mut cut = kept.len() - MAX_STDERR_BYTES;
+                    while !kept.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    kept.drain(..cut);
+                }
+            }
+        });
+
+        let mut session = Self {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()).lines(),
+            _child: child,
+            stderr,
+        };
+        let ready = session
+            .read_reply(Duration::from_secs(STARTUP_TIMEOUT_SECS))
+            .await
+            .map_err(|e| {
+                let message = if e.message.contains("No module named 'jupyter_client'") {
+                    "Executing notebook cells needs Jupyter, install it with `pip install jupyter_client ipykernel`".to_string()
+                } else {
+                    format!("Failed to start the notebook kernel: {}", e.message)
+                };
+                ErrorData::new(ErrorCode::INTERNAL_ERROR, message, None)
+            })?;
+        if ready["ready"] != true {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                "The notebook kernel did not start".to_string(),
+                None,
+            ));
+        }
+        Ok(session)
+    }
+
+    /// The next JSON line from the bridge, skipping anything else it printed.
+    async fn read_reply(&mut self, timeout: Duration) -> Result<Value, ErrorData> {
+        let read = async {
+            while let Some(line) = self.stdout.next_line().await? {
+                if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                    return Ok(Some(value));
+                }
+            }
+            Ok::<_, std::io::Error>(None)
+        };
+        match tokio::time::timeout(timeout, read).await {
+            Ok(Ok(Some(value))) => Ok(value),
+            Ok(Ok(None)) | Ok(Err(_)) => {
+                // Give the stderr task a moment to catch the bridge's last words
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let stderr = self.stderr.lock().unwrap().trim().to_string();
+                Err(ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("The notebook kernel stopped: {}", stderr),
+                    None,
+                ))
+            }
+            Err(_) => Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                "The notebook kernel stopped responding".to_string(),
+                None,
+            )),
+        }
+    }
+
+    async fn execute(&mut self, code: &str) -> Result<Execution, ErrorData> {
+        let request = json!({ "code": code, "timeout": EXECUTION_TIMEOUT_SECS }).to_string() + "\n";
+        self.stdin
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("The notebook kernel stopped: {}", e),
+                    None,
+                )
+            })?;
+        // The bridge interrupts a cell at the timeout, allow it time to report back
+        let reply = self
+            .read_reply(Duration::from_secs(EXECUTION_TIMEOUT_SECS + 30))
+            .await?;
+        serde_json::from_value(reply).map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Unexpected reply from the notebook kernel: {}", e),
+                None,
+            )
+        })
+    }
+}
+
+/// A notebook's kernel, `None` until a cell runs or after it stopped.
+type KernelSlot = Arc<Mutex<Option<KernelSession>>>;
+
+/// Kernels for the notebooks cells were run in, started on first use and kept so later
+/// cells see the state earlier ones left, as in Jupyter. They stop with the extension.
+#[derive(Clone, Default)]
+pub struct NotebookKernels {
+    // Each notebook's kernel has its own lock, so a long cell only holds up its own notebook
+    sessions: Arc<std::sync::Mutex<HashMap<PathBuf, KernelSlot>>>,
+}
+
+impl NotebookKernels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `code` in the notebook's kernel. Cancelling stops the kernel, as the cell may
+    /// have left it in any state, and the next run starts a fresh one.
+    pub async fn execute(
+        &self,
+        notebook: &Path,
+        kernel_name: Option<&str>,
+        code: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<Execution, ErrorData> {
+        let kernel = self
+            .sessions
+            .lock()
+            .unwrap()
+            .entry(notebook.to_path_buf())
+            .or_default()
+            .clone();
+        let mut kernel = tokio::select! {
+            kernel = kernel.lock() => kernel,
+            _ = cancellation_token.cancelled() => {
+                return Err(cancelled("while waiting for the cell running before it"))
+            }
+        };
+
+        let run = async {
+            if kernel.is_none() {
+                let dir = notebook.parent().unwrap_or(Path::new("."));
+                *kernel = Some(KernelSession::start(kernel_name, dir).await?);
+            }
+            kernel.as_mut().expect("started above").execute(code).await
+        };
+        let result = tokio::select! {
+            result = run => result,
+            _ = cancellation_token.cancelled() => {
+                Err(cancelled("and the notebook's kernel was stopped"))
+            }
+        };
+        if result.is_err() {
+            // A kernel that failed to answer is gone, the next run starts a fresh one
+            *kernel = None;
+        }
+        result
+    }
+}
+
+fn cancelled(detail: &str) -> ErrorData {
+    ErrorData::new(
+        ErrorCode::INTERNAL_ERROR,
+        format!("The cell was cancelled {}", detail),
+        None,
+    )
+}
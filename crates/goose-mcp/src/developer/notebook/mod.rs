@@ -0,0 +1,843 @@
+mod kernel;
+
+pub use kernel::NotebookKernels;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rmcp::{
+    model::{Content, ErrorCode, ErrorData, Role},
+    schemars::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, ser::PrettyFormatter, Value};
+use std::{
+    collections::{hash_map::RandomState, HashMap, HashSet},
+    fmt::Write,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use super::rmcp_developer::TextEditorParams;
+use super::text_editor::save_file_history;
+
+/// Lines kept from one cell output, split between its start and end.
+const MAX_OUTPUT_LINES: usize = 30;
+
+/// Characters kept from one cell output.
+const MAX_OUTPUT_CHARS: usize = 3_000;
+
+/// Characters of a whole notebook view before the remaining cells are left out.
+const MAX_VIEW_CHARS: usize = 100_000;
+
+static ANSI_ESCAPE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+/// Parameters for the notebook_execute tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NotebookExecuteParams {
+    /// Absolute path to the `.ipynb` notebook.
+    pub path: String,
+
+    /// The 0-indexed code cell to run.
+    pub cell_index: usize,
+}
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "ipynb")
+}
+
+fn invalid_params(message: impl Into<String>) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message.into(), None)
+}
+
+fn internal_error(message: impl Into<String>) -> ErrorData {
+    ErrorData::new(ErrorCode::INTERNAL_ERROR, message.into(), None)
+}
+
+/// A Jupyter notebook kept as JSON, so fields this module doesn't know about survive edits.
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    value: Value,
+    indent: usize,
+}
+
+impl Notebook {
+    pub fn parse(text: &str) -> Result<Self, ErrorData> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| invalid_params(format!("The notebook is not valid JSON: {}", e)))?;
+        if !value["cells"].is_array() || value["nbformat"].as_u64() != Some(4) {
+            return Err(invalid_params(
+                "Only nbformat 4 notebooks with a cells list are supported",
+            ));
+        }
+        // Jupyter writes one space of indentation, keep whatever the file uses
+        let indent = text
+            .lines()
+            .nth(1)
+            .map(|line| line.len() - line.trim_start().len())
+            .filter(|&indent| indent > 0)
+            .unwrap_or(1);
+        Ok(Self { value, indent })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ErrorData> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| internal_error(format!("Failed to read notebook: {}", e)))?;
+        Self::parse(&text)
+    }
+
+    pub fn to_json(&self) -> String {
+        let indent = " ".repeat(self.indent);
+        let mut buffer = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(
+            &mut buffer,
+            PrettyFormatter::with_indent(indent.as_bytes()),
+        );
+        self.value
+            .serialize(&mut serializer)
+            .expect("a JSON value always serializes");
+        String::from_utf8(buffer).expect("serde_json writes UTF-8") + "\n"
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ErrorData> {
+        std::fs::write(path, self.to_json())
+            .map_err(|e| internal_error(format!("Failed to write notebook: {}", e)))
+    }
+
+    fn cells(&self) -> &Vec<Value> {
+        self.value["cells"].as_array().expect("checked on parse")
+    }
+
+    fn cells_mut(&mut self) -> &mut Vec<Value> {
+        self.value["cells"]
+            .as_array_mut()
+            .expect("checked on parse")
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells().is_empty()
+    }
+
+    fn cell(&self, index: usize) -> Result<&Value, ErrorData> {
+        self.cells().get(index).ok_or_else(|| {
+            invalid_params(format!(
+                "No cell {}, the notebook has {} cells (0-indexed)",
+                index,
+                self.len()
+            ))
+        })
+    }
+
+    fn cell_mut(&mut self, index: usize) -> Result<&mut Value, ErrorData> {
+        let len = self.len();
+        self.cells_mut().get_mut(index).ok_or_else(|| {
+            invalid_params(format!(
+                "No cell {}, the notebook has {} cells (0-indexed)",
+                index, len
+            ))
+        })
+    }
+
+    /// The kernel the notebook was last run with, from its metadata.
+    pub fn kernel_name(&self) -> Option<&str> {
+        self.value["metadata"]["kernelspec"]["name"].as_str()
+    }
+
+    pub fn source(&self, index: usize) -> Result<String, ErrorData> {
+        Ok(multiline(&self.cell(index)?["source"]))
+    }
+
+    pub fn cell_type(&self, index: usize) -> Result<&str, ErrorData> {
+        Ok(self.cell(index)?["cell_type"].as_str().unwrap_or("code"))
+    }
+
+    /// Cells need an id from nbformat 4.5 on.
+    fn new_cell(&self, cell_type: &str, source: &str) -> Result<Value, ErrorData> {
+        let mut cell = match cell_type {
+            "code" => json!({
+                "cell_type": "code",
+                "execution_count": null,
+                "metadata": {},
+                "outputs": [],
+                "source": source_lines(source),
+            }),
+            "markdown" | "raw" => json!({
+                "cell_type": cell_type,
+                "metadata": {},
+                "source": source_lines(source),
+            }),
+            _ => {
+                return Err(invalid_params(format!(
+                    "Unknown cell_type '{}'. Allowed options are: code, markdown, raw",
+                    cell_type
+                )))
+            }
+        };
+        if self.value["nbformat_minor"].as_u64().unwrap_or(0) >= 5 {
+            let ids: HashSet<&str> = self
+                .cells()
+                .iter()
+                .filter_map(|c| c["id"].as_str())
+                .collect();
+            let id = loop {
+                let mut hasher = RandomState::new().build_hasher();
+                hasher.write_usize(ids.len());
+                let id = format!("{:08x}", hasher.finish() as u32);
+                if !ids.contains(id.as_str()) {
+                    break id;
+                }
+            };
+            cell["id"] = json!(id);
+        }
+        Ok(cell)
+    }
+
+    /// Replace a cell's source, and its type if `cell_type` is given. Outputs of an edited
+    /// code cell are cleared because they no longer match its source.
+    pub fn edit_cell(
+        &mut self,
+        index: usize,
+        source: &str,
+        cell_type: Option<&str>,
+    ) -> Result<(), ErrorData> {
+        let current = self.cell_type(index)?.to_string();
+        let cell_type = cell_type.unwrap_or(&current);
+        if cell_type != current {
+            let mut replacement = self.new_cell(cell_type, source)?;
+            let cell = self.cell_mut(index)?;
+            for key in ["id", "metadata"] {
+                if let Some(value) = cell.get(key).filter(|_| replacement.get(key).is_some()) {
+                    replacement[key] = value.clone();
+                }
+            }
+            *cell = replacement;
+            return Ok(());
+        }
+
+        let cell = self.cell_mut(index)?;
+        cell["source"] = source_lines(source);
+        if cell_type == "code" {
+            cell["outputs"] = json!([]);
+            cell["execution_count"] = Value::Null;
+        }
+        Ok(())
+    }
+
+    /// Insert a cell so it ends up at `index`, or at the end without one.
+    pub fn insert_cell(
+        &mut self,
+        index: Option<usize>,
+        cell_type: &str,
+        source: &str,
+    ) -> Result<usize, ErrorData> {
+        let index = index.unwrap_or(self.len());
+        if index > self.len() {
+            return Err(invalid_params(format!(
+                "Can't insert at {}, the notebook has {} cells",
+                index,
+                self.len()
+            )));
+        }
+        let cell = self.new_cell(cell_type, source)?;
+        self.cells_mut().insert(index, cell);
+        Ok(index)
+    }
+
+    pub fn delete_cell(&mut self, index: usize) -> Result<(), ErrorData> {
+        self.cell(index)?;
+        self.cells_mut().remove(index);
+        Ok(())
+    }
+
+    /// Move a cell so it ends up at `to`.
+    pub fn move_cell(&mut self, from: usize, to: usize) -> Result<(), ErrorData> {
+        self.cell(from)?;
+        if to >= self.len() {
+            return Err(invalid_params(format!(
+                "Can't move to {}, the notebook has {} cells (0-indexed)",
+                to,
+                self.len()
+            )));
+        }
+        let cell = self.cells_mut().remove(from);
+        self.cells_mut().insert(to, cell);
+        Ok(())
+    }
+
+    /// Replace text that appears exactly once across all cell sources, returning the cell.
+    pub fn replace_in_cells(&mut self, old_str: &str, new_str: &str) -> Result<usize, ErrorData> {
+        let matches: Vec<(usize, usize)> = (0..self.len())
+            .map(|index| (index, multiline(&self.cells()[index]["source"])))
+            .map(|(index, source)| (index, source.matches(old_str).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        match matches.as_slice() {
+            [] => Err(invalid_params(
+                "'old_str' was not found in any cell. Make sure it matches the cell source exactly",
+            )),
+            [(index, 1)] => {
+                let index = *index;
+                let source = self.source(index)?.replacen(old_str, new_str, 1);
+                let cell_type = self.cell_type(index)?.to_string();
+                self.edit_cell(index, &source, Some(&cell_type))?;
+                Ok(index)
+            }
+            _ => Err(invalid_params(
+                "'old_str' appears more than once across the cells, include more context or use edit_cell",
+            )),
+        }
+    }
+
+    pub fn set_outputs(
+        &mut self,
+        index: usize,
+        outputs: Vec<Value>,
+        execution_count: Option<i64>,
+    ) -> Result<(), ErrorData> {
+        let cell = self.cell_mut(index)?;
+        cell["outputs"] = Value::Array(outputs);
+        cell["execution_count"] = execution_count.map_or(Value::Null, Value::from);
+        Ok(())
+    }
+
+    /// Where a cell is after the file changed: the cell with its id if it has one, otherwise
+    /// the code cell with its source, preferring `index`. `None` once it was edited or removed.
+    fn find_cell(&self, index: usize, id: Option<&str>, source: &str) -> Option<usize> {
+        let cells = self.cells();
+        let is_same = |cell: &Value| {
+            cell["cell_type"].as_str().unwrap_or("code") == "code"
+                && multiline(&cell["source"]) == source
+        };
+        match id {
+            Some(id) => cells
+                .iter()
+                .position(|cell| cell["id"].as_str() == Some(id))
+                .filter(|&found| is_same(&cells[found])),
+            None if cells.get(index).is_some_and(is_same) => Some(index),
+            None => cells.iter().position(is_same),
+        }
+    }
+
+    fn render_cell(&self, index: usize, output: &mut String) {
+        let cell = &self.cells()[index];
+        let cell_type = cell["cell_type"].as_str().unwrap_or("code");
+        let _ = write!(output, "[{}] {}", index, cell_type);
+        if let Some(count) = cell["execution_count"].as_i64() {
+            let _ = write!(output, " (execution {})", count);
+        }
+        output.push('\n');
+        let source = multiline(&cell["source"]);
+        if !source.is_empty() {
+            output.push_str(source.trim_end_matches('\n'));
+            output.push('\n');
+        }
+        if let Some(outputs) = cell["outputs"].as_array().filter(|o| !o.is_empty()) {
+            let (text, images) = render_outputs(outputs);
+            output.push_str("Output:\n");
+            output.push_str(&text);
+            for (_, mime) in images {
+                let _ = writeln!(output, "[{} output]", mime);
+            }
+        }
+        output.push('\n');
+    }
+
+    /// Cells with their index and type, code cells with their outputs. `view_range` picks
+    /// cells by index, with -1 as the end meaning the last cell.
+    pub fn view(&self, view_range: Option<(usize, i64)>) -> Result<String, ErrorData> {
+        let last = self.len().saturating_sub(1);
+        let (start, end) = match view_range {
+            Some((start, end)) => {
+                let end = if end < 0 {
+                    last
+                } else {
+                    (end as usize).min(last)
+                };
+                if start > end {
+                    return Err(invalid_params(format!(
+                        "Invalid cell range {}-{}, the notebook has {} cells (0-indexed)",
+                        start,
+                        end,
+                        self.len()
+                    )));
+                }
+                (start, end)
+            }
+            None => (0, last),
+        };
+
+        let mut output = format!(
+            "{} cells, {} kernel\n\n",
+            self.len(),
+            self.kernel_name().unwrap_or("unknown")
+        );
+        if self.is_empty() {
+            return Ok(output);
+        }
+        for index in start..=end {
+            if output.len() > MAX_VIEW_CHARS {
+                let _ = writeln!(
+                    output,
+                    "Cells {}-{} not shown, use view_range to see them",
+                    index, end
+                );
+                break;
+            }
+            self.render_cell(index, &mut output);
+        }
+        Ok(output)
+    }
+}
+
+/// nbformat stores multiline strings either whole or as a list of lines.
+fn multiline(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Split a source into lines the way Jupyter saves them, each keeping its newline.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+fn truncate_output(text: &str) -> String {
+    let text = ANSI_ESCAPE.replace_all(text, "");
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let mut kept = if lines.len() > MAX_OUTPUT_LINES {
+        let head = MAX_OUTPUT_LINES / 2;
+        let tail = MAX_OUTPUT_LINES - head;
+        let mut kept = lines[..head].join("\n");
+        let _ = write!(
+            kept,
+            "\n... {} lines omitted ...\n",
+            lines.len() - MAX_OUTPUT_LINES
+        );
+        kept.push_str(&lines[lines.len() - tail..].join("\n"));
+        kept
+    } else {
+        lines.join("\n")
+    };
+    if kept.len() > MAX_OUTPUT_CHARS {
+        let mut end = MAX_OUTPUT_CHARS;
+        while !kept.is_char_boundary(end) {
+            end -= 1;
+        }
+        kept.truncate(end);
+        kept.push_str("\n... output truncated ...");
+    }
+    kept + "\n"
+}
+
+/// The text of cell outputs and the images among them, as base64 data and mime type.
+pub fn render_outputs(outputs: &[Value]) -> (String, Vec<(String, String)>) {
+    let mut text = String::new();
+    let mut images = Vec::new();
+    for output in outputs {
+        match output["output_type"].as_str() {
+            Some("stream") => text.push_str(&truncate_output(&multiline(&output["text"]))),
+            Some("error") => {
+                let traceback: Vec<String> = output["traceback"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|line| line.as_str().map(str::to_string))
+                    .collect();
+                let error = if traceback.is_empty() {
+                    format!(
+                        "{}: {}",
+                        output["ename"].as_str().unwrap_or("Error"),
+                        output["evalue"].as_str().unwrap_or_default()
+                    )
+                } else {
+                    traceback.join("\n")
+                };
+                text.push_str(&truncate_output(&error));
+            }
+            Some("execute_result") | Some("display_data") => {
+                let data = &output["data"];
+                let mut shown = false;
+                for mime in ["image/png", "image/jpeg", "image/gif"] {
+                    if let Some(image) = data.get(mime) {
+                        images.push((multiline(image).replace('\n', ""), mime.to_string()));
+                        shown = true;
+                    }
+                }
+                if let Some(plain) = data.get("text/plain").filter(|_| !shown) {
+                    text.push_str(&truncate_output(&multiline(plain)));
+                } else if !shown {
+                    let kinds: Vec<&String> = data
+                        .as_object()
+                        .map(|d| d.keys().collect())
+                        .unwrap_or_default();
+                    let kinds: Vec<&str> = kinds.iter().map(|k| k.as_str()).collect();
+                    let _ = writeln!(text, "[{} output]", kinds.join(", "));
+                }
+            }
+            _ => {}
+        }
+    }
+    (text, images)
+}
+
+fn required<T>(value: Option<T>, name: &str, command: &str) -> Result<T, ErrorData> {
+    value.ok_or_else(|| {
+        invalid_params(format!(
+            "Missing '{}' parameter for {} command",
+            name, command
+        ))
+    })
+}
+
+/// Run a text_editor command on a notebook. Edits go through the notebook's cells so the
+/// file stays a valid notebook, and are saved in the file history for `undo_edit`.
+pub async fn notebook_command(
+    path: &PathBuf,
+    params: &TextEditorParams,
+    file_history: &Arc<Mutex<HashMap<PathBuf, Vec<String>>>>,
+) -> Result<Vec<Content>, ErrorData> {
+    let command = params.command.as_str();
+    if command == "write" {
+        let file_text = required(params.file_text.as_deref(), "file_text", command)?;
+        let notebook = Notebook::parse(file_text)?;
+        save_file_history(path, file_history)?;
+        notebook.save(path)?;
+        return Ok(vec![Content::text(format!(
+            "Successfully wrote notebook {} with {} cells",
+            path.display(),
+            notebook.len()
+        ))]);
+    }
+
+    let mut notebook = Notebook::load(path)?;
+    // What changed, and the cell to show after the change
+    let (summary, edited) = match command {
+        "view" => {
+            let view_range = params
+                .view_range
+                .as_ref()
+                .filter(|range| range.len() == 2)
+                .map(|range| (range[0].max(0) as usize, range[1]));
+            let view = notebook.view(view_range)?;
+            return Ok(vec![
+                Content::text(view.clone()).with_audience(vec![Role::Assistant]),
+                Content::text(view)
+                    .with_audience(vec![Role::User])
+                    .with_priority(0.0),
+            ]);
+        }
+        "execute_cell" => {
+            return Err(invalid_params(
+                "Use the notebook_execute tool to run a cell",
+            ))
+        }
+        "edit_cell" => {
+            let index = required(params.cell_index, "cell_index", command)?;
+            let source = required(params.new_str.as_deref(), "new_str", command)?;
+            notebook.edit_cell(index, source, params.cell_type.as_deref())?;
+            (format!("Edited cell {}", index), Some(index))
+        }
+        "str_replace" => {
+            let old_str = required(params.old_str.as_deref(), "old_str", command)?;
+            let new_str = required(params.new_str.as_deref(), "new_str", command)?;
+            let index = notebook.replace_in_cells(old_str, new_str)?;
+            (format!("Edited cell {}", index), Some(index))
+        }
+        "insert_cell" => {
+            let source = params.new_str.as_deref().unwrap_or_default();
+            let cell_type = params.cell_type.as_deref().unwrap_or("code");
+            let index = notebook.insert_cell(params.cell_index, cell_type, source)?;
+            (format!("Inserted {} cell {}", cell_type, index), Some(index))
+        }
+        "delete_cell" => {
+            let index = required(params.cell_index, "cell_index", command)?;
+            notebook.delete_cell(index)?;
+            let summary = format!("Deleted cell {}, {} cells remain", index, notebook.len());
+            (summary, None)
+        }
+        "move_cell" => {
+            let from = required(params.cell_index, "cell_index", command)?;
+            let to = required(params.to_index, "to_index", command)?;
+            notebook.move_cell(from, to)?;
+            (format!("Moved cell {} to {}", from, to), None)
+        }
+        "insert" => {
+            return Err(invalid_params(
+                "Use insert_cell to add a cell to a notebook, or edit_cell to change one",
+            ))
+        }
+        _ => {
+            return Err(invalid_params(format!(
+                "Unknown notebook command '{}'. Allowed options are: view, write, str_replace, edit_cell, insert_cell, delete_cell, move_cell, undo_edit",
+                command
+            )))
+        }
+    };
+
+    save_file_history(path, file_history)?;
+    notebook.save(path)?;
+
+    let mut text = format!("{} of {}\n", summary, path.display());
+    if let Some(index) = edited {
+        text.push('\n');
+        notebook.render_cell(index, &mut text);
+    }
+    Ok(vec![
+        Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+        Content::text(text)
+            .with_audience(vec![Role::User])
+            .with_priority(0.2),
+    ])
+}
+
+/// Run a code cell in the notebook's kernel, store its outputs in the notebook the way
+/// Jupyter does, and return them as text and images.
+pub async fn execute_cell(
+    path: &PathBuf,
+    index: usize,
+    file_history: &Arc<Mutex<HashMap<PathBuf, Vec<String>>>>,
+    kernels: &NotebookKernels,
+    cancellation_token: CancellationToken,
+) -> Result<Vec<Content>, ErrorData> {
+    let notebook = Notebook::load(path)?;
+    if notebook.cell_type(index)? != "code" {
+        return Err(invalid_params(format!("Cell {} is not a code cell", index)));
+    }
+    let code = notebook.source(index)?;
+    let id = notebook.cell(index)?["id"].as_str().map(str::to_string);
+    let execution = kernels
+        .execute(path, notebook.kernel_name(), &code, cancellation_token)
+        .await?;
+
+    // The file may have changed while the cell ran, so the cell is found again rather than
+    // trusting its index
+    let mut notebook = Notebook::load(path)?;
+    let stored = notebook.find_cell(index, id.as_deref(), &code);
+    if let Some(current) = stored {
+        notebook.set_outputs(
+            current,
+            execution.outputs.clone(),
+            execution.execution_count,
+        )?;
+        save_file_history(path, file_history)?;
+        notebook.save(path)?;
+    }
+
+    let (output, images) = render_outputs(&execution.outputs);
+    let mut text = format!("Executed cell {}", stored.unwrap_or(index));
+    if let Some(count) = execution.execution_count {
+        let _ = write!(text, " (execution {})", count);
+    }
+    let _ = writeln!(text, ", status {}", execution.status);
+    if stored.is_none() {
+        text.push_str(
+            "The cell was edited or removed while it ran, so its outputs were not saved\n",
+        );
+    }
+    if !output.is_empty() {
+        text.push_str(&output);
+    }
+
+    let mut content = vec![
+        Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+        Content::text(text)
+            .with_audience(vec![Role::User])
+            .with_priority(0.0),
+    ];
+    for (data, mime) in images {
+        content.push(Content::image(data, mime).with_priority(0.0));
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "intro",
+   "metadata": {},
+   "source": [
+    "# Analysis\n",
+    "Some notes"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 2,
+   "id": "load",
+   "metadata": {},
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": [
+      "loaded 3 rows\n"
+     ]
+    },
+    {
+     "data": {
+      "image/png": "iVBORw0KGgo=",
+      "text/plain": [
+       "<Figure size 640x480>"
+      ]
+     },
+     "metadata": {},
+     "output_type": "display_data"
+    }
+   ],
+   "source": [
+    "rows = load()\n",
+    "print(f'loaded {len(rows)} rows')"
+   ]
+  }
+ ],
+ "metadata": {
+  "kernelspec": {
+   "display_name": "Python 3",
+   "language": "python",
+   "name": "python3"
+  }
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    #[test]
+    fn test_round_trip_keeps_formatting() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(notebook.to_json(), NOTEBOOK);
+        assert!(Notebook::parse("{\"cells\": []}").is_err());
+        assert!(Notebook::parse("not json").is_err());
+    }
+
+    #[test]
+    fn test_view() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        let view = notebook.view(None).unwrap();
+        assert!(view.starts_with("2 cells, python3 kernel"));
+        assert!(view.contains("[0] markdown\n# Analysis\nSome notes\n"));
+        assert!(view.contains("[1] code (execution 2)\nrows = load()"));
+        assert!(view.contains("Output:\nloaded 3 rows\n[image/png output]"));
+
+        let view = notebook.view(Some((1, -1))).unwrap();
+        assert!(!view.contains("[0]"));
+        assert!(notebook.view(Some((2, 1))).is_err());
+    }
+
+    #[test]
+    fn test_cell_edits() {
+        let mut notebook = Notebook::parse(NOTEBOOK).unwrap();
+
+        notebook
+            .edit_cell(1, "rows = load(limit=10)\n", None)
+            .unwrap();
+        assert_eq!(notebook.source(1).unwrap(), "rows = load(limit=10)\n");
+        assert_eq!(notebook.cells()[1]["outputs"], json!([]));
+        assert_eq!(notebook.cells()[1]["execution_count"], Value::Null);
+
+        let index = notebook
+            .insert_cell(Some(1), "code", "import pandas as pd")
+            .unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(notebook.len(), 3);
+        let id = notebook.cells()[1]["id"].as_str().unwrap();
+        assert_eq!(id.len(), 8);
+        assert!(notebook.insert_cell(Some(9), "code", "").is_err());
+        assert!(notebook.insert_cell(None, "chart", "").is_err());
+
+        notebook.move_cell(1, 0).unwrap();
+        assert_eq!(notebook.source(0).unwrap(), "import pandas as pd");
+        notebook.delete_cell(0).unwrap();
+        assert_eq!(notebook.cell_type(0).unwrap(), "markdown");
+        assert!(notebook.delete_cell(5).is_err());
+
+        // Changing the type keeps the id but drops code-only fields
+        notebook.edit_cell(0, "print(1)", Some("code")).unwrap();
+        assert_eq!(notebook.cells()[0]["id"], "intro");
+        assert_eq!(notebook.cells()[0]["outputs"], json!([]));
+        notebook.edit_cell(0, "Notes", Some("markdown")).unwrap();
+        assert!(notebook.cells()[0].get("outputs").is_none());
+
+        // Every edit leaves a notebook that parses again
+        Notebook::parse(&notebook.to_json()).unwrap();
+    }
+
+    #[test]
+    fn test_replace_in_cells() {
+        let mut notebook = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(
+            notebook
+                .replace_in_cells("Some notes", "Better notes")
+                .unwrap(),
+            0
+        );
+        assert_eq!(notebook.source(0).unwrap(), "# Analysis\nBetter notes");
+        assert!(notebook.replace_in_cells("missing", "x").is_err());
+
+        notebook.insert_cell(None, "code", "load()").unwrap();
+        assert!(notebook.replace_in_cells("load()", "x").is_err());
+    }
+
+    #[test]
+    fn test_find_cell_after_changes() {
+        let source = "rows = load()\nprint(f'loaded {len(rows)} rows')";
+        let mut notebook = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(notebook.find_cell(1, Some("load"), source), Some(1));
+
+        // By id once cells moved, and not at all once the cell was edited
+        notebook.insert_cell(Some(0), "code", source).unwrap();
+        assert_eq!(notebook.find_cell(1, Some("load"), source), Some(2));
+        notebook.edit_cell(2, "rows = load()", None).unwrap();
+        assert_eq!(notebook.find_cell(1, Some("load"), source), None);
+
+        // Without ids, by source, preferring the cell that ran
+        assert_eq!(notebook.find_cell(5, None, source), Some(0));
+        notebook.insert_cell(Some(1), "code", source).unwrap();
+        assert_eq!(notebook.find_cell(1, None, source), Some(1));
+        assert_eq!(notebook.find_cell(1, None, "missing"), None);
+    }
+
+    #[test]
+    fn test_render_outputs() {
+        let long: String = (0..100).map(|i| format!("row {}\n", i)).collect();
+        let outputs = vec![
+            json!({"output_type": "stream", "name": "stdout", "text": long}),
+            json!({
+                "output_type": "error",
+                "ename": "ValueError",
+                "evalue": "bad",
+                "traceback": ["\u{1b}[0;31mValueError\u{1b}[0m: bad"]
+            }),
+            json!({
+                "output_type": "execute_result",
+                "data": {"text/html": "<table></table>"},
+                "metadata": {},
+                "execution_count": 3
+            }),
+        ];
+        let (text, images) = render_outputs(&outputs);
+        assert!(text.contains("row 0\n") && text.contains("row 99\n"));
+        assert!(text.contains("... 70 lines omitted ..."));
+        assert!(text.contains("ValueError: bad"));
+        assert!(text.contains("[text/html output]"));
+        assert!(images.is_empty());
+    }
+}
@@ -0,0 +1,483 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader},
+    process::{ChildStdin, Command},
+    sync::watch,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::shell::kill_process_group;
+
+/// Lines of output kept per background process, older lines are dropped.
+const MAX_BUFFERED_LINES: usize = 5000;
+/// Finished processes kept for their output, the oldest are forgotten beyond this.
+const MAX_FINISHED_PROCESSES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    Exited(Option<i32>),
+    Killed,
+}
+
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Exited(Some(code)) => write!(f, "exited ({})", code),
+            ProcessStatus::Exited(None) => write!(f, "exited (signal)"),
+            ProcessStatus::Killed => write!(f, "killed"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct OutputBuffer {
+    lines: VecDeque<String>,
+    dropped: usize,
+}
+
+impl OutputBuffer {
+    fn push(&mut self, line: String) {
+        if self.lines.len() == MAX_BUFFERED_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    fn tail(&self, count: usize) -> Vec<String> {
+        let start = self.lines.len().saturating_sub(count);
+        self.lines.range(start..).cloned().collect()
+    }
+
+    fn total(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+}
+
+/// A command started with `background: true`, its output is buffered until it is tailed.
+struct BackgroundProcess {
+    command: String,
+    pid: Option<u32>,
+    started_at: Instant,
+    stdin: tokio::sync::Mutex<Option<ChildStdin>>,
+    output: Arc<Mutex<OutputBuffer>>,
+    status: watch::Receiver<ProcessStatus>,
+    cancel: CancellationToken,
+}
+
+/// Summary of a background process as shown by `process_list`.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub id: String,
+    pub command: String,
+    pub pid: Option<u32>,
+    pub status: ProcessStatus,
+    pub elapsed: Duration,
+    pub output_lines: usize,
+}
+
+/// The most recent output of a background process.
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub status: ProcessStatus,
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+}
+
+/// Background processes and named shell sessions started by the developer extension.
+///
+/// Everything is torn down when the last handle is dropped: running process groups are
+/// killed and the directory holding shell session state is removed.
+#[derive(Clone)]
+pub struct ProcessRegistry {
+    inner: Arc<RegistryInner>,
+}
+
+struct RegistryInner {
+    next_id: AtomicUsize,
+    processes: Mutex<HashMap<String, Arc<BackgroundProcess>>>,
+    state_dir: Mutex<Option<TempDir>>,
+}
+
+impl Default for ProcessRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessRegistry {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RegistryInner {
+                next_id: AtomicUsize::new(1),
+                processes: Mutex::new(HashMap::new()),
+                state_dir: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Starts a configured shell command without waiting for it, returning its id.
+    pub fn spawn(&self, mut command: Command, description: &str) -> Result<String> {
+        command.stdin(Stdio::piped());
+        let mut child = command
+            .spawn()
+            .context("Failed to start background process")?;
+        let pid = child.id();
+
+        let output = Arc::new(Mutex::new(OutputBuffer::default()));
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(collect_output(stdout, output.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(collect_output(stderr, output.clone()));
+        }
+
+        let (status_tx, status_rx) = watch::channel(ProcessStatus::Running);
+        let cancel = CancellationToken::new();
+        let process = Arc::new(BackgroundProcess {
+            command: description.to_string(),
+            pid,
+            started_at: Instant::now(),
+            stdin: tokio::sync::Mutex::new(child.stdin.take()),
+            output,
+            status: status_rx,
+            cancel: cancel.clone(),
+        });
+
+        tokio::spawn(async move {
+            let status = tokio::select! {
+                result = child.wait() => match result {
+                    Ok(status) => ProcessStatus::Exited(status.code()),
+                    Err(e) => {
+                        tracing::warn!("Failed to wait for background process: {}", e);
+                        ProcessStatus::Exited(None)
+                    }
+                },
+                _ = cancel.cancelled() => {
+                    if let Err(e) = kill_process_group(&mut child, pid).await {
+                        tracing::warn!("Failed to kill background process: {}", e);
+                    }
+                    ProcessStatus::Killed
+                }
+            };
+            let _ = status_tx.send(status);
+        });
+
+        let id = format!("p{}", self.inner.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut processes = self.inner.processes.lock().unwrap();
+        forget_finished(&mut processes);
+        processes.insert(id.clone(), process);
+        Ok(id)
+    }
+
+    /// All background processes, oldest first, including the latest ones that have finished.
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        let processes = self.inner.processes.lock().unwrap();
+        let mut infos: Vec<ProcessInfo> = processes
+            .iter()
+            .map(|(id, process)| ProcessInfo {
+                id: id.clone(),
+                command: process.command.clone(),
+                pid: process.pid,
+                status: *process.status.borrow(),
+                elapsed: process.started_at.elapsed(),
+                output_lines: process.output.lock().unwrap().total(),
+            })
+            .collect();
+        infos.sort_by_key(|info| std::cmp::Reverse(info.elapsed));
+        infos
+    }
+
+    pub fn tail(&self, id: &str, lines: usize) -> Result<ProcessOutput> {
+        let process = self.get(id)?;
+        let status = *process.status.borrow();
+        let output = process.output.lock().unwrap();
+        Ok(ProcessOutput {
+            status,
+            lines: output.tail(lines),
+            total_lines: output.total(),
+        })
+    }
+
+    /// Writes to the process's stdin, closing it afterwards when `close` is set.
+    pub async fn send_input(&self, id: &str, input: &str, close: bool) -> Result<()> {
+        let process = self.get(id)?;
+        if *process.status.borrow() != ProcessStatus::Running {
+            bail!("Process {} is no longer running", id);
+        }
+        let mut stdin = process.stdin.lock().await;
+        let Some(pipe) = stdin.as_mut() else {
+            bail!("Stdin of process {} has been closed", id);
+        };
+        pipe.write_all(input.as_bytes()).await?;
+        pipe.flush().await?;
+        if close {
+            *stdin = None;
+        }
+        Ok(())
+    }
+
+    /// Kills the process and its children, waiting for it to go away.
+    pub async fn kill(&self, id: &str) -> Result<ProcessStatus> {
+        let process = self.get(id)?;
+        process.cancel.cancel();
+        let mut status = process.status.clone();
+        let _ = tokio::time::timeout(
+            Duration::from_secs(5),
+            status.wait_for(|status| *status != ProcessStatus::Running),
+        )
+        .await;
+        let status = *status.borrow();
+        Ok(status)
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<BackgroundProcess>> {
+        self.inner
+            .processes
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| anyhow!("No background process with id '{}'", id))
+    }
+
+    /// Wraps `command` so it starts in the session's directory and environment, saving both
+    /// when it finishes if `persist` is set. Returns the script and the directory to run it in.
+    pub fn session_script(
+        &self,
+        name: &str,
+        command: &str,
+        persist: bool,
+    ) -> Result<(String, Option<PathBuf>)> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("Session names may only contain letters, digits, '-' and '_'");
+        }
+        let dir = self.session_dir(name)?;
+        let cwd_file = dir.join("cwd");
+        let env_file = dir.join("env");
+
+        let cwd = std::fs::read_to_string(&cwd_file)
+            .ok()
+            .map(|cwd| PathBuf::from(cwd.trim_end_matches('\n')))
+            .filter(|cwd| cwd.is_dir());
+        let mut script = format!(
+            "[ -f {env} ] && . {env} >/dev/null 2>&1\n{command}\n",
+            env = shell_quote(&env_file),
+        );
+        if persist {
+            script.push_str(&format!(
+                "__goose_status=$?\npwd > {cwd}\nexport -p > {env}\nexit $__goose_status\n",
+                env = shell_quote(&env_file),
+                cwd = shell_quote(&cwd_file),
+            ));
+        }
+        Ok((script, cwd))
+    }
+
+    fn session_dir(&self, name: &str) -> Result<PathBuf> {
+        let mut state_dir = self.inner.state_dir.lock().unwrap();
+        if state_dir.is_none() {
+            *state_dir = Some(
+                tempfile::Builder::new()
+                    .prefix("goose-shell-sessions")
+                    .tempdir()?,
+            );
+        }
+        let dir = state_dir.as_ref().unwrap().path().join(name);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+}
+
+impl Drop for RegistryInner {
+    fn drop(&mut self) {
+        let processes = self.processes.get_mut().unwrap();
+        for process in processes.values() {
+            if *process.status.borrow() != ProcessStatus::Running {
+                continue;
+            }
+            process.cancel.cancel();
+            // The runtime may be going away with us, so signal the group directly
+            // instead of relying on the waiting task.
+            if let Some(pid) = process.pid {
+                #[cfg(unix)]
+                unsafe {
+                    libc::kill(-(pid as i32), libc::SIGKILL);
+                }
+                #[cfg(windows)]
+                {
+                    let _ = std::process::Command::new("taskkill")
+                        .args(["/F", "/T", "/PID", &pid.to_string()])
+                        .output();
+                }
+            }
+        }
+    }
+}
+
+/// Drops the oldest finished processes beyond `MAX_FINISHED_PROCESSES`, with their output
+fn forget_finished(processes: &mut HashMap<String, Arc<BackgroundProcess>>) {
+    let mut finished: Vec<(String, Instant)> = processes
+        .iter()
+        .filter(|(_, process)| *process.status.borrow() != ProcessStatus::Running)
+        .map(|(id, process)| (id.clone(), process.started_at))
+        .collect();
+    if finished.len() <= MAX_FINISHED_PROCESSES {
+        return;
+    }
+    finished.sort_by_key(|(_, started_at)| *started_at);
+    let excess = finished.len() - MAX_FINISHED_PROCESSES;
+    for (id, _) in finished.into_iter().take(excess) {
+        processes.remove(&id);
+    }
+}
+
+async fn collect_output(stream: impl AsyncRead + Unpin, output: Arc<Mutex<OutputBuffer>>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                output
+                    .lock()
+                    .unwrap()
+                    .push(text.trim_end_matches(['\n', '\r']).to_string());
+            }
+        }
+    }
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::developer::shell::{configure_shell_command, ShellConfig};
+
+    fn sh(command: &str) -> Command {
+        let config = ShellConfig {
+            executable: "sh".to_string(),
+            args: vec!["-c".to_string()],
+            envs: vec![],
+        };
+        configure_shell_command(&config, command)
+    }
+
+    async fn wait_for_output(registry: &ProcessRegistry, id: &str, expected: &str) {
+        for _ in 0..50 {
+            let output = registry.tail(id, 10).unwrap();
+            if output.lines.iter().any(|line| line == expected) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("'{}' never appeared in the output of {}", expected, id);
+    }
+
+    #[tokio::test]
+    async fn test_background_process_input_output_and_kill() {
+        let registry = ProcessRegistry::new();
+        let id = registry
+            .spawn(
+                sh("echo ready; while read line; do echo \"got $line\"; done"),
+                "echo",
+            )
+            .unwrap();
+        assert_eq!(id, "p1");
+        wait_for_output(&registry, &id, "ready").await;
+
+        registry.send_input(&id, "hello\n", false).await.unwrap();
+        wait_for_output(&registry, &id, "got hello").await;
+
+        let sleeper = registry.spawn(sh("sleep 30"), "sleep 30").unwrap();
+        assert_eq!(registry.list().len(), 2);
+        assert_eq!(
+            registry.kill(&sleeper).await.unwrap(),
+            ProcessStatus::Killed
+        );
+
+        registry.send_input(&id, "", true).await.unwrap();
+        let mut status = registry.get(&id).unwrap().status.clone();
+        status
+            .wait_for(|status| *status == ProcessStatus::Exited(Some(0)))
+            .await
+            .unwrap();
+        assert!(registry.send_input(&id, "again\n", false).await.is_err());
+        assert!(registry.tail("p99", 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_the_latest_finished_processes_are_kept() {
+        let registry = ProcessRegistry::new();
+        let sleeper = registry.spawn(sh("sleep 30"), "sleep 30").unwrap();
+        for _ in 0..MAX_FINISHED_PROCESSES + 2 {
+            let id = registry.spawn(sh("true"), "true").unwrap();
+            let mut status = registry.get(&id).unwrap().status.clone();
+            status
+                .wait_for(|status| *status != ProcessStatus::Running)
+                .await
+                .unwrap();
+        }
+        registry.spawn(sh("true"), "true").unwrap();
+
+        let listed = registry.list();
+        assert!(listed.len() <= MAX_FINISHED_PROCESSES + 2);
+        assert!(listed.iter().any(|info| info.id == sleeper));
+        // The oldest finished ones are gone, running ones are never dropped
+        assert!(registry.tail("p2", 10).is_err());
+        assert!(registry.tail("p3", 10).is_err());
+        assert!(registry.tail(&sleeper, 10).is_ok());
+        registry.kill(&sleeper).await.unwrap();
+    }
+
+    async fn run_in_session(registry: &ProcessRegistry, name: &str, command: &str) -> String {
+        let (script, cwd) = registry.session_script(name, command, true).unwrap();
+        let mut command = sh(&script);
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        let output = command.output().await.unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_session_keeps_cwd_and_env() {
+        let registry = ProcessRegistry::new();
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = format!("cd '{}' && export GREETING=hi", dir.path().display());
+        run_in_session(&registry, "build", &first).await;
+        let output = run_in_session(&registry, "build", "pwd; echo $GREETING").await;
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![dir.path().to_str().unwrap(), "hi"]
+        );
+
+        let other = run_in_session(&registry, "other", "echo \"[$GREETING]\"").await;
+        assert_eq!(other.trim(), "[]");
+        assert!(registry.session_script("../escape", "true", true).is_err());
+    }
+}
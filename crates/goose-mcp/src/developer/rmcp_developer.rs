@@ -36,11 +36,20 @@ use tokio_util::sync::CancellationToken;
 
 use crate::developer::{paths::get_shell_path_dirs, shell::ShellConfig};
 
-use super::analyze::{types::AnalyzeParams, CodeAnalyzer};
+use super::analyze::{index::AnalysisIndex, types::AnalyzeParams, CodeAnalyzer};
 use super::editor_models::{create_editor_model, EditorModel};
+use super::git::{self, GitParams};
+use super::lsp::{LspManager, LspParams};
+use super::notebook::{
+    execute_cell, is_notebook, notebook_command, Notebook, NotebookExecuteParams, NotebookKernels,
+};
+use super::processes::{ProcessRegistry, ProcessStatus};
+use super::search::{self, SearchParams};
 use super::shell::{configure_shell_command, expand_path, is_absolute_path, kill_process_group};
+use super::test_runner::{self, RunTestsParams, TestRunner};
 use super::text_editor::{
-    text_editor_insert, text_editor_replace, text_editor_undo, text_editor_view, text_editor_write,
+    text_editor_batch, text_editor_insert, text_editor_replace, text_editor_undo,
+    text_editor_undo_batch, text_editor_view, text_editor_write, BatchHistory, FileEdit,
 };
 
 /// Parameters for the screen_capture tool
@@ -61,7 +70,8 @@ pub struct TextEditorParams {
     /// Absolute path to file or directory, e.g. `/repo/file.py` or `/repo`.
     pub path: String,
 
-    /// The operation to perform. Allowed options are: `view`, `write`, `str_replace`, `insert`, `undo_edit`.
+    /// The operation to perform. Allowed options are: `view`, `write`, `str_replace`, `insert`, `batch`, `undo_edit`.
+    /// Jupyter notebooks (`.ipynb`) also allow `edit_cell`, `insert_cell`, `delete_cell` and `move_cell`.
     pub command: String,
 
     /// Unified diff to apply. Supports editing multiple files simultaneously. Cannot create or delete files
@@ -71,6 +81,7 @@ pub struct TextEditorParams {
 
     /// Optional array of two integers specifying the start and end line numbers to view.
     /// Line numbers are 1-indexed, and -1 for the end line means read to the end of the file.
+    /// For notebooks these are 0-indexed cell numbers instead.
     /// This parameter only applies when viewing files, not directories.
     pub view_range: Option<Vec<i64>>,
 
@@ -85,13 +96,93 @@ pub struct TextEditorParams {
 
     /// The line number after which to insert text (0 for beginning). Required for `insert` command.
     pub insert_line: Option<i64>,
+
+    /// Edits to apply together with the `batch` command: all of them or none are applied.
+    pub edits: Option<Vec<BatchEditParams>>,
+
+    /// With the `batch` command, only return the combined diff without changing any file.
+    pub preview: Option<bool>,
+
+    /// The 0-indexed notebook cell for `edit_cell`, `delete_cell` and `move_cell`,
+    /// or the position of the new cell for `insert_cell` (the end if omitted).
+    pub cell_index: Option<usize>,
+
+    /// The type of cell for `insert_cell` (default `code`) or to convert to with `edit_cell`:
+    /// `code`, `markdown` or `raw`.
+    pub cell_type: Option<String>,
+
+    /// Where `move_cell` moves the cell to, as its 0-indexed position afterwards.
+    pub to_index: Option<usize>,
 }
 
-/// Parameters for the shell tool
+/// A single edit within a text_editor `batch` command
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BatchEditParams {
+    /// Absolute path to the file to edit.
+    pub path: String,
+
+    /// The edit to make. Allowed options are: `write`, `str_replace`, `insert`.
+    pub command: String,
+
+    /// The content to write to the file. Required for `write`.
+    pub file_text: Option<String>,
+
+    /// The old string to replace, must appear exactly once. Required for `str_replace`.
+    pub old_str: Option<String>,
+
+    /// The new string. Required for `str_replace` and `insert`.
+    pub new_str: Option<String>,
+
+    /// The line number after which to insert text (0 for beginning, -1 for end). Required for `insert`.
+    pub insert_line: Option<i64>,
+}
+
+/// Parameters for the shell tool
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ShellParams {
     /// The command string to execute in the shell
     pub command: String,
+
+    /// Optional: name of a persistent shell session. Commands in the same session keep the
+    /// working directory and exported environment variables of the previous command.
+    #[serde(default)]
+    pub session: Option<String>,
+
+    /// Start the command as a background process and return its id immediately instead of
+    /// waiting for it. Use for servers, watchers and other long lived commands.
+    #[serde(default)]
+    pub background: bool,
+}
+
+/// Parameters for the process_output tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessOutputParams {
+    /// Id of the background process, as returned by shell or process_list
+    pub id: String,
+
+    /// Number of most recent output lines to return, 50 by default
+    pub lines: Option<usize>,
+}
+
+/// Parameters for the process_input tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessInputParams {
+    /// Id of the background process, as returned by shell or process_list
+    pub id: String,
+
+    /// Text to write to the process's stdin, include a trailing newline to submit a line
+    pub input: String,
+
+    /// Close stdin after writing, signalling end of input
+    #[serde(default)]
+    pub close_stdin: bool,
+}
+
+/// Parameters for the process_kill tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessKillParams {
+    /// Id of the background process, as returned by shell or process_list
+    pub id: String,
 }
 
 /// Parameters for the image_processor tool
@@ -174,14 +265,18 @@ fn load_prompt_files() -> HashMap<String, Prompt> {
 pub struct DeveloperServer {
     tool_router: ToolRouter<Self>,
     file_history: Arc<Mutex<HashMap<PathBuf, Vec<String>>>>,
+    batch_history: BatchHistory,
     ignore_patterns: Gitignore,
     editor_model: Option<EditorModel>,
     prompts: HashMap<String, Prompt>,
     code_analyzer: CodeAnalyzer,
+    lsp: LspManager,
+    notebook_kernels: NotebookKernels,
     #[cfg(test)]
     pub running_processes: Arc<RwLock<HashMap<String, CancellationToken>>>,
     #[cfg(not(test))]
     running_processes: Arc<RwLock<HashMap<String, CancellationToken>>>,
+    processes: ProcessRegistry,
     bash_env_file: Option<PathBuf>,
     extend_path_with_shell: bool,
 }
@@ -235,7 +330,11 @@ impl ServerHandler for DeveloperServer {
             Your windows/screen tools can be used for visual debugging. You should not use these tools unless
             prompted to, but you can mention they are available if they are relevant.
 
-            Always prefer ripgrep (rg -C 3) to grep.
+            Use the search tool to find code or text in files. For anything it can't do, always prefer ripgrep (rg -C 3) to grep.
+
+            Use the git tool rather than running git in the shell for status, diffs, history, blame, branches and commits.
+
+            Use the run_tests tool to run cargo, pytest, go or vitest test suites, it reports only what failed.
 
             operating system: {os}
             current directory: {cwd}
@@ -262,6 +361,7 @@ impl ServerHandler for DeveloperServer {
                 - `write`: Create or overwrite a file with the given content
                 - `str_replace`: Replace text in one or more files.
                 - `insert`: Insert text at a specific line location in the file.
+                - `batch`: Apply a list of write, str_replace and insert edits across files as one change.
                 - `undo_edit`: Undo the last edit made to a file.
 
                 To use the write command, you must specify `file_text` which will become the new content of the file. Be careful with
@@ -277,6 +377,10 @@ impl ServerHandler for DeveloperServer {
 
                 When possible, batch file edits together by using a multi-file unified `diff` within a single str_replace tool call.
 
+                To use the batch command, specify `edits`, a list of objects with `path`, `command` (`write`, `str_replace` or `insert`)
+                and the parameters of that command. Every edit is checked before any file is written, so either all edits are applied or
+                none are. Set `preview` to true to only see the combined diff. `undo_edit` on any file of the batch reverts the whole batch.
+
                 {}
 
             "#, editor.get_str_replace_description()}
@@ -292,6 +396,7 @@ impl ServerHandler for DeveloperServer {
                 - `write`: Create or overwrite a file with the given content
                 - `str_replace`: Replace text in one or more files.
                 - `insert`: Insert text at a specific line location in the file.
+                - `batch`: Apply a list of write, str_replace and insert edits across files as one change.
                 - `undo_edit`: Undo the last edit made to a file.
 
                 To use the write command, you must specify `file_text` which will become the new content of the file. Be careful with
@@ -304,6 +409,10 @@ impl ServerHandler for DeveloperServer {
 
                 When possible, batch file edits together by using a multi-file unified `diff` within a single str_replace tool call.
 
+                To use the batch command, specify `edits`, a list of objects with `path`, `command` (`write`, `str_replace` or `insert`)
+                and the parameters of that command. Every edit is checked before any file is written, so either all edits are applied or
+                none are. Set `preview` to true to only see the combined diff. `undo_edit` on any file of the batch reverts the whole batch.
+
                 To use the insert command, you must specify both `insert_line` (the line number after which to insert, 0 for beginning, -1 for end)
                 and `new_str` (the text to insert).
 
@@ -324,7 +433,13 @@ impl ServerHandler for DeveloperServer {
 
             **Important**: Each shell command runs in its own process. Things like directory changes or
             sourcing files do not persist between tool calls. So you may need to repeat them each time by
-            stringing together commands.
+            stringing together commands, or pass the same `session` name to each call: commands in a
+            session start in the directory the previous one ended in, with the variables it exported.
+
+            If you need to run a long lived command such as a server or a watcher, set `background: true`.
+            The tool returns a process id right away; use `process_output` to read what it printed,
+            `process_input` to write to its stdin, `process_list` to see all background processes and
+            `process_kill` to stop it. Background processes are stopped when the session ends.
 
             If fetching web content, consider adding Accept: text/markdown header
         "#};
@@ -332,9 +447,8 @@ impl ServerHandler for DeveloperServer {
         let windows_specific = indoc! {r#"
             **Important**: For searching files and code:
 
-            Preferred: Use ripgrep (`rg`) when available - it respects .gitignore and is fast:
-              - To locate a file by name: `rg --files | rg example.py`
-              - To locate content inside files: `rg 'class Example'`
+            Preferred: Use the search tool to locate content inside files - it respects .gitignore and .gooseignore.
+            Use ripgrep (`rg`) when available to locate a file by name: `rg --files | rg example.py`
 
             Alternative Windows commands (if ripgrep is not installed):
               - To locate a file by name: `dir /s /b example.py`
@@ -351,11 +465,8 @@ impl ServerHandler for DeveloperServer {
         "#};
 
         let unix_specific = indoc! {r#"
-            If you need to run a long lived command, background it - e.g. `uvicorn main:app &` so that
-            this tool does not run indefinitely.
-
-            **Important**: Use ripgrep - `rg` - exclusively when you need to locate a file or a code reference,
-            other solutions may produce too large output because of hidden files! For example *do not* use `find` or `ls -r`
+            **Important**: Use the search tool to locate a code reference, and ripgrep - `rg` - exclusively when you need to
+            locate a file, other solutions may produce too large output because of hidden files! For example *do not* use `find` or `ls -r`
               - List files by name: `rg --files | rg <filename>`
               - List files that contain a regex: `rg '<regex>' -l`
 
@@ -551,11 +662,15 @@ impl DeveloperServer {
         Self {
             tool_router: Self::tool_router(),
             file_history: Arc::new(Mutex::new(HashMap::new())),
+            batch_history: Arc::new(Mutex::new(Vec::new())),
             ignore_patterns,
             editor_model,
             prompts: load_prompt_files(),
-            code_analyzer: CodeAnalyzer::new(),
+            code_analyzer: CodeAnalyzer::with_index(AnalysisIndex::new()),
+            lsp: LspManager::new(),
+            notebook_kernels: NotebookKernels::new(),
             running_processes: Arc::new(RwLock::new(HashMap::new())),
+            processes: ProcessRegistry::new(),
             extend_path_with_shell: false,
             bash_env_file: None,
         }
@@ -719,10 +834,13 @@ impl DeveloperServer {
     /// - `write`: Create or overwrite a file with the given content
     /// - `str_replace`: Replace old_str with new_str in the file.
     /// - `insert`: Insert text at a specific line location in the file.
-    /// - `undo_edit`: Undo the last edit made to a file.
+    /// - `batch`: Apply several edits across files atomically, or preview their diff.
+    /// - `undo_edit`: Undo the last edit made to a file, or the whole batch it was part of.
+    ///
+    /// Jupyter notebooks are viewed and edited by cell, so edits always leave a valid notebook.
     #[tool(
         name = "text_editor",
-        description = "Perform text editing operations on files. Commands: view (show file content), write (create/overwrite file), str_replace (edit file), insert (insert at line), undo_edit (undo last change)."
+        description = "Perform text editing operations on files. Commands: view (show file content), write (create/overwrite file), str_replace (edit file), insert (insert at line), batch (apply several edits across files atomically), undo_edit (undo last change or batch). For Jupyter notebooks (.ipynb), view lists cells with their 0-indexed cell_index, type and truncated outputs, str_replace edits the cell containing old_str, and edit_cell (new_str replaces the source of cell_index), insert_cell (new_str, cell_type, at cell_index), delete_cell and move_cell (cell_index to to_index) work on whole cells; run cells with notebook_execute."
     )]
     pub async fn text_editor(
         &self,
@@ -743,6 +861,11 @@ impl DeveloperServer {
             ));
         }
 
+        if is_notebook(&path) && !matches!(params.command.as_str(), "batch" | "undo_edit") {
+            let content = notebook_command(&path, &params, &self.file_history).await?;
+            return Ok(CallToolResult::success(content));
+        }
+
         match params.command.as_str() {
             "view" => {
                 let view_range = params.view_range.as_ref().and_then(|vr| {
@@ -828,7 +951,34 @@ impl DeveloperServer {
                         .await?;
                 Ok(CallToolResult::success(content))
             }
+            "batch" => {
+                let edits = params.edits.ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing 'edits' parameter for batch command".to_string(),
+                        None,
+                    )
+                })?;
+                let edits = edits
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, edit)| self.resolve_batch_edit(index, edit))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let content = text_editor_batch(
+                    edits,
+                    params.preview.unwrap_or(false),
+                    &self.file_history,
+                    &self.batch_history,
+                )
+                .await?;
+                Ok(CallToolResult::success(content))
+            }
             "undo_edit" => {
+                if let Some(content) =
+                    text_editor_undo_batch(&path, &self.file_history, &self.batch_history).await?
+                {
+                    return Ok(CallToolResult::success(content));
+                }
                 let content = text_editor_undo(&path, &self.file_history).await?;
                 Ok(CallToolResult::success(content))
             }
@@ -840,6 +990,61 @@ impl DeveloperServer {
         }
     }
 
+    /// Run a code cell of a Jupyter notebook.
+    ///
+    /// A separate tool from text_editor, since running code needs its own permission. Each
+    /// notebook gets a kernel on first use that keeps its state between calls.
+    #[tool(
+        name = "notebook_execute",
+        description = "Run a code cell of a Jupyter notebook (.ipynb) in a local Jupyter kernel. The kernel keeps its state between calls, like running cells in Jupyter, so run the cells a cell depends on first. The outputs are saved in the notebook and returned as text and images. Use text_editor to view and edit the cells."
+    )]
+    pub async fn notebook_execute(
+        &self,
+        params: Parameters<NotebookExecuteParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let path = self.resolve_path(&params.path)?;
+
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+        if !is_notebook(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("'{}' is not a Jupyter notebook", path.display()),
+                None,
+            ));
+        }
+
+        // Tracked like shell commands so the cell can be cancelled
+        let cancellation_token = CancellationToken::new();
+        let request_id = context.id.to_string();
+        self.running_processes
+            .write()
+            .await
+            .insert(request_id.clone(), cancellation_token.clone());
+
+        let result = execute_cell(
+            &path,
+            params.cell_index,
+            &self.file_history,
+            &self.notebook_kernels,
+            cancellation_token,
+        )
+        .await;
+        self.running_processes.write().await.remove(&request_id);
+
+        Ok(CallToolResult::success(result?))
+    }
+
     /// Execute a command in the shell.
     ///
     /// This will return the output and error concatenated into a single string, as
@@ -847,11 +1052,11 @@ impl DeveloperServer {
     /// of if the command succeeded or failed.
     ///
     /// Avoid commands that produce a large amount of output, and consider piping those outputs to files.
-    /// If you need to run a long lived command, background it - e.g. `uvicorn main:app &` so that
-    /// this tool does not run indefinitely.
+    /// Commands sharing a `session` keep their working directory and exported variables, and
+    /// long lived commands run with `background` set are tracked as background processes.
     #[tool(
         name = "shell",
-        description = "Execute a command in the shell.This will return the output and error concatenated into a single string, as you would see from running on the command line. There will also be an indication of if the command succeeded or failed. Avoid commands that produce a large amount of output, and consider piping those outputs to files. If you need to run a long lived command, background it - e.g. `uvicorn main:app &` so that this tool does not run indefinitely."
+        description = "Execute a command in the shell.This will return the output and error concatenated into a single string, as you would see from running on the command line. There will also be an indication of if the command succeeded or failed. Avoid commands that produce a large amount of output, and consider piping those outputs to files. Pass the same `session` name to keep the working directory and exported environment variables between calls. If you need to run a long lived command, set `background: true` and manage it with process_output, process_input, process_list and process_kill so that this tool does not run indefinitely."
     )]
     pub async fn shell(
         &self,
@@ -866,6 +1071,25 @@ impl DeveloperServer {
         // Validate the shell command
         self.validate_shell_command(command)?;
 
+        if params.background {
+            let shell_command = self
+                .build_shell_command(command, params.session.as_deref(), false)
+                .await?;
+            let id = self
+                .processes
+                .spawn(shell_command, command)
+                .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            let text = format!(
+                "Started background process {id}: {command}\nUse process_output with id \"{id}\" to see its output and process_kill to stop it."
+            );
+            return Ok(CallToolResult::success(vec![
+                Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+                Content::text(text)
+                    .with_audience(vec![Role::User])
+                    .with_priority(0.0),
+            ]));
+        }
+
         let cancellation_token = CancellationToken::new();
         // Track the process using the request ID
         {
@@ -876,7 +1100,12 @@ impl DeveloperServer {
 
         // Execute the command and capture output
         let output_result = self
-            .execute_shell_command(command, &peer, cancellation_token.clone())
+            .execute_shell_command(
+                command,
+                params.session.as_deref(),
+                &peer,
+                cancellation_token.clone(),
+            )
             .await;
 
         // Clean up the process from tracking
@@ -952,20 +1181,41 @@ impl DeveloperServer {
         Ok(())
     }
 
-    /// Execute a shell command and return the combined output.
-    ///
-    /// Streams output in real-time to the client using logging notifications.
-    async fn execute_shell_command(
+    /// Build the process for a shell command, wrapped to restore and save the state of the
+    /// named session if one is given.
+    async fn build_shell_command(
         &self,
         command: &str,
-        peer: &rmcp::service::Peer<RoleServer>,
-        cancellation_token: CancellationToken,
-    ) -> Result<String, ErrorData> {
+        session: Option<&str>,
+        persist_session: bool,
+    ) -> Result<tokio::process::Command, ErrorData> {
         let mut shell_config = ShellConfig::default();
-        let shell_name = std::path::Path::new(&shell_config.executable)
+        let mut shell_name = std::path::Path::new(&shell_config.executable)
             .file_name()
             .and_then(|s| s.to_str())
-            .unwrap_or("bash");
+            .unwrap_or("bash")
+            .to_string();
+
+        let (script, session_cwd) = match session {
+            Some(_) if cfg!(windows) => {
+                return Err(ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "Shell sessions are not supported on Windows".to_string(),
+                    None,
+                ));
+            }
+            Some(name) => {
+                // Session state is saved and restored with POSIX shell syntax
+                if !matches!(shell_name.as_str(), "bash" | "zsh" | "sh" | "dash" | "ksh") {
+                    shell_config.executable = "sh".to_string();
+                    shell_name = "sh".to_string();
+                }
+                self.processes
+                    .session_script(name, command, persist_session)
+                    .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
+            }
+            None => (command.to_string(), None),
+        };
 
         if let Some(ref env_file) = self.bash_env_file {
             if shell_name == "bash" {
@@ -976,7 +1226,10 @@ impl DeveloperServer {
             }
         }
 
-        let mut command = configure_shell_command(&shell_config, command);
+        let mut command = configure_shell_command(&shell_config, &script);
+        if let Some(cwd) = session_cwd {
+            command.current_dir(cwd);
+        }
 
         if self.extend_path_with_shell {
             if let Err(e) = get_shell_path_dirs()
@@ -988,6 +1241,21 @@ impl DeveloperServer {
             }
         }
 
+        Ok(command)
+    }
+
+    /// Execute a shell command and return the combined output.
+    ///
+    /// Streams output in real-time to the client using logging notifications.
+    async fn execute_shell_command(
+        &self,
+        command: &str,
+        session: Option<&str>,
+        peer: &rmcp::service::Peer<RoleServer>,
+        cancellation_token: CancellationToken,
+    ) -> Result<String, ErrorData> {
+        let mut command = self.build_shell_command(command, session, true).await?;
+
         let mut child = command
             .spawn()
             .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
@@ -1121,6 +1389,129 @@ impl DeveloperServer {
         Ok(())
     }
 
+    /// List the background processes started with the shell tool.
+    #[tool(
+        name = "process_list",
+        description = "List the background processes started by the shell tool with `background: true`, with their id, status, running time and command."
+    )]
+    pub async fn process_list(&self) -> Result<CallToolResult, ErrorData> {
+        let processes = self.processes.list();
+        let text = if processes.is_empty() {
+            "No background processes".to_string()
+        } else {
+            processes
+                .iter()
+                .map(|process| {
+                    format!(
+                        "{} [{}] pid {} for {}s, {} lines of output: {}",
+                        process.id,
+                        process.status,
+                        process
+                            .pid
+                            .map_or_else(|| "?".to_string(), |pid| pid.to_string()),
+                        process.elapsed.as_secs(),
+                        process.output_lines,
+                        process.command
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        Ok(CallToolResult::success(vec![
+            Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(text)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Show the most recent output of a background process.
+    #[tool(
+        name = "process_output",
+        description = "Show the most recent stdout and stderr lines of a background process along with whether it is still running."
+    )]
+    pub async fn process_output(
+        &self,
+        params: Parameters<ProcessOutputParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let output = self
+            .processes
+            .tail(&params.id, params.lines.unwrap_or(50))
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let mut text = format!(
+            "Process {} is {}, showing {} of {} lines of output",
+            params.id,
+            output.status,
+            output.lines.len(),
+            output.total_lines
+        );
+        if !output.lines.is_empty() {
+            text.push_str(":\n");
+            text.push_str(&output.lines.join("\n"));
+        }
+
+        Ok(CallToolResult::success(vec![
+            Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(text)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Write to the stdin of a background process.
+    #[tool(
+        name = "process_input",
+        description = "Write text to the stdin of a running background process, e.g. to answer a prompt. Include a trailing newline to submit a line; set close_stdin to signal end of input."
+    )]
+    pub async fn process_input(
+        &self,
+        params: Parameters<ProcessInputParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        self.processes
+            .send_input(&params.id, &params.input, params.close_stdin)
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Sent {} bytes to process {}",
+            params.input.len(),
+            params.id
+        ))
+        .with_audience(vec![Role::Assistant])]))
+    }
+
+    /// Stop a background process and its children.
+    #[tool(
+        name = "process_kill",
+        description = "Stop a background process and every process it started."
+    )]
+    pub async fn process_kill(
+        &self,
+        params: Parameters<ProcessKillParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let status = self
+            .processes
+            .kill(&params.id)
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let text = match status {
+            ProcessStatus::Running => format!("Process {} did not stop in time", params.id),
+            status => format!("Process {} {}", params.id, status),
+        };
+        Ok(CallToolResult::success(vec![
+            Content::text(text.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(text)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
     /// Analyze code structure and relationships.
     ///
     /// Automatically selects the appropriate analysis:
@@ -1146,6 +1537,179 @@ impl DeveloperServer {
             .analyze(params, path, &self.ignore_patterns)
     }
 
+    /// Search file contents across the workspace.
+    ///
+    /// Files are walked like ripgrep does, skipping hidden and gitignored files as well as
+    /// anything excluded by .gooseignore. Matches come back grouped by file, one page at a time,
+    /// so the output stays small however many files match.
+    #[tool(
+        name = "search",
+        description = "Search file contents for a regular expression, or plain text with literal=true. Skips hidden, gitignored and .gooseignore'd files. Narrow with file_types (ripgrep type names like rust, py, ts) and globs (e.g. src/**/*.rs, or !**/tests/** to exclude), add context lines around matches, and page through large result sets with max_results and offset. Results are grouped by file with 1-indexed line numbers."
+    )]
+    pub async fn search(
+        &self,
+        params: Parameters<SearchParams>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let path = match &params.path {
+            Some(path) => self.resolve_path(path)?,
+            None => std::env::current_dir().expect("should have a current working dir"),
+        };
+
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+
+        let output = search::search(&params, &path, &self.ignore_patterns)?;
+        Ok(CallToolResult::success(vec![
+            Content::text(output.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(output)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Run git operations on the repository containing the working directory or `repo`.
+    ///
+    /// Everything but push goes through libgit2, so the output is compact and never waits on a
+    /// pager or editor. Operations that can lose work must be flagged destructive, which makes
+    /// the permission inspector ask the user before they run.
+    #[tool(
+        name = "git",
+        description = "Run git operations without the shell. Commands: status (branch, upstream distance and changed files), diff (unstaged by default, staged=true for the index, limited to paths; hunks are numbered), log (max_count, author, grep, since/until as YYYY-MM-DD, paths, revision), blame (one file in paths, start_line/end_line), branch (create branch at revision), switch (check out an existing branch, refuses to overwrite local changes), stage/unstage (paths, or all; with one path, hunks selects hunks by the numbers diff shows), commit (message, commits what is staged), reset (revision and mode soft, mixed or hard), push (remote and branch, force to overwrite), delete_branch. reset with mode hard, push with force and delete_branch lose work and must be called with destructive=true; the user is asked to approve them."
+    )]
+    pub async fn git(&self, params: Parameters<GitParams>) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let path = match &params.repo {
+            Some(repo) => self.resolve_path(repo)?,
+            None => std::env::current_dir().expect("should have a current working dir"),
+        };
+
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+
+        let output = git::run(&params, &path, &self.ignore_patterns)?;
+        Ok(CallToolResult::success(vec![
+            Content::text(output.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(output)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Run a project's tests and report the results in a compact form.
+    ///
+    /// The framework is detected from the nearest project file. Results are read from each
+    /// framework's machine-readable output rather than its console output, so the report can
+    /// list failures with their messages and locations and only count what passed.
+    #[tool(
+        name = "run_tests",
+        description = "Run the tests of a cargo, pytest, go or vitest project and get a structured report: counts of failed, passed and skipped tests, then each failure with its message and file location. Passing tests are only counted, so prefer this over running test commands in the shell. path limits the run to a file or directory (pytest, go, vitest) or picks the crate (cargo); filter selects tests by name. Build errors are shown when no tests could run."
+    )]
+    pub async fn run_tests(
+        &self,
+        params: Parameters<RunTestsParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let path = match &params.path {
+            Some(path) => self.resolve_path(path)?,
+            None => std::env::current_dir().expect("should have a current working dir"),
+        };
+
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+
+        let (framework, root) = test_runner::detect(&path, params.framework.as_deref())?;
+
+        let path_env = if self.extend_path_with_shell {
+            match get_shell_path_dirs()
+                .await
+                .and_then(|dirs| join_paths(dirs).map_err(|e| anyhow!(e)))
+            {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    tracing::error!("Failed to extend PATH with shell directories: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Tracked like shell commands so the run can be cancelled
+        let cancellation_token = CancellationToken::new();
+        let request_id = context.id.to_string();
+        self.running_processes
+            .write()
+            .await
+            .insert(request_id.clone(), cancellation_token.clone());
+
+        let runner = TestRunner::new(params.timeout_secs, path_env, cancellation_token);
+        let result = runner
+            .run_tests(framework, &root, &path, params.filter.as_deref())
+            .await;
+        self.running_processes.write().await.remove(&request_id);
+
+        let output = result?;
+        Ok(CallToolResult::success(vec![
+            Content::text(output.clone()).with_audience(vec![Role::Assistant]),
+            Content::text(output)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ]))
+    }
+
+    /// Query a language server for precise code intelligence.
+    ///
+    /// Servers (rust-analyzer, pyright, gopls, typescript-language-server) are started on
+    /// first use for the file's workspace and stopped with the extension. Files are re-read
+    /// from disk on every call, so answers reflect edits made since the last one.
+    #[tool(
+        name = "lsp",
+        description = "Query a language server (rust-analyzer, pyright, gopls, typescript-language-server) for precise answers where analyze is approximate. Commands: definition (where a symbol is defined), references (every use of a symbol), hover (type and documentation), diagnostics (current errors and warnings for a file, use after edits), symbols (search workspace symbols by name with query). Positions are a 1-indexed line plus the symbol name on that line, or a 1-indexed character. The first call in a workspace starts the server and can be slow while it indexes."
+    )]
+    pub async fn lsp(&self, params: Parameters<LspParams>) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let path = self.resolve_path(&params.path)?;
+
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+
+        self.lsp.run(params, path).await
+    }
+
     /// Process an image file from disk.
     ///
     /// The image will be:
@@ -1281,6 +1845,84 @@ impl DeveloperServer {
         }
     }
 
+    // Helper method to turn one edit of a batch into a checked path and edit
+    fn resolve_batch_edit(
+        &self,
+        index: usize,
+        edit: BatchEditParams,
+    ) -> Result<(PathBuf, FileEdit), ErrorData> {
+        let missing = |name: &str| {
+            ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Edit {} is missing the '{}' parameter for {}",
+                    index + 1,
+                    name,
+                    edit.command
+                ),
+                None,
+            )
+        };
+
+        let path = self.resolve_path(&edit.path)?;
+        if self.is_ignored(&path) {
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Access to '{}' is restricted by .gooseignore",
+                    path.display()
+                ),
+                None,
+            ));
+        }
+
+        // Line edits can leave a notebook's JSON broken, so a batch may only write whole ones
+        if is_notebook(&path) {
+            let invalid = |message: String| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Edit {}: {}", index + 1, message),
+                    None,
+                )
+            };
+            if edit.command != "write" {
+                return Err(invalid(format!(
+                    "'{}' is a notebook, which a batch can only write as a whole; edit its cells with text_editor's cell commands",
+                    path.display()
+                )));
+            }
+            if let Some(file_text) = &edit.file_text {
+                Notebook::parse(file_text).map_err(|e| invalid(e.message.to_string()))?;
+            }
+        }
+
+        let file_edit = match edit.command.as_str() {
+            "write" => FileEdit::Write {
+                file_text: edit.file_text.clone().ok_or_else(|| missing("file_text"))?,
+            },
+            "str_replace" => FileEdit::Replace {
+                old_str: edit.old_str.clone().ok_or_else(|| missing("old_str"))?,
+                new_str: edit.new_str.clone().ok_or_else(|| missing("new_str"))?,
+            },
+            "insert" => FileEdit::Insert {
+                insert_line: edit.insert_line.ok_or_else(|| missing("insert_line"))?,
+                new_str: edit.new_str.clone().ok_or_else(|| missing("new_str"))?,
+            },
+            command => {
+                return Err(ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Edit {} has unknown command '{}', use write, str_replace or insert",
+                        index + 1,
+                        command
+                    ),
+                    None,
+                ))
+            }
+        };
+        Ok((path, file_edit))
+    }
+
     fn build_ignore_patterns(cwd: &PathBuf) -> Gitignore {
         let mut builder = GitignoreBuilder::new(cwd);
         let local_ignore_path = cwd.join(".gooseignore");
@@ -1504,6 +2146,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: "".to_string(),
+                        ..Default::default()
                     }),
                     RequestContext {
                         ct: Default::default(),
@@ -1539,6 +2182,7 @@ mod tests {
             // Test PowerShell command
             let shell_params = Parameters(ShellParams {
                 command: "Get-ChildItem".to_string(),
+                ..Default::default()
             });
 
             let result = server
@@ -1592,6 +2236,11 @@ mod tests {
                 new_str: None,
                 insert_line: None,
                 diff: None,
+                edits: None,
+                preview: None,
+                cell_index: None,
+                cell_type: None,
+                to_index: None,
             });
 
             let result = server.text_editor(view_params).await;
@@ -1619,6 +2268,11 @@ mod tests {
                 new_str: None,
                 insert_line: None,
                 diff: None,
+                edits: None,
+                preview: None,
+                cell_index: None,
+                cell_type: None,
+                to_index: None,
             });
 
             let result = server.text_editor(view_params).await;
@@ -1650,6 +2304,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -1664,6 +2323,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let view_result = server.text_editor(view_params).await.unwrap();
@@ -1702,6 +2366,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -1716,6 +2385,11 @@ mod tests {
             new_str: Some("Rust".to_string()),
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let replace_result = server.text_editor(replace_params).await.unwrap();
@@ -1761,6 +2435,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -1775,6 +2454,11 @@ mod tests {
             new_str: Some("Modified".to_string()),
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(replace_params).await.unwrap();
@@ -1793,6 +2477,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let undo_result = server.text_editor(undo_params).await.unwrap();
@@ -1811,6 +2500,101 @@ mod tests {
         assert!(undo_content.text.contains("Undid the last edit"));
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_text_editor_notebook_cells() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let notebook_path = temp_dir.path().join("analysis.ipynb");
+        std::env::set_current_dir(&temp_dir).unwrap();
+        fs::write(
+            &notebook_path,
+            r#"{"cells": [{"cell_type": "code", "execution_count": 1, "metadata": {}, "outputs": [], "source": ["x = 1\n", "x"]}], "metadata": {}, "nbformat": 4, "nbformat_minor": 4}"#,
+        )
+        .unwrap();
+
+        let server = create_test_server();
+        let params = |command: &str, cell_index: Option<usize>, new_str: Option<&str>| {
+            Parameters(TextEditorParams {
+                path: notebook_path.to_str().unwrap().to_string(),
+                command: command.to_string(),
+                view_range: None,
+                file_text: None,
+                old_str: None,
+                new_str: new_str.map(str::to_string),
+                insert_line: None,
+                diff: None,
+                edits: None,
+                preview: None,
+                cell_index,
+                cell_type: None,
+                to_index: None,
+            })
+        };
+
+        server
+            .text_editor(params("insert_cell", Some(0), Some("# Setup")))
+            .await
+            .unwrap();
+        server
+            .text_editor(params("edit_cell", Some(1), Some("x = 2\nx")))
+            .await
+            .unwrap();
+
+        let result = server
+            .text_editor(params("view", None, None))
+            .await
+            .unwrap();
+        let view = &result.content[0].as_text().unwrap().text;
+        assert!(view.contains("[0] code\n# Setup"));
+        assert!(view.contains("[1] code\nx = 2\nx"));
+
+        // The file is still a notebook, and line-based insert is refused
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&notebook_path).unwrap()).unwrap();
+        assert_eq!(saved["cells"].as_array().unwrap().len(), 2);
+        let result = server.text_editor(params("insert", None, Some("y"))).await;
+        assert_eq!(result.unwrap_err().code, ErrorCode::INVALID_PARAMS);
+
+        server
+            .text_editor(params("undo_edit", None, None))
+            .await
+            .unwrap();
+        let content = fs::read_to_string(&notebook_path).unwrap();
+        assert!(content.contains("# Setup") && content.contains("x = 1"));
+
+        // Batches only write whole notebooks, and running cells is a tool of its own
+        let batch = |command: &str, file_text: Option<&str>| {
+            let mut params = params("batch", None, None);
+            params.0.edits = Some(vec![BatchEditParams {
+                path: notebook_path.to_str().unwrap().to_string(),
+                command: command.to_string(),
+                file_text: file_text.map(str::to_string),
+                old_str: Some("x = 1".to_string()),
+                new_str: Some("x = 3".to_string()),
+                insert_line: None,
+            }]);
+            params
+        };
+        let error = server
+            .text_editor(batch("str_replace", None))
+            .await
+            .unwrap_err();
+        assert!(error.message.contains("a batch can only write as a whole"));
+        let error = server
+            .text_editor(batch("write", Some("{}")))
+            .await
+            .unwrap_err();
+        assert!(error
+            .message
+            .starts_with("Edit 1: Only nbformat 4 notebooks"));
+        assert_eq!(fs::read_to_string(&notebook_path).unwrap(), content);
+
+        let result = server
+            .text_editor(params("execute_cell", Some(0), None))
+            .await;
+        assert!(result.unwrap_err().message.contains("notebook_execute"));
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_goose_ignore_basic_patterns() {
@@ -1873,6 +2657,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(write_params).await;
@@ -1893,6 +2682,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(write_params).await;
@@ -1902,6 +2696,46 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_search_respects_ignore_patterns() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::env::set_current_dir(&temp_dir).unwrap();
+
+        fs::write(".gooseignore", "secret.txt").unwrap();
+        fs::write("secret.txt", "api_token = 1234").unwrap();
+        fs::write("config.txt", "api_token = <redacted>").unwrap();
+
+        let server = create_test_server();
+        let search_params = |path: Option<String>| {
+            Parameters(SearchParams {
+                query: "api_token".to_string(),
+                path,
+                literal: false,
+                ignore_case: false,
+                file_types: None,
+                globs: None,
+                context: 0,
+                max_results: None,
+                offset: 0,
+            })
+        };
+
+        let result = server.search(search_params(None)).await.unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.contains("config.txt"));
+        assert!(!text.contains("secret.txt"));
+
+        let secret_path = temp_dir.path().join("secret.txt");
+        let result = server
+            .search(search_params(Some(
+                secret_path.to_string_lossy().to_string(),
+            )))
+            .await;
+        assert!(result.is_err(), "Should not be able to search ignored file");
+        assert_eq!(result.unwrap_err().code, ErrorCode::INTERNAL_ERROR);
+    }
+
     #[test]
     #[serial]
     fn test_shell_respects_ignore_patterns() {
@@ -1922,6 +2756,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: format!("cat {}", secret_file_path.to_str().unwrap()),
+                        ..Default::default()
                     }),
                     RequestContext {
                         ct: Default::default(),
@@ -1944,6 +2779,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: format!("cat {}", allowed_file_path.to_str().unwrap()),
+                        ..Default::default()
                     }),
                     RequestContext {
                         ct: Default::default(),
@@ -2010,6 +2846,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2024,6 +2865,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let view_result = server.text_editor(view_params).await.unwrap();
@@ -2071,6 +2917,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2085,6 +2936,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let view_result = server.text_editor(view_params).await.unwrap();
@@ -2131,6 +2987,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2145,6 +3006,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2175,6 +3041,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2189,6 +3060,11 @@ mod tests {
             new_str: Some("Line 1".to_string()),
             insert_line: Some(0),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let insert_result = server.text_editor(insert_params).await.unwrap();
@@ -2232,6 +3108,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2246,6 +3127,11 @@ mod tests {
             new_str: Some("Line 3".to_string()),
             insert_line: Some(2),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let insert_result = server.text_editor(insert_params).await.unwrap();
@@ -2294,6 +3180,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2308,6 +3199,11 @@ mod tests {
             new_str: Some("Line 4".to_string()),
             insert_line: Some(3),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let insert_result = server.text_editor(insert_params).await.unwrap();
@@ -2351,6 +3247,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2365,6 +3266,11 @@ mod tests {
             new_str: Some("Line 4".to_string()),
             insert_line: Some(-1),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let insert_result = server.text_editor(insert_params).await.unwrap();
@@ -2408,6 +3314,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2422,6 +3333,11 @@ mod tests {
             new_str: Some("Line 11".to_string()),
             insert_line: Some(10),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(insert_params).await;
@@ -2452,6 +3368,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2466,6 +3387,11 @@ mod tests {
             new_str: None, // Missing required parameter
             insert_line: Some(1),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(insert_params).await;
@@ -2484,6 +3410,11 @@ mod tests {
             new_str: Some("New text".to_string()),
             insert_line: None, // Missing required parameter
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(insert_params).await;
@@ -2514,6 +3445,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2528,6 +3464,11 @@ mod tests {
             new_str: Some("Inserted Line".to_string()),
             insert_line: Some(1),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(insert_params).await.unwrap();
@@ -2542,6 +3483,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let undo_result = server.text_editor(undo_params).await.unwrap();
@@ -2581,6 +3527,11 @@ mod tests {
             new_str: Some("New line".to_string()),
             insert_line: Some(0),
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(insert_params).await;
@@ -2616,6 +3567,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2630,6 +3586,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2655,6 +3616,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2687,6 +3653,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2718,6 +3689,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2732,6 +3708,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2779,6 +3760,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         server.text_editor(write_params).await.unwrap();
@@ -2793,6 +3779,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(view_params).await;
@@ -2846,6 +3837,11 @@ mod tests {
                 new_str: None,
                 insert_line: None,
                 diff: None,
+                edits: None,
+                preview: None,
+                cell_index: None,
+                cell_type: None,
+                to_index: None,
             }))
             .await;
 
@@ -2909,6 +3905,11 @@ mod tests {
                 new_str: None,
                 insert_line: None,
                 diff: None,
+                edits: None,
+                preview: None,
+                cell_index: None,
+                cell_type: None,
+                to_index: None,
             }))
             .await;
 
@@ -2951,6 +3952,11 @@ mod tests {
                 new_str: None,
                 insert_line: None,
                 diff: None,
+                edits: None,
+                preview: None,
+                cell_index: None,
+                cell_type: None,
+                to_index: None,
             }))
             .await;
 
@@ -2988,6 +3994,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: command.to_string(),
+                        ..Default::default()
                     }),
                     RequestContext {
                         ct: Default::default(),
@@ -3137,6 +4144,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: command.to_string(),
+                        ..Default::default()
                     }),
                     RequestContext {
                         ct: Default::default(),
@@ -3284,6 +4292,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(write_params).await;
@@ -3311,6 +4324,11 @@ mod tests {
             new_str: None,
             insert_line: None,
             diff: None,
+            edits: None,
+            preview: None,
+            cell_index: None,
+            cell_type: None,
+            to_index: None,
         });
 
         let result = server.text_editor(write_params).await;
@@ -3347,6 +4365,7 @@ mod tests {
                     .shell(
                         Parameters(ShellParams {
                             command: "sleep 30".to_string(),
+                            ..Default::default()
                         }),
                         context,
                     )
@@ -3435,6 +4454,7 @@ mod tests {
                     .shell(
                         Parameters(ShellParams {
                             command: "bash -c 'sleep 60 & wait'".to_string(),
+                            ..Default::default()
                         }),
                         context,
                     )
@@ -3532,6 +4552,7 @@ mod tests {
                 .shell(
                     Parameters(ShellParams {
                         command: "echo 'Hello, World!'".to_string(),
+                        ..Default::default()
                     }),
                     context,
                 )
@@ -3553,4 +4574,101 @@ mod tests {
             cleanup_test_service(running_service, peer);
         });
     }
+
+    #[test]
+    #[serial]
+    #[cfg(unix)]
+    fn test_shell_sessions_and_background_processes() {
+        run_shell_test(|| async {
+            let dir = TempDir::new().unwrap();
+            std::env::set_current_dir(&dir).unwrap();
+
+            let server = create_test_server();
+            let running_service = serve_directly(server.clone(), create_test_transport(), None);
+            let peer = running_service.peer().clone();
+
+            let context = |id| RequestContext {
+                ct: Default::default(),
+                id: NumberOrString::Number(id),
+                meta: Default::default(),
+                extensions: Default::default(),
+                peer: peer.clone(),
+            };
+            let text = |result: CallToolResult| {
+                result.content[0]
+                    .as_text()
+                    .map(|text| text.text.clone())
+                    .unwrap()
+            };
+
+            server
+                .shell(
+                    Parameters(ShellParams {
+                        command: format!("cd '{}' && export STAGE=test", dir.path().display()),
+                        session: Some("work".to_string()),
+                        background: false,
+                    }),
+                    context(1),
+                )
+                .await
+                .unwrap();
+            let output = server
+                .shell(
+                    Parameters(ShellParams {
+                        command: "echo \"$(pwd) $STAGE\"".to_string(),
+                        session: Some("work".to_string()),
+                        background: false,
+                    }),
+                    context(2),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                text(output).trim(),
+                format!("{} test", dir.path().display())
+            );
+
+            let started = server
+                .shell(
+                    Parameters(ShellParams {
+                        command: "echo started; sleep 30".to_string(),
+                        session: None,
+                        background: true,
+                    }),
+                    context(3),
+                )
+                .await
+                .unwrap();
+            assert!(text(started).contains("p1"));
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let output = server
+                .process_output(Parameters(ProcessOutputParams {
+                    id: "p1".to_string(),
+                    lines: None,
+                }))
+                .await
+                .unwrap();
+            let output = text(output);
+            assert!(output.contains("is running"), "{}", output);
+            assert!(output.ends_with("started"), "{}", output);
+
+            let killed = server
+                .process_kill(Parameters(ProcessKillParams {
+                    id: "p1".to_string(),
+                }))
+                .await
+                .unwrap();
+            assert_eq!(text(killed), "Process p1 killed");
+            assert!(server
+                .process_output(Parameters(ProcessOutputParams {
+                    id: "p2".to_string(),
+                    lines: None,
+                }))
+                .await
+                .is_err());
+
+            cleanup_test_service(running_service, peer);
+        });
+    }
 }
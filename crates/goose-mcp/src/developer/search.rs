@@ -0,0 +1,453 @@
+use ignore::{gitignore::Gitignore, overrides::OverrideBuilder, types::TypesBuilder, WalkBuilder};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    schemars::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Matches listed in one page when `max_results` is not given.
+const DEFAULT_MAX_RESULTS: usize = 100;
+
+/// Most matches listed in one page.
+const MAX_RESULTS_LIMIT: usize = 1000;
+
+/// Most lines of context shown before and after a match.
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// Characters one page may use, far below the shell tool's output limit.
+const MAX_PAGE_CHARS: usize = 50_000;
+
+/// Longer lines are cut, so one minified file can't fill a page.
+const MAX_LINE_CHARS: usize = 300;
+
+/// Larger files are skipped, they are almost always generated or data.
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Parameters for the search tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SearchParams {
+    /// Regular expression to search for, matched line by line, e.g. `fn\s+parse_\w+`.
+    /// Matched as plain text when `literal` is true.
+    pub query: String,
+
+    /// Absolute path to the directory or file to search. Defaults to the working directory.
+    pub path: Option<String>,
+
+    /// Match `query` as plain text rather than a regular expression.
+    #[serde(default)]
+    pub literal: bool,
+
+    /// Match regardless of case.
+    #[serde(default)]
+    pub ignore_case: bool,
+
+    /// Only search these file types, using ripgrep's type names, e.g. `["rust", "py", "ts"]`.
+    pub file_types: Option<Vec<String>>,
+
+    /// Only search paths matching these globs, e.g. `["src/**/*.rs"]`. A glob starting with
+    /// `!` excludes paths instead, e.g. `["!**/tests/**"]`.
+    pub globs: Option<Vec<String>>,
+
+    /// Lines of context to show before and after each match, at most 10.
+    #[serde(default)]
+    pub context: usize,
+
+    /// Most matches to return, 100 by default and at most 1000.
+    pub max_results: Option<usize>,
+
+    /// Matches to skip, to fetch the next page of a previous search.
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// Lines of one file that matched the query, 0-indexed.
+struct FileMatches {
+    path: PathBuf,
+    lines: Vec<usize>,
+}
+
+fn invalid_params(message: String) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message, None)
+}
+
+/// Search the files under `root` that are neither gitignored nor excluded by
+/// `.gooseignore`, returning matches grouped by file.
+pub fn search(
+    params: &SearchParams,
+    root: &Path,
+    ignore_patterns: &Gitignore,
+) -> Result<String, ErrorData> {
+    if params.query.is_empty() {
+        return Err(invalid_params("The query must not be empty".to_string()));
+    }
+    if !root.exists() {
+        return Err(invalid_params(format!(
+            "Path '{}' does not exist",
+            root.display()
+        )));
+    }
+
+    let pattern = if params.literal {
+        regex::escape(&params.query)
+    } else {
+        params.query.clone()
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(params.ignore_case)
+        .build()
+        .map_err(|e| invalid_params(format!("Invalid regular expression: {}", e)))?;
+
+    let files = collect_files(params, root, ignore_patterns)?;
+    let matches: Vec<FileMatches> = files
+        .into_par_iter()
+        .filter_map(|path| {
+            let lines = matching_lines(&path, &regex);
+            (!lines.is_empty()).then_some(FileMatches { path, lines })
+        })
+        .collect();
+
+    Ok(render_page(params, root, &matches))
+}
+
+fn collect_files(
+    params: &SearchParams,
+    root: &Path,
+    ignore_patterns: &Gitignore,
+) -> Result<Vec<PathBuf>, ErrorData> {
+    let mut builder = WalkBuilder::new(root);
+
+    if let Some(file_types) = &params.file_types {
+        let mut types = TypesBuilder::new();
+        types.add_defaults();
+        for file_type in file_types {
+            types.select(file_type);
+        }
+        builder.types(
+            types
+                .build()
+                .map_err(|e| invalid_params(format!("Invalid file type: {}", e)))?,
+        );
+    }
+
+    if let Some(globs) = &params.globs {
+        let glob_root = if root.is_file() {
+            root.parent().unwrap_or(root)
+        } else {
+            root
+        };
+        let mut overrides = OverrideBuilder::new(glob_root);
+        for glob in globs {
+            overrides
+                .add(glob)
+                .map_err(|e| invalid_params(format!("Invalid glob '{}': {}", glob, e)))?;
+        }
+        builder.overrides(
+            overrides
+                .build()
+                .map_err(|e| invalid_params(format!("Invalid globs: {}", e)))?,
+        );
+    }
+
+    let ignore_patterns = ignore_patterns.clone();
+    builder
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !ignore_patterns.matched(entry.path(), is_dir).is_ignore()
+        })
+        .sort_by_file_path(|a, b| a.cmp(b));
+
+    let mut files = Vec::new();
+    for entry in builder.build() {
+        match entry {
+            Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+                files.push(entry.into_path())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Skipping unreadable entry during search: {}", e),
+        }
+    }
+    Ok(files)
+}
+
+/// Text of a file, or None for files that are binary, too large or unreadable.
+fn read_text(path: &Path) -> Option<String> {
+    if std::fs::metadata(path).ok()?.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn matching_lines(path: &Path, regex: &Regex) -> Vec<usize> {
+    let Some(text) = read_text(path) else {
+        return vec![];
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", line.get(..end).unwrap_or(line)),
+        None => line.to_string(),
+    }
+}
+
+fn display_path<'a>(path: &'a Path, root: &Path) -> &'a Path {
+    path.strip_prefix(root)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .unwrap_or(path)
+}
+
+/// Render the matches from `offset` on, grouped by file, until the page holds
+/// `max_results` matches or runs out of room.
+fn render_page(params: &SearchParams, root: &Path, matches: &[FileMatches]) -> String {
+    let total: usize = matches.iter().map(|file| file.lines.len()).sum();
+    if total == 0 {
+        return format!("No matches for '{}' in {}", params.query, root.display());
+    }
+    if params.offset >= total {
+        return format!(
+            "No matches past offset {}, the search found {} matches",
+            params.offset, total
+        );
+    }
+
+    let max_results = params
+        .max_results
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS_LIMIT);
+    let context = params.context.min(MAX_CONTEXT_LINES);
+
+    let mut output = format!(
+        "Found {} matches in {} files for '{}'\n",
+        total,
+        matches.len(),
+        params.query
+    );
+    let mut skipped = 0;
+    let mut shown = 0;
+    let page_full = |shown: usize, output: &str| {
+        shown == max_results || (shown > 0 && output.len() > MAX_PAGE_CHARS)
+    };
+
+    'files: for file in matches {
+        if page_full(shown, &output) {
+            break;
+        }
+        if skipped + file.lines.len() <= params.offset {
+            skipped += file.lines.len();
+            continue;
+        }
+        let first = params.offset - skipped;
+        skipped = params.offset;
+
+        let Some(text) = read_text(&file.path) else {
+            continue;
+        };
+        let lines: Vec<&str> = text.lines().collect();
+
+        let _ = write!(output, "\n{}\n", display_path(&file.path, root).display());
+        let mut printed_until = None;
+        for &line in &file.lines[first..] {
+            if page_full(shown, &output) {
+                break 'files;
+            }
+
+            let start = line.saturating_sub(context);
+            let start = match printed_until {
+                Some(until) if start <= until + 1 => until + 1,
+                // Groups of context are separated, as in ripgrep
+                Some(_) if context > 0 => {
+                    output.push_str("--\n");
+                    start
+                }
+                _ => start,
+            };
+            let end = (line + context).min(lines.len().saturating_sub(1));
+            for (index, text) in lines.iter().enumerate().take(end + 1).skip(start) {
+                // Context after this match is only printed up to the match itself, the
+                // next match prints the rest if it is within range
+                if index > line && file.lines.binary_search(&index).is_ok() {
+                    break;
+                }
+                let separator = if index == line { ':' } else { '-' };
+                let _ = writeln!(output, "{}{}{}", index + 1, separator, truncate_line(text));
+                printed_until = Some(index);
+            }
+            shown += 1;
+        }
+    }
+
+    let next = params.offset + shown;
+    if next < total {
+        let _ = write!(
+            output,
+            "\nShowing matches {}-{} of {}. Search again with offset={} for more.",
+            params.offset + 1,
+            next,
+            total,
+            next
+        );
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ignore::gitignore::GitignoreBuilder;
+    use tempfile::TempDir;
+
+    fn params(query: &str) -> SearchParams {
+        SearchParams {
+            query: query.to_string(),
+            path: None,
+            literal: false,
+            ignore_case: false,
+            file_types: None,
+            globs: None,
+            context: 0,
+            max_results: None,
+            offset: 0,
+        }
+    }
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn parse_config() {}\nfn other() {\n    parse_config();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("src/app.py"),
+            "def parse_config():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join(".env"), "PARSE_CONFIG=secret\n").unwrap();
+        std::fs::write(dir.path().join("data.bin"), b"parse_config\0\x01").unwrap();
+        dir
+    }
+
+    fn no_ignores(root: &Path) -> Gitignore {
+        GitignoreBuilder::new(root).build().unwrap()
+    }
+
+    #[test]
+    fn test_search_groups_matches_by_file() {
+        let dir = workspace();
+        let output = search(&params("parse_config"), dir.path(), &no_ignores(dir.path())).unwrap();
+
+        assert!(output.starts_with("Found 3 matches in 2 files"));
+        assert!(output.contains("src/app.py\n1:def parse_config():\n"));
+        assert!(output.contains("src/lib.rs\n1:fn parse_config() {}\n3:    parse_config();\n"));
+        // Hidden and binary files are skipped
+        assert!(!output.contains(".env"));
+        assert!(!output.contains("data.bin"));
+    }
+
+    #[test]
+    fn test_search_literal_case_and_filters() {
+        let dir = workspace();
+        let ignore = no_ignores(dir.path());
+
+        let mut literal = params("parse_config(");
+        literal.literal = true;
+        assert!(search(&literal, dir.path(), &ignore)
+            .unwrap()
+            .starts_with("Found 3 matches in 2 files"));
+        assert!(search(&params("parse_config("), dir.path(), &ignore).is_err());
+
+        let mut insensitive = params("PARSE_CONFIG");
+        insensitive.ignore_case = true;
+        insensitive.file_types = Some(vec!["py".to_string()]);
+        let output = search(&insensitive, dir.path(), &ignore).unwrap();
+        assert!(output.starts_with("Found 1 matches in 1 files"));
+
+        let mut globbed = params("parse_config");
+        globbed.globs = Some(vec!["!*.py".to_string()]);
+        let output = search(&globbed, dir.path(), &ignore).unwrap();
+        assert!(output.contains("src/lib.rs") && !output.contains("app.py"));
+
+        let mut unknown = params("x");
+        unknown.file_types = Some(vec!["not-a-language".to_string()]);
+        assert!(search(&unknown, dir.path(), &ignore).is_err());
+    }
+
+    #[test]
+    fn test_search_respects_gooseignore() {
+        let dir = workspace();
+        let mut builder = GitignoreBuilder::new(dir.path());
+        builder.add_line(None, "*.py").unwrap();
+        let ignore = builder.build().unwrap();
+
+        let output = search(&params("parse_config"), dir.path(), &ignore).unwrap();
+        assert!(output.starts_with("Found 2 matches in 1 files"));
+        assert!(!output.contains("app.py"));
+    }
+
+    #[test]
+    fn test_search_context_and_pagination() {
+        let dir = TempDir::new().unwrap();
+        let text: String = (1..=20)
+            .map(|n| {
+                if n % 5 == 0 {
+                    format!("match {}\n", n)
+                } else {
+                    format!("line {}\n", n)
+                }
+            })
+            .collect();
+        std::fs::write(dir.path().join("notes.txt"), text).unwrap();
+        let ignore = no_ignores(dir.path());
+
+        let mut page = params("match");
+        page.context = 1;
+        page.max_results = Some(2);
+        let output = search(&page, dir.path(), &ignore).unwrap();
+        assert!(output.contains(
+            "notes.txt\n4-line 4\n5:match 5\n6-line 6\n--\n9-line 9\n10:match 10\n11-line 11\n"
+        ));
+        assert!(output.ends_with("Showing matches 1-2 of 4. Search again with offset=2 for more."));
+
+        page.offset = 2;
+        let output = search(&page, dir.path(), &ignore).unwrap();
+        assert!(output.contains("14-line 14\n15:match 15\n"));
+        assert!(output.contains("20:match 20\n"));
+        assert!(!output.contains("offset="));
+
+        page.offset = 4;
+        assert!(search(&page, dir.path(), &ignore)
+            .unwrap()
+            .starts_with("No matches past offset 4"));
+    }
+
+    #[test]
+    fn test_search_caps_long_lines() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("min.js"),
+            format!("{}needle\n", "x".repeat(1000)),
+        )
+        .unwrap();
+
+        let output = search(&params("needle"), dir.path(), &no_ignores(dir.path())).unwrap();
+        let line = output.lines().find(|l| l.starts_with("1:")).unwrap();
+        assert_eq!(line.chars().count(), MAX_LINE_CHARS + 3);
+    }
+}
@@ -0,0 +1,634 @@
+mod parse;
+
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    schemars::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+use tokio::{io::AsyncReadExt, process::Command, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::shell::kill_process_group;
+
+/// How long a run may take when `timeout_secs` is not given.
+const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// Characters of report shown before remaining failures are only counted.
+const MAX_REPORT_CHARS: usize = 20_000;
+
+/// Lines kept from one failure message, split between its start and end.
+const MAX_MESSAGE_LINES: usize = 40;
+
+/// Skipped tests named before the rest are only counted.
+const MAX_SKIPPED_NAMES: usize = 10;
+
+/// Lines of raw output shown when a run produced no results, typically a build error.
+const MAX_RAW_LINES: usize = 80;
+
+/// Parameters for the run_tests tool
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RunTestsParams {
+    /// Absolute path of the project, or a file or directory in it to limit the run to.
+    /// Defaults to the working directory.
+    pub path: Option<String>,
+
+    /// The test framework: `cargo`, `pytest`, `go` or `vitest`. Detected from the project
+    /// files when not given.
+    pub framework: Option<String>,
+
+    /// Only run tests whose name matches: a substring for cargo, a -k expression for pytest,
+    /// a -run regex for go and a -t pattern for vitest.
+    pub filter: Option<String>,
+
+    /// Stop the run after this many seconds, 600 by default.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framework {
+    Cargo,
+    Pytest,
+    Go,
+    Vitest,
+}
+
+impl Framework {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cargo" => Some(Self::Cargo),
+            "pytest" => Some(Self::Pytest),
+            "go" => Some(Self::Go),
+            "vitest" => Some(Self::Vitest),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Pytest => "pytest",
+            Self::Go => "go",
+            Self::Vitest => "vitest",
+        }
+    }
+
+    /// Whether `dir` holds the project file this framework is run from.
+    fn marks(self, dir: &Path) -> bool {
+        match self {
+            Self::Cargo => dir.join("Cargo.toml").is_file(),
+            Self::Go => dir.join("go.mod").is_file(),
+            Self::Vitest => std::fs::read_to_string(dir.join("package.json"))
+                .is_ok_and(|package| package.contains("\"vitest\"")),
+            Self::Pytest => [
+                "pytest.ini",
+                "pyproject.toml",
+                "setup.py",
+                "setup.cfg",
+                "tox.ini",
+                "conftest.py",
+            ]
+            .iter()
+            .any(|file| dir.join(file).is_file()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// The result of one test, with the failure message and where it failed if it did.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: Outcome,
+    pub message: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Find the framework and project directory for `path`, from the nearest directory holding
+/// a project file. `framework` skips detection but still finds its project directory.
+pub fn detect(path: &Path, framework: Option<&str>) -> Result<(Framework, PathBuf), ErrorData> {
+    let candidates = match framework {
+        Some(name) => vec![Framework::parse(name).ok_or_else(|| {
+            ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Unknown framework '{}'. Allowed options are: cargo, pytest, go, vitest",
+                    name
+                ),
+                None,
+            )
+        })?],
+        None => vec![
+            Framework::Cargo,
+            Framework::Go,
+            Framework::Vitest,
+            Framework::Pytest,
+        ],
+    };
+
+    let start = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    for dir in start.ancestors() {
+        if let Some(framework) = candidates.iter().find(|f| f.marks(dir)) {
+            return Ok((*framework, dir.to_path_buf()));
+        }
+    }
+    Err(ErrorData::new(
+        ErrorCode::INVALID_PARAMS,
+        format!(
+            "No {} project found at or above '{}'",
+            framework.unwrap_or("cargo, pytest, go or vitest"),
+            path.display()
+        ),
+        None,
+    ))
+}
+
+/// Output of one finished, or stopped, command.
+struct Run {
+    stdout: String,
+    stderr: String,
+    success: bool,
+    timed_out: bool,
+}
+
+/// Runs test commands for one tool call, sharing its deadline and cancellation.
+pub struct TestRunner {
+    path_env: Option<OsString>,
+    deadline: Instant,
+    cancellation_token: CancellationToken,
+}
+
+impl TestRunner {
+    pub fn new(
+        timeout_secs: Option<u64>,
+        path_env: Option<OsString>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        Self {
+            path_env,
+            deadline: Instant::now() + timeout,
+            cancellation_token,
+        }
+    }
+
+    fn command(&self, program: impl AsRef<std::ffi::OsStr>, dir: &Path) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .env("GOOSE_TERMINAL", "1")
+            .env("CI", "1");
+        if let Some(path) = &self.path_env {
+            command.env("PATH", path);
+        }
+        #[cfg(unix)]
+        {
+            command.process_group(0);
+        }
+        command
+    }
+
+    async fn run(&self, mut command: Command) -> Result<Run, ErrorData> {
+        let mut child = command.spawn().map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Failed to start {:?}, is it installed and on PATH? {}",
+                    command.as_std().get_program(),
+                    e
+                ),
+                None,
+            )
+        })?;
+        let pid = child.id();
+        let mut stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let stdout = tokio::spawn(async move {
+            let mut buffer = Vec::new();
+            let _ = stdout.read_to_end(&mut buffer).await;
+            String::from_utf8_lossy(&buffer).into_owned()
+        });
+        let stderr = tokio::spawn(async move {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer).await;
+            String::from_utf8_lossy(&buffer).into_owned()
+        });
+
+        let (success, timed_out) = tokio::select! {
+            status = child.wait() => {
+                let status = status
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+                (status.success(), false)
+            }
+            _ = tokio::time::sleep_until(self.deadline) => {
+                if let Err(e) = kill_process_group(&mut child, pid).await {
+                    tracing::error!("Failed to stop timed out test run: {}", e);
+                }
+                (false, true)
+            }
+            _ = self.cancellation_token.cancelled() => {
+                if let Err(e) = kill_process_group(&mut child, pid).await {
+                    tracing::error!("Failed to stop cancelled test run: {}", e);
+                }
+                return Err(ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    "Test run was cancelled by user".to_string(),
+                    None,
+                ));
+            }
+        };
+
+        Ok(Run {
+            stdout: stdout.await.unwrap_or_default(),
+            stderr: stderr.await.unwrap_or_default(),
+            success,
+            timed_out,
+        })
+    }
+
+    /// Run the tests of the project at `root`, limited to `target` when it is a file or
+    /// directory inside it, and report the results.
+    pub async fn run_tests(
+        &self,
+        framework: Framework,
+        root: &Path,
+        target: &Path,
+        filter: Option<&str>,
+    ) -> Result<String, ErrorData> {
+        let started = std::time::Instant::now();
+        let relative = target
+            .strip_prefix(root)
+            .ok()
+            .filter(|r| !r.as_os_str().is_empty());
+
+        let (cases, runs) = match framework {
+            Framework::Cargo => self.cargo(root, filter).await?,
+            Framework::Go => {
+                let mut command = self.command("go", root);
+                command.args(["test", "-json"]);
+                if let Some(filter) = filter {
+                    command.args(["-run", filter]);
+                }
+                match relative {
+                    Some(dir) => command.arg(format!("./{}/...", dir.display())),
+                    None => command.arg("./..."),
+                };
+                let run = self.run(command).await?;
+                (parse::go_json(&run.stdout), vec![run])
+            }
+            Framework::Pytest | Framework::Vitest => {
+                self.junit(framework, root, relative, filter).await?
+            }
+        };
+
+        let timed_out = runs.iter().any(|r| r.timed_out);
+        let success = runs.iter().all(|r| r.success);
+        let raw: String = runs
+            .iter()
+            .map(|r| format!("{}{}", r.stderr, r.stdout))
+            .collect();
+        Ok(render(
+            framework,
+            &cases,
+            started.elapsed(),
+            timed_out,
+            (!success).then_some(raw.as_str()),
+        ))
+    }
+
+    /// Build the test binaries, then run each one with libtest's JSON output. Compiling with
+    /// RUSTC_BOOTSTRAP would rebuild dependencies that probe for nightly, so it is only set
+    /// for the binaries, which need it to accept `--format json` on stable.
+    async fn cargo(
+        &self,
+        root: &Path,
+        filter: Option<&str>,
+    ) -> Result<(Vec<TestCase>, Vec<Run>), ErrorData> {
+        let mut command = self.command("cargo", root);
+        command.args(["test", "--no-run", "--message-format", "json"]);
+        let build = self.run(command).await?;
+        let (executables, errors) = parse::cargo_executables(&build.stdout);
+        if !build.success {
+            // Cargo's own stderr only says which crate failed, the errors are in the messages
+            let stderr = if errors.is_empty() {
+                build.stderr.clone()
+            } else {
+                errors.join("\n\n")
+            };
+            let build = Run {
+                stdout: String::new(),
+                stderr,
+                ..build
+            };
+            return Ok((Vec::new(), vec![build]));
+        }
+
+        let mut cases = Vec::new();
+        let mut runs = Vec::new();
+        for executable in executables {
+            let mut command = self.command(&executable.path, &executable.package_dir);
+            command
+                .env("RUSTC_BOOTSTRAP", "1")
+                .env("RUST_BACKTRACE", "0")
+                .env("CARGO_MANIFEST_DIR", &executable.package_dir);
+            if let Some(filter) = filter {
+                command.arg(filter);
+            }
+            command.args(["-Z", "unstable-options", "--format", "json"]);
+            let run = self.run(command).await?;
+            cases.extend(parse::libtest_json(&run.stdout));
+            let timed_out = run.timed_out;
+            runs.push(run);
+            if timed_out {
+                break;
+            }
+        }
+        Ok((cases, runs))
+    }
+
+    /// Run pytest or vitest with a JUnit XML report and parse it.
+    async fn junit(
+        &self,
+        framework: Framework,
+        root: &Path,
+        relative: Option<&Path>,
+        filter: Option<&str>,
+    ) -> Result<(Vec<TestCase>, Vec<Run>), ErrorData> {
+        let report = tempfile::Builder::new()
+            .suffix(".xml")
+            .tempfile()
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        let report_path = report.path().display().to_string();
+
+        let mut command = match framework {
+            Framework::Pytest => {
+                let mut command = match which::which("pytest") {
+                    Ok(pytest) => self.command(pytest, root),
+                    Err(_) => {
+                        let mut command = self.command("python3", root);
+                        command.args(["-m", "pytest"]);
+                        command
+                    }
+                };
+                // xunit1 reports include the file and line of each test
+                command.args([
+                    "-q",
+                    "-o",
+                    "junit_family=xunit1",
+                    &format!("--junitxml={}", report_path),
+                ]);
+                if let Some(filter) = filter {
+                    command.args(["-k", filter]);
+                }
+                command
+            }
+            _ => {
+                let mut command = self.command("npx", root);
+                command.args([
+                    "vitest",
+                    "run",
+                    "--reporter=junit",
+                    &format!("--outputFile={}", report_path),
+                ]);
+                if let Some(filter) = filter {
+                    command.args(["-t", filter]);
+                }
+                command
+            }
+        };
+        if let Some(relative) = relative {
+            command.arg(relative);
+        }
+
+        let run = self.run(command).await?;
+        let xml = std::fs::read_to_string(report.path()).unwrap_or_default();
+        let cases = if xml.trim().is_empty() {
+            Vec::new()
+        } else {
+            parse::junit_xml(&xml).map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read the {} report: {}", framework.name(), e),
+                    None,
+                )
+            })?
+        };
+        Ok((cases, vec![run]))
+    }
+}
+
+/// Summarize a run, showing failures in full and only counting passes, so a large suite
+/// with a few failures stays small. `failed_output` is the raw output of a run that did not
+/// succeed, shown when no results could be read from it.
+pub fn render(
+    framework: Framework,
+    cases: &[TestCase],
+    elapsed: Duration,
+    timed_out: bool,
+    failed_output: Option<&str>,
+) -> String {
+    let count = |outcome| cases.iter().filter(|c| c.outcome == outcome).count();
+    let (passed, failed, skipped) = (
+        count(Outcome::Passed),
+        count(Outcome::Failed),
+        count(Outcome::Skipped),
+    );
+
+    let mut output = String::new();
+    if timed_out {
+        output.push_str("The run timed out, results are partial.\n");
+    }
+    let _ = writeln!(
+        output,
+        "{}: {} failed, {} passed, {} skipped ({:.1}s)",
+        framework.name(),
+        failed,
+        passed,
+        skipped,
+        elapsed.as_secs_f64()
+    );
+
+    if cases.is_empty() {
+        match failed_output {
+            Some(raw) => {
+                output.push_str("\nNo test results, the run failed:\n");
+                output.push_str(&tail(raw, MAX_RAW_LINES));
+            }
+            None => output.push_str("No tests matched\n"),
+        }
+        return output;
+    }
+
+    let failures: Vec<&TestCase> = cases
+        .iter()
+        .filter(|c| c.outcome == Outcome::Failed)
+        .collect();
+    for (shown, case) in failures.iter().enumerate() {
+        if output.len() > MAX_REPORT_CHARS {
+            let _ = writeln!(
+                output,
+                "\n{} more failures not shown, use filter to see them",
+                failures.len() - shown
+            );
+            break;
+        }
+        let _ = write!(output, "\nFAILED {}", case.name);
+        if let Some(location) = &case.location {
+            let _ = write!(output, " at {}", location);
+        }
+        output.push('\n');
+        if let Some(message) = case.message.as_deref().filter(|m| !m.trim().is_empty()) {
+            for line in truncate_lines(message.trim_matches('\n'), MAX_MESSAGE_LINES) {
+                let _ = writeln!(output, "    {}", line);
+            }
+        }
+    }
+
+    if skipped > 0 {
+        let names: Vec<&str> = cases
+            .iter()
+            .filter(|c| c.outcome == Outcome::Skipped)
+            .take(MAX_SKIPPED_NAMES)
+            .map(|c| c.name.as_str())
+            .collect();
+        let _ = write!(output, "\nSkipped: {}", names.join(", "));
+        if skipped > names.len() {
+            let _ = write!(output, " and {} more", skipped - names.len());
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// The first and last lines of a long text, with a marker for the lines left out.
+fn truncate_lines(text: &str, max: usize) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() <= max {
+        return lines.into_iter().map(str::to_string).collect();
+    }
+    let head = max / 2;
+    let tail = max - head;
+    let mut kept: Vec<String> = lines[..head].iter().map(|l| l.to_string()).collect();
+    kept.push(format!("... {} lines omitted ...", lines.len() - max));
+    kept.extend(lines[lines.len() - tail..].iter().map(|l| l.to_string()));
+    kept
+}
+
+fn tail(text: &str, max: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let start = lines.len().saturating_sub(max);
+    lines[start..].join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn case(name: &str, outcome: Outcome, message: Option<&str>) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            outcome,
+            message: message.map(str::to_string),
+            location: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_nearest_project() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[workspace]").unwrap();
+        let tools = dir.path().join("tools");
+        std::fs::create_dir_all(tools.join("tests")).unwrap();
+        std::fs::write(tools.join("pyproject.toml"), "").unwrap();
+        std::fs::write(tools.join("tests/test_x.py"), "").unwrap();
+
+        let (framework, root) = detect(&tools.join("tests/test_x.py"), None).unwrap();
+        assert_eq!(framework, Framework::Pytest);
+        assert_eq!(root, tools);
+
+        let (framework, root) = detect(&tools, Some("cargo")).unwrap();
+        assert_eq!(framework, Framework::Cargo);
+        assert_eq!(root, dir.path());
+
+        assert!(detect(&tools, Some("go")).is_err());
+        assert!(detect(&tools, Some("maven")).is_err());
+    }
+
+    #[test]
+    fn test_render_shows_only_failures() {
+        let mut cases: Vec<TestCase> = (0..500)
+            .map(|i| case(&format!("passes_{}", i), Outcome::Passed, None))
+            .collect();
+        cases.push(TestCase {
+            location: Some("src/lib.rs:3:5".to_string()),
+            ..case("breaks", Outcome::Failed, Some("assertion failed"))
+        });
+        cases.push(case("later", Outcome::Skipped, None));
+        let long: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        cases.push(case("noisy", Outcome::Failed, Some(&long)));
+
+        let output = render(
+            Framework::Cargo,
+            &cases,
+            Duration::from_secs(2),
+            false,
+            None,
+        );
+        assert!(output.starts_with("cargo: 2 failed, 500 passed, 1 skipped"));
+        assert!(output.contains("FAILED breaks at src/lib.rs:3:5\n    assertion failed"));
+        assert!(output.contains("... 60 lines omitted ..."));
+        assert!(output.contains("line 99"));
+        assert!(output.contains("Skipped: later"));
+        assert!(!output.contains("passes_"));
+    }
+
+    #[test]
+    fn test_render_without_results() {
+        let output = render(
+            Framework::Go,
+            &[],
+            Duration::ZERO,
+            false,
+            Some("# example.com/m\n./main.go:3:1: syntax error\n"),
+        );
+        assert!(output.contains("the run failed:\n# example.com/m\n./main.go:3:1: syntax error"));
+
+        let output = render(Framework::Go, &[], Duration::ZERO, false, None);
+        assert!(output.contains("No tests matched"));
+    }
+
+    #[test]
+    fn test_render_caps_failures() {
+        let message: String = (0..30)
+            .map(|i| format!("{} {}\n", i, "x".repeat(50)))
+            .collect();
+        let cases: Vec<TestCase> = (0..100)
+            .map(|i| case(&format!("fails_{}", i), Outcome::Failed, Some(&message)))
+            .collect();
+        let output = render(Framework::Pytest, &cases, Duration::ZERO, true, None);
+        assert!(output.starts_with("The run timed out"));
+        assert!(output.len() < MAX_REPORT_CHARS + 5_000);
+        assert!(output.contains("more failures not shown"));
+    }
+}
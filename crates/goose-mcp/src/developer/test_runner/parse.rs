@@ -0,0 +1,392 @@
+use once_cell::sync::Lazy;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{Outcome, TestCase};
+
+static RUST_PANIC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"panicked at (?:'.*', )?([^\s:]+:\d+:\d+)").unwrap());
+static GO_LOCATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"([\w./-]+_test\.go:\d+)").unwrap());
+static PYTHON_LOCATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^([^\s:]+\.py):(\d+): ").unwrap());
+
+/// A test binary built by `cargo test --no-run`, and the package directory it runs in.
+#[derive(Debug, PartialEq)]
+pub struct TestExecutable {
+    pub path: PathBuf,
+    pub package_dir: PathBuf,
+}
+
+/// Read the test binaries out of `cargo test --no-run --message-format json`, or the
+/// rendered compiler errors if the build failed.
+pub fn cargo_executables(stdout: &str) -> (Vec<TestExecutable>, Vec<String>) {
+    let mut executables = Vec::new();
+    let mut errors = Vec::new();
+    for message in stdout
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+    {
+        match message["reason"].as_str() {
+            Some("compiler-artifact") if message["profile"]["test"] == true => {
+                let (Some(path), Some(manifest)) = (
+                    message["executable"].as_str(),
+                    message["manifest_path"].as_str(),
+                ) else {
+                    continue;
+                };
+                executables.push(TestExecutable {
+                    path: PathBuf::from(path),
+                    package_dir: Path::new(manifest)
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_default(),
+                });
+            }
+            Some("compiler-message") if message["message"]["level"] == "error" => {
+                if let Some(rendered) = message["message"]["rendered"].as_str() {
+                    errors.push(rendered.trim_end().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    (executables, errors)
+}
+
+/// Parse the events of a test binary run with `-Z unstable-options --format json`.
+pub fn libtest_json(stdout: &str) -> Vec<TestCase> {
+    let mut cases = Vec::new();
+    for event in stdout
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+    {
+        if event["type"] != "test" {
+            continue;
+        }
+        let Some(name) = event["name"].as_str() else {
+            continue;
+        };
+        let outcome = match event["event"].as_str() {
+            Some("ok") => Outcome::Passed,
+            Some("failed") | Some("timeout") => Outcome::Failed,
+            Some("ignored") => Outcome::Skipped,
+            _ => continue,
+        };
+
+        let (message, location) = match outcome {
+            Outcome::Failed => {
+                let output = event["stdout"].as_str().unwrap_or_default();
+                let location = RUST_PANIC.captures(output).map(|c| c[1].to_string());
+                let message = output
+                    .lines()
+                    .filter(|line| {
+                        !line.starts_with("thread '")
+                            && !line.starts_with("note: run with `RUST_BACKTRACE=1`")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                (Some(message), location)
+            }
+            _ => (None, None),
+        };
+        cases.push(TestCase {
+            name: name.to_string(),
+            outcome,
+            message,
+            location,
+        });
+    }
+    cases
+}
+
+/// Parse the events of `go test -json`. Packages that fail without a failing test, such as
+/// ones that don't build, are reported as a failure of the package itself.
+pub fn go_json(stdout: &str) -> Vec<TestCase> {
+    let mut output: HashMap<(String, String), String> = HashMap::new();
+    let mut cases = Vec::new();
+    let mut failed_packages = Vec::new();
+    for event in stdout
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+    {
+        let package = event["Package"].as_str().unwrap_or_default().to_string();
+        let test = event["Test"].as_str().unwrap_or_default().to_string();
+        let outcome = match event["Action"].as_str() {
+            Some("output") => {
+                let text = event["Output"].as_str().unwrap_or_default();
+                let trimmed = text.trim_start();
+                if !trimmed.starts_with("=== ") && !trimmed.starts_with("--- ") {
+                    output.entry((package, test)).or_default().push_str(text);
+                }
+                continue;
+            }
+            Some("pass") => Outcome::Passed,
+            Some("fail") => Outcome::Failed,
+            Some("skip") => Outcome::Skipped,
+            _ => continue,
+        };
+
+        if test.is_empty() {
+            if outcome == Outcome::Failed {
+                failed_packages.push(package);
+            }
+            continue;
+        }
+        let message = (outcome == Outcome::Failed).then(|| {
+            output
+                .remove(&(package.clone(), test.clone()))
+                .unwrap_or_default()
+                .trim_end()
+                .to_string()
+        });
+        let location = message
+            .as_deref()
+            .and_then(|m| GO_LOCATION.captures(m))
+            .map(|c| c[1].to_string());
+        cases.push(TestCase {
+            name: format!("{}/{}", package, test),
+            outcome,
+            message,
+            location,
+        });
+    }
+
+    for package in failed_packages {
+        let prefix = format!("{}/", package);
+        if !cases
+            .iter()
+            .any(|c| c.outcome == Outcome::Failed && c.name.starts_with(&prefix))
+        {
+            let message = output
+                .remove(&(package.clone(), String::new()))
+                .unwrap_or_default();
+            cases.push(TestCase {
+                name: package,
+                outcome: Outcome::Failed,
+                message: Some(message.trim_end().to_string()),
+                location: None,
+            });
+        }
+    }
+
+    // A failing subtest also fails its parent, which adds nothing to the report
+    let failed: Vec<String> = cases
+        .iter()
+        .filter(|c| c.outcome == Outcome::Failed)
+        .map(|c| c.name.clone())
+        .collect();
+    cases.retain(|case| {
+        case.outcome != Outcome::Failed || {
+            let prefix = format!("{}/", case.name);
+            !failed.iter().any(|name| name.starts_with(&prefix))
+        }
+    });
+    cases
+}
+
+/// Parse a JUnit XML report, as written by pytest and vitest among others.
+pub fn junit_xml(xml: &str) -> Result<Vec<TestCase>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut cases = Vec::new();
+    let mut current: Option<TestCase> = None;
+    let mut in_failure = false;
+
+    loop {
+        let event = reader.read_event()?;
+        let opens = matches!(event, Event::Start(_));
+        match event {
+            Event::Start(tag) if tag.name().as_ref() == b"testcase" => {
+                current = Some(junit_case(&tag));
+            }
+            Event::Empty(tag) if tag.name().as_ref() == b"testcase" => {
+                cases.push(junit_case(&tag));
+            }
+            Event::Start(tag) | Event::Empty(tag) => {
+                let Some(case) = current.as_mut() else {
+                    continue;
+                };
+                match tag.name().as_ref() {
+                    b"failure" | b"error" => {
+                        case.outcome = Outcome::Failed;
+                        case.message = attribute(&tag, b"message");
+                        // A self-closing tag has no text and no end to wait for
+                        in_failure = opens;
+                    }
+                    b"skipped" if case.outcome != Outcome::Failed => {
+                        case.outcome = Outcome::Skipped;
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) if in_failure => {
+                if let (Some(case), Ok(text)) = (current.as_mut(), text.unescape()) {
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        if let Some(m) = PYTHON_LOCATION.captures_iter(text).last() {
+                            case.location = Some(format!("{}:{}", &m[1], &m[2]));
+                        }
+                        case.message = Some(text.to_string());
+                    }
+                }
+            }
+            Event::CData(text) if in_failure => {
+                if let Some(case) = current.as_mut() {
+                    case.message = Some(String::from_utf8_lossy(&text).trim().to_string());
+                }
+            }
+            Event::End(tag) => match tag.name().as_ref() {
+                b"failure" | b"error" => in_failure = false,
+                b"testcase" => cases.extend(current.take()),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(cases)
+}
+
+fn attribute(tag: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    tag.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+fn junit_case(tag: &BytesStart<'_>) -> TestCase {
+    let name = attribute(tag, b"name").unwrap_or_default();
+    let name = match attribute(tag, b"classname").filter(|c| !c.is_empty()) {
+        Some(class) => format!("{}::{}", class, name),
+        None => name,
+    };
+    let location = attribute(tag, b"file").map(|file| match attribute(tag, b"line") {
+        Some(line) => format!("{}:{}", file, line),
+        None => file,
+    });
+    TestCase {
+        name,
+        outcome: Outcome::Passed,
+        message: None,
+        location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_executables() {
+        let stdout = r#"{"reason":"compiler-artifact","profile":{"test":false},"executable":null,"manifest_path":"/p/Cargo.toml"}
+{"reason":"compiler-artifact","profile":{"test":true},"executable":"/p/target/debug/deps/app-123","manifest_path":"/p/crates/app/Cargo.toml"}
+{"reason":"compiler-message","message":{"level":"warning","rendered":"warning: unused"}}
+{"reason":"build-finished","success":true}"#;
+        let (executables, errors) = cargo_executables(stdout);
+        assert_eq!(
+            executables,
+            vec![TestExecutable {
+                path: PathBuf::from("/p/target/debug/deps/app-123"),
+                package_dir: PathBuf::from("/p/crates/app"),
+            }]
+        );
+        assert!(errors.is_empty());
+
+        let stdout = r#"{"reason":"compiler-message","message":{"level":"error","rendered":"error[E0425]: cannot find value `x`\n"}}"#;
+        let (_, errors) = cargo_executables(stdout);
+        assert_eq!(errors, vec!["error[E0425]: cannot find value `x`"]);
+    }
+
+    #[test]
+    fn test_libtest_json() {
+        let stdout = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::adds" }
+{ "type": "test", "name": "tests::adds", "event": "ok" }
+{ "type": "test", "name": "tests::slow", "event": "ignored" }
+{ "type": "test", "name": "tests::parses", "event": "failed", "stdout": "\nthread 'tests::parses' panicked at src/lib.rs:12:9:\nassertion `left == right` failed\n  left: 1\n right: 2\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }"#;
+        let cases = libtest_json(stdout);
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].outcome, Outcome::Passed);
+        assert_eq!(cases[1].outcome, Outcome::Skipped);
+        let failed = &cases[2];
+        assert_eq!(failed.outcome, Outcome::Failed);
+        assert_eq!(failed.location.as_deref(), Some("src/lib.rs:12:9"));
+        assert_eq!(
+            failed.message.as_deref().map(str::trim),
+            Some("assertion `left == right` failed\n  left: 1\n right: 2")
+        );
+    }
+
+    #[test]
+    fn test_go_json() {
+        let stdout = r#"{"Action":"run","Package":"example.com/m","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/m","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Action":"pass","Package":"example.com/m","Test":"TestAdd"}
+{"Action":"output","Package":"example.com/m","Test":"TestParse/empty","Output":"    parse_test.go:14: got 1, want 2\n"}
+{"Action":"fail","Package":"example.com/m","Test":"TestParse/empty"}
+{"Action":"fail","Package":"example.com/m","Test":"TestParse"}
+{"Action":"skip","Package":"example.com/m","Test":"TestSlow"}
+{"Action":"fail","Package":"example.com/m"}
+{"Action":"output","Package":"example.com/broken","Output":"FAIL\texample.com/broken [build failed]\n"}
+{"Action":"fail","Package":"example.com/broken"}"#;
+        let cases = go_json(stdout);
+        let failed: Vec<&TestCase> = cases
+            .iter()
+            .filter(|c| c.outcome == Outcome::Failed)
+            .collect();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].name, "example.com/m/TestParse/empty");
+        assert_eq!(failed[0].location.as_deref(), Some("parse_test.go:14"));
+        assert_eq!(failed[1].name, "example.com/broken");
+        assert!(failed[1]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("build failed"));
+        assert_eq!(
+            cases
+                .iter()
+                .filter(|c| c.outcome == Outcome::Skipped)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites><testsuite name="pytest" tests="3">
+<testcase classname="tests.test_math" name="test_add" file="tests/test_math.py" line="3" time="0.001"/>
+<testcase classname="tests.test_math" name="test_div" file="tests/test_math.py" line="7" time="0.002">
+<failure message="assert 1 == 2">def test_div():
+&gt;       assert 1 == 2
+E       assert 1 == 2
+
+tests/test_math.py:9: AssertionError</failure></testcase>
+<testcase classname="tests.test_math" name="test_slow" file="tests/test_math.py" line="11">
+<skipped type="pytest.skip" message="slow">tests/test_math.py:12: slow</skipped></testcase>
+<testcase classname="tests.test_io" name="test_read"><error message="file missing"/>
+<system-out>captured output</system-out></testcase>
+</testsuite></testsuites>"#;
+        let cases = junit_xml(xml).unwrap();
+        assert_eq!(cases.len(), 4);
+        assert_eq!(cases[0].name, "tests.test_math::test_add");
+        assert_eq!(cases[0].outcome, Outcome::Passed);
+        assert_eq!(cases[1].outcome, Outcome::Failed);
+        assert_eq!(cases[1].location.as_deref(), Some("tests/test_math.py:9"));
+        assert!(cases[1].message.as_deref().unwrap().contains("> "));
+        assert_eq!(cases[2].outcome, Outcome::Skipped);
+        assert_eq!(cases[2].location.as_deref(), Some("tests/test_math.py:11"));
+        assert_eq!(cases[3].outcome, Outcome::Failed);
+        assert_eq!(cases[3].message.as_deref(), Some("file missing"));
+    }
+}
@@ -1 +1,2 @@
+mod test_batch;
 mod test_diff;
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+    use crate::developer::text_editor::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    type FileHistory = Arc<Mutex<HashMap<PathBuf, Vec<String>>>>;
+
+    fn histories() -> (FileHistory, BatchHistory) {
+        (
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(Mutex::new(Vec::new())),
+        )
+    }
+
+    fn replace(old_str: &str, new_str: &str) -> FileEdit {
+        FileEdit::Replace {
+            old_str: old_str.to_string(),
+            new_str: new_str.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_applies_all_edits_and_undoes_as_unit() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.txt");
+        let second = temp_dir.path().join("second.txt");
+        let created = temp_dir.path().join("created.txt");
+        std::fs::write(&first, "alpha\nbeta\n").unwrap();
+        std::fs::write(&second, "one\ntwo\n").unwrap();
+        let (file_history, batch_history) = histories();
+
+        let edits = vec![
+            (first.clone(), replace("beta", "gamma")),
+            (
+                second.clone(),
+                FileEdit::Insert {
+                    insert_line: 0,
+                    new_str: "zero".to_string(),
+                },
+            ),
+            (
+                created.clone(),
+                FileEdit::Write {
+                    file_text: "new file".to_string(),
+                },
+            ),
+            (first.clone(), replace("alpha", "delta")),
+        ];
+        let result = text_editor_batch(edits, false, &file_history, &batch_history)
+            .await
+            .unwrap();
+
+        let text = result[0].as_text().unwrap().text.clone();
+        assert!(text.contains("3 files"));
+        assert!(text.contains("+gamma"));
+        assert!(text.contains("--- /dev/null"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "delta\ngamma\n");
+        assert_eq!(
+            std::fs::read_to_string(&second).unwrap(),
+            "zero\none\ntwo\n"
+        );
+        assert_eq!(std::fs::read_to_string(&created).unwrap(), "new file\n");
+
+        let undone = text_editor_undo_batch(&second, &file_history, &batch_history)
+            .await
+            .unwrap();
+        assert!(undone.is_some());
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "alpha\nbeta\n");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "one\ntwo\n");
+        assert!(!created.exists());
+
+        // Nothing left to undo as a batch
+        let undone = text_editor_undo_batch(&first, &file_history, &batch_history)
+            .await
+            .unwrap();
+        assert!(undone.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch_changes_nothing_when_an_edit_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.txt");
+        let second = temp_dir.path().join("second.txt");
+        std::fs::write(&first, "alpha\n").unwrap();
+        std::fs::write(&second, "one\none\n").unwrap();
+        let (file_history, batch_history) = histories();
+
+        let edits = vec![
+            (first.clone(), replace("alpha", "beta")),
+            (second.clone(), replace("one", "two")),
+        ];
+        let error = text_editor_batch(edits, false, &file_history, &batch_history)
+            .await
+            .unwrap_err();
+
+        assert!(error.message.contains("Edit 2"));
+        assert!(error.message.contains("no files were changed"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "alpha\n");
+        assert!(file_history.lock().unwrap().is_empty());
+        assert!(batch_history.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_preview_does_not_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("file.txt");
+        std::fs::write(&file, "alpha\n").unwrap();
+        let (file_history, batch_history) = histories();
+
+        let edits = vec![(file.clone(), replace("alpha", "beta"))];
+        let result = text_editor_batch(edits, true, &file_history, &batch_history)
+            .await
+            .unwrap();
+
+        let text = result[0].as_text().unwrap().text.clone();
+        assert!(text.contains("no files were changed"));
+        assert!(text.contains("-alpha\n+beta"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "alpha\n");
+        assert!(file_history.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_edits_an_empty_file_it_created() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("empty.txt");
+        let (file_history, batch_history) = histories();
+
+        let edits = vec![
+            (
+                file.clone(),
+                FileEdit::Write {
+                    file_text: String::new(),
+                },
+            ),
+            (
+                file.clone(),
+                FileEdit::Insert {
+                    insert_line: 0,
+                    new_str: "first".to_string(),
+                },
+            ),
+        ];
+        text_editor_batch(edits, false, &file_history, &batch_history)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "first\n");
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let original = (1..=20)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+        let updated = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+
+        let diff = unified_diff("file.txt", Some(&original), &updated);
+        assert_eq!(
+            diff,
+            "--- a/file.txt\n+++ b/file.txt\n\
+             @@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n line 4\n line 5\n\
+             @@ -15,6 +15,5 @@\n line 15\n line 16\n line 17\n-line 18\n line 19\n line 20\n"
+        );
+
+        let created = unified_diff("new.txt", None, "a\nb\n");
+        assert_eq!(
+            created,
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use indoc::formatdoc;
 use mpatch::{apply_patch, parse_diffs, PatchError};
+use similar::TextDiff;
 use std::{
     collections::HashMap,
     fs::File,
@@ -11,7 +12,7 @@ use url::Url;
 
 use rmcp::model::{Content, ErrorCode, ErrorData, Role};
 
-use super::editor_models::EditorModel;
+use super::editor_models::{EditorChain, EditorModel, EditorModelImpl, ReplaceEditor};
 use super::lang;
 use super::shell::normalize_line_endings;
 
@@ -287,7 +288,7 @@ fn apply_single_patch(
 }
 
 /// Parses diff content into patches with proper error handling
-fn parse_diff_content(diff_content: &str) -> Result<Vec<mpatch::Patch>, ErrorData> {
+pub(crate) fn parse_diff_content(diff_content: &str) -> Result<Vec<mpatch::Patch>, ErrorData> {
     let wrapped_diff = if diff_content.contains("```diff") || diff_content.contains("```patch") {
         diff_content.to_string()
     } else {
@@ -789,65 +790,16 @@ pub async fn text_editor_replace(
         )
     })?;
 
-    // Check if Editor API is configured and use it as the primary path
-    if let Some(ref editor) = editor_model {
-        // Editor API path - save history then call API directly
-        save_file_history(path, file_history)?;
+    // Try the fast-apply model first if one is configured, then the local fallbacks
+    let edit = EditorChain::new(editor_model.clone())
+        .edit(path, &content, old_str, new_str)
+        .await
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None))?;
 
-        match editor.edit_code(&content, old_str, new_str).await {
-            Ok(updated_content) => {
-                // Write the updated content directly
-                let mut normalized_content = normalize_line_endings(&updated_content);
-
-                if !normalized_content.ends_with('\n') {
-                    normalized_content.push('\n');
-                }
-
-                std::fs::write(path, &normalized_content).map_err(|e| {
-                    ErrorData::new(
-                        ErrorCode::INTERNAL_ERROR,
-                        format!("Failed to write file: {}", e),
-                        None,
-                    )
-                })?;
-
-                // Simple success message for Editor API
-                return Ok(vec![
-                    Content::text(format!("Successfully edited {}", path.display()))
-                        .with_audience(vec![Role::Assistant]),
-                    Content::text(format!("File {} has been edited", path.display()))
-                        .with_audience(vec![Role::User])
-                        .with_priority(0.2),
-                ]);
-            }
-            Err(e) => {
-                tracing::debug!(
-                    "Editor API call failed: {}, falling back to string replacement",
-                    e
-                );
-                // Fall through to traditional path below
-            }
-        }
-    }
-
-    // Traditional string replacement path (original logic)
-    // Ensure 'old_str' appears exactly once
-    if content.matches(old_str).count() > 1 {
-        return Err(ErrorData::new(
-            ErrorCode::INVALID_PARAMS,
-            "'old_str' must appear exactly once in the file, but it appears multiple times"
-                .to_string(),
-            None,
-        ));
-    }
-    if content.matches(old_str).count() == 0 {
-        return Err(ErrorData::new(ErrorCode::INVALID_PARAMS, "'old_str' must appear exactly once in the file, but it does not appear in the file. Make sure the string exactly matches existing file content, including whitespace!".to_string(), None));
-    }
-
-    // Save history for undo (original behavior - after validation)
+    // Save history for undo, only once an editor has applied the edit
     save_file_history(path, file_history)?;
 
-    let new_content = content.replace(old_str, new_str);
+    let new_content = edit.content;
     let mut normalized_content = normalize_line_endings(&new_content);
 
     if !normalized_content.ends_with('\n') {
@@ -862,6 +814,31 @@ pub async fn text_editor_replace(
         )
     })?;
 
+    let mut notes = String::new();
+    if !edit.fallbacks.is_empty() {
+        notes.push_str(&format!("\nApplied with {} after:\n", edit.editor));
+        for (editor, error) in &edit.fallbacks {
+            notes.push_str(&format!("- {} did not apply it: {}\n", editor, error));
+        }
+    }
+    if let Some(ref error) = edit.syntax_error {
+        notes.push_str(&format!(
+            "\nWarning: the file no longer parses at {}. Fix it or undo the edit.\n",
+            error
+        ));
+    }
+
+    if edit.editor != ReplaceEditor.name() {
+        // Edits by a model or a diff have no single replaced span to show a snippet of
+        return Ok(vec![
+            Content::text(format!("Successfully edited {}{}", path.display(), notes))
+                .with_audience(vec![Role::Assistant]),
+            Content::text(format!("File {} has been edited", path.display()))
+                .with_audience(vec![Role::User])
+                .with_priority(0.2),
+        ]);
+    }
+
     // Try to detect the language from the file extension
     let language = lang::get_language_identifier(path);
 
@@ -903,9 +880,10 @@ pub async fn text_editor_replace(
         The file {} has been edited, and the section now reads:
         {}
         Review the changes above for errors. Undo and edit the file again if necessary!
-        "#,
+        {}"#,
         path.display(),
-        output
+        output,
+        notes
     };
 
     Ok(vec![
@@ -1104,3 +1082,332 @@ pub fn save_file_history(
     history.entry(path.clone()).or_default().push(content);
     Ok(())
 }
+
+/// A single change within a `batch` command.
+#[derive(Debug, Clone)]
+pub enum FileEdit {
+    Write { file_text: String },
+    Replace { old_str: String, new_str: String },
+    Insert { insert_line: i64, new_str: String },
+}
+
+/// Files changed together by one `batch` command, so `undo_edit` can revert them as a unit.
+#[derive(Debug, Default)]
+pub struct EditBatch {
+    /// Each file with the length of its history right after the batch and whether the batch
+    /// created it
+    files: Vec<(PathBuf, usize, bool)>,
+}
+
+pub type BatchHistory = std::sync::Arc<std::sync::Mutex<Vec<EditBatch>>>;
+
+/// Lines of unchanged context around each hunk of a preview diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Most lines of combined diff returned by a batch.
+const MAX_PREVIEW_LINES: usize = 1000;
+
+struct StagedFile {
+    path: PathBuf,
+    original: Option<String>,
+    content: String,
+    /// Whether the file is there at this point of the batch, on disk or written by an edit
+    exists: bool,
+}
+
+fn replace_once(content: &str, old_str: &str, new_str: &str) -> Result<String, String> {
+    match content.matches(old_str).count() {
+        1 => Ok(content.replacen(old_str, new_str, 1)),
+        0 => Err("'old_str' does not appear in the file. Make sure the string exactly matches existing file content, including whitespace!".to_string()),
+        _ => Err("'old_str' must appear exactly once in the file, but it appears multiple times".to_string()),
+    }
+}
+
+fn insert_at(content: &str, insert_line_spec: i64, new_str: &str) -> Result<String, String> {
+    let mut lines: Vec<&str> = content.lines().collect();
+    let total_lines = lines.len();
+    let insert_line = if insert_line_spec < 0 {
+        total_lines as i64 + 1 + insert_line_spec
+    } else {
+        insert_line_spec
+    };
+    if insert_line < 0 || insert_line as usize > total_lines {
+        return Err(format!(
+            "Insert line {} is beyond the end of the file (total lines: {}). Use 0 to insert at the beginning or {} to insert at the end.",
+            insert_line, total_lines, total_lines
+        ));
+    }
+    lines.insert(insert_line as usize, new_str);
+    Ok(lines.join("\n"))
+}
+
+/// Applies a list of edits across files as one transaction.
+///
+/// Every edit is validated against the files as they will be after the edits before it, then
+/// all files are written, or none if anything fails. Returns a combined diff of the changes;
+/// with `preview` nothing is written.
+pub async fn text_editor_batch(
+    edits: Vec<(PathBuf, FileEdit)>,
+    preview: bool,
+    file_history: &std::sync::Arc<
+        std::sync::Mutex<std::collections::HashMap<PathBuf, Vec<String>>>,
+    >,
+    batch_history: &BatchHistory,
+) -> Result<Vec<Content>, ErrorData> {
+    if edits.is_empty() {
+        return Err(ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            "The 'edits' parameter must contain at least one edit for the batch command"
+                .to_string(),
+            None,
+        ));
+    }
+
+    let mut staged: Vec<StagedFile> = Vec::new();
+    for (index, (path, edit)) in edits.into_iter().enumerate() {
+        let fail = |message: String| {
+            ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Edit {} ({}) failed, no files were changed: {}",
+                    index + 1,
+                    path.display(),
+                    message
+                ),
+                None,
+            )
+        };
+
+        let position = match staged.iter().position(|file| file.path == path) {
+            Some(position) => position,
+            None => {
+                let original = if path.is_file() {
+                    Some(std::fs::read_to_string(&path).map_err(|e| fail(e.to_string()))?)
+                } else if path.exists() {
+                    return Err(fail("the path is not a file".to_string()));
+                } else {
+                    None
+                };
+                staged.push(StagedFile {
+                    path: path.clone(),
+                    content: original.clone().unwrap_or_default(),
+                    exists: original.is_some(),
+                    original,
+                });
+                staged.len() - 1
+            }
+        };
+        let file = &mut staged[position];
+
+        let updated = match edit {
+            FileEdit::Write { file_text } => {
+                if !file.exists && !path.parent().is_some_and(|parent| parent.is_dir()) {
+                    return Err(fail("the parent directory does not exist".to_string()));
+                }
+                file.exists = true;
+                file_text
+            }
+            _ if !file.exists => {
+                return Err(fail(
+                    "the file does not exist, create it with a `write` edit first".to_string(),
+                ));
+            }
+            FileEdit::Replace { old_str, new_str } => {
+                replace_once(&file.content, &old_str, &new_str).map_err(fail)?
+            }
+            FileEdit::Insert {
+                insert_line,
+                new_str,
+            } => insert_at(&file.content, insert_line, &new_str).map_err(fail)?,
+        };
+
+        let mut normalized = normalize_line_endings(&updated);
+        if !normalized.ends_with('\n') {
+            normalized.push('\n');
+        }
+        file.content = normalized;
+    }
+
+    staged.retain(|file| file.original.as_deref() != Some(file.content.as_str()));
+    if staged.is_empty() {
+        return Ok(vec![Content::text(
+            "The edits leave every file unchanged, nothing to apply",
+        )]);
+    }
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut diff = String::new();
+    for file in &staged {
+        let label = file.path.strip_prefix(&cwd).unwrap_or(&file.path);
+        diff.push_str(&unified_diff(
+            &label.display().to_string(),
+            file.original.as_deref(),
+            &file.content,
+        ));
+    }
+    let (lines_added, lines_removed) = count_line_changes(&diff);
+    let diff_lines = diff.lines().count();
+    if diff_lines > MAX_PREVIEW_LINES {
+        diff = diff
+            .lines()
+            .take(MAX_PREVIEW_LINES)
+            .collect::<Vec<_>>()
+            .join("\n");
+        diff.push_str(&format!(
+            "\n... {} more lines of diff not shown\n",
+            diff_lines - MAX_PREVIEW_LINES
+        ));
+    }
+
+    let summary = format!(
+        "{} files, {} lines added, {} lines removed",
+        staged.len(),
+        lines_added,
+        lines_removed
+    );
+    if preview {
+        let text = format!(
+            "Preview of batch edit ({}), no files were changed:\n\n```diff\n{}```\n",
+            summary, diff
+        );
+        return Ok(vec![Content::text(text)]);
+    }
+
+    commit_staged(&staged)?;
+
+    {
+        let mut history = file_history.lock().unwrap();
+        let mut batch = EditBatch::default();
+        for file in &staged {
+            let entries = history.entry(file.path.clone()).or_default();
+            entries.push(file.original.clone().unwrap_or_default());
+            batch
+                .files
+                .push((file.path.clone(), entries.len(), file.original.is_none()));
+        }
+        batch_history.lock().unwrap().push(batch);
+    }
+
+    let message = format!(
+        "Applied batch edit ({}):\n\n```diff\n{}```\n\nUse 'undo_edit' on any of these files to revert the whole batch.",
+        summary, diff
+    );
+    Ok(vec![
+        Content::text(message).with_audience(vec![Role::Assistant]),
+        Content::text(format!("```diff\n{}```\n", diff))
+            .with_audience(vec![Role::User])
+            .with_priority(0.2),
+    ])
+}
+
+/// Writes every staged file, restoring the ones already written if a write fails.
+fn commit_staged(staged: &[StagedFile]) -> Result<(), ErrorData> {
+    for (index, file) in staged.iter().enumerate() {
+        if let Err(e) = std::fs::write(&file.path, &file.content) {
+            for written in &staged[..index] {
+                let restored = match &written.original {
+                    Some(original) => std::fs::write(&written.path, original),
+                    None => std::fs::remove_file(&written.path),
+                };
+                if let Err(e) = restored {
+                    tracing::error!(
+                        "Failed to roll back {} after a failed batch edit: {}",
+                        written.path.display(),
+                        e
+                    );
+                }
+            }
+            return Err(ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Failed to write {}, no files were changed: {}",
+                    file.path.display(),
+                    e
+                ),
+                None,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reverts the most recent batch containing `path` when it is also the file's latest edit.
+/// Returns `None` when the last edit to the file was not part of a batch.
+pub async fn text_editor_undo_batch(
+    path: &PathBuf,
+    file_history: &std::sync::Arc<
+        std::sync::Mutex<std::collections::HashMap<PathBuf, Vec<String>>>,
+    >,
+    batch_history: &BatchHistory,
+) -> Result<Option<Vec<Content>>, ErrorData> {
+    let mut history = file_history.lock().unwrap();
+    let mut batches = batch_history.lock().unwrap();
+    let depth = history.get(path).map_or(0, Vec::len);
+
+    let Some(index) = batches.iter().rposition(|batch| {
+        batch
+            .files
+            .iter()
+            .any(|(file, batch_depth, _)| file == path && *batch_depth == depth)
+    }) else {
+        return Ok(None);
+    };
+    let batch = batches.remove(index);
+
+    let mut reverted = Vec::new();
+    let mut skipped = Vec::new();
+    for (file, batch_depth, created) in batch.files {
+        let entries = history.entry(file.clone()).or_default();
+        // Files edited again since the batch keep their newer changes
+        if entries.len() != batch_depth {
+            skipped.push(file.display().to_string());
+            continue;
+        }
+        let previous = entries.pop().unwrap_or_default();
+        let result = if created {
+            std::fs::remove_file(&file)
+        } else {
+            std::fs::write(&file, previous)
+        };
+        result.map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to restore {}: {}", file.display(), e),
+                None,
+            )
+        })?;
+        reverted.push(file.display().to_string());
+    }
+
+    let mut message = format!(
+        "Undid the batch edit of {} files:\n{}",
+        reverted.len(),
+        reverted.join("\n")
+    );
+    if !skipped.is_empty() {
+        message.push_str(&format!(
+            "\n\nLeft alone because they were edited again after the batch:\n{}",
+            skipped.join("\n")
+        ));
+    }
+    Ok(Some(vec![Content::text(message)]))
+}
+
+/// Unified diff of one file, `original` is `None` for a file being created.
+pub fn unified_diff(label: &str, original: Option<&str>, updated: &str) -> String {
+    let old_label = match original {
+        Some(_) => format!("a/{}", label),
+        None => "/dev/null".to_string(),
+    };
+    let new_label = format!("b/{}", label);
+    let diff = TextDiff::from_lines(original.unwrap_or_default(), updated)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .header(&old_label, &new_label)
+        .to_string();
+    if diff.is_empty() {
+        // Creating an empty file has no hunks, but should still show up
+        return format!("--- {}\n+++ {}\n", old_label, new_label);
+    }
+    diff
+}
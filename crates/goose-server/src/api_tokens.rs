@@ -0,0 +1,355 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use goose::config::paths::Paths;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const TOKEN_PREFIX: &str = "goosed_";
+const TOKENS_FILE: &str = "goosed_tokens.json";
+
+/// What a goosed API token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "sessions:read")]
+    ReadSessions,
+    #[serde(rename = "agent:run")]
+    RunAgent,
+    #[serde(rename = "config:manage")]
+    ManageConfig,
+    #[serde(rename = "schedules:manage")]
+    ManageSchedules,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [
+        Scope::ReadSessions,
+        Scope::RunAgent,
+        Scope::ManageConfig,
+        Scope::ManageSchedules,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadSessions => "sessions:read",
+            Scope::RunAgent => "agent:run",
+            Scope::ManageConfig => "config:manage",
+            Scope::ManageSchedules => "schedules:manage",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                let valid: Vec<_> = Scope::ALL.iter().map(Scope::as_str).collect();
+                anyhow::anyhow!(
+                    "Unknown scope '{}', expected one of: {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+/// A named API token. Only a hash of the secret is stored; the plaintext is shown once when the
+/// token is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    /// Owner of the sessions created with this token
+    pub user: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Overrides the server's daily token quota for the token's user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_token_quota: Option<u64>,
+    secret_hash: String,
+}
+
+impl ApiToken {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
+
+/// When the tokens file was last modified and its size, `None` when there is no file
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// File backed store of the API tokens goosed accepts next to the shared secret key. Lookups
+/// reload the file whenever it changes, so tokens created or revoked from the CLI apply to a
+/// running server.
+pub struct TokenStore {
+    path: PathBuf,
+    /// The tokens as of the file's modification time and size, so lookups only parse it again
+    /// after it changes
+    cache: Mutex<Option<(FileStamp, Arc<Vec<ApiToken>>)>>,
+}
+
+impl Default for TokenStore {
+    fn default() -> Self {
+        Self::new(Paths::config_dir().join(TOKENS_FILE))
+    }
+}
+
+impl TokenStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cache: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn list(&self) -> Result<Vec<ApiToken>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    /// Creates a token and returns it together with the plaintext value to hand to the client.
+    pub fn create(
+        &self,
+        name: &str,
+        user: Option<&str>,
+        scopes: Vec<Scope>,
+        expires_in: Option<Duration>,
+    ) -> Result<(ApiToken, String)> {
+        if scopes.is_empty() {
+            bail!("A token needs at least one scope");
+        }
+        let mut tokens = self.list()?;
+        if tokens.iter().any(|token| token.name == name) {
+            bail!("A token named '{}' already exists", name);
+        }
+
+        let id = hex::encode(rand::random::<[u8; 6]>());
+        let secret = hex::encode(rand::random::<[u8; 32]>());
+        let now = Utc::now();
+        let token = ApiToken {
+            id: id.clone(),
+            name: name.to_string(),
+            user: user.unwrap_or(name).to_string(),
+            scopes,
+            created_at: now,
+            expires_at: expires_in.map(|duration| now + duration),
+            daily_token_quota: None,
+            secret_hash: hash_secret(&secret),
+        };
+        tokens.push(token.clone());
+        self.save(&tokens)?;
+
+        Ok((token, format!("{}{}.{}", TOKEN_PREFIX, id, secret)))
+    }
+
+    /// Removes the token with the given name or id, returning whether one was found.
+    pub fn revoke(&self, name_or_id: &str) -> Result<bool> {
+        let mut tokens = self.list()?;
+        let before = tokens.len();
+        tokens.retain(|token| token.name != name_or_id && token.id != name_or_id);
+        if tokens.len() == before {
+            return Ok(false);
+        }
+        self.save(&tokens)?;
+        Ok(true)
+    }
+
+    /// Sets or clears the daily token quota of the token with the given name or id, returning
+    /// whether one was found.
+    pub fn set_daily_token_quota(&self, name_or_id: &str, quota: Option<u64>) -> Result<bool> {
+        let mut tokens = self.list()?;
+        let Some(token) = tokens
+            .iter_mut()
+            .find(|token| token.name == name_or_id || token.id == name_or_id)
+        else {
+            return Ok(false);
+        };
+        token.daily_token_quota = quota;
+        self.save(&tokens)?;
+        Ok(true)
+    }
+
+    /// Looks up the token a client presented. Unknown, malformed and expired tokens all yield
+    /// `None`.
+    pub fn verify(&self, presented: &str) -> Option<ApiToken> {
+        let (id, secret) = presented.strip_prefix(TOKEN_PREFIX)?.split_once('.')?;
+        let tokens = match self.cached() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                tracing::warn!("Failed to load API tokens: {}", e);
+                return None;
+            }
+        };
+        let presented_hash = hash_secret(secret);
+        tokens
+            .iter()
+            .find(|token| token.id == id && token.secret_hash == presented_hash)
+            .filter(|token| !token.is_expired())
+            .cloned()
+    }
+
+    fn cached(&self) -> Result<Arc<Vec<ApiToken>>> {
+        let stamp = match std::fs::metadata(&self.path) {
+            Ok(metadata) => Some((metadata.modified()?, metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((cached_stamp, tokens)) = cache.as_ref() {
+            if *cached_stamp == stamp {
+                return Ok(tokens.clone());
+            }
+        }
+        let tokens = Arc::new(self.list()?);
+        *cache = Some((stamp, tokens.clone()));
+        Ok(tokens)
+    }
+
+    fn save(&self, tokens: &[ApiToken]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(tokens)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn store(dir: &TempDir) -> TokenStore {
+        TokenStore::new(dir.path().join(TOKENS_FILE))
+    }
+
+    #[test]
+    fn test_create_verify_revoke() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let (token, plaintext) = store
+            .create("ci", Some("alice"), vec![Scope::ReadSessions], None)
+            .unwrap();
+        assert!(plaintext.starts_with(TOKEN_PREFIX));
+        assert!(!std::fs::read_to_string(store.path())
+            .unwrap()
+            .contains(plaintext.split_once('.').unwrap().1));
+
+        let verified = store.verify(&plaintext).unwrap();
+        assert_eq!(verified.id, token.id);
+        assert_eq!(verified.user, "alice");
+        assert_eq!(verified.scopes, vec![Scope::ReadSessions]);
+
+        assert!(store.verify(&format!("{}x", plaintext)).is_none());
+        assert!(store.verify("not-a-token").is_none());
+
+        assert!(store.set_daily_token_quota("ci", Some(1000)).unwrap());
+        assert_eq!(
+            store.verify(&plaintext).unwrap().daily_token_quota,
+            Some(1000)
+        );
+        assert!(!store.set_daily_token_quota("missing", None).unwrap());
+
+        assert!(store.revoke("ci").unwrap());
+        assert!(!store.revoke("ci").unwrap());
+        assert!(store.verify(&plaintext).is_none());
+    }
+
+    #[test]
+    fn test_verify_sees_tokens_changed_by_another_store() {
+        let dir = TempDir::new().unwrap();
+        let server = store(&dir);
+        let cli = store(&dir);
+
+        assert!(server.verify("goosed_abc.def").is_none());
+        let (_, plaintext) = cli.create("ci", None, vec![Scope::RunAgent], None).unwrap();
+        assert!(server.verify(&plaintext).is_some());
+        assert!(cli.revoke("ci").unwrap());
+        assert!(server.verify(&plaintext).is_none());
+    }
+
+    #[test]
+    fn test_create_rejects_duplicates_and_missing_scopes() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let (token, _) = store
+            .create("laptop", None, vec![Scope::RunAgent], None)
+            .unwrap();
+        assert_eq!(token.user, "laptop");
+        assert!(store
+            .create("laptop", None, vec![Scope::RunAgent], None)
+            .is_err());
+        assert!(store.create("other", None, vec![], None).is_err());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_expired_tokens_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let (_, plaintext) = store
+            .create(
+                "old",
+                None,
+                vec![Scope::RunAgent],
+                Some(Duration::seconds(-1)),
+            )
+            .unwrap();
+        assert!(store.verify(&plaintext).is_none());
+    }
+
+    #[test]
+    fn test_scope_round_trip() {
+        for scope in Scope::ALL {
+            assert_eq!(Scope::from_str(scope.as_str()).unwrap(), scope);
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::Value::String(scope.to_string())
+            );
+        }
+        assert!(Scope::from_str("everything").is_err());
+    }
+}
@@ -1,25 +1,388 @@
+use crate::api_tokens::{Scope, TokenStore};
+use crate::jwt::JwtValidator;
 use axum::{
+    body::Body,
     extract::{Request, State},
-    http::StatusCode,
+    http::{header, HeaderMap, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
+use goose::session::{Session, SessionManager};
+use std::sync::Arc;
+
+const MAX_INSPECTED_BODY: usize = 50 * 1024 * 1024;
+
+/// Everything goosed accepts as credentials: the shared secret key the desktop app uses, named
+/// API tokens, and optionally JWTs signed by a key in a local JWKS file.
+pub struct AuthConfig {
+    pub secret_key: String,
+    pub tokens: TokenStore,
+    pub jwt: Option<JwtValidator>,
+}
+
+impl AuthConfig {
+    pub fn new(secret_key: String) -> Self {
+        Self {
+            secret_key,
+            tokens: TokenStore::default(),
+            jwt: None,
+        }
+    }
+
+    pub fn with_jwt(mut self, jwt: Option<JwtValidator>) -> Self {
+        self.jwt = jwt;
+        self
+    }
+
+    fn authenticate(&self, headers: &HeaderMap) -> Option<Principal> {
+        let secret_key = headers
+            .get("X-Secret-Key")
+            .and_then(|value| value.to_str().ok());
+        if secret_key == Some(self.secret_key.as_str()) {
+            return Some(Principal::full_access());
+        }
+
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or(secret_key)?
+            .trim();
+
+        if let Some(token) = self.tokens.verify(presented) {
+            return Some(Principal {
+                user: Some(token.user),
+                scopes: token.scopes,
+                token_id: Some(token.id),
+                daily_token_quota: token.daily_token_quota,
+            });
+        }
+
+        let jwt = self.jwt.as_ref()?;
+        match jwt.validate(presented) {
+            Ok(identity) => Some(Principal {
+                user: Some(identity.user),
+                scopes: identity.scopes,
+                token_id: None,
+                daily_token_quota: None,
+            }),
+            Err(e) => {
+                tracing::debug!("Rejected JWT: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// The authenticated caller of a request, available to handlers as a request extension.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// `None` for the shared secret key, which sees every session
+    pub user: Option<String>,
+    pub scopes: Vec<Scope>,
+    /// Id of the API token used, if any
+    pub token_id: Option<String>,
+    /// Tokens the user may spend per day, overriding the server default
+    pub daily_token_quota: Option<u64>,
+}
+
+impl Principal {
+    pub fn full_access() -> Self {
+        Self {
+            user: None,
+            scopes: Scope::ALL.to_vec(),
+            token_id: None,
+            daily_token_quota: None,
+        }
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn can_access(&self, session: &Session) -> bool {
+        match &self.user {
+            None => true,
+            Some(user) => session.owner.as_deref() == Some(user.as_str()),
+        }
+    }
+}
 
 pub async fn check_token(
-    State(state): State<String>,
+    State(auth): State<Arc<AuthConfig>>,
     request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    if request.uri().path() == "/status" || request.uri().path() == "/mcp-ui-proxy" {
+    let path = request.uri().path();
+    // Schedule webhooks are called by external systems and check their own per-job token, and
+    // shared snapshots are readable by anyone holding their random link
+    if path == "/status"
+        || path == "/mcp-ui-proxy"
+        || path.starts_with("/hooks/")
+        || path.starts_with("/share/")
+    {
         return Ok(next.run(request).await);
     }
-    let secret_key = request
-        .headers()
-        .get("X-Secret-Key")
-        .and_then(|value| value.to_str().ok());
 
-    match secret_key {
-        Some(key) if key == state => Ok(next.run(request).await),
-        _ => Err(StatusCode::UNAUTHORIZED),
+    let principal = auth
+        .authenticate(request.headers())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !principal.allows(required_scope(request.method(), path)) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mut request = if principal.user.is_some() {
+        check_session_ownership(&principal, request).await?
+    } else {
+        request
+    };
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+fn required_scope(method: &Method, path: &str) -> Scope {
+    // metrics aggregate over every user's sessions, so they are for operators only
+    if path.starts_with("/config")
+        || path.starts_with("/tunnel")
+        || path.starts_with("/handle_")
+        || path.starts_with("/webhooks")
+        || path == "/metrics"
+    {
+        Scope::ManageConfig
+    } else if path.starts_with("/schedule")
+        || path == "/recipes/schedule"
+        || path.starts_with("/workers")
+    {
+        Scope::ManageSchedules
+    } else if method == Method::GET
+        && (path.starts_with("/sessions")
+            || path.starts_with("/diagnostics")
+            || path.starts_with("/shares"))
+    {
+        Scope::ReadSessions
+    } else {
+        Scope::RunAgent
+    }
+}
+
+fn path_session_id(path: &str) -> Option<&str> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    match (segments.next(), segments.next()) {
+        (Some("sessions"), Some(id)) if !matches!(id, "" | "import" | "insights") => Some(id),
+        (Some("diagnostics"), Some(id)) if !id.is_empty() => Some(id),
+        _ => None,
+    }
+}
+
+fn query_session_id(query: Option<&str>) -> Option<String> {
+    url::form_urlencoded::parse(query?.as_bytes())
+        .find(|(key, _)| key == "session_id")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Whether a Content-Type is one axum's `Json` extractor accepts, `None` if it can't be parsed.
+fn is_json_content_type(value: &[u8]) -> Option<bool> {
+    let value = std::str::from_utf8(value).ok()?;
+    let essence = value.split(';').next()?.trim().to_ascii_lowercase();
+    let (kind, subtype) = essence.split_once('/')?;
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    if !is_token(kind) || !is_token(subtype) {
+        return None;
+    }
+    Some(kind == "application" && (subtype == "json" || subtype.ends_with("+json")))
+}
+
+// Token users only get to see and drive their own sessions. Sessions they don't own are
+// reported as missing rather than forbidden so their ids don't leak.
+async fn check_session_ownership(
+    principal: &Principal,
+    request: Request,
+) -> Result<Request, StatusCode> {
+    let path = request.uri().path();
+    if path == "/sessions/insights" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mut session_ids: Vec<String> = path_session_id(path)
+        .map(str::to_string)
+        .into_iter()
+        .collect();
+    session_ids.extend(query_session_id(request.uri().query()));
+
+    let is_json = match request.headers().get(header::CONTENT_TYPE) {
+        Some(value) => is_json_content_type(value.as_bytes()).ok_or(StatusCode::BAD_REQUEST)?,
+        None => false,
+    };
+    let request = if is_json {
+        let (parts, body) = request.into_parts();
+        let bytes = axum::body::to_bytes(body, MAX_INSPECTED_BODY)
+            .await
+            .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+        if let Ok(serde_json::Value::Object(body)) = serde_json::from_slice(&bytes) {
+            if let Some(id) = body.get("session_id").and_then(|id| id.as_str()) {
+                session_ids.push(id.to_string());
+            }
+        }
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        request
+    };
+
+    for session_id in session_ids {
+        // Missing sessions are left for the handler to report
+        if let Ok(session) = SessionManager::get_session(&session_id, false).await {
+            if !principal.can_access(&session) {
+                return Err(StatusCode::NOT_FOUND);
+            }
+        }
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_required_scope() {
+        let cases = [
+            (Method::GET, "/sessions", Scope::ReadSessions),
+            (Method::GET, "/sessions/abc/export", Scope::ReadSessions),
+            (Method::GET, "/diagnostics/abc", Scope::ReadSessions),
+            (Method::DELETE, "/sessions/abc", Scope::RunAgent),
+            (Method::GET, "/shares", Scope::ReadSessions),
+            (Method::POST, "/sessions/abc/share", Scope::RunAgent),
+            (Method::POST, "/reply", Scope::RunAgent),
+            (Method::POST, "/agent/start", Scope::RunAgent),
+            (Method::GET, "/config/providers", Scope::ManageConfig),
+            (Method::POST, "/tunnel/start", Scope::ManageConfig),
+            (Method::POST, "/handle_openrouter", Scope::ManageConfig),
+            (Method::GET, "/metrics", Scope::ManageConfig),
+            (Method::POST, "/webhooks", Scope::ManageConfig),
+            (Method::GET, "/schedule/list", Scope::ManageSchedules),
+            (Method::POST, "/recipes/schedule", Scope::ManageSchedules),
+            (Method::POST, "/workers/lease", Scope::ManageSchedules),
+            (Method::POST, "/recipes/save", Scope::RunAgent),
+        ];
+        for (method, path, scope) in cases {
+            assert_eq!(required_scope(&method, path), scope, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn test_session_id_extraction() {
+        assert_eq!(path_session_id("/sessions/abc"), Some("abc"));
+        assert_eq!(path_session_id("/sessions/abc/events"), Some("abc"));
+        assert_eq!(path_session_id("/diagnostics/abc"), Some("abc"));
+        assert_eq!(path_session_id("/sessions"), None);
+        assert_eq!(path_session_id("/sessions/import"), None);
+        assert_eq!(path_session_id("/schedule/abc/sessions"), None);
+
+        assert_eq!(
+            query_session_id(Some("extension_name=dev&session_id=a%20b")),
+            Some("a b".to_string())
+        );
+        assert_eq!(query_session_id(Some("format=html")), None);
+        assert_eq!(query_session_id(None), None);
+    }
+
+    #[test]
+    fn test_json_content_type() {
+        for value in [
+            "application/json",
+            "Application/JSON",
+            "application/json; charset=utf-8",
+            "application/vnd.api+json",
+            " APPLICATION/problem+JSON ",
+        ] {
+            assert_eq!(
+                is_json_content_type(value.as_bytes()),
+                Some(true),
+                "{}",
+                value
+            );
+        }
+        for value in [
+            "text/plain",
+            "multipart/form-data; boundary=x",
+            "application/jsonp",
+        ] {
+            assert_eq!(
+                is_json_content_type(value.as_bytes()),
+                Some(false),
+                "{}",
+                value
+            );
+        }
+        for value in ["", "json", "application/", "/json", "application/js on"] {
+            assert_eq!(is_json_content_type(value.as_bytes()), None, "{}", value);
+        }
+        assert_eq!(is_json_content_type(b"application/json\xff"), None);
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let dir = TempDir::new().unwrap();
+        let auth = AuthConfig {
+            secret_key: "shared".to_string(),
+            tokens: TokenStore::new(dir.path().join("tokens.json")),
+            jwt: None,
+        };
+        let (_, token) = auth
+            .tokens
+            .create("ci", Some("bob"), vec![Scope::ReadSessions], None)
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert!(auth.authenticate(&headers).is_none());
+
+        headers.insert("X-Secret-Key", "shared".parse().unwrap());
+        let principal = auth.authenticate(&headers).unwrap();
+        assert_eq!(principal.user, None);
+        assert!(principal.allows(Scope::ManageConfig));
+
+        headers.insert("X-Secret-Key", token.parse().unwrap());
+        let principal = auth.authenticate(&headers).unwrap();
+        assert_eq!(principal.user.as_deref(), Some("bob"));
+        assert!(principal.allows(Scope::ReadSessions));
+        assert!(!principal.allows(Scope::RunAgent));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        assert!(auth.authenticate(&headers).is_some());
+        headers.insert(header::AUTHORIZATION, "Bearer shared".parse().unwrap());
+        assert!(auth.authenticate(&headers).is_none());
+    }
+
+    #[test]
+    fn test_can_access() {
+        let session = Session {
+            owner: Some("bob".to_string()),
+            ..Default::default()
+        };
+        let bob = Principal {
+            user: Some("bob".to_string()),
+            scopes: vec![Scope::ReadSessions],
+            token_id: None,
+            daily_token_quota: None,
+        };
+        let alice = Principal {
+            user: Some("alice".to_string()),
+            scopes: vec![Scope::ReadSessions],
+            token_id: None,
+            daily_token_quota: None,
+        };
+        assert!(bob.can_access(&session));
+        assert!(!alice.can_access(&session));
+        assert!(Principal::full_access().can_access(&session));
+        assert!(!bob.can_access(&Session::default()));
     }
 }
@@ -1,8 +1,12 @@
+use crate::auth::{check_token, AuthConfig};
 use crate::configuration;
+use crate::jwt::JwtValidator;
+use crate::limits::{rate_limit, Limits};
 use crate::state;
 use anyhow::Result;
 use axum::middleware;
-use goose_server::auth::check_token;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
 
@@ -42,7 +46,19 @@ pub async fn run() -> Result<()> {
     let secret_key =
         std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
 
-    let app_state = state::AppState::new().await?;
+    let jwt = match &settings.jwks_file {
+        Some(jwks_file) => Some(JwtValidator::from_file(
+            jwks_file,
+            settings.jwt_issuer.clone(),
+            settings.jwt_audience.clone(),
+            settings.jwt_user_claim.clone(),
+        )?),
+        None => None,
+    };
+    let auth = Arc::new(AuthConfig::new(secret_key.clone()).with_jwt(jwt));
+
+    let app_state = state::AppState::with_limits(Limits::new((&settings).into())).await?;
+    app_state.webhooks.forward_schedule_runs();
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -51,9 +67,10 @@ pub async fn run() -> Result<()> {
 
     let app = crate::routes::configure(app_state.clone(), secret_key.clone())
         .layer(middleware::from_fn_with_state(
-            secret_key.clone(),
-            check_token,
+            app_state.limits.clone(),
+            rate_limit,
         ))
+        .layer(middleware::from_fn_with_state(auth, check_token))
         .layer(cors);
 
     let listener = tokio::net::TcpListener::bind(settings.socket_addr()).await?;
@@ -64,9 +81,13 @@ pub async fn run() -> Result<()> {
         tunnel_manager.check_auto_start().await;
     });
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+    app_state.agent_manager.save_snapshots().await;
     info!("server shutdown complete");
     Ok(())
 }
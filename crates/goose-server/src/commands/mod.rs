@@ -1 +1,3 @@
 pub mod agent;
+pub mod token;
+pub mod worker;
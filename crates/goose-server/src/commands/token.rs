@@ -0,0 +1,75 @@
+use crate::api_tokens::{Scope, TokenStore};
+use anyhow::Result;
+use chrono::Duration;
+
+pub fn create(
+    name: &str,
+    user: Option<&str>,
+    scopes: Vec<Scope>,
+    expires_in_days: Option<i64>,
+    daily_token_quota: Option<u64>,
+) -> Result<()> {
+    let store = TokenStore::default();
+    let (token, plaintext) =
+        store.create(name, user, scopes, expires_in_days.map(Duration::days))?;
+    if daily_token_quota.is_some() {
+        store.set_daily_token_quota(&token.id, daily_token_quota)?;
+    }
+
+    println!("Created token '{}' for user '{}'", token.name, token.user);
+    if let Some(expires_at) = token.expires_at {
+        println!("Expires: {}", expires_at.to_rfc3339());
+    }
+    if let Some(quota) = daily_token_quota {
+        println!("Daily token quota: {}", quota);
+    }
+    println!();
+    println!("{}", plaintext);
+    println!();
+    println!(
+        "Store it now, it will not be shown again. Send it as 'Authorization: Bearer <token>'."
+    );
+    Ok(())
+}
+
+pub fn list() -> Result<()> {
+    let store = TokenStore::default();
+    let tokens = store.list()?;
+    if tokens.is_empty() {
+        println!("No tokens in {}", store.path().display());
+        return Ok(());
+    }
+
+    for token in tokens {
+        let scopes: Vec<_> = token.scopes.iter().map(Scope::as_str).collect();
+        let expiry = match token.expires_at {
+            Some(_) if token.is_expired() => "expired".to_string(),
+            Some(expires_at) => format!("expires {}", expires_at.format("%Y-%m-%d")),
+            None => "no expiry".to_string(),
+        };
+        let quota = token
+            .daily_token_quota
+            .map(|quota| format!("  quota={}/day", quota))
+            .unwrap_or_default();
+        println!(
+            "{}  {}  user={}  scopes={}  created {}  {}{}",
+            token.id,
+            token.name,
+            token.user,
+            scopes.join(","),
+            token.created_at.format("%Y-%m-%d"),
+            expiry,
+            quota
+        );
+    }
+    Ok(())
+}
+
+pub fn revoke(name: &str) -> Result<()> {
+    if TokenStore::default().revoke(name)? {
+        println!("Revoked token '{}'", name);
+        Ok(())
+    } else {
+        anyhow::bail!("No token named '{}'", name)
+    }
+}
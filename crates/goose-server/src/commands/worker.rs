@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use goose::conversation::message::Message;
+use goose::scheduler_workers::WorkItem;
+use goose::session::session_manager::SessionType;
+use goose::session::SessionManager;
+use reqwest::StatusCode;
+use serde_json::json;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct WorkerOptions {
+    pub coordinator: String,
+    pub token: String,
+    pub worker_id: String,
+    pub concurrency: usize,
+    pub lease_seconds: u64,
+}
+
+#[derive(Clone)]
+struct Coordinator {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+    worker_id: String,
+    lease_seconds: u64,
+}
+
+impl Coordinator {
+    async fn post(&self, path: &str, body: serde_json::Value) -> Result<reqwest::Response> {
+        Ok(self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .header("X-Secret-Key", &self.token)
+            .json(&body)
+            .send()
+            .await?)
+    }
+
+    async fn lease(&self) -> Result<Option<WorkItem>> {
+        let response = self
+            .post(
+                "/workers/lease",
+                json!({"worker_id": self.worker_id, "lease_seconds": self.lease_seconds}),
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(None),
+            status if status.is_success() => Ok(Some(response.json().await?)),
+            status => bail!("Coordinator refused lease request: {}", status),
+        }
+    }
+
+    /// Returns false when the coordinator no longer wants the run.
+    async fn heartbeat(&self, run_id: &str, messages: Vec<Message>) -> Result<bool> {
+        let response = self
+            .post(
+                &format!("/workers/runs/{}/heartbeat", run_id),
+                json!({
+                    "worker_id": self.worker_id,
+                    "lease_seconds": self.lease_seconds,
+                    "messages": messages,
+                }),
+            )
+            .await?;
+        match response.status() {
+            StatusCode::CONFLICT => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => bail!("Heartbeat for {} failed: {}", run_id, status),
+        }
+    }
+
+    async fn complete(
+        &self,
+        run_id: &str,
+        result: Result<Option<String>>,
+        messages: Vec<Message>,
+    ) -> Result<()> {
+        let (output, error) = match result {
+            Ok(output) => (output, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let response = self
+            .post(
+                &format!("/workers/runs/{}/complete", run_id),
+                json!({
+                    "worker_id": self.worker_id,
+                    "output": output,
+                    "error": error,
+                    "messages": messages,
+                }),
+            )
+            .await?;
+        if !response.status().is_success() {
+            bail!("Reporting {} failed: {}", run_id, response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Default worker id: the host name plus the process id, so several workers on one machine
+/// can be told apart.
+pub fn default_worker_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "worker".to_string());
+    format!("{}-{}", host, std::process::id())
+}
+
+pub async fn run(options: WorkerOptions) -> Result<()> {
+    crate::logging::setup_logging(Some("goosed-worker"))?;
+
+    let coordinator = Coordinator {
+        client: reqwest::Client::new(),
+        base_url: options.coordinator.trim_end_matches('/').to_string(),
+        token: options.token,
+        worker_id: options.worker_id,
+        lease_seconds: options.lease_seconds.max(3),
+    };
+    let slots = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let shutdown = CancellationToken::new();
+
+    info!(
+        "worker {} pulling runs from {}",
+        coordinator.worker_id, coordinator.base_url
+    );
+
+    loop {
+        let permit = tokio::select! {
+            permit = slots.clone().acquire_owned() => permit?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+
+        let item = tokio::select! {
+            item = coordinator.lease() => item,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+
+        match item {
+            Ok(Some(item)) => {
+                let coordinator = coordinator.clone();
+                let cancel_token = shutdown.child_token();
+                tokio::spawn(async move {
+                    execute(coordinator, item, cancel_token).await;
+                    drop(permit);
+                });
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("{}", e),
+        }
+
+        drop(permit);
+        tokio::select! {
+            _ = tokio::time::sleep(IDLE_POLL_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    // Runs stopped here go back to the coordinator's queue once their leases expire
+    info!("worker {} shutting down", coordinator.worker_id);
+    shutdown.cancel();
+    let _ = slots.acquire_many(options.concurrency.max(1) as u32).await;
+    Ok(())
+}
+
+async fn execute(coordinator: Coordinator, item: WorkItem, cancel_token: CancellationToken) {
+    info!(
+        "running {} of job '{}' (attempt {})",
+        item.run_id, item.job_id, item.attempt
+    );
+
+    let session = match SessionManager::create_session(
+        std::env::current_dir().unwrap_or_default(),
+        format!("Scheduled job: {}", item.job_id),
+        SessionType::Scheduled,
+    )
+    .await
+    {
+        Ok(session) => session,
+        Err(e) => {
+            if let Err(e) = coordinator.complete(&item.run_id, Err(e), Vec::new()).await {
+                error!("{}", e);
+            }
+            return;
+        }
+    };
+
+    let pending = Arc::new(Mutex::new(Vec::<Message>::new()));
+    let heartbeats = tokio::spawn(send_heartbeats(
+        coordinator.clone(),
+        item.run_id.clone(),
+        pending.clone(),
+        cancel_token.clone(),
+    ));
+
+    let result = {
+        let pending = pending.clone();
+        goose::scheduler::run_recipe(
+            &item.recipe,
+            &session.id,
+            Some(item.job_id.clone()),
+            cancel_token.clone(),
+            move |message| pending.lock().unwrap().push(message.clone()),
+        )
+        .await
+    };
+    heartbeats.abort();
+
+    if cancel_token.is_cancelled() {
+        info!("stopped {}", item.run_id);
+        return;
+    }
+
+    let messages = std::mem::take(&mut *pending.lock().unwrap());
+    match coordinator.complete(&item.run_id, result, messages).await {
+        Ok(()) => info!("finished {}", item.run_id),
+        Err(e) => error!("{}", e),
+    }
+}
+
+/// Keeps the lease alive and forwards the run's messages, cancelling the run when the
+/// coordinator has given it up.
+async fn send_heartbeats(
+    coordinator: Coordinator,
+    run_id: String,
+    pending: Arc<Mutex<Vec<Message>>>,
+    cancel_token: CancellationToken,
+) {
+    let interval = Duration::from_secs(coordinator.lease_seconds / 3);
+    loop {
+        tokio::time::sleep(interval).await;
+        let messages = std::mem::take(&mut *pending.lock().unwrap());
+        match coordinator.heartbeat(&run_id, messages.clone()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!("coordinator gave up {}, stopping it", run_id);
+                cancel_token.cancel();
+                return;
+            }
+            Err(e) => {
+                // Resend the messages with the next heartbeat
+                warn!("{}", e);
+                let mut pending = pending.lock().unwrap();
+                let newer = std::mem::replace(&mut *pending, messages);
+                pending.extend(newer);
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::error::{to_env_var, ConfigError};
 use config::{Config, Environment};
 use serde::Deserialize;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 #[derive(Debug, Default, Deserialize)]
 pub struct Settings {
@@ -9,6 +10,30 @@ pub struct Settings {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// JWKS file used to validate JWT bearer tokens, enables OIDC authentication when set
+    #[serde(default)]
+    pub jwks_file: Option<PathBuf>,
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+    #[serde(default)]
+    pub jwt_audience: Option<String>,
+    /// Claim holding the user name, `sub` by default
+    #[serde(default)]
+    pub jwt_user_claim: Option<String>,
+    /// Requests per minute allowed per client
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    /// Agent runs executing at once across all clients
+    #[serde(default)]
+    pub max_concurrent_runs: Option<usize>,
+    #[serde(default)]
+    pub max_concurrent_runs_per_client: Option<usize>,
+    /// Runs that wait for a free slot instead of being rejected
+    #[serde(default)]
+    pub max_queued_runs: Option<usize>,
+    /// Tokens each user may spend per day
+    #[serde(default)]
+    pub daily_token_quota: Option<u64>,
 }
 
 impl Settings {
@@ -83,6 +108,7 @@ mod tests {
         let server_settings = Settings {
             host: "127.0.0.1".to_string(),
             port: 3000,
+            ..Default::default()
         };
         let addr = server_settings.socket_addr();
         assert_eq!(addr.to_string(), "127.0.0.1:3000");
@@ -0,0 +1,274 @@
+use crate::api_tokens::Scope;
+use anyhow::{Context, Result};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::str::FromStr;
+
+const DEFAULT_USER_CLAIM: &str = "sub";
+
+/// Identity extracted from a validated JWT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtIdentity {
+    pub user: String,
+    pub scopes: Vec<Scope>,
+}
+
+/// Validates OIDC access tokens against a JWKS file on disk, so goosed can sit behind an
+/// identity provider without making network calls itself.
+pub struct JwtValidator {
+    keys: JwkSet,
+    issuer: Option<String>,
+    audience: Option<String>,
+    user_claim: String,
+}
+
+impl JwtValidator {
+    pub fn from_file(
+        jwks_file: &Path,
+        issuer: Option<String>,
+        audience: Option<String>,
+        user_claim: Option<String>,
+    ) -> Result<Self> {
+        let contents = std::fs::read_to_string(jwks_file)
+            .with_context(|| format!("Failed to read JWKS file {}", jwks_file.display()))?;
+        let keys: JwkSet = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse JWKS file {}", jwks_file.display()))?;
+        Ok(Self {
+            keys,
+            issuer,
+            audience,
+            user_claim: user_claim.unwrap_or_else(|| DEFAULT_USER_CLAIM.to_string()),
+        })
+    }
+
+    pub fn validate(&self, token: &str) -> Result<JwtIdentity> {
+        let header = decode_header(token)?;
+        let jwk = match &header.kid {
+            Some(kid) => self.keys.find(kid),
+            None if self.keys.keys.len() == 1 => self.keys.keys.first(),
+            None => None,
+        }
+        .context("No matching key in JWKS")?;
+        let key = DecodingKey::from_jwk(jwk)?;
+
+        let algorithms = key_algorithms(jwk)?;
+        if !algorithms.contains(&header.alg) {
+            anyhow::bail!("Token algorithm {:?} does not match the key", header.alg);
+        }
+        let mut validation = Validation::new(header.alg);
+        validation.algorithms = algorithms;
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let claims = decode::<Map<String, Value>>(token, &key, &validation)?.claims;
+        let user = claims
+            .get(&self.user_claim)
+            .and_then(Value::as_str)
+            .with_context(|| format!("Token has no '{}' claim", self.user_claim))?
+            .to_string();
+
+        Ok(JwtIdentity {
+            user,
+            scopes: scopes_from_claims(&claims),
+        })
+    }
+}
+
+// The algorithms a key verifies, its `alg` if the JWKS sets one and otherwise every algorithm
+// of its key type, so a token header can't choose one the key wasn't meant for
+fn key_algorithms(jwk: &Jwk) -> Result<Vec<Algorithm>> {
+    use Algorithm::*;
+    let family = match &jwk.algorithm {
+        AlgorithmParameters::OctetKey(_) => vec![HS256, HS384, HS512],
+        AlgorithmParameters::RSA(_) => vec![RS256, RS384, RS512, PS256, PS384, PS512],
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => vec![ES256],
+            EllipticCurve::P384 => vec![ES384],
+            _ => vec![],
+        },
+        AlgorithmParameters::OctetKeyPair(_) => vec![EdDSA],
+    };
+
+    let Some(key_algorithm) = jwk.common.key_algorithm else {
+        return Ok(family);
+    };
+    let algorithm = match key_algorithm {
+        KeyAlgorithm::HS256 => HS256,
+        KeyAlgorithm::HS384 => HS384,
+        KeyAlgorithm::HS512 => HS512,
+        KeyAlgorithm::ES256 => ES256,
+        KeyAlgorithm::ES384 => ES384,
+        KeyAlgorithm::RS256 => RS256,
+        KeyAlgorithm::RS384 => RS384,
+        KeyAlgorithm::RS512 => RS512,
+        KeyAlgorithm::PS256 => PS256,
+        KeyAlgorithm::PS384 => PS384,
+        KeyAlgorithm::PS512 => PS512,
+        KeyAlgorithm::EdDSA => EdDSA,
+        other => anyhow::bail!("JWKS key algorithm {:?} can't verify tokens", other),
+    };
+    if !family.contains(&algorithm) {
+        anyhow::bail!(
+            "JWKS key algorithm {:?} does not match its key type",
+            algorithm
+        );
+    }
+    Ok(vec![algorithm])
+}
+
+// OIDC providers put scopes either in a space separated `scope` string or a `scp` array
+fn scopes_from_claims(claims: &Map<String, Value>) -> Vec<Scope> {
+    let mut names: Vec<&str> = Vec::new();
+    for claim in ["scope", "scp"] {
+        match claims.get(claim) {
+            Some(Value::String(value)) => names.extend(value.split_whitespace()),
+            Some(Value::Array(values)) => names.extend(values.iter().filter_map(Value::as_str)),
+            _ => {}
+        }
+    }
+
+    let mut scopes = Vec::new();
+    for scope in names
+        .into_iter()
+        .filter_map(|name| Scope::from_str(name).ok())
+    {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    scopes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const SECRET: &str = "test-signing-secret";
+
+    fn validator(dir: &TempDir, audience: Option<&str>) -> JwtValidator {
+        let path = dir.path().join("jwks.json");
+        let jwks = json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "k1",
+                "alg": "HS256",
+                "k": base64::Engine::encode(
+                    &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+                    SECRET
+                ),
+            }]
+        });
+        std::fs::write(&path, jwks.to_string()).unwrap();
+        JwtValidator::from_file(
+            &path,
+            Some("https://idp.example.com".to_string()),
+            audience.map(str::to_string),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn sign(claims: Value, kid: &str) -> String {
+        sign_with(jsonwebtoken::Algorithm::HS256, claims, kid)
+    }
+
+    fn sign_with(alg: jsonwebtoken::Algorithm, claims: Value, kid: &str) -> String {
+        let mut header = Header::new(alg);
+        header.kid = Some(kid.to_string());
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn claims(extra: Value) -> Value {
+        let mut claims = json!({
+            "sub": "alice",
+            "iss": "https://idp.example.com",
+            "exp": chrono::Utc::now().timestamp() + 600,
+        });
+        claims
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        claims
+    }
+
+    #[test]
+    fn test_valid_token_yields_user_and_scopes() {
+        let dir = TempDir::new().unwrap();
+        let validator = validator(&dir, None);
+
+        let token = sign(
+            claims(json!({"scope": "openid sessions:read agent:run", "aud": "other"})),
+            "k1",
+        );
+        let identity = validator.validate(&token).unwrap();
+        assert_eq!(identity.user, "alice");
+        assert_eq!(identity.scopes, vec![Scope::ReadSessions, Scope::RunAgent]);
+
+        let token = sign(claims(json!({"scp": ["config:manage"]})), "k1");
+        assert_eq!(
+            validator.validate(&token).unwrap().scopes,
+            vec![Scope::ManageConfig]
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_issuer_audience_key_or_expiry() {
+        let dir = TempDir::new().unwrap();
+        let validator = validator(&dir, Some("goosed"));
+
+        assert!(validator
+            .validate(&sign(claims(json!({"aud": "goosed"})), "k1"))
+            .is_ok());
+        assert!(validator
+            .validate(&sign(claims(json!({"aud": "other"})), "k1"))
+            .is_err());
+        assert!(validator
+            .validate(&sign(
+                claims(json!({"aud": "goosed", "iss": "https://evil.example.com"})),
+                "k1"
+            ))
+            .is_err());
+        assert!(validator
+            .validate(&sign(claims(json!({"aud": "goosed"})), "unknown"))
+            .is_err());
+        assert!(validator
+            .validate(&sign(
+                claims(json!({"aud": "goosed", "exp": chrono::Utc::now().timestamp() - 600})),
+                "k1"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn test_rejects_algorithms_other_than_the_keys() {
+        let dir = TempDir::new().unwrap();
+        let validator = validator(&dir, None);
+
+        // Same secret, but the key is only for HS256
+        let token = sign_with(jsonwebtoken::Algorithm::HS384, claims(json!({})), "k1");
+        assert!(validator.validate(&token).is_err());
+
+        let mut jwk: Jwk = serde_json::from_value(json!({"kty": "oct", "k": "c2VjcmV0"})).unwrap();
+        assert_eq!(
+            key_algorithms(&jwk).unwrap(),
+            vec![Algorithm::HS256, Algorithm::HS384, Algorithm::HS512]
+        );
+        jwk.common.key_algorithm = Some(KeyAlgorithm::RS256);
+        assert!(key_algorithms(&jwk).is_err());
+    }
+}
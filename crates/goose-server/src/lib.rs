@@ -1,10 +1,16 @@
+pub mod api_tokens;
 pub mod auth;
 pub mod configuration;
 pub mod error;
+pub mod jwt;
+pub mod limits;
 pub mod openapi;
 pub mod routes;
+pub mod session_events;
+pub mod shares;
 pub mod state;
 pub mod tunnel;
+pub mod webhooks;
 
 // Re-export commonly used items
 pub use openapi::*;
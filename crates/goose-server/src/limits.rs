@@ -0,0 +1,479 @@
+use crate::auth::Principal;
+use crate::configuration::Settings;
+use crate::routes::errors::ErrorResponse;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{NaiveDate, Utc};
+use goose::session::SessionManager;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Idle buckets are dropped once this many clients have been seen.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Who a request counts against: the API token, the JWT user, or the client address for
+/// everything else, including the shared secret key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientKey(pub String);
+
+impl ClientKey {
+    pub fn new(principal: Option<&Principal>, addr: Option<SocketAddr>) -> Self {
+        if let Some(principal) = principal {
+            if let Some(token_id) = &principal.token_id {
+                return Self(format!("token:{}", token_id));
+            }
+            if let Some(user) = &principal.user {
+                return Self(format!("user:{}", user));
+            }
+        }
+        match addr {
+            Some(addr) => Self(format!("ip:{}", addr.ip())),
+            None => Self("local".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitError {
+    #[error("Rate limit exceeded, retry in {} seconds", .retry_after.as_secs().max(1))]
+    RateLimited { retry_after: Duration },
+    #[error("Too many agent runs in progress for this client (limit {limit})")]
+    TooManyRuns { limit: usize },
+    #[error("The agent run queue is full")]
+    QueueFull,
+    #[error("Daily token quota of {quota} exhausted, it resets at midnight UTC")]
+    QuotaExceeded { quota: u64 },
+}
+
+impl From<LimitError> for ErrorResponse {
+    fn from(err: LimitError) -> Self {
+        ErrorResponse {
+            message: err.to_string(),
+            status: StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+/// Limits on what a single client can ask of goosed. Everything is off unless configured.
+#[derive(Debug, Clone, Default)]
+pub struct LimitSettings {
+    /// Requests per minute per client, with bursts up to the same number
+    pub requests_per_minute: Option<u32>,
+    /// Agent runs executing at once across all clients
+    pub max_concurrent_runs: Option<usize>,
+    /// Agent runs, running or queued, one client may have at once
+    pub max_concurrent_runs_per_client: Option<usize>,
+    /// Runs that may wait for a free slot once `max_concurrent_runs` is reached
+    pub max_queued_runs: usize,
+    /// Tokens a user may spend per day unless their API token says otherwise
+    pub daily_token_quota: Option<u64>,
+}
+
+impl From<&Settings> for LimitSettings {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            requests_per_minute: settings.rate_limit_per_minute.filter(|limit| *limit > 0),
+            max_concurrent_runs: settings.max_concurrent_runs.filter(|limit| *limit > 0),
+            max_concurrent_runs_per_client: settings
+                .max_concurrent_runs_per_client
+                .filter(|limit| *limit > 0),
+            max_queued_runs: settings.max_queued_runs.unwrap_or(0),
+            daily_token_quota: settings.daily_token_quota,
+        }
+    }
+}
+
+pub struct Limits {
+    rate: Option<RateLimiter>,
+    runs: Arc<RunQueue>,
+    daily_token_quota: Option<u64>,
+    usage: tokio::sync::Mutex<HashMap<String, DailyUsage>>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(LimitSettings::default())
+    }
+}
+
+impl Limits {
+    pub fn new(settings: LimitSettings) -> Self {
+        Self {
+            rate: settings.requests_per_minute.map(RateLimiter::new),
+            runs: Arc::new(RunQueue {
+                max_running: settings.max_concurrent_runs,
+                max_per_client: settings.max_concurrent_runs_per_client,
+                max_queued: settings.max_queued_runs,
+                state: Mutex::new(QueueState::default()),
+                changed: Notify::new(),
+            }),
+            daily_token_quota: settings.daily_token_quota,
+            usage: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn check_rate(&self, client: &ClientKey) -> Result<(), LimitError> {
+        match &self.rate {
+            Some(rate) => rate
+                .check(client, Instant::now())
+                .map_err(|retry_after| LimitError::RateLimited { retry_after }),
+            None => Ok(()),
+        }
+    }
+
+    /// Claims a place for an agent run. The run may start once [`RunSlot::wait`] returns and
+    /// holds its place until the slot is dropped.
+    pub fn reserve_run(&self, client: &ClientKey) -> Result<RunSlot, LimitError> {
+        self.runs.reserve(client)
+    }
+
+    /// Checks the caller's daily token quota. Returns the user to charge the run's tokens to, or
+    /// `None` when no quota applies.
+    pub async fn check_quota(
+        &self,
+        principal: Option<&Principal>,
+    ) -> Result<Option<String>, LimitError> {
+        let Some(principal) = principal else {
+            return Ok(None);
+        };
+        let Some(user) = &principal.user else {
+            return Ok(None);
+        };
+        let Some(quota) = principal.daily_token_quota.or(self.daily_token_quota) else {
+            return Ok(None);
+        };
+
+        if self.used_today(user).await >= quota {
+            return Err(LimitError::QuotaExceeded { quota });
+        }
+        Ok(Some(user.clone()))
+    }
+
+    /// Adds tokens a run of the user spent to today's usage.
+    pub async fn charge(&self, user: &str, tokens: i64) {
+        let today = Utc::now().date_naive();
+        let mut usage = self.usage.lock().await;
+        let entry = usage.entry(user.to_string()).or_insert(DailyUsage {
+            day: today,
+            tokens: 0,
+        });
+        if entry.day != today {
+            *entry = DailyUsage {
+                day: today,
+                tokens: 0,
+            };
+        }
+        entry.tokens += tokens.max(0) as u64;
+    }
+
+    /// Tokens the user spent today. The first lookup of a day starts from the sessions the user
+    /// created that day, so usage survives restarts; later runs are added by [`Limits::charge`].
+    async fn used_today(&self, user: &str) -> u64 {
+        let today = Utc::now().date_naive();
+        let mut usage = self.usage.lock().await;
+        if let Some(entry) = usage.get(user).filter(|entry| entry.day == today) {
+            return entry.tokens;
+        }
+
+        let tokens = match SessionManager::list_sessions().await {
+            Ok(sessions) => sessions
+                .iter()
+                .filter(|session| session.owner.as_deref() == Some(user))
+                .filter(|session| session.created_at.date_naive() == today)
+                .map(|session| session.accumulated_total_tokens.unwrap_or(0).max(0) as u64)
+                .sum(),
+            Err(e) => {
+                tracing::warn!("Failed to load token usage of {}: {}", user, e);
+                0
+            }
+        };
+        usage.insert(user.to_string(), DailyUsage { day: today, tokens });
+        tokens
+    }
+}
+
+struct DailyUsage {
+    day: NaiveDate,
+    tokens: u64,
+}
+
+/// Rejects requests over the client's rate limit with 429 and tags the rest with their
+/// [`ClientKey`]. Runs after authentication so tokens are told apart.
+pub async fn rate_limit(
+    State(limits): State<Arc<Limits>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let addr = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let client = ClientKey::new(request.extensions().get::<Principal>(), addr);
+
+    if let Err(err) = limits.check_rate(&client) {
+        let retry_after = match &err {
+            LimitError::RateLimited { retry_after } => retry_after.as_secs().max(1),
+            _ => 1,
+        };
+        let mut response = ErrorResponse::from(err).into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+/// Token bucket per client that refills continuously at the configured rate.
+struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<ClientKey, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check(&self, client: &ClientKey, now: Instant) -> Result<(), Duration> {
+        let capacity = self.per_minute as f64;
+        let per_second = capacity / 60.0;
+        let refill = |bucket: &Bucket| {
+            (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_second)
+                .min(capacity)
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+        }
+
+        let bucket = buckets.entry(client.clone()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+struct RunQueue {
+    max_running: Option<usize>,
+    max_per_client: Option<usize>,
+    max_queued: usize,
+    state: Mutex<QueueState>,
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_ticket: u64,
+    running: usize,
+    waiting: VecDeque<u64>,
+    /// Running and waiting runs per client
+    per_client: HashMap<ClientKey, usize>,
+}
+
+impl RunQueue {
+    fn reserve(self: &Arc<Self>, client: &ClientKey) -> Result<RunSlot, LimitError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(limit) = self.max_per_client {
+            if state.per_client.get(client).copied().unwrap_or(0) >= limit {
+                return Err(LimitError::TooManyRuns { limit });
+            }
+        }
+        let must_wait = self
+            .max_running
+            .is_some_and(|max| state.running >= max || !state.waiting.is_empty());
+        if must_wait && state.waiting.len() >= self.max_queued {
+            return Err(LimitError::QueueFull);
+        }
+
+        state.next_ticket += 1;
+        let ticket = state.next_ticket;
+        state.waiting.push_back(ticket);
+        *state.per_client.entry(client.clone()).or_default() += 1;
+
+        Ok(RunSlot {
+            queue: self.clone(),
+            ticket,
+            client: client.clone(),
+            admitted: false,
+        })
+    }
+}
+
+/// A run's place in the queue, and once admitted, its share of the concurrency limit. Dropping
+/// it leaves the queue or frees the slot for the next run.
+pub struct RunSlot {
+    queue: Arc<RunQueue>,
+    ticket: u64,
+    client: ClientKey,
+    admitted: bool,
+}
+
+impl RunSlot {
+    /// Waits until the run may start, calling `on_position` with the 1-based queue position
+    /// whenever it changes while waiting.
+    pub async fn wait(&mut self, mut on_position: impl FnMut(usize)) {
+        let mut reported = None;
+        while !self.admitted {
+            let notified = self.queue.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.queue.state.lock().unwrap();
+                let position = state
+                    .waiting
+                    .iter()
+                    .position(|ticket| *ticket == self.ticket)
+                    .expect("a waiting slot stays queued");
+                let free = self.queue.max_running.is_none_or(|max| state.running < max);
+                if position == 0 && free {
+                    state.waiting.pop_front();
+                    state.running += 1;
+                    self.admitted = true;
+                    drop(state);
+                    // The next run in line may fit as well
+                    self.queue.changed.notify_waiters();
+                    return;
+                }
+                if reported != Some(position + 1) {
+                    reported = Some(position + 1);
+                    on_position(position + 1);
+                }
+            }
+
+            notified.await;
+        }
+    }
+}
+
+impl Drop for RunSlot {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if self.admitted {
+            state.running -= 1;
+        } else {
+            state.waiting.retain(|ticket| *ticket != self.ticket);
+        }
+        if let Some(count) = state.per_client.get_mut(&self.client) {
+            *count -= 1;
+            if *count == 0 {
+                state.per_client.remove(&self.client);
+            }
+        }
+        drop(state);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    fn client(name: &str) -> ClientKey {
+        ClientKey(name.to_string())
+    }
+
+    #[test]
+    fn test_client_key() {
+        let addr: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        assert_eq!(ClientKey::new(None, Some(addr)), client("ip:10.0.0.1"));
+        assert_eq!(
+            ClientKey::new(Some(&Principal::full_access()), Some(addr)),
+            client("ip:10.0.0.1")
+        );
+        let principal = Principal {
+            user: Some("alice".to_string()),
+            token_id: Some("abc".to_string()),
+            ..Principal::full_access()
+        };
+        assert_eq!(ClientKey::new(Some(&principal), None), client("token:abc"));
+    }
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let limiter = RateLimiter::new(60);
+        let start = Instant::now();
+        for _ in 0..60 {
+            assert!(limiter.check(&client("a"), start).is_ok());
+        }
+        let retry_after = limiter.check(&client("a"), start).unwrap_err();
+        assert!(retry_after <= Duration::from_secs(1));
+        assert!(limiter.check(&client("b"), start).is_ok());
+        assert!(limiter
+            .check(&client("a"), start + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_runs_queue_in_order() {
+        let limits = Limits::new(LimitSettings {
+            max_concurrent_runs: Some(1),
+            max_concurrent_runs_per_client: Some(2),
+            max_queued_runs: 2,
+            ..Default::default()
+        });
+
+        let mut first = limits.reserve_run(&client("a")).unwrap();
+        first
+            .wait(|_| panic!("the first run starts right away"))
+            .await;
+        let mut second = limits.reserve_run(&client("a")).unwrap();
+        assert_eq!(
+            limits.reserve_run(&client("a")).err(),
+            Some(LimitError::TooManyRuns { limit: 2 })
+        );
+        let mut third = limits.reserve_run(&client("b")).unwrap();
+        assert_eq!(
+            limits.reserve_run(&client("c")).err(),
+            Some(LimitError::QueueFull)
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let waiting = tokio::spawn(async move {
+            third.wait(|position| tx.send(position).unwrap()).await;
+            third
+        });
+        assert_eq!(rx.recv().await, Some(2));
+
+        let mut positions = Vec::new();
+        second
+            .wait(|position| positions.push(position))
+            .now_or_never();
+        assert_eq!(positions, vec![1]);
+
+        drop(first);
+        second.wait(|_| {}).await;
+        assert_eq!(rx.recv().await, Some(1));
+        drop(second);
+        let third = waiting.await.unwrap();
+        assert!(third.admitted);
+    }
+}
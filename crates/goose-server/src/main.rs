@@ -1,12 +1,20 @@
+mod api_tokens;
+mod auth;
 mod commands;
 mod configuration;
 mod error;
+mod jwt;
+mod limits;
 mod logging;
 mod openapi;
 mod routes;
+mod session_events;
+mod shares;
 mod state;
 mod tunnel;
+mod webhooks;
 
+use api_tokens::Scope;
 use clap::{Parser, Subcommand};
 use goose::config::paths::Paths;
 use goose_mcp::{
@@ -31,6 +39,55 @@ enum Commands {
         #[arg(value_parser = clap::value_parser!(McpCommand))]
         server: McpCommand,
     },
+    /// Manage the API tokens accepted by the agent server
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+    /// Run scheduled jobs handed out by an agent server started with GOOSE_SCHEDULER_WORKERS=true
+    Worker {
+        /// Base URL of the coordinating agent server
+        #[arg(long, env = "GOOSE_WORKER_COORDINATOR")]
+        coordinator: String,
+        /// Secret key or API token with the schedules:manage scope
+        #[arg(long, env = "GOOSE_WORKER_TOKEN")]
+        token: String,
+        /// Name reported to the coordinator, defaults to the host name and process id
+        #[arg(long)]
+        worker_id: Option<String>,
+        /// Number of runs executed at the same time
+        #[arg(long, default_value_t = 1)]
+        concurrency: usize,
+        /// Seconds the coordinator waits for a heartbeat before handing a run to another worker
+        #[arg(long, default_value_t = 60)]
+        lease_seconds: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Create a token and print it; it is not shown again
+    Create {
+        /// Unique name for the token
+        #[arg(long)]
+        name: String,
+        /// User owning the sessions created with the token, defaults to the token name
+        #[arg(long)]
+        user: Option<String>,
+        /// Scope granted to the token, repeat for several (sessions:read, agent:run, config:manage, schedules:manage)
+        #[arg(long = "scope", required = true, value_parser = clap::value_parser!(Scope))]
+        scopes: Vec<Scope>,
+        /// Number of days until the token expires
+        #[arg(long)]
+        expires_in_days: Option<i64>,
+        /// Tokens the user may spend per day, overriding GOOSE_DAILY_TOKEN_QUOTA
+        #[arg(long)]
+        daily_token_quota: Option<u64>,
+    },
+    /// List tokens
+    List,
+    /// Revoke a token by name or id
+    Revoke { name: String },
 }
 
 #[tokio::main]
@@ -41,6 +98,39 @@ async fn main() -> anyhow::Result<()> {
         Commands::Agent => {
             commands::agent::run().await?;
         }
+        Commands::Token { command } => match command {
+            TokenCommand::Create {
+                name,
+                user,
+                scopes,
+                expires_in_days,
+                daily_token_quota,
+            } => commands::token::create(
+                &name,
+                user.as_deref(),
+                scopes,
+                expires_in_days,
+                daily_token_quota,
+            )?,
+            TokenCommand::List => commands::token::list()?,
+            TokenCommand::Revoke { name } => commands::token::revoke(&name)?,
+        },
+        Commands::Worker {
+            coordinator,
+            token,
+            worker_id,
+            concurrency,
+            lease_seconds,
+        } => {
+            commands::worker::run(commands::worker::WorkerOptions {
+                coordinator,
+                token,
+                worker_id: worker_id.unwrap_or_else(commands::worker::default_worker_id),
+                concurrency,
+                lease_seconds,
+            })
+            .await?;
+        }
         Commands::Mcp { server } => {
             logging::setup_logging(Some(&format!("mcp-{}", server.name())))?;
             match server {
@@ -328,6 +328,7 @@ derive_utoipa!(Icon as IconSchema);
     paths(
         super::routes::status::status,
         super::routes::status::diagnostics,
+        super::routes::metrics::metrics,
         super::routes::mcp_ui_proxy::mcp_ui_proxy,
         super::routes::config_management::backup_config,
         super::routes::config_management::detect_provider,
@@ -361,6 +362,8 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::update_router_tool_selector,
         super::routes::action_required::confirm_tool_action,
         super::routes::reply::reply,
+        super::routes::reply::session_events,
+        super::routes::reply::cancel_reply,
         super::routes::session::list_sessions,
         super::routes::session::get_session,
         super::routes::session::get_session_insights,
@@ -380,6 +383,24 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::schedule::kill_running_job,
         super::routes::schedule::inspect_running_job,
         super::routes::schedule::sessions_handler,
+        super::routes::schedule::runs_handler,
+        super::routes::schedule::preview_schedule,
+        super::routes::schedule::schedule_webhook,
+        super::routes::workers::lease_run,
+        super::routes::workers::heartbeat,
+        super::routes::workers::complete_run,
+        super::routes::workers::list_workers,
+        super::routes::shares::share_session,
+        super::routes::shares::list_shares,
+        super::routes::shares::delete_share,
+        super::routes::shares::import_share,
+        super::routes::shares::view_share,
+        super::routes::webhooks::list_webhooks,
+        super::routes::webhooks::create_webhook,
+        super::routes::webhooks::get_webhook,
+        super::routes::webhooks::update_webhook,
+        super::routes::webhooks::delete_webhook,
+        super::routes::webhooks::list_deliveries,
         super::routes::recipe::create_recipe,
         super::routes::recipe::encode_recipe,
         super::routes::recipe::decode_recipe,
@@ -395,6 +416,8 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::tunnel::start_tunnel,
         super::routes::tunnel::stop_tunnel,
         super::routes::tunnel::get_tunnel_status,
+        super::routes::openai_compat::chat_completions,
+        super::routes::openai_compat::list_models,
     ),
     components(schemas(
         super::routes::config_management::UpsertConfigQuery,
@@ -480,9 +503,32 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::schedule::KillJobResponse,
         super::routes::schedule::InspectJobResponse,
         goose::scheduler::ScheduledJob,
+        goose::scheduler_trigger::ScheduleTrigger,
+        goose::scheduler_trigger::ChainCondition,
+        goose::scheduler_runs::JobRun,
+        goose::scheduler_runs::JobRunStatus,
+        goose::scheduler_workers::WorkItem,
+        goose::scheduler_workers::WorkerInfo,
+        super::routes::workers::LeaseRequest,
+        super::routes::workers::HeartbeatRequest,
+        super::routes::workers::CompleteRunRequest,
+        super::routes::shares::ShareSessionRequest,
+        super::shares::ShareInfo,
+        super::webhooks::WebhookEvent,
+        super::webhooks::WebhookInfo,
+        super::webhooks::WebhookChanges,
+        super::webhooks::WebhookDelivery,
+        super::webhooks::DeliveryStatus,
+        goose::scheduler_runs::RetryPolicy,
+        goose::scheduler_runs::ConcurrencyPolicy,
+        goose::scheduler_runs::CatchUpPolicy,
+        goose::scheduler_runs::FailureNotification,
         super::routes::schedule::RunNowResponse,
         super::routes::schedule::ListSchedulesResponse,
         super::routes::schedule::SessionsQuery,
+        super::routes::schedule::RunsQuery,
+        super::routes::schedule::PreviewScheduleRequest,
+        super::routes::schedule::PreviewScheduleResponse,
         super::routes::schedule::SessionDisplayInfo,
         super::routes::recipe::CreateRecipeRequest,
         super::routes::recipe::AuthorRequest,
@@ -523,6 +569,14 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::setup::SetupResponse,
         super::tunnel::TunnelInfo,
         super::tunnel::TunnelState,
+        super::routes::openai_compat::ChatCompletionRequest,
+        super::routes::openai_compat::ChatCompletionMessage,
+        super::routes::openai_compat::ChatCompletionResponse,
+        super::routes::openai_compat::ChatCompletionResponseMessage,
+        super::routes::openai_compat::ChatCompletionChoice,
+        super::routes::openai_compat::ChatCompletionUsage,
+        super::routes::openai_compat::ModelList,
+        super::routes::openai_compat::ModelObject,
     ))
 )]
 pub struct ApiDoc;
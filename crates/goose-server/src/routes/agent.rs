@@ -1,9 +1,11 @@
+use crate::auth::Principal;
 use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::{
     apply_recipe_to_agent, build_recipe_with_parameter_values, load_recipe_by_id, validate_recipe,
 };
 use crate::state::AppState;
 use axum::response::IntoResponse;
+use axum::Extension;
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -12,7 +14,7 @@ use axum::{
 };
 use goose::config::PermissionManager;
 
-use goose::agents::ExtensionConfig;
+use goose::agents::{Agent, ExtensionConfig};
 use goose::config::{Config, GooseMode};
 use goose::model::ModelConfig;
 use goose::prompt_template::render_global_file;
@@ -103,6 +105,7 @@ pub struct RemoveExtensionRequest {
 )]
 async fn start_agent(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<StartAgentRequest>,
 ) -> Result<Json<Session>, ErrorResponse> {
     goose::posthog::set_session_context("desktop", false);
@@ -157,15 +160,17 @@ async fn start_agent(
                 }
             })?;
 
-    if let Some(recipe) = original_recipe {
+    let owner = principal.and_then(|Extension(principal)| principal.user);
+    if original_recipe.is_some() || owner.is_some() {
         SessionManager::update_session(&session.id)
-            .recipe(Some(recipe))
+            .recipe(original_recipe)
+            .owner(owner)
             .apply()
             .await
             .map_err(|err| {
-                error!("Failed to update session with recipe: {}", err);
+                error!("Failed to update session: {}", err);
                 ErrorResponse {
-                    message: format!("Failed to update session with recipe: {}", err),
+                    message: format!("Failed to update session: {}", err),
                     status: StatusCode::INTERNAL_SERVER_ERROR,
                 }
             })?;
@@ -181,6 +186,7 @@ async fn start_agent(
             })?;
     }
 
+    state.webhooks.session_created(&session);
     Ok(Json(session))
 }
 
@@ -211,7 +217,13 @@ async fn resume_agent(
             }
         })?;
 
-    if payload.load_model_and_extensions {
+    // Sessions with a snapshot get their agent restored on first access instead
+    let has_snapshot = matches!(
+        SessionManager::get_agent_snapshot(&payload.session_id).await,
+        Ok(Some(_))
+    );
+
+    if payload.load_model_and_extensions && !has_snapshot {
         let agent = state
             .get_agent_for_route(payload.session_id.clone())
             .await
@@ -288,6 +300,7 @@ async fn resume_agent(
 
         let (provider_result, _) = tokio::join!(provider_result, extensions_result);
         provider_result?;
+        save_snapshot(&agent, &payload.session_id).await;
     }
 
     Ok(Json(session))
@@ -348,6 +361,7 @@ async fn update_from_session(
         }
     }
     agent.extend_system_prompt(update_prompt).await;
+    save_snapshot(&agent, &payload.session_id).await;
 
     Ok(StatusCode::OK)
 }
@@ -460,6 +474,7 @@ async fn update_agent_provider(
                 format!("Failed to update provider: {}", e),
             )
         })?;
+    save_snapshot(&agent, &payload.session_id).await;
 
     Ok(())
 }
@@ -508,11 +523,12 @@ async fn agent_add_extension(
     State(state): State<Arc<AppState>>,
     Json(request): Json<AddExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
-    let agent = state.get_agent(request.session_id).await?;
+    let agent = state.get_agent(request.session_id.clone()).await?;
     agent
         .add_extension(request.config)
         .await
         .map_err(|e| ErrorResponse::internal(format!("Failed to add extension: {}", e)))?;
+    save_snapshot(&agent, &request.session_id).await;
     Ok(StatusCode::OK)
 }
 
@@ -531,8 +547,9 @@ async fn agent_remove_extension(
     State(state): State<Arc<AppState>>,
     Json(request): Json<RemoveExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
-    let agent = state.get_agent(request.session_id).await?;
+    let agent = state.get_agent(request.session_id.clone()).await?;
     agent.remove_extension(&request.name).await?;
+    save_snapshot(&agent, &request.session_id).await;
     Ok(StatusCode::OK)
 }
 
@@ -564,6 +581,12 @@ async fn stop_agent(
     Ok(StatusCode::OK)
 }
 
+async fn save_snapshot(agent: &Agent, session_id: &str) {
+    if let Err(e) = agent.save_snapshot(session_id).await {
+        warn!("Failed to save agent snapshot for {}: {}", session_id, e);
+    }
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/agent/start", post(start_agent))
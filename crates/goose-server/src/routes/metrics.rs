@@ -0,0 +1,24 @@
+use crate::state::AppState;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::{routing::get, Router};
+use std::sync::Arc;
+
+#[utoipa::path(get, path = "/metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain", body = String),
+        (status = 401, description = "Unauthorized - invalid secret key or token"),
+    )
+)]
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, goose::metrics::content_type())],
+        goose::metrics::gather(),
+    )
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(state)
+}
@@ -4,15 +4,20 @@ pub mod audio;
 pub mod config_management;
 pub mod errors;
 pub mod mcp_ui_proxy;
+pub mod metrics;
+pub mod openai_compat;
 pub mod recipe;
 pub mod recipe_utils;
 pub mod reply;
 pub mod schedule;
 pub mod session;
 pub mod setup;
+pub mod shares;
 pub mod status;
 pub mod tunnel;
 pub mod utils;
+pub mod webhooks;
+pub mod workers;
 
 use std::sync::Arc;
 
@@ -29,8 +34,13 @@ pub fn configure(state: Arc<crate::state::AppState>, secret_key: String) -> Rout
         .merge(config_management::routes(state.clone()))
         .merge(recipe::routes(state.clone()))
         .merge(session::routes(state.clone()))
+        .merge(shares::routes(state.clone()))
         .merge(schedule::routes(state.clone()))
         .merge(setup::routes(state.clone()))
         .merge(tunnel::routes(state.clone()))
+        .merge(openai_compat::routes(state.clone()))
+        .merge(metrics::routes(state.clone()))
+        .merge(workers::routes(state.clone()))
+        .merge(webhooks::routes(state.clone()))
         .merge(mcp_ui_proxy::routes(secret_key))
 }
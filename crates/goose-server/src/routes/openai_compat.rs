@@ -0,0 +1,763 @@
+use crate::auth::Principal;
+use crate::limits::ClientKey;
+use crate::routes::errors::ErrorResponse;
+use crate::routes::recipe_utils::{
+    apply_recipe_to_agent, build_recipe_with_parameter_values, get_all_recipes_manifests,
+};
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use futures::StreamExt;
+use goose::agents::{Agent, AgentEvent, SessionConfig};
+use goose::config::Config;
+use goose::conversation::message::{ActionRequiredData, Message, MessageContent};
+use goose::model::ModelConfig;
+use goose::permission::permission_confirmation::PrincipalType;
+use goose::permission::{Permission, PermissionConfirmation};
+use goose::providers::create;
+use goose::recipe::Recipe;
+use goose::session::session_manager::SessionType;
+use goose::session::SessionManager;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+/// Model id of the plain goose agent with the configured provider and extensions
+const DEFAULT_MODEL: &str = "goose";
+/// Prefix of model ids that run a recipe from the local recipe library
+const RECIPE_MODEL_PREFIX: &str = "recipe:";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChatCompletionMessage {
+    /// One of system, developer, user or assistant
+    role: String,
+    /// Either a string or a list of content parts
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChatCompletionRequest {
+    /// `goose` or `recipe:<name>`, see `/v1/models`
+    model: String,
+    messages: Vec<ChatCompletionMessage>,
+    #[serde(default)]
+    stream: bool,
+    temperature: Option<f32>,
+    /// Used as recipe parameter values when the model is a recipe
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatCompletionResponseMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatCompletionChoice {
+    index: u32,
+    message: ChatCompletionResponseMessage,
+    finish_reason: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatCompletionUsage {
+    prompt_tokens: i32,
+    completion_tokens: i32,
+    total_tokens: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatCompletionResponse {
+    id: String,
+    object: String,
+    created: i64,
+    model: String,
+    choices: Vec<ChatCompletionChoice>,
+    usage: ChatCompletionUsage,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelObject {
+    id: String,
+    object: String,
+    created: i64,
+    owned_by: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelList {
+    object: String,
+    data: Vec<ModelObject>,
+}
+
+/// What a request asked goose to do, resolved from the OpenAI `model` field.
+enum Profile {
+    Default,
+    Recipe(Box<Recipe>),
+}
+
+fn recipe_models() -> Vec<(String, PathBuf)> {
+    let mut models: Vec<(String, PathBuf)> = Vec::new();
+    for manifest in get_all_recipes_manifests().unwrap_or_default() {
+        let Some(stem) = manifest.file_path.file_stem() else {
+            continue;
+        };
+        let id = format!("{}{}", RECIPE_MODEL_PREFIX, stem.to_string_lossy());
+        // Recipes are listed newest first, keep the newest when names collide
+        if !models.iter().any(|(existing, _)| existing == &id) {
+            models.push((id, manifest.file_path));
+        }
+    }
+    models
+}
+
+async fn resolve_profile(
+    model: &str,
+    parameters: HashMap<String, String>,
+) -> Result<Profile, ErrorResponse> {
+    if model == DEFAULT_MODEL {
+        return Ok(Profile::Default);
+    }
+
+    let path = recipe_models()
+        .into_iter()
+        .find(|(id, _)| id == model)
+        .map(|(_, path)| path)
+        .ok_or_else(|| ErrorResponse {
+            message: format!("Unknown model '{}', see /v1/models", model),
+            status: StatusCode::NOT_FOUND,
+        })?;
+    let recipe = Recipe::from_file_path(&path).map_err(|e| ErrorResponse {
+        message: format!("Failed to load recipe: {}", e),
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    let recipe = build_recipe_with_parameter_values(&recipe, parameters)
+        .await
+        .map_err(|e| ErrorResponse {
+            message: e.to_string(),
+            status: StatusCode::BAD_REQUEST,
+        })?
+        .ok_or_else(|| ErrorResponse {
+            message: "Recipe parameters are missing, pass them in the request metadata".into(),
+            status: StatusCode::BAD_REQUEST,
+        })?;
+    Ok(Profile::Recipe(Box::new(recipe)))
+}
+
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn to_goose_message(message: &ChatCompletionMessage) -> Result<Message, ErrorResponse> {
+    let mut converted = match message.role.as_str() {
+        "user" => Message::user(),
+        "assistant" => Message::assistant(),
+        role => {
+            return Err(ErrorResponse {
+                message: format!("Unsupported message role '{}'", role),
+                status: StatusCode::BAD_REQUEST,
+            })
+        }
+    };
+
+    let text = content_text(&message.content);
+    if !text.is_empty() {
+        converted = converted.with_text(text);
+    }
+    if let Value::Array(parts) = &message.content {
+        let image_urls = parts
+            .iter()
+            .filter(|part| part.get("type").and_then(Value::as_str) == Some("image_url"))
+            .filter_map(|part| part.pointer("/image_url/url").and_then(Value::as_str));
+        for url in image_urls {
+            // Only inline images are supported, goose does not fetch remote content here
+            let Some((mime_type, data)) = url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            else {
+                return Err(ErrorResponse {
+                    message: "Only base64 data URLs are supported for images".into(),
+                    status: StatusCode::BAD_REQUEST,
+                });
+            };
+            converted = converted.with_image(data, mime_type);
+        }
+    }
+    Ok(converted)
+}
+
+async fn configure_agent(
+    agent: &Arc<Agent>,
+    session_id: &str,
+    profile: &Profile,
+    temperature: Option<f32>,
+) -> Result<(), ErrorResponse> {
+    let config = Config::global();
+    let settings = match profile {
+        Profile::Recipe(recipe) => recipe.settings.clone(),
+        Profile::Default => None,
+    };
+
+    let provider_name = settings
+        .as_ref()
+        .and_then(|settings| settings.goose_provider.clone())
+        .or_else(|| config.get_goose_provider().ok())
+        .ok_or_else(|| ErrorResponse {
+            message: "Could not configure agent: missing provider".into(),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    let model_name = settings
+        .as_ref()
+        .and_then(|settings| settings.goose_model.clone())
+        .or_else(|| config.get_goose_model().ok())
+        .ok_or_else(|| ErrorResponse {
+            message: "Could not configure agent: missing model".into(),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    let model_config = ModelConfig::new(&model_name)
+        .map_err(|e| ErrorResponse {
+            message: format!("Could not configure agent: invalid model {}", e),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        })?
+        .with_temperature(temperature.or(settings.and_then(|settings| settings.temperature)));
+    let provider = create(&provider_name, model_config)
+        .await
+        .map_err(|e| ErrorResponse {
+            message: format!("Could not create provider: {}", e),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    agent
+        .update_provider(provider, session_id)
+        .await
+        .map_err(|e| ErrorResponse {
+            message: format!("Could not configure agent: {}", e),
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    let extensions = match profile {
+        Profile::Recipe(recipe) if recipe.extensions.is_some() => {
+            recipe.extensions.clone().unwrap_or_default()
+        }
+        _ => goose::config::get_enabled_extensions(),
+    };
+    for extension in extensions {
+        if let Err(e) = agent.add_extension(extension.clone()).await {
+            tracing::warn!("Failed to load extension {}: {}", extension.name(), e);
+        }
+    }
+
+    if let Profile::Recipe(recipe) = profile {
+        if let Some(prompt) = apply_recipe_to_agent(agent, recipe, false).await {
+            agent.extend_system_prompt(prompt).await;
+        }
+    }
+    Ok(())
+}
+
+/// Assistant text in a message, None for tool calls and their results
+fn assistant_text(message: &Message) -> Option<String> {
+    if message.is_tool_call() || message.is_tool_response() {
+        return None;
+    }
+    (message.role == rmcp::model::Role::Assistant && message.metadata.agent_visible)
+        .then(|| message.as_concat_text())
+        .filter(|text| !text.is_empty())
+}
+
+/// Collects the text of the agent's final answer: assistant text after the last tool round trip.
+#[derive(Default)]
+struct FinalText {
+    text: String,
+}
+
+impl FinalText {
+    fn push(&mut self, message: &Message) {
+        // Text sent along with a tool call comes before the tool's result, so it isn't final
+        if message.is_tool_call() || message.is_tool_response() {
+            self.text.clear();
+            return;
+        }
+        if let Some(text) = assistant_text(message) {
+            self.text.push_str(&text);
+        }
+    }
+}
+
+enum RunEvent {
+    /// Assistant text as it arrives
+    Delta(String),
+    Error(String),
+}
+
+// The caller can't answer tool confirmations, so tools that would need approval are declined
+async fn decline_confirmations(agent: &Agent, message: &Message) {
+    for content in &message.content {
+        if let MessageContent::ActionRequired(action) = content {
+            if let ActionRequiredData::ToolConfirmation { id, .. } = &action.data {
+                agent
+                    .handle_confirmation(
+                        id.clone(),
+                        PermissionConfirmation {
+                            principal_type: PrincipalType::Tool,
+                            permission: Permission::DenyOnce,
+                        },
+                    )
+                    .await;
+            }
+        }
+    }
+}
+
+async fn run_agent(
+    agent: Arc<Agent>,
+    session_id: String,
+    user_message: Message,
+    cancel_token: CancellationToken,
+    tx: mpsc::Sender<RunEvent>,
+) -> String {
+    let session_config = SessionConfig {
+        id: session_id,
+        schedule_id: None,
+        max_turns: None,
+        retry_config: None,
+    };
+    let mut stream = match agent
+        .reply(user_message, session_config, Some(cancel_token.clone()))
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = tx.send(RunEvent::Error(e.to_string())).await;
+            return String::new();
+        }
+    };
+
+    let mut final_text = FinalText::default();
+    loop {
+        tokio::select! {
+            _ = cancel_token.cancelled() => break,
+            event = stream.next() => match event {
+                Some(Ok(AgentEvent::Message(message))) => {
+                    decline_confirmations(&agent, &message).await;
+                    final_text.push(&message);
+                    if let Some(text) = assistant_text(&message) {
+                        let _ = tx.send(RunEvent::Delta(text)).await;
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    let _ = tx.send(RunEvent::Error(e.to_string())).await;
+                    return String::new();
+                }
+                None => break,
+            }
+        }
+    }
+    final_text.text
+}
+
+/// Completions are one-off, so neither the agent nor the session outlive the request
+async fn release(state: &AppState, session_id: &str) {
+    if let Err(e) = state.agent_manager.remove_session(session_id).await {
+        tracing::debug!("Failed to release agent for {}: {}", session_id, e);
+    }
+    if let Err(e) = SessionManager::delete_session(session_id).await {
+        tracing::warn!("Failed to delete session {}: {}", session_id, e);
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    request_body = ChatCompletionRequest,
+    responses(
+        (status = 200, description = "Final assistant message, or a stream of chat.completion.chunk events when stream is set", body = ChatCompletionResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized - invalid secret key or token"),
+        (status = 404, description = "Unknown model", body = ErrorResponse),
+        (status = 429, description = "Too many runs for this client, run queue full or daily token quota exhausted", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+async fn chat_completions(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    client: Option<Extension<ClientKey>>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Result<Response, ErrorResponse> {
+    let ChatCompletionRequest {
+        model,
+        messages,
+        stream,
+        temperature,
+        metadata,
+    } = request;
+
+    let mut system_prompts = Vec::new();
+    let mut history = Vec::new();
+    for message in &messages {
+        if matches!(message.role.as_str(), "system" | "developer") {
+            system_prompts.push(content_text(&message.content));
+        } else {
+            history.push(to_goose_message(message)?);
+        }
+    }
+    let user_message = match history.pop() {
+        Some(message) if message.role == rmcp::model::Role::User => message,
+        _ => {
+            return Err(ErrorResponse {
+                message: "The last message must come from the user".into(),
+                status: StatusCode::BAD_REQUEST,
+            })
+        }
+    };
+
+    let profile = resolve_profile(&model, metadata).await?;
+    let principal = principal.map(|Extension(principal)| principal);
+    let client = client
+        .map(|Extension(client)| client)
+        .unwrap_or_else(|| ClientKey::new(principal.as_ref(), None));
+    let charge_to = state.limits.check_quota(principal.as_ref()).await?;
+    let mut slot = state.limits.reserve_run(&client)?;
+    let working_dir = std::env::current_dir().unwrap_or_default();
+    // Hidden and deleted once the completion is done, so API clients don't fill the session list
+    let session = SessionManager::create_session(
+        working_dir,
+        format!("Chat completion ({})", model),
+        SessionType::Hidden,
+    )
+    .await
+    .map_err(|e| ErrorResponse {
+        message: format!("Failed to create session: {}", e),
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    let prepared = async {
+        let owner = principal.and_then(|principal| principal.user);
+        if owner.is_some() {
+            SessionManager::update_session(&session.id)
+                .owner(owner)
+                .apply()
+                .await
+                .map_err(|e| ErrorResponse {
+                    message: format!("Failed to update session: {}", e),
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                })?;
+        }
+        state.webhooks.session_created(&session);
+        for message in &history {
+            SessionManager::add_message(&session.id, message)
+                .await
+                .map_err(|e| ErrorResponse {
+                    message: format!("Failed to store message: {}", e),
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                })?;
+        }
+
+        let agent = state
+            .get_agent_for_route(session.id.clone())
+            .await
+            .map_err(|status| ErrorResponse {
+                message: "Failed to get agent".into(),
+                status,
+            })?;
+        configure_agent(&agent, &session.id, &profile, temperature).await?;
+        Ok(agent)
+    }
+    .await;
+    let agent = match prepared {
+        Ok(agent) => agent,
+        Err(e) => {
+            release(&state, &session.id).await;
+            return Err(e);
+        }
+    };
+    for prompt in system_prompts.into_iter().filter(|p| !p.is_empty()) {
+        agent.extend_system_prompt(prompt).await;
+    }
+
+    let completion_id = format!("chatcmpl-{}", session.id);
+    let created = chrono::Utc::now().timestamp();
+    let cancel_token = CancellationToken::new();
+    let (tx, mut rx) = mpsc::channel(100);
+    let session_id = session.id.clone();
+    let run = {
+        let state = state.clone();
+        let cancel_token = cancel_token.clone();
+        tokio::spawn(async move {
+            let text = tokio::select! {
+                _ = slot.wait(|_| {}) => {
+                    run_agent(agent, session_id.clone(), user_message, cancel_token, tx).await
+                }
+                _ = cancel_token.cancelled() => String::new(),
+            };
+            drop(slot);
+            let session = SessionManager::get_session(&session_id, false).await.ok();
+            if let (Some(user), Some(session)) = (&charge_to, &session) {
+                let spent = session.accumulated_total_tokens.unwrap_or(0);
+                state.limits.charge(user, spent as i64).await;
+            }
+            release(&state, &session_id).await;
+            let usage = session
+                .map(|session| {
+                    (
+                        session.accumulated_input_tokens.unwrap_or_default(),
+                        session.accumulated_output_tokens.unwrap_or_default(),
+                    )
+                })
+                .unwrap_or_default();
+            (text, usage)
+        })
+    };
+
+    if stream {
+        let (sse_tx, sse_rx) = mpsc::channel::<Result<Event, Infallible>>(100);
+        tokio::spawn(async move {
+            let chunk = |delta: Value, finish_reason: Option<&str>| -> Result<Event, Infallible> {
+                let chunk = serde_json::json!({
+                    "id": completion_id,
+                    "object": "chat.completion.chunk",
+                    "created": created,
+                    "model": model,
+                    "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+                });
+                Ok(Event::default().data(chunk.to_string()))
+            };
+
+            let mut role_sent = false;
+            loop {
+                let event = tokio::select! {
+                    event = rx.recv() => event,
+                    _ = sse_tx.closed() => {
+                        // The client went away
+                        cancel_token.cancel();
+                        return;
+                    }
+                };
+                match event {
+                    Some(RunEvent::Delta(text)) => {
+                        let delta = if role_sent {
+                            serde_json::json!({"content": text})
+                        } else {
+                            serde_json::json!({"role": "assistant", "content": text})
+                        };
+                        role_sent = true;
+                        if sse_tx.send(chunk(delta, None)).await.is_err() {
+                            cancel_token.cancel();
+                            return;
+                        }
+                    }
+                    Some(RunEvent::Error(error)) => {
+                        // Like OpenAI, a failed stream ends with the error rather than a
+                        // finish reason and [DONE]
+                        let error = serde_json::json!({"error": {"message": error}});
+                        let _ = sse_tx
+                            .send(Ok(Event::default().data(error.to_string())))
+                            .await;
+                        return;
+                    }
+                    None => break,
+                }
+            }
+            let _ = sse_tx
+                .send(chunk(serde_json::json!({}), Some("stop")))
+                .await;
+            let _ = sse_tx.send(Ok(Event::default().data("[DONE]"))).await;
+        });
+        // Tool calls can take a while without any text, so keep the connection alive meanwhile
+        return Ok(Sse::new(ReceiverStream::new(sse_rx))
+            .keep_alive(KeepAlive::default())
+            .into_response());
+    }
+
+    // Stops the agent if the client disconnects before the answer is ready
+    let _cancel_on_drop = cancel_token.drop_guard();
+    let mut error = None;
+    while let Some(event) = rx.recv().await {
+        if let RunEvent::Error(e) = event {
+            error = Some(e);
+        }
+    }
+    let (content, (prompt_tokens, completion_tokens)) = run.await.unwrap_or_default();
+    if let Some(error) = error {
+        return Err(ErrorResponse {
+            message: error,
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        });
+    }
+
+    Ok(Json(ChatCompletionResponse {
+        id: completion_id,
+        object: "chat.completion".to_string(),
+        created,
+        model,
+        choices: vec![ChatCompletionChoice {
+            index: 0,
+            message: ChatCompletionResponseMessage {
+                role: "assistant".to_string(),
+                content,
+            },
+            finish_reason: "stop".to_string(),
+        }],
+        usage: ChatCompletionUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        },
+    })
+    .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/models",
+    responses(
+        (status = 200, description = "Agent profiles and recipes usable as models", body = ModelList),
+        (status = 401, description = "Unauthorized - invalid secret key or token")
+    )
+)]
+async fn list_models() -> Json<ModelList> {
+    let created = chrono::Utc::now().timestamp();
+    let model = |id: String| ModelObject {
+        id,
+        object: "model".to_string(),
+        created,
+        owned_by: "goose".to_string(),
+    };
+
+    let mut data = vec![model(DEFAULT_MODEL.to_string())];
+    data.extend(recipe_models().into_iter().map(|(id, _)| model(id)));
+    Json(ModelList {
+        object: "list".to_string(),
+        data,
+    })
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(role: &str, content: Value) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role: role.to_string(),
+            content,
+        }
+    }
+
+    #[test]
+    fn test_content_parts_are_converted() {
+        let converted = to_goose_message(&message(
+            "user",
+            json!([
+                {"type": "text", "text": "What is in"},
+                {"type": "text", "text": "this picture?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,aGVsbG8="}}
+            ]),
+        ))
+        .unwrap();
+        assert_eq!(converted.role, rmcp::model::Role::User);
+        assert_eq!(converted.as_concat_text(), "What is in\nthis picture?");
+        assert!(matches!(
+            converted.content.last(),
+            Some(MessageContent::Image(image)) if image.mime_type == "image/png" && image.data == "aGVsbG8="
+        ));
+
+        assert!(to_goose_message(&message(
+            "user",
+            json!([{"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}])
+        ))
+        .is_err());
+        assert!(to_goose_message(&message("tool", json!("result"))).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_completions_leave_no_sessions_behind() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let completion_sessions = || async {
+            SessionManager::list_sessions_by_types(&[SessionType::User, SessionType::Hidden])
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|session| session.name == "Chat completion (goose)")
+                .count()
+        };
+        let before = completion_sessions().await;
+
+        let app = routes(AppState::new().await.unwrap());
+        let request = Request::builder()
+            .uri("/v1/chat/completions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                json!({
+                    "model": "goose",
+                    "messages": [{"role": "user", "content": "hello"}],
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        app.oneshot(request).await.unwrap();
+
+        assert_eq!(completion_sessions().await, before);
+    }
+
+    #[test]
+    fn test_final_text_restarts_after_tool_use() {
+        let mut final_text = FinalText::default();
+        final_text.push(&Message::assistant().with_text("Let me check"));
+        assert_eq!(final_text.text, "Let me check");
+        final_text.push(
+            &Message::assistant()
+                .with_text("Running it")
+                .with_tool_request(
+                    "1",
+                    Ok(rmcp::model::CallToolRequestParam {
+                        name: "shell".into(),
+                        arguments: None,
+                    }),
+                ),
+        );
+        final_text.push(&Message::user().with_text("ignored"));
+        assert!(final_text.text.is_empty());
+        final_text.push(&Message::assistant().with_text("The answer"));
+        final_text.push(&Message::assistant().with_text(" is 42"));
+        assert_eq!(final_text.text, "The answer is 42");
+    }
+}
@@ -1,9 +1,13 @@
+use crate::auth::Principal;
+use crate::limits::ClientKey;
+use crate::session_events::{RunHandle, SessionEventHub, Subscription};
 use crate::state::AppState;
+use crate::webhooks::WebhookEvent;
 use axum::{
-    extract::{DefaultBodyLimit, State},
-    http::{self, StatusCode},
+    extract::{DefaultBodyLimit, Extension, Path, Query, State},
+    http::{self, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use bytes::Bytes;
@@ -14,6 +18,7 @@ use goose::conversation::Conversation;
 use goose::session::SessionManager;
 use rmcp::model::ServerNotification;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::{
     convert::Infallible,
     pin::Pin,
@@ -21,10 +26,11 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
-use tokio::time::timeout;
 use tokio_stream::wrappers::ReceiverStream;
-use tokio_util::sync::CancellationToken;
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
 
 fn track_tool_telemetry(content: &MessageContent, all_messages: &[Message]) {
     match content {
@@ -141,6 +147,10 @@ pub enum MessageEvent {
     UpdateConversation {
         conversation: Conversation,
     },
+    /// The run waits for a free slot; position 1 starts next
+    Queued {
+        position: usize,
+    },
     Ping,
 }
 
@@ -165,42 +175,151 @@ async fn get_token_state(session_id: &str) -> TokenState {
         .unwrap_or_default()
 }
 
-async fn stream_event(
-    event: MessageEvent,
-    tx: &mpsc::Sender<String>,
-    cancel_token: &CancellationToken,
-) {
-    let json = serde_json::to_string(&event).unwrap_or_else(|e| {
+fn serialize_event(event: &MessageEvent) -> String {
+    serde_json::to_string(event).unwrap_or_else(|e| {
         format!(
             r#"{{"type":"Error","error":"Failed to serialize event: {}"}}"#,
             e
         )
-    });
+    })
+}
 
-    if tx.send(format!("data: {}\n\n", json)).await.is_err() {
-        tracing::info!("client hung up");
-        cancel_token.cancel();
-    }
+fn stream_event(event: MessageEvent, events: &SessionEventHub, run: &RunHandle) {
+    events.publish(run, serialize_event(&event));
+}
+
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Streams a subscription to one client: the backlog first, then live events until the run
+/// finishes. The client going away only ends this stream, never the run.
+fn attach(
+    events: Arc<SessionEventHub>,
+    session_id: String,
+    mut subscription: Subscription,
+) -> SseResponse {
+    let (tx, rx) = mpsc::channel(100);
+
+    drop(tokio::spawn(async move {
+        let mut last_sent = 0;
+        for event in std::mem::take(&mut subscription.backlog) {
+            last_sent = event.id;
+            if tx.send(event.to_sse()).await.is_err() {
+                return;
+            }
+        }
+
+        let Some(mut live) = subscription.live.take() else {
+            return;
+        };
+        let ping = format!("data: {}\n\n", serialize_event(&MessageEvent::Ping));
+        let mut heartbeat_interval = tokio::time::interval(Duration::from_millis(500));
+        loop {
+            let events_to_send = tokio::select! {
+                _ = heartbeat_interval.tick() => {
+                    if tx.send(ping.clone()).await.is_err() {
+                        tracing::info!("client hung up, run continues in the background");
+                        return;
+                    }
+                    continue;
+                }
+                received = live.recv() => match received {
+                    Ok(event) => vec![event],
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!("client fell {} events behind, catching up from buffer", skipped);
+                        events.events_after(&session_id, last_sent)
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            };
+
+            for event in events_to_send {
+                if event.id <= last_sent {
+                    continue;
+                }
+                last_sent = event.id;
+                if tx.send(event.to_sse()).await.is_err() {
+                    tracing::info!("client hung up, run continues in the background");
+                    return;
+                }
+            }
+        }
+    }));
+
+    SseResponse::new(ReceiverStream::new(rx))
+}
+
+/// Stream for a client reconnecting to a session the server has no events for, e.g. after a
+/// restart, so it stops retrying.
+fn lost_stream(session_id: &str) -> SseResponse {
+    let (tx, rx) = mpsc::channel(1);
+    let error = MessageEvent::Error {
+        error: format!(
+            "No reply in progress for session {}; it may have been lost when the server restarted",
+            session_id
+        ),
+    };
+    let _ = tx.try_send(format!("data: {}\n\n", serialize_event(&error)));
+    SseResponse::new(ReceiverStream::new(rx))
 }
 
-#[allow(clippy::too_many_lines)]
 #[utoipa::path(
     post,
     path = "/reply",
     request_body = ChatRequest,
+    params(
+        ("Last-Event-ID" = Option<u64>, Header, description = "Reattach to the reply in progress for the session instead of starting a new one, resuming after this event id")
+    ),
     responses(
         (status = 200, description = "Streaming response initiated",
          body = MessageEvent,
          content_type = "text/event-stream"),
+        (status = 409, description = "A reply is already in progress for this session"),
+        (status = 429, description = "Too many runs for this client, run queue full or daily token quota exhausted"),
         (status = 424, description = "Agent not initialized"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn reply(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    client: Option<Extension<ClientKey>>,
+    headers: HeaderMap,
     Json(request): Json<ChatRequest>,
 ) -> Result<SseResponse, StatusCode> {
-    let session_start = std::time::Instant::now();
+    let events = state.session_events.clone();
+
+    if let Some(last_event_id) = last_event_id(&headers) {
+        return Ok(
+            match events.subscribe(&request.session_id, Some(last_event_id)) {
+                Some(subscription) => attach(events, request.session_id, subscription),
+                None => lost_stream(&request.session_id),
+            },
+        );
+    }
+
+    let principal = principal.map(|Extension(principal)| principal);
+    let client = client
+        .map(|Extension(client)| client)
+        .unwrap_or_else(|| ClientKey::new(principal.as_ref(), None));
+    let refuse = |e: crate::limits::LimitError| {
+        tracing::info!("Refused reply for {}: {}", request.session_id, e);
+        StatusCode::TOO_MANY_REQUESTS
+    };
+    let charge_to = state
+        .limits
+        .check_quota(principal.as_ref())
+        .await
+        .map_err(refuse)?;
+    let mut slot = state.limits.reserve_run(&client).map_err(refuse)?;
+
+    let run = events
+        .start_run(&request.session_id)
+        .ok_or(StatusCode::CONFLICT)?;
 
     tracing::info!(
         counter.goose.session_starts = 1,
@@ -209,10 +328,8 @@ pub async fn reply(
         "Session started"
     );
 
-    let session_id = request.session_id.clone();
-
     if let Some(recipe_name) = request.recipe_name.clone() {
-        if state.mark_recipe_run_if_absent(&session_id).await {
+        if state.mark_recipe_run_if_absent(&request.session_id).await {
             let recipe_version = request
                 .recipe_version
                 .clone()
@@ -229,221 +346,339 @@ pub async fn reply(
         }
     }
 
-    let (tx, rx) = mpsc::channel(100);
-    let stream = ReceiverStream::new(rx);
-    let cancel_token = CancellationToken::new();
-
+    // Subscribe before the run starts so the client sees every event it produces
+    let subscription = events
+        .subscribe(&run.session_id, Some(run.started_after()))
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
     let messages = Conversation::new_unvalidated(request.messages);
 
-    let task_cancel = cancel_token.clone();
-    let task_tx = tx.clone();
-
+    let task_events = events.clone();
     drop(tokio::spawn(async move {
-        let agent = match state.get_agent(session_id.clone()).await {
-            Ok(agent) => agent,
-            Err(e) => {
-                tracing::error!("Failed to get session agent: {}", e);
-                let _ = stream_event(
-                    MessageEvent::Error {
-                        error: format!("Failed to get session agent: {}", e),
-                    },
-                    &task_tx,
-                    &task_cancel,
-                )
-                .await;
-                return;
-            }
-        };
-
-        let session = match SessionManager::get_session(&session_id, false).await {
-            Ok(metadata) => metadata,
-            Err(e) => {
-                tracing::error!("Failed to read session for {}: {}", session_id, e);
-                let _ = stream_event(
-                    MessageEvent::Error {
-                        error: format!("Failed to read session: {}", e),
-                    },
-                    &task_tx,
-                    &cancel_token,
-                )
-                .await;
-                return;
-            }
-        };
+        let queued = slot
+            .wait(|position| stream_event(MessageEvent::Queued { position }, &task_events, &run));
+        tokio::select! {
+            _ = queued => run_reply(state, &task_events, &run, messages, charge_to).await,
+            _ = run.cancel_token.cancelled() => {}
+        }
+        drop(slot);
+        // The run also finishes if the reply panics, when the task drops its handle
+        drop(run);
+    }));
 
-        let session_config = SessionConfig {
-            id: session_id.clone(),
-            schedule_id: session.schedule_id.clone(),
-            max_turns: None,
-            retry_config: None,
-        };
+    Ok(attach(events, request.session_id, subscription))
+}
 
-        let user_message = match messages.last() {
-            Some(msg) => msg,
-            _ => {
-                let _ = stream_event(
-                    MessageEvent::Error {
-                        error: "Reply started with empty messages".to_string(),
-                    },
-                    &task_tx,
-                    &task_cancel,
-                )
-                .await;
-                return;
-            }
-        };
+#[allow(clippy::too_many_lines)]
+async fn run_reply(
+    state: Arc<AppState>,
+    events: &SessionEventHub,
+    run: &RunHandle,
+    messages: Conversation,
+    charge_to: Option<String>,
+) {
+    let session_start = std::time::Instant::now();
+    let session_id = run.session_id.clone();
+    let task_cancel = run.cancel_token.clone();
+
+    let agent = match state.get_agent(session_id.clone()).await {
+        Ok(agent) => agent,
+        Err(e) => {
+            tracing::error!("Failed to get session agent: {}", e);
+            stream_event(
+                MessageEvent::Error {
+                    error: format!("Failed to get session agent: {}", e),
+                },
+                events,
+                run,
+            );
+            return;
+        }
+    };
+
+    let session = match SessionManager::get_session(&session_id, false).await {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::error!("Failed to read session for {}: {}", session_id, e);
+            stream_event(
+                MessageEvent::Error {
+                    error: format!("Failed to read session: {}", e),
+                },
+                events,
+                run,
+            );
+            return;
+        }
+    };
+
+    let tokens_before = session.accumulated_total_tokens.unwrap_or(0);
+
+    let session_config = SessionConfig {
+        id: session_id.clone(),
+        schedule_id: session.schedule_id.clone(),
+        max_turns: None,
+        retry_config: None,
+    };
+
+    let user_message = match messages.last() {
+        Some(msg) => msg,
+        _ => {
+            stream_event(
+                MessageEvent::Error {
+                    error: "Reply started with empty messages".to_string(),
+                },
+                events,
+                run,
+            );
+            return;
+        }
+    };
 
-        let mut stream = match agent
-            .reply(
-                user_message.clone(),
-                session_config,
-                Some(task_cancel.clone()),
-            )
-            .await
-        {
-            Ok(stream) => stream,
-            Err(e) => {
-                tracing::error!("Failed to start reply stream: {:?}", e);
-                stream_event(
-                    MessageEvent::Error {
-                        error: e.to_string(),
-                    },
-                    &task_tx,
-                    &cancel_token,
-                )
-                .await;
-                return;
-            }
-        };
+    let mut stream = match agent
+        .reply(
+            user_message.clone(),
+            session_config,
+            Some(task_cancel.clone()),
+        )
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => {
+            tracing::error!("Failed to start reply stream: {:?}", e);
+            stream_event(
+                MessageEvent::Error {
+                    error: e.to_string(),
+                },
+                events,
+                run,
+            );
+            return;
+        }
+    };
 
-        let mut all_messages = messages.clone();
+    let mut all_messages = messages.clone();
+    let mut error = None;
 
-        let mut heartbeat_interval = tokio::time::interval(Duration::from_millis(500));
-        loop {
-            tokio::select! {
-                _ = task_cancel.cancelled() => {
-                    tracing::info!("Agent task cancelled");
-                    break;
-                }
-                _ = heartbeat_interval.tick() => {
-                    stream_event(MessageEvent::Ping, &tx, &cancel_token).await;
-                }
-                response = timeout(Duration::from_millis(500), stream.next()) => {
-                    match response {
-                        Ok(Some(Ok(AgentEvent::Message(message)))) => {
-                            for content in &message.content {
-                                track_tool_telemetry(content, all_messages.messages());
+    loop {
+        tokio::select! {
+            _ = task_cancel.cancelled() => {
+                tracing::info!("Agent task cancelled");
+                break;
+            }
+            response = stream.next() => {
+                match response {
+                    Some(Ok(AgentEvent::Message(message))) => {
+                        for content in &message.content {
+                            track_tool_telemetry(content, all_messages.messages());
+                            if let MessageContent::ActionRequired(action) = content {
+                                state.webhooks.emit(
+                                    WebhookEvent::ActionRequired,
+                                    json!({"session_id": session_id, "action": action}),
+                                );
                             }
+                        }
 
-                            all_messages.push(message.clone());
+                        all_messages.push(message.clone());
 
-                            let token_state = get_token_state(&session_id).await;
+                        let token_state = get_token_state(&session_id).await;
 
-                            stream_event(MessageEvent::Message { message, token_state }, &tx, &cancel_token).await;
-                        }
-                        Ok(Some(Ok(AgentEvent::HistoryReplaced(new_messages)))) => {
-                            all_messages = new_messages.clone();
-                            stream_event(MessageEvent::UpdateConversation {conversation: new_messages}, &tx, &cancel_token).await;
-
-                        }
-                        Ok(Some(Ok(AgentEvent::ModelChange { model, mode }))) => {
-                            stream_event(MessageEvent::ModelChange { model, mode }, &tx, &cancel_token).await;
-                        }
-                        Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
-                            stream_event(MessageEvent::Notification{
-                                request_id: request_id.clone(),
-                                message: n,
-                            }, &tx, &cancel_token).await;
-                        }
+                        stream_event(MessageEvent::Message { message, token_state }, events, run);
+                    }
+                    Some(Ok(AgentEvent::HistoryReplaced(new_messages))) => {
+                        all_messages = new_messages.clone();
+                        state.webhooks.emit(
+                            WebhookEvent::SessionCompacted,
+                            json!({"session_id": session_id, "message_count": all_messages.len()}),
+                        );
+                        stream_event(MessageEvent::UpdateConversation {conversation: new_messages}, events, run);
+                    }
+                    Some(Ok(AgentEvent::ModelChange { model, mode })) => {
+                        stream_event(MessageEvent::ModelChange { model, mode }, events, run);
+                    }
+                    Some(Ok(AgentEvent::McpNotification((request_id, n)))) => {
+                        stream_event(MessageEvent::Notification{
+                            request_id: request_id.clone(),
+                            message: n,
+                        }, events, run);
+                    }
 
-                        Ok(Some(Err(e))) => {
-                            tracing::error!("Error processing message: {}", e);
-                            stream_event(
-                                MessageEvent::Error {
-                                    error: e.to_string(),
-                                },
-                                &tx,
-                                &cancel_token,
-                            ).await;
-                            break;
-                        }
-                        Ok(None) => {
-                            break;
-                        }
-                        Err(_) => {
-                            if tx.is_closed() {
-                                break;
-                            }
-                            continue;
-                        }
+                    Some(Err(e)) => {
+                        tracing::error!("Error processing message: {}", e);
+                        error = Some(e.to_string());
+                        stream_event(
+                            MessageEvent::Error {
+                                error: e.to_string(),
+                            },
+                            events,
+                            run,
+                        );
+                        break;
+                    }
+                    None => {
+                        break;
                     }
                 }
             }
         }
+    }
 
-        let session_duration = session_start.elapsed();
+    drop(stream);
+    if let Err(e) = agent.save_snapshot(&session_id).await {
+        tracing::warn!("Failed to save agent snapshot for {}: {}", session_id, e);
+    }
 
-        if let Ok(session) = SessionManager::get_session(&session_id, true).await {
-            let total_tokens = session.total_tokens.unwrap_or(0);
+    let session_duration = session_start.elapsed();
+    let status = if error.is_some() {
+        "failed"
+    } else if task_cancel.is_cancelled() {
+        "cancelled"
+    } else {
+        "completed"
+    };
+
+    if let Ok(session) = SessionManager::get_session(&session_id, true).await {
+        let total_tokens = session.total_tokens.unwrap_or(0);
+        if let Some(user) = &charge_to {
+            let spent = session.accumulated_total_tokens.unwrap_or(0) - tokens_before;
+            state.limits.charge(user, spent as i64).await;
+        }
+        state.webhooks.emit(
+            WebhookEvent::TurnCompleted,
+            json!({
+                "session_id": session_id,
+                "status": status,
+                "error": error,
+                "duration_ms": session_duration.as_millis() as u64,
+                "message_count": session.message_count,
+                "total_tokens": total_tokens,
+            }),
+        );
+        tracing::info!(
+            counter.goose.session_completions = 1,
+            session_type = "app",
+            interface = "ui",
+            exit_type = "normal",
+            duration_ms = session_duration.as_millis() as u64,
+            total_tokens = total_tokens,
+            message_count = session.message_count,
+            "Session completed"
+        );
+
+        tracing::info!(
+            counter.goose.session_duration_ms = session_duration.as_millis() as u64,
+            session_type = "app",
+            interface = "ui",
+            "Session duration"
+        );
+
+        if total_tokens > 0 {
             tracing::info!(
-                counter.goose.session_completions = 1,
+                counter.goose.session_tokens = total_tokens,
                 session_type = "app",
                 interface = "ui",
-                exit_type = "normal",
-                duration_ms = session_duration.as_millis() as u64,
-                total_tokens = total_tokens,
-                message_count = session.message_count,
-                "Session completed"
+                "Session tokens"
             );
+        }
+    } else {
+        state.webhooks.emit(
+            WebhookEvent::TurnCompleted,
+            json!({
+                "session_id": session_id,
+                "status": status,
+                "error": error,
+                "duration_ms": session_duration.as_millis() as u64,
+                "message_count": all_messages.len(),
+                "total_tokens": null,
+            }),
+        );
+        tracing::info!(
+            counter.goose.session_completions = 1,
+            session_type = "app",
+            interface = "ui",
+            exit_type = "normal",
+            duration_ms = session_duration.as_millis() as u64,
+            total_tokens = 0u64,
+            message_count = all_messages.len(),
+            "Session completed"
+        );
+
+        tracing::info!(
+            counter.goose.session_duration_ms = session_duration.as_millis() as u64,
+            session_type = "app",
+            interface = "ui",
+            "Session duration"
+        );
+    }
 
-            tracing::info!(
-                counter.goose.session_duration_ms = session_duration.as_millis() as u64,
-                session_type = "app",
-                interface = "ui",
-                "Session duration"
-            );
+    let final_token_state = get_token_state(&session_id).await;
 
-            if total_tokens > 0 {
-                tracing::info!(
-                    counter.goose.session_tokens = total_tokens,
-                    session_type = "app",
-                    interface = "ui",
-                    "Session tokens"
-                );
-            }
-        } else {
-            tracing::info!(
-                counter.goose.session_completions = 1,
-                session_type = "app",
-                interface = "ui",
-                exit_type = "normal",
-                duration_ms = session_duration.as_millis() as u64,
-                total_tokens = 0u64,
-                message_count = all_messages.len(),
-                "Session completed"
-            );
+    stream_event(
+        MessageEvent::Finish {
+            reason: "stop".to_string(),
+            token_state: final_token_state,
+        },
+        events,
+        run,
+    );
+}
 
-            tracing::info!(
-                counter.goose.session_duration_ms = session_duration.as_millis() as u64,
-                session_type = "app",
-                interface = "ui",
-                "Session duration"
-            );
-        }
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SessionEventsQuery {
+    /// Resume after this event id, for clients that cannot send a Last-Event-ID header
+    last_event_id: Option<u64>,
+}
 
-        let final_token_state = get_token_state(&session_id).await;
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/events",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event id"),
+        SessionEventsQuery
+    ),
+    responses(
+        (status = 200, description = "Buffered and live events of the session's latest reply",
+         body = MessageEvent,
+         content_type = "text/event-stream"),
+        (status = 404, description = "No reply in progress or recently finished for this session")
+    ),
+    tag = "Session Management"
+)]
+pub async fn session_events(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Query(query): Query<SessionEventsQuery>,
+    headers: HeaderMap,
+) -> Result<SseResponse, StatusCode> {
+    let events = state.session_events.clone();
+    let after = last_event_id(&headers).or(query.last_event_id);
+    let subscription = events
+        .subscribe(&session_id, after)
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-        let _ = stream_event(
-            MessageEvent::Finish {
-                reason: "stop".to_string(),
-                token_state: final_token_state,
-            },
-            &task_tx,
-            &cancel_token,
-        )
-        .await;
-    }));
-    Ok(SseResponse::new(stream))
+    Ok(attach(events, session_id, subscription))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/cancel",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Reply cancelled"),
+        (status = 404, description = "No reply in progress for this session")
+    ),
+    tag = "Session Management"
+)]
+pub async fn cancel_reply(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> StatusCode {
+    if state.session_events.cancel_run(&session_id) {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
 }
 
 pub fn routes(state: Arc<AppState>) -> Router {
@@ -452,6 +687,8 @@ pub fn routes(state: Arc<AppState>) -> Router {
             "/reply",
             post(reply).layer(DefaultBodyLimit::max(50 * 1024 * 1024)),
         )
+        .route("/sessions/{session_id}/events", get(session_events))
+        .route("/sessions/{session_id}/cancel", post(cancel_reply))
         .with_state(state)
 }
 
@@ -491,5 +728,46 @@ mod tests {
 
             assert_eq!(response.status(), StatusCode::OK);
         }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn test_reconnect_without_buffered_reply() {
+            let state = AppState::new().await.unwrap();
+
+            let app = routes(state);
+
+            let request = Request::builder()
+                .uri("/sessions/unknown-session/events")
+                .method("GET")
+                .header("x-secret-key", "test-secret")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let request = Request::builder()
+                .uri("/reply")
+                .method("POST")
+                .header("content-type", "application/json")
+                .header("x-secret-key", "test-secret")
+                .header("last-event-id", "42")
+                .body(Body::from(
+                    serde_json::to_string(&ChatRequest {
+                        messages: vec![Message::user().with_text("test message")],
+                        session_id: "unknown-session".to_string(),
+                        recipe_name: None,
+                        recipe_version: None,
+                    })
+                    .unwrap(),
+                ))
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.starts_with("data: {\"type\":\"Error\""));
+        }
     }
 }
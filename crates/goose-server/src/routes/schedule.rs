@@ -1,21 +1,70 @@
 use std::sync::Arc;
 
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     routing::{delete, get, post, put},
-    Json, Router,
+    Extension, Json, Router,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::api_tokens::Scope;
+use crate::auth::Principal;
 use crate::state::AppState;
 use goose::scheduler::ScheduledJob;
+use goose::scheduler_expression::{
+    next_fire_times, parse_schedule_expression, DEFAULT_PREVIEW_COUNT,
+};
+use goose::scheduler_runs::{
+    CatchUpPolicy, ConcurrencyPolicy, FailureNotification, JobRun, RetryPolicy,
+};
+use goose::scheduler_trigger::{webhook_body_to_params, ScheduleTrigger};
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
 pub struct CreateScheduleRequest {
     id: String,
     recipe_source: String,
+    /// Cron expression or phrase such as "every weekday at 9am", required when `trigger` is
+    /// omitted or `cron`
+    #[serde(default)]
     cron: String,
+    #[serde(default)]
+    trigger: Option<ScheduleTrigger>,
+    /// IANA timezone for the cron expression; local time when omitted
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    retry: Option<RetryPolicy>,
+    #[serde(default)]
+    concurrency: Option<ConcurrencyPolicy>,
+    #[serde(default)]
+    catch_up: Option<CatchUpPolicy>,
+    /// `command` notifications run on the host, so they need full access rather than only
+    /// `schedules:manage`
+    #[serde(default)]
+    notifications: Vec<FailureNotification>,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+pub struct PreviewScheduleRequest {
+    /// Cron expression or phrase such as "every 15 minutes"
+    expression: String,
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Number of upcoming fire times to return (default 5, at most 50)
+    #[serde(default)]
+    count: Option<usize>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct PreviewScheduleResponse {
+    valid: bool,
+    /// The expression as a 6-field cron expression
+    cron: Option<String>,
+    /// Upcoming fire times as RFC 3339 timestamps in the schedule's timezone
+    next_runs: Vec<String>,
+    error: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -53,6 +102,12 @@ pub struct SessionsQuery {
     limit: usize,
 }
 
+#[derive(Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+pub struct RunsQuery {
+    /// Maximum number of runs to return, newest first (default 20)
+    limit: Option<usize>,
+}
+
 // Struct for the frontend session list
 #[derive(Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -78,6 +133,7 @@ pub struct SessionDisplayInfo {
     responses(
         (status = 200, description = "Scheduled job created successfully", body = ScheduledJob),
         (status = 400, description = "Invalid cron expression or recipe file"),
+        (status = 403, description = "Command notifications need full access"),
         (status = 409, description = "Job ID already exists"),
         (status = 500, description = "Internal server error")
     ),
@@ -86,8 +142,21 @@ pub struct SessionDisplayInfo {
 #[axum::debug_handler]
 async fn create_schedule(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(req): Json<CreateScheduleRequest>,
 ) -> Result<Json<ScheduledJob>, StatusCode> {
+    // Commands run through the shell on the host, which a schedules token alone must not allow
+    let runs_commands = req
+        .notifications
+        .iter()
+        .any(|notification| matches!(notification, FailureNotification::Command { .. }));
+    let full_access = principal.is_none_or(|Extension(principal)| {
+        principal.user.is_none() || principal.allows(Scope::ManageConfig)
+    });
+    if runs_commands && !full_access {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     let scheduler = state.scheduler();
 
     tracing::info!(
@@ -98,11 +167,17 @@ async fn create_schedule(
         id: req.id,
         source: req.recipe_source,
         cron: req.cron,
+        trigger: req.trigger.unwrap_or_default(),
+        timezone: req.timezone,
         last_run: None,
         currently_running: false,
         paused: false,
         current_session_id: None,
         process_start_time: None,
+        retry: req.retry.unwrap_or_default(),
+        concurrency: req.concurrency.unwrap_or_default(),
+        catch_up: req.catch_up.unwrap_or_default(),
+        notifications: req.notifications,
     };
     scheduler
         .add_scheduled_job(job.clone(), true)
@@ -112,6 +187,7 @@ async fn create_schedule(
             match e {
                 goose::scheduler::SchedulerError::JobNotFound(_) => StatusCode::NOT_FOUND,
                 goose::scheduler::SchedulerError::CronParseError(_) => StatusCode::BAD_REQUEST,
+                goose::scheduler::SchedulerError::InvalidTrigger(_) => StatusCode::BAD_REQUEST,
                 goose::scheduler::SchedulerError::RecipeLoadError(_) => StatusCode::BAD_REQUEST,
                 goose::scheduler::SchedulerError::JobIdExists(_) => StatusCode::CONFLICT,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
@@ -120,6 +196,41 @@ async fn create_schedule(
     Ok(Json(job))
 }
 
+#[utoipa::path(
+    post,
+    path = "/schedule/preview",
+    request_body = PreviewScheduleRequest,
+    responses(
+        (status = 200, description = "Whether the schedule is valid and when it would fire next", body = PreviewScheduleResponse)
+    ),
+    tag = "schedule"
+)]
+#[axum::debug_handler]
+async fn preview_schedule(
+    Json(req): Json<PreviewScheduleRequest>,
+) -> Json<PreviewScheduleResponse> {
+    let count = req.count.unwrap_or(DEFAULT_PREVIEW_COUNT).min(50);
+    let result = parse_schedule_expression(&req.expression).and_then(|cron| {
+        let next_runs = next_fire_times(&cron, req.timezone.as_deref(), chrono::Utc::now(), count)?;
+        Ok((cron, next_runs))
+    });
+
+    Json(match result {
+        Ok((cron, next_runs)) => PreviewScheduleResponse {
+            valid: true,
+            cron: Some(cron),
+            next_runs: next_runs.iter().map(|time| time.to_rfc3339()).collect(),
+            error: None,
+        },
+        Err(error) => PreviewScheduleResponse {
+            valid: false,
+            cron: None,
+            next_runs: Vec::new(),
+            error: Some(error),
+        },
+    })
+}
+
 #[utoipa::path(
     get,
     path = "/schedule/list",
@@ -149,6 +260,7 @@ async fn list_schedules(
     responses(
         (status = 204, description = "Scheduled job deleted successfully"),
         (status = 404, description = "Scheduled job not found"),
+        (status = 409, description = "Other scheduled jobs run after this one"),
         (status = 500, description = "Internal server error")
     ),
     tag = "schedule"
@@ -166,6 +278,7 @@ async fn delete_schedule(
             eprintln!("Error deleting schedule '{}': {:?}", id, e);
             match e {
                 goose::scheduler::SchedulerError::JobNotFound(_) => StatusCode::NOT_FOUND,
+                goose::scheduler::SchedulerError::AnyhowError(_) => StatusCode::CONFLICT,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             }
         })?;
@@ -412,6 +525,7 @@ async fn update_schedule(
                 goose::scheduler::SchedulerError::JobNotFound(_) => StatusCode::NOT_FOUND,
                 goose::scheduler::SchedulerError::AnyhowError(_) => StatusCode::BAD_REQUEST,
                 goose::scheduler::SchedulerError::CronParseError(_) => StatusCode::BAD_REQUEST,
+                goose::scheduler::SchedulerError::InvalidTrigger(_) => StatusCode::BAD_REQUEST,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             }
         })?;
@@ -501,10 +615,118 @@ pub async fn inspect_running_job(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/schedule/{id}/runs",
+    params(
+        ("id" = String, Path, description = "ID of the schedule"),
+        RunsQuery
+    ),
+    responses(
+        (status = 200, description = "Run history of the schedule, newest first", body = Vec<JobRun>),
+        (status = 404, description = "Scheduled job not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "schedule"
+)]
+#[axum::debug_handler]
+async fn runs_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<RunsQuery>,
+) -> Result<Json<Vec<JobRun>>, StatusCode> {
+    let scheduler = state.scheduler();
+
+    let runs = scheduler
+        .runs(&id, query.limit.unwrap_or(20))
+        .await
+        .map_err(|e| {
+            eprintln!("Error fetching runs for schedule '{}': {:?}", id, e);
+            match e {
+                goose::scheduler::SchedulerError::JobNotFound(_) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+    Ok(Json(runs))
+}
+
+fn webhook_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("X-Goose-Webhook-Token")
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(axum::http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/hooks/schedule/{id}",
+    params(
+        ("id" = String, Path, description = "ID of the webhook-triggered schedule")
+    ),
+    responses(
+        (status = 202, description = "Job started; the fields of the JSON body are passed as recipe parameters"),
+        (status = 400, description = "Body is not a JSON object"),
+        (status = 401, description = "Unknown schedule or invalid webhook token"),
+        (status = 409, description = "Job is paused or already running"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "schedule"
+)]
+#[axum::debug_handler]
+async fn schedule_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    let scheduler = state.scheduler();
+
+    // This route is exempt from the X-Secret-Key check, so the per-job token is the only
+    // credential. Unknown jobs get the same response as bad tokens.
+    let token = webhook_token(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let authorized = scheduler
+        .list_scheduled_jobs()
+        .await
+        .into_iter()
+        .any(|job| job.id == id && job.trigger.verify_webhook_token(token));
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let body: serde_json::Value = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&body).map_err(|_| StatusCode::BAD_REQUEST)?
+    };
+    let params = webhook_body_to_params(&body).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    tracing::info!(
+        "Server: webhook triggered schedule '{}' with {} parameter(s)",
+        id,
+        params.len()
+    );
+
+    scheduler.trigger_job(&id, params).await.map_err(|e| {
+        eprintln!("Error triggering schedule '{}' from webhook: {:?}", id, e);
+        match e {
+            goose::scheduler::SchedulerError::JobNotFound(_) => StatusCode::UNAUTHORIZED,
+            goose::scheduler::SchedulerError::AnyhowError(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    })?;
+    Ok(StatusCode::ACCEPTED)
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/schedule/create", post(create_schedule))
         .route("/schedule/list", get(list_schedules))
+        .route("/schedule/preview", post(preview_schedule))
         .route("/schedule/delete/{id}", delete(delete_schedule)) // Corrected
         .route("/schedule/{id}", put(update_schedule))
         .route("/schedule/{id}/run_now", post(run_now_handler)) // Corrected
@@ -513,5 +735,42 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/schedule/{id}/kill", post(kill_running_job))
         .route("/schedule/{id}/inspect", get(inspect_running_job))
         .route("/schedule/{id}/sessions", get(sessions_handler)) // Corrected
+        .route("/schedule/{id}/runs", get(runs_handler))
+        .route("/hooks/schedule/{id}", post(schedule_webhook))
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_command_notifications_need_full_access() {
+        let state = AppState::new().await.unwrap();
+        let app = routes(state).layer(Extension(Principal {
+            user: Some("ci".to_string()),
+            scopes: vec![Scope::ManageSchedules],
+            token_id: Some("abc".to_string()),
+            daily_token_quota: None,
+        }));
+
+        let request = Request::builder()
+            .uri("/schedule/create")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "id": "notify-by-command",
+                    "recipe_source": "recipe.yaml",
+                    "cron": "0 0 * * * *",
+                    "notifications": [{"type": "command", "command": "touch /tmp/pwned"}],
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
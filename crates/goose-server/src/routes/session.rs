@@ -1,19 +1,24 @@
+use crate::auth::Principal;
 use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::{apply_recipe_to_agent, build_recipe_with_parameter_values};
 use crate::state::AppState;
 use axum::extract::State;
 use axum::routing::post;
+use axum::Extension;
 use axum::{
-    extract::Path,
-    http::StatusCode,
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, put},
     Json, Router,
 };
 use goose::recipe::Recipe;
+use goose::session::export::{self, ExportError, ExportFormat};
 use goose::session::session_manager::SessionInsights;
 use goose::session::{Session, SessionManager};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use utoipa::ToSchema;
 
@@ -89,10 +94,15 @@ const MAX_NAME_LENGTH: usize = 200;
     ),
     tag = "Session Management"
 )]
-async fn list_sessions() -> Result<Json<SessionListResponse>, StatusCode> {
-    let sessions = SessionManager::list_sessions()
+async fn list_sessions(
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<SessionListResponse>, StatusCode> {
+    let mut sessions = SessionManager::list_sessions()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Some(Extension(principal)) = principal {
+        sessions.retain(|session| principal.can_access(session));
+    }
 
     Ok(Json(SessionListResponse { sessions }))
 }
@@ -238,6 +248,9 @@ async fn update_session_user_recipe_values(
             if let Some(prompt) = apply_recipe_to_agent(&agent, &recipe, false).await {
                 agent.extend_system_prompt(prompt).await;
             }
+            if let Err(e) = agent.save_snapshot(&session_id).await {
+                tracing::warn!("Failed to save agent snapshot for {}: {}", session_id, e);
+            }
             Ok(Json(UpdateSessionUserRecipeValuesResponse { recipe }))
         }
         Ok(None) => Err(ErrorResponse {
@@ -282,16 +295,25 @@ async fn delete_session(Path(session_id): Path<String>) -> Result<StatusCode, St
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ExportSessionQuery {
+    /// json (default), html, openai-jsonl, anthropic-jsonl or otlp
+    format: Option<String>,
+}
+
 #[utoipa::path(
     get,
     path = "/sessions/{session_id}/export",
     params(
-        ("session_id" = String, Path, description = "Unique identifier for the session")
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ExportSessionQuery
     ),
     responses(
-        (status = 200, description = "Session exported successfully", body = String),
+        (status = 200, description = "Session exported successfully. The default json format is returned as a JSON encoded string, other formats as the raw document", body = String),
+        (status = 400, description = "Unsupported export format"),
         (status = 401, description = "Unauthorized - Invalid or missing API key"),
         (status = 404, description = "Session not found"),
+        (status = 422, description = "The session has no messages to export in this format"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -299,12 +321,34 @@ async fn delete_session(Path(session_id): Path<String>) -> Result<StatusCode, St
     ),
     tag = "Session Management"
 )]
-async fn export_session(Path(session_id): Path<String>) -> Result<Json<String>, StatusCode> {
-    let exported = SessionManager::export_session(&session_id)
+async fn export_session(
+    Path(session_id): Path<String>,
+    Query(query): Query<ExportSessionQuery>,
+) -> Result<Response, StatusCode> {
+    let format = match query.format.as_deref() {
+        Some(format) => ExportFormat::from_str(format).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => ExportFormat::Json,
+    };
+
+    if format == ExportFormat::Json {
+        let exported = SessionManager::export_session(&session_id)
+            .await
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+        return Ok(Json(exported).into_response());
+    }
+
+    let session = SessionManager::get_session(&session_id, true)
         .await
         .map_err(|_| StatusCode::NOT_FOUND)?;
+    let exported = export::export_session(&session, format).map_err(|e| match e {
+        ExportError::NoMessages => StatusCode::UNPROCESSABLE_ENTITY,
+        ExportError::Serialize(_) => {
+            tracing::error!("Failed to export session {}: {}", session_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
 
-    Ok(Json(exported))
+    Ok(([(header::CONTENT_TYPE, format.content_type())], exported).into_response())
 }
 
 #[utoipa::path(
@@ -323,12 +367,22 @@ async fn export_session(Path(session_id): Path<String>) -> Result<Json<String>,
     tag = "Session Management"
 )]
 async fn import_session(
+    principal: Option<Extension<Principal>>,
     Json(request): Json<ImportSessionRequest>,
 ) -> Result<Json<Session>, StatusCode> {
-    let session = SessionManager::import_session(&request.json)
+    let mut session = SessionManager::import_session(&request.json)
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
+    if let Some(user) = principal.and_then(|Extension(principal)| principal.user) {
+        SessionManager::update_session(&session.id)
+            .owner(Some(user.clone()))
+            .apply()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        session.owner = Some(user);
+    }
+
     Ok(Json(session))
 }
 
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use goose::session::export::{export_html_with_note, ExportError};
+use goose::session::{Session, SessionManager};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::Principal;
+use crate::routes::errors::ErrorResponse;
+use crate::shares::{SessionSnapshot, ShareInfo};
+use crate::state::AppState;
+
+const DEFAULT_EXPIRY_HOURS: i64 = 7 * 24;
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ShareSessionRequest {
+    /// Replace API keys, tokens and other secrets, on by default
+    #[serde(default)]
+    redact: Option<bool>,
+    /// Hours until the link stops working, 168 by default and 0 for never
+    #[serde(default)]
+    expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ViewShareQuery {
+    /// `json` returns the session in the format `/sessions/import` reads, HTML otherwise
+    format: Option<String>,
+}
+
+fn not_found(id: &str) -> ErrorResponse {
+    ErrorResponse {
+        message: format!("Share '{}' not found or expired", id),
+        status: StatusCode::NOT_FOUND,
+    }
+}
+
+fn owned_by(snapshot: &SessionSnapshot, principal: Option<&Principal>) -> bool {
+    match principal.and_then(|principal| principal.user.as_deref()) {
+        None => true,
+        Some(user) => snapshot.owner.as_deref() == Some(user),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/share",
+    params(("session_id" = String, Path, description = "Session to share")),
+    request_body = ShareSessionRequest,
+    responses(
+        (status = 200, description = "Snapshot created", body = ShareInfo),
+        (status = 400, description = "The session has no messages", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+    ),
+    tag = "Session Sharing"
+)]
+async fn share_session(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(session_id): Path<String>,
+    request: Option<Json<ShareSessionRequest>>,
+) -> Result<Json<ShareInfo>, ErrorResponse> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let session = SessionManager::get_session(&session_id, true)
+        .await
+        .map_err(|_| ErrorResponse {
+            message: format!("Session '{}' not found", session_id),
+            status: StatusCode::NOT_FOUND,
+        })?;
+    let expires_in = match request.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS) {
+        hours if hours > 0 => Some(chrono::Duration::hours(hours)),
+        _ => None,
+    };
+    let owner = principal.and_then(|Extension(principal)| principal.user);
+
+    let snapshot = state
+        .shares
+        .create(session, owner, request.redact.unwrap_or(true), expires_in)
+        .map_err(|e| ErrorResponse {
+            message: e.to_string(),
+            status: StatusCode::BAD_REQUEST,
+        })?;
+    Ok(Json(ShareInfo::from(&snapshot)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/shares",
+    responses(
+        (status = 200, description = "Snapshots shared by the caller that have not expired", body = Vec<ShareInfo>),
+    ),
+    tag = "Session Sharing"
+)]
+async fn list_shares(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<Vec<ShareInfo>>, ErrorResponse> {
+    let principal = principal.map(|Extension(principal)| principal);
+    let shares = state.shares.list()?;
+    Ok(Json(
+        shares
+            .iter()
+            .filter(|snapshot| owned_by(snapshot, principal.as_ref()))
+            .map(ShareInfo::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/shares/{share_id}",
+    params(("share_id" = String, Path, description = "Share id")),
+    responses(
+        (status = 204, description = "Snapshot deleted, its link stops working"),
+        (status = 404, description = "Share not found", body = ErrorResponse),
+    ),
+    tag = "Session Sharing"
+)]
+async fn delete_share(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(share_id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    let principal = principal.map(|Extension(principal)| principal);
+    match state.shares.get(&share_id)? {
+        Some(snapshot) if owned_by(&snapshot, principal.as_ref()) => {
+            state.shares.delete(&share_id)?;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        _ => Err(not_found(&share_id)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/shares/{share_id}/import",
+    params(("share_id" = String, Path, description = "Share id")),
+    responses(
+        (status = 200, description = "A new local session holding the snapshot's conversation", body = Session),
+        (status = 404, description = "Share not found or expired", body = ErrorResponse),
+    ),
+    tag = "Session Sharing"
+)]
+async fn import_share(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(share_id): Path<String>,
+) -> Result<Json<Session>, ErrorResponse> {
+    let snapshot = state
+        .shares
+        .get(&share_id)?
+        .ok_or_else(|| not_found(&share_id))?;
+    let json = serde_json::to_string(&snapshot.session).map_err(anyhow::Error::from)?;
+    let mut session = SessionManager::import_session(&json).await?;
+
+    if let Some(user) = principal.and_then(|Extension(principal)| principal.user) {
+        SessionManager::update_session(&session.id)
+            .owner(Some(user.clone()))
+            .apply()
+            .await?;
+        session.owner = Some(user);
+    }
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    get,
+    path = "/share/{share_id}",
+    params(
+        ("share_id" = String, Path, description = "Share id"),
+        ViewShareQuery
+    ),
+    responses(
+        (status = 200, description = "The snapshot rendered as HTML, or its session as JSON", body = String),
+        (status = 404, description = "Share not found or expired"),
+    ),
+    tag = "Session Sharing"
+)]
+async fn view_share(
+    State(state): State<Arc<AppState>>,
+    Path(share_id): Path<String>,
+    Query(query): Query<ViewShareQuery>,
+) -> Result<Response, ErrorResponse> {
+    let snapshot = state
+        .shares
+        .get(&share_id)?
+        .ok_or_else(|| not_found(&share_id))?;
+
+    if query.format.as_deref() == Some("json") {
+        return Ok((
+            [(header::CONTENT_TYPE, "application/json")],
+            serde_json::to_string_pretty(&snapshot.session).map_err(anyhow::Error::from)?,
+        )
+            .into_response());
+    }
+
+    let mut note = format!(
+        "Read-only snapshot shared {}",
+        snapshot.created_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    if let Some(expires_at) = snapshot.expires_at {
+        note.push_str(&format!(
+            ", available until {}",
+            expires_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    match snapshot.redactions {
+        Some(0) | None => {}
+        Some(count) => note.push_str(&format!(", {} secrets redacted", count)),
+    }
+    note.push_str(&format!(
+        ". Import it with /share/{}?format=json.",
+        snapshot.id
+    ));
+
+    let html = export_html_with_note(&snapshot.session, &note).map_err(|e| ErrorResponse {
+        status: match e {
+            ExportError::NoMessages => StatusCode::UNPROCESSABLE_ENTITY,
+            ExportError::Serialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        },
+        message: e.to_string(),
+    })?;
+    Ok(Html(html).into_response())
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/sessions/{session_id}/share", post(share_session))
+        .route("/shares", get(list_shares))
+        .route("/shares/{share_id}", delete(delete_share))
+        .route("/shares/{share_id}/import", post(import_share))
+        .route("/share/{share_id}", get(view_share))
+        .with_state(state)
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use crate::webhooks::{validate_url, WebhookChanges, WebhookDelivery, WebhookInfo};
+
+fn bad_request(message: impl Into<String>) -> ErrorResponse {
+    ErrorResponse {
+        message: message.into(),
+        status: StatusCode::BAD_REQUEST,
+    }
+}
+
+fn not_found(id: &str) -> ErrorResponse {
+    ErrorResponse {
+        message: format!("Webhook '{}' not found", id),
+        status: StatusCode::NOT_FOUND,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "Configured webhooks", body = Vec<WebhookInfo>),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<WebhookInfo>>, ErrorResponse> {
+    let webhooks = state.webhooks.store().list()?;
+    Ok(Json(webhooks.iter().map(WebhookInfo::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = WebhookChanges,
+    responses(
+        (status = 200, description = "Webhook created", body = WebhookInfo),
+        (status = 400, description = "Missing or invalid url", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(changes): Json<WebhookChanges>,
+) -> Result<Json<WebhookInfo>, ErrorResponse> {
+    let Some(url) = &changes.url else {
+        return Err(bad_request("A webhook needs a url"));
+    };
+    validate_url(url).map_err(|e| bad_request(e.to_string()))?;
+    let webhook = state.webhooks.store().create(changes)?;
+    Ok(Json(WebhookInfo::from(&webhook)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "The webhook", body = WebhookInfo),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn get_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookInfo>, ErrorResponse> {
+    let webhook = state
+        .webhooks
+        .store()
+        .get(&id)?
+        .ok_or_else(|| not_found(&id))?;
+    Ok(Json(WebhookInfo::from(&webhook)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/webhooks/{id}",
+    params(("id" = String, Path, description = "Webhook id")),
+    request_body = WebhookChanges,
+    responses(
+        (status = 200, description = "Webhook updated", body = WebhookInfo),
+        (status = 400, description = "Invalid url", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn update_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(changes): Json<WebhookChanges>,
+) -> Result<Json<WebhookInfo>, ErrorResponse> {
+    if let Some(url) = &changes.url {
+        validate_url(url).map_err(|e| bad_request(e.to_string()))?;
+    }
+    let webhook = state
+        .webhooks
+        .store()
+        .update(&id, changes)?
+        .ok_or_else(|| not_found(&id))?;
+    Ok(Json(WebhookInfo::from(&webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    if !state.webhooks.store().delete(&id)? {
+        return Err(not_found(&id));
+    }
+    state.webhooks.forget(&id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Latest deliveries, newest first", body = Vec<WebhookDelivery>),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    tag = "Webhooks"
+)]
+async fn list_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<WebhookDelivery>>, ErrorResponse> {
+    if state.webhooks.store().get(&id)?.is_none() {
+        return Err(not_found(&id));
+    }
+    Ok(Json(state.webhooks.deliveries(&id)))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/{id}",
+            get(get_webhook).put(update_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/{id}/deliveries", get(list_deliveries))
+        .with_state(state)
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use goose::conversation::message::Message;
+use goose::conversation::Conversation;
+use goose::scheduler_workers::{WorkItem, WorkerInfo, WorkerPool, DEFAULT_LEASE};
+use goose::session::SessionManager;
+use serde::{Deserialize, Serialize};
+
+use crate::state::AppState;
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+pub struct LeaseRequest {
+    worker_id: String,
+    /// How long the worker may go without a heartbeat, 60 seconds when omitted
+    #[serde(default)]
+    lease_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+pub struct HeartbeatRequest {
+    worker_id: String,
+    #[serde(default)]
+    lease_seconds: Option<u64>,
+    /// Messages produced since the last heartbeat, appended to the run's session
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize, Serialize, utoipa::ToSchema)]
+pub struct CompleteRunRequest {
+    worker_id: String,
+    /// Text of the last assistant message
+    #[serde(default)]
+    output: Option<String>,
+    /// Set when the run failed on the worker
+    #[serde(default)]
+    error: Option<String>,
+    /// Messages produced since the last heartbeat
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+fn lease_duration(lease_seconds: Option<u64>) -> Duration {
+    lease_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_LEASE)
+}
+
+async fn append_messages(session_id: &str, messages: &[Message]) -> Result<(), StatusCode> {
+    for message in messages {
+        SessionManager::add_message(session_id, message)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to store worker message in {}: {}", session_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/workers/lease",
+    request_body = LeaseRequest,
+    responses(
+        (status = 200, description = "A scheduled run for the worker to execute", body = WorkItem),
+        (status = 204, description = "No runs are waiting"),
+    ),
+    tag = "workers"
+)]
+async fn lease_run(Json(req): Json<LeaseRequest>) -> Result<Json<WorkItem>, StatusCode> {
+    let leased = WorkerPool::global()
+        .lease(&req.worker_id, lease_duration(req.lease_seconds))
+        .ok_or(StatusCode::NO_CONTENT)?;
+    if leased.requeued {
+        // The attempt starts over, so drop what the worker that lost the run sent
+        let session_id = &leased.item.session_id;
+        SessionManager::replace_conversation(session_id, &Conversation::empty())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to reset {} for a new attempt: {}", session_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+    Ok(Json(leased.item))
+}
+
+#[utoipa::path(
+    post,
+    path = "/workers/runs/{run_id}/heartbeat",
+    params(("run_id" = String, Path, description = "Run leased by the worker")),
+    request_body = HeartbeatRequest,
+    responses(
+        (status = 200, description = "Lease extended"),
+        (status = 409, description = "The worker no longer holds the run and should stop it"),
+    ),
+    tag = "workers"
+)]
+async fn heartbeat(
+    Path(run_id): Path<String>,
+    Json(req): Json<HeartbeatRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let session_id = WorkerPool::global()
+        .heartbeat(&run_id, &req.worker_id, lease_duration(req.lease_seconds))
+        .map_err(|e| {
+            tracing::warn!("Rejected heartbeat from {}: {}", req.worker_id, e);
+            StatusCode::CONFLICT
+        })?;
+    append_messages(&session_id, &req.messages).await?;
+    Ok(Json(serde_json::json!({})))
+}
+
+#[utoipa::path(
+    post,
+    path = "/workers/runs/{run_id}/complete",
+    params(("run_id" = String, Path, description = "Run leased by the worker")),
+    request_body = CompleteRunRequest,
+    responses(
+        (status = 200, description = "Result recorded"),
+        (status = 409, description = "The worker no longer holds the run"),
+    ),
+    tag = "workers"
+)]
+async fn complete_run(
+    Path(run_id): Path<String>,
+    Json(req): Json<CompleteRunRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let pool = WorkerPool::global();
+    // Store the last messages before the scheduler sees the run as finished
+    let session_id = pool
+        .heartbeat(&run_id, &req.worker_id, DEFAULT_LEASE)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    append_messages(&session_id, &req.messages).await?;
+    pool.complete(&run_id, &req.worker_id, req.output, req.error)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    Ok(Json(serde_json::json!({})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/workers",
+    responses(
+        (status = 200, description = "Workers seen recently and the runs they hold", body = Vec<WorkerInfo>),
+    ),
+    tag = "workers"
+)]
+async fn list_workers() -> Json<Vec<WorkerInfo>> {
+    Json(WorkerPool::global().workers())
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/workers", get(list_workers))
+        .route("/workers/lease", post(lease_run))
+        .route("/workers/runs/{run_id}/heartbeat", post(heartbeat))
+        .route("/workers/runs/{run_id}/complete", post(complete_run))
+        .with_state(state)
+}
@@ -0,0 +1,393 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+/// Number of events kept per session for clients that reconnect mid-run.
+pub const MAX_BUFFERED_EVENTS: usize = 1000;
+/// How long the events of a finished run stay available to late clients.
+const FINISHED_RUN_RETENTION: Duration = Duration::from_secs(10 * 60);
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct BufferedEvent {
+    pub id: u64,
+    /// The serialized `MessageEvent`
+    pub data: String,
+}
+
+impl BufferedEvent {
+    pub fn to_sse(&self) -> String {
+        format!("id: {}\ndata: {}\n\n", self.id, self.data)
+    }
+}
+
+/// Identifies one agent run of a session. Events published with a handle whose run has been
+/// superseded by a newer one are dropped. Dropping the handle finishes the run, so it ends even
+/// if the task driving it panics.
+pub struct RunHandle {
+    pub session_id: String,
+    pub cancel_token: CancellationToken,
+    run: u64,
+    started_after: u64,
+    hub: Arc<Mutex<Sessions>>,
+}
+
+impl RunHandle {
+    /// Id of the last event published before this run started.
+    pub fn started_after(&self) -> u64 {
+        self.started_after
+    }
+}
+
+impl Drop for RunHandle {
+    // Live subscribers receive the remaining events and then see the channel close
+    fn drop(&mut self) {
+        let mut hub = self.hub.lock().unwrap();
+        if let Some(session) = hub.sessions.get_mut(&self.session_id) {
+            if session
+                .active
+                .as_ref()
+                .is_some_and(|active| active.run == self.run)
+            {
+                session.active = None;
+                session.finished_at = Some(Instant::now());
+            }
+        }
+    }
+}
+
+/// A client attached to a session's events. The session's entry is kept while any client is
+/// attached, and for `FINISHED_RUN_RETENTION` after its run finished.
+pub struct Subscription {
+    /// Buffered events the client has not seen yet, oldest first
+    pub backlog: Vec<BufferedEvent>,
+    /// Live events of the active run, `None` once the run has finished
+    pub live: Option<broadcast::Receiver<BufferedEvent>>,
+    session_id: String,
+    hub: Arc<Mutex<Sessions>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Finished sessions stay around for clients that reconnect, `prune_finished` drops them
+        let mut hub = self.hub.lock().unwrap();
+        if let Some(session) = hub.sessions.get_mut(&self.session_id) {
+            session.subscribers -= 1;
+        }
+    }
+}
+
+struct ActiveRun {
+    run: u64,
+    sender: broadcast::Sender<BufferedEvent>,
+    cancel_token: CancellationToken,
+}
+
+#[derive(Default)]
+struct SessionEvents {
+    run_started_after: u64,
+    events: VecDeque<BufferedEvent>,
+    active: Option<ActiveRun>,
+    finished_at: Option<Instant>,
+    subscribers: usize,
+}
+
+#[derive(Default)]
+struct Sessions {
+    // Shared by all sessions rather than kept per session, so ids and runs stay monotonic for a
+    // session whose entry was dropped and created again
+    last_id: u64,
+    last_run: u64,
+    sessions: HashMap<String, SessionEvents>,
+}
+
+/// Decouples agent runs from the HTTP connections streaming them. Every event a run produces is
+/// numbered and buffered per session, so a client that drops can reattach with the last id it
+/// saw and continue where it left off while the run keeps going server-side.
+#[derive(Default)]
+pub struct SessionEventHub {
+    hub: Arc<Mutex<Sessions>>,
+}
+
+impl SessionEventHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new run for the session. Returns `None` while another run is still active;
+    /// a run that has been cancelled but not finished yet is replaced.
+    pub fn start_run(&self, session_id: &str) -> Option<RunHandle> {
+        let mut hub = self.hub.lock().unwrap();
+        prune_finished(&mut hub.sessions);
+
+        let Sessions {
+            last_id,
+            last_run,
+            sessions,
+        } = &mut *hub;
+        let session = sessions.entry(session_id.to_string()).or_default();
+        if let Some(active) = &session.active {
+            if !active.cancel_token.is_cancelled() {
+                return None;
+            }
+        }
+
+        let (sender, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        let cancel_token = CancellationToken::new();
+        *last_run += 1;
+        session.run_started_after = *last_id;
+        session.finished_at = None;
+        session.active = Some(ActiveRun {
+            run: *last_run,
+            sender,
+            cancel_token: cancel_token.clone(),
+        });
+
+        Some(RunHandle {
+            session_id: session_id.to_string(),
+            cancel_token,
+            run: *last_run,
+            started_after: *last_id,
+            hub: self.hub.clone(),
+        })
+    }
+
+    pub fn publish(&self, handle: &RunHandle, data: String) {
+        let mut hub = self.hub.lock().unwrap();
+        let Sessions {
+            last_id, sessions, ..
+        } = &mut *hub;
+        let Some(session) = sessions.get_mut(&handle.session_id) else {
+            return;
+        };
+        let Some(active) = session
+            .active
+            .as_ref()
+            .filter(|active| active.run == handle.run)
+        else {
+            return;
+        };
+
+        *last_id += 1;
+        let event = BufferedEvent { id: *last_id, data };
+        // Nobody listening is fine, the event is buffered for later
+        let _ = active.sender.send(event.clone());
+        session.events.push_back(event);
+        while session.events.len() > MAX_BUFFERED_EVENTS {
+            session.events.pop_front();
+        }
+    }
+
+    /// Cancels the active run of the session, returning whether there was one.
+    pub fn cancel_run(&self, session_id: &str) -> bool {
+        let hub = self.hub.lock().unwrap();
+        match hub
+            .sessions
+            .get(session_id)
+            .and_then(|session| session.active.as_ref())
+        {
+            Some(active) => {
+                active.cancel_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is_running(&self, session_id: &str) -> bool {
+        let hub = self.hub.lock().unwrap();
+        hub.sessions
+            .get(session_id)
+            .is_some_and(|session| session.active.is_some())
+    }
+
+    /// Attaches to the session's event stream. Without `after`, the client gets every buffered
+    /// event of the latest run. Returns `None` if there is nothing to attach to.
+    pub fn subscribe(&self, session_id: &str, after: Option<u64>) -> Option<Subscription> {
+        let mut hub = self.hub.lock().unwrap();
+        prune_finished(&mut hub.sessions);
+        let session = hub.sessions.get_mut(session_id)?;
+        if session.active.is_none() && session.events.is_empty() {
+            return None;
+        }
+
+        let after = after.unwrap_or(session.run_started_after);
+        session.subscribers += 1;
+        Some(Subscription {
+            backlog: events_after(session, after),
+            live: session
+                .active
+                .as_ref()
+                .map(|active| active.sender.subscribe()),
+            session_id: session_id.to_string(),
+            hub: self.hub.clone(),
+        })
+    }
+
+    /// Buffered events newer than `after`, used to recover when a live subscriber falls behind.
+    pub fn events_after(&self, session_id: &str, after: u64) -> Vec<BufferedEvent> {
+        let hub = self.hub.lock().unwrap();
+        hub.sessions
+            .get(session_id)
+            .map(|session| events_after(session, after))
+            .unwrap_or_default()
+    }
+}
+
+fn events_after(session: &SessionEvents, after: u64) -> Vec<BufferedEvent> {
+    session
+        .events
+        .iter()
+        .filter(|event| event.id > after)
+        .cloned()
+        .collect()
+}
+
+// Drops sessions whose run finished long enough ago, once no client is attached anymore
+fn prune_finished(sessions: &mut HashMap<String, SessionEvents>) {
+    sessions.retain(|_, session| {
+        session.active.is_some()
+            || session.subscribers > 0
+            || session
+                .finished_at
+                .is_some_and(|finished_at| finished_at.elapsed() <= FINISHED_RUN_RETENTION)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(events: &[BufferedEvent]) -> Vec<u64> {
+        events.iter().map(|event| event.id).collect()
+    }
+
+    #[test]
+    fn test_only_one_active_run_per_session() {
+        let hub = SessionEventHub::new();
+        let first = hub.start_run("s1").unwrap();
+        assert!(hub.start_run("s1").is_none());
+        assert!(hub.start_run("s2").is_some());
+
+        assert!(hub.cancel_run("s1"));
+        let second = hub.start_run("s1").unwrap();
+
+        // The cancelled run can no longer publish into the session or end the new run
+        hub.publish(&first, "stale".to_string());
+        drop(first);
+        hub.publish(&second, "fresh".to_string());
+        assert!(hub.is_running("s1"));
+        let subscription = hub.subscribe("s1", None).unwrap();
+        assert_eq!(subscription.backlog.len(), 1);
+        assert_eq!(subscription.backlog[0].data, "fresh");
+    }
+
+    #[test]
+    fn test_reconnect_replays_events_after_last_id() {
+        let hub = SessionEventHub::new();
+        let run = hub.start_run("s1").unwrap();
+        for i in 0..5 {
+            hub.publish(&run, format!("event {}", i));
+        }
+
+        let subscription = hub.subscribe("s1", Some(3)).unwrap();
+        assert_eq!(ids(&subscription.backlog), vec![4, 5]);
+        assert!(subscription.live.is_some());
+        assert_eq!(
+            subscription.backlog[0].to_sse(),
+            "id: 4\ndata: event 3\n\n".to_string()
+        );
+
+        drop(run);
+        let subscription = hub.subscribe("s1", Some(3)).unwrap();
+        assert_eq!(ids(&subscription.backlog), vec![4, 5]);
+        assert!(subscription.live.is_none());
+        assert!(!hub.cancel_run("s1"));
+    }
+
+    #[test]
+    fn test_ids_keep_increasing_across_runs() {
+        let hub = SessionEventHub::new();
+        let first = hub.start_run("s1").unwrap();
+        hub.publish(&first, "a".to_string());
+        hub.publish(&first, "b".to_string());
+        drop(first);
+
+        let second = hub.start_run("s1").unwrap();
+        assert_eq!(second.started_after(), 2);
+        hub.publish(&second, "c".to_string());
+
+        let subscription = hub.subscribe("s1", None).unwrap();
+        assert_eq!(ids(&subscription.backlog), vec![3]);
+        assert_eq!(ids(&hub.events_after("s1", 0)), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_live_subscribers_see_new_events_until_finish() {
+        let hub = SessionEventHub::new();
+        let run = hub.start_run("s1").unwrap();
+        let mut subscription = hub.subscribe("s1", None).unwrap();
+        let mut live = subscription.live.take().unwrap();
+
+        hub.publish(&run, "hello".to_string());
+        drop(run);
+
+        assert_eq!(live.recv().await.unwrap().id, 1);
+        assert!(matches!(
+            live.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        ));
+    }
+
+    #[test]
+    fn test_finished_sessions_are_kept_for_the_retention_window() {
+        let hub = SessionEventHub::new();
+        let run = hub.start_run("s1").unwrap();
+        hub.publish(&run, "a".to_string());
+        let subscription = hub.subscribe("s1", None).unwrap();
+        drop(run);
+        drop(subscription);
+
+        // A client whose stream dropped, or a second tab, can still catch up
+        assert_eq!(hub.subscribe("s1", Some(0)).unwrap().backlog.len(), 1);
+        assert_eq!(hub.subscribe("s1", Some(0)).unwrap().backlog.len(), 1);
+        assert_eq!(hub.events_after("s1", 0).len(), 1);
+
+        // Until the retention window is over
+        let attached = hub.subscribe("s1", Some(0)).unwrap();
+        let expired = Instant::now() - FINISHED_RUN_RETENTION - Duration::from_secs(1);
+        hub.hub
+            .lock()
+            .unwrap()
+            .sessions
+            .get_mut("s1")
+            .unwrap()
+            .finished_at = Some(expired);
+        // but not while a client is still attached
+        assert!(hub.subscribe("s1", Some(0)).is_some());
+        drop(attached);
+        assert!(hub.subscribe("s1", Some(0)).is_none());
+        assert!(hub.hub.lock().unwrap().sessions.is_empty());
+
+        // Ids keep increasing for a session that comes back
+        let run = hub.start_run("s1").unwrap();
+        assert_eq!(run.started_after(), 1);
+    }
+
+    #[test]
+    fn test_buffer_is_bounded() {
+        let hub = SessionEventHub::new();
+        let run = hub.start_run("s1").unwrap();
+        for _ in 0..MAX_BUFFERED_EVENTS + 10 {
+            hub.publish(&run, "x".to_string());
+        }
+
+        let backlog = hub.events_after("s1", 0);
+        assert_eq!(backlog.len(), MAX_BUFFERED_EVENTS);
+        assert_eq!(backlog[0].id, 11);
+        assert!(hub.subscribe("unknown", None).is_none());
+    }
+}
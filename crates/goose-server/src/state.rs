@@ -7,7 +7,11 @@ use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::limits::Limits;
+use crate::session_events::SessionEventHub;
+use crate::shares::ShareStore;
 use crate::tunnel::TunnelManager;
+use crate::webhooks::WebhookDispatcher;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -17,10 +21,19 @@ pub struct AppState {
     /// Tracks sessions that have already emitted recipe telemetry to prevent double counting.
     recipe_session_tracker: Arc<Mutex<HashSet<String>>>,
     pub tunnel_manager: Arc<TunnelManager>,
+    pub session_events: Arc<SessionEventHub>,
+    pub webhooks: Arc<WebhookDispatcher>,
+    pub limits: Arc<Limits>,
+    pub shares: Arc<ShareStore>,
 }
 
 impl AppState {
+    #[cfg(test)]
     pub async fn new() -> anyhow::Result<Arc<AppState>> {
+        Self::with_limits(Limits::default()).await
+    }
+
+    pub async fn with_limits(limits: Limits) -> anyhow::Result<Arc<AppState>> {
         let agent_manager = AgentManager::instance().await?;
         let tunnel_manager = Arc::new(TunnelManager::new());
 
@@ -30,6 +43,10 @@ impl AppState {
             session_counter: Arc::new(AtomicUsize::new(0)),
             recipe_session_tracker: Arc::new(Mutex::new(HashSet::new())),
             tunnel_manager,
+            session_events: Arc::new(SessionEventHub::new()),
+            webhooks: Arc::new(WebhookDispatcher::default()),
+            limits: Arc::new(limits),
+            shares: Arc::new(ShareStore::default()),
         }))
     }
 
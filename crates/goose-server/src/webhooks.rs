@@ -0,0 +1,640 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use goose::config::paths::Paths;
+use goose::session::Session;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+const WEBHOOKS_FILE: &str = "goosed_webhooks.json";
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Deliveries kept per webhook in the delivery log.
+const MAX_LOGGED_DELIVERIES: usize = 100;
+
+pub const SIGNATURE_HEADER: &str = "X-Goose-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Goose-Timestamp";
+pub const EVENT_HEADER: &str = "X-Goose-Event";
+pub const DELIVERY_HEADER: &str = "X-Goose-Delivery";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "session.created")]
+    SessionCreated,
+    #[serde(rename = "turn.completed")]
+    TurnCompleted,
+    #[serde(rename = "action.required")]
+    ActionRequired,
+    #[serde(rename = "session.compacted")]
+    SessionCompacted,
+    #[serde(rename = "schedule.run_finished")]
+    ScheduleRunFinished,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::SessionCreated => "session.created",
+            WebhookEvent::TurnCompleted => "turn.completed",
+            WebhookEvent::ActionRequired => "action.required",
+            WebhookEvent::SessionCompacted => "session.compacted",
+            WebhookEvent::ScheduleRunFinished => "schedule.run_finished",
+        }
+    }
+}
+
+/// An outgoing webhook. The signing secret is kept in the file but never returned by the API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Events delivered to the webhook, all of them when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    fn wants(&self, event: WebhookEvent) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+/// A webhook as returned by the API.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+    /// Whether deliveries are signed
+    pub has_secret: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Webhook> for WebhookInfo {
+    fn from(webhook: &Webhook) -> Self {
+        Self {
+            id: webhook.id.clone(),
+            url: webhook.url.clone(),
+            events: webhook.events.clone(),
+            enabled: webhook.enabled,
+            has_secret: webhook.secret.is_some(),
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// Changes to a webhook; fields left out keep their value. An empty `secret` removes it.
+#[derive(Debug, Default, Clone, Deserialize, utoipa::ToSchema)]
+pub struct WebhookChanges {
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub secret: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    pub created_at: DateTime<Utc>,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last attempt
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// When the webhooks file was last modified and its size, `None` when there is no file
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// File backed list of webhooks. Events reload the file whenever it changes, so changes apply
+/// right away.
+pub struct WebhookStore {
+    path: PathBuf,
+    /// The webhooks as of the file's modification time and size, so events only parse it again
+    /// after it changes
+    cache: Mutex<Option<(FileStamp, Arc<Vec<Webhook>>)>>,
+    /// Held across each read-modify-write of the file, so concurrent changes don't get lost
+    write_lock: Mutex<()>,
+}
+
+impl Default for WebhookStore {
+    fn default() -> Self {
+        Self::new(Paths::config_dir().join(WEBHOOKS_FILE))
+    }
+}
+
+impl WebhookStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cache: Mutex::new(None),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<Webhook>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Webhook>> {
+        Ok(self.list()?.into_iter().find(|webhook| webhook.id == id))
+    }
+
+    pub fn create(&self, changes: WebhookChanges) -> Result<Webhook> {
+        let Some(url) = changes.url else {
+            bail!("A webhook needs a url");
+        };
+        validate_url(&url)?;
+
+        let webhook = Webhook {
+            id: hex::encode(rand::random::<[u8; 6]>()),
+            url,
+            events: changes.events.unwrap_or_default(),
+            secret: changes.secret.filter(|secret| !secret.is_empty()),
+            enabled: changes.enabled.unwrap_or(true),
+            created_at: Utc::now(),
+        };
+        let _write = self.write_lock.lock().unwrap();
+        let mut webhooks = self.list()?;
+        webhooks.push(webhook.clone());
+        self.save(&webhooks)?;
+        Ok(webhook)
+    }
+
+    /// Applies the changes and returns the updated webhook, or `None` when there is no webhook
+    /// with that id.
+    pub fn update(&self, id: &str, changes: WebhookChanges) -> Result<Option<Webhook>> {
+        let _write = self.write_lock.lock().unwrap();
+        let mut webhooks = self.list()?;
+        let Some(webhook) = webhooks.iter_mut().find(|webhook| webhook.id == id) else {
+            return Ok(None);
+        };
+        if let Some(url) = changes.url {
+            validate_url(&url)?;
+            webhook.url = url;
+        }
+        if let Some(events) = changes.events {
+            webhook.events = events;
+        }
+        if let Some(secret) = changes.secret {
+            webhook.secret = Some(secret).filter(|secret| !secret.is_empty());
+        }
+        if let Some(enabled) = changes.enabled {
+            webhook.enabled = enabled;
+        }
+        let webhook = webhook.clone();
+        self.save(&webhooks)?;
+        Ok(Some(webhook))
+    }
+
+    /// Removes a webhook, returning whether it existed.
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let _write = self.write_lock.lock().unwrap();
+        let mut webhooks = self.list()?;
+        let before = webhooks.len();
+        webhooks.retain(|webhook| webhook.id != id);
+        if webhooks.len() == before {
+            return Ok(false);
+        }
+        self.save(&webhooks)?;
+        Ok(true)
+    }
+
+    fn cached(&self) -> Result<Arc<Vec<Webhook>>> {
+        let stamp = match std::fs::metadata(&self.path) {
+            Ok(metadata) => Some((metadata.modified()?, metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((cached_stamp, webhooks)) = cache.as_ref() {
+            if *cached_stamp == stamp {
+                return Ok(webhooks.clone());
+            }
+        }
+        let webhooks = Arc::new(self.list()?);
+        *cache = Some((stamp, webhooks.clone()));
+        Ok(webhooks)
+    }
+
+    fn save(&self, webhooks: &[Webhook]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(webhooks)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+pub fn validate_url(url: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).with_context(|| format!("Invalid url '{}'", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("Webhook urls must use http or https");
+    }
+    Ok(())
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `<timestamp>.<body>`, sent in
+/// `X-Goose-Signature`. The timestamp is the unix time sent in `X-Goose-Timestamp`, so receivers
+/// can reject old deliveries being replayed.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Sends lifecycle events to the configured webhooks. Deliveries run in the background and are
+/// retried with exponential backoff; the latest ones are kept per webhook for the delivery log.
+pub struct WebhookDispatcher {
+    store: WebhookStore,
+    client: reqwest::Client,
+    initial_backoff: Duration,
+    deliveries: Mutex<HashMap<String, VecDeque<WebhookDelivery>>>,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new(WebhookStore::default())
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn new(store: WebhookStore) -> Self {
+        Self {
+            store,
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            initial_backoff: INITIAL_BACKOFF,
+            deliveries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn store(&self) -> &WebhookStore {
+        &self.store
+    }
+
+    /// Delivers the event to every enabled webhook subscribed to it.
+    pub fn emit(self: &Arc<Self>, event: WebhookEvent, data: Value) {
+        let webhooks = match self.store.cached() {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::warn!("Failed to load webhooks: {}", e);
+                return;
+            }
+        };
+
+        let created_at = Utc::now();
+        for webhook in webhooks.iter().filter(|webhook| webhook.wants(event)) {
+            let delivery = WebhookDelivery {
+                id: uuid::Uuid::new_v4().to_string(),
+                webhook_id: webhook.id.clone(),
+                event,
+                created_at,
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                response_status: None,
+                error: None,
+            };
+            let body = serde_json::json!({
+                "id": delivery.id,
+                "event": event.as_str(),
+                "created_at": created_at,
+                "data": data,
+            });
+            self.record(&delivery);
+
+            let dispatcher = self.clone();
+            let webhook = webhook.clone();
+            tokio::spawn(async move { dispatcher.deliver(webhook, delivery, body).await });
+        }
+    }
+
+    pub fn session_created(self: &Arc<Self>, session: &Session) {
+        self.emit(
+            WebhookEvent::SessionCreated,
+            serde_json::json!({
+                "session_id": session.id,
+                "name": session.name,
+                "working_dir": session.working_dir,
+                "owner": session.owner,
+            }),
+        );
+    }
+
+    /// Latest deliveries of a webhook, newest first.
+    pub fn deliveries(&self, webhook_id: &str) -> Vec<WebhookDelivery> {
+        self.deliveries
+            .lock()
+            .unwrap()
+            .get(webhook_id)
+            .map(|deliveries| deliveries.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn forget(&self, webhook_id: &str) {
+        self.deliveries.lock().unwrap().remove(webhook_id);
+    }
+
+    /// Turns finished scheduler runs into `schedule.run_finished` events until the server stops.
+    pub fn forward_schedule_runs(self: &Arc<Self>) {
+        let mut runs = goose::scheduler_runs::subscribe_finished_runs();
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match runs.recv().await {
+                    Ok(run) => dispatcher.emit(
+                        WebhookEvent::ScheduleRunFinished,
+                        serde_json::json!({ "run": run }),
+                    ),
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Webhooks missed {} finished schedule runs", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn deliver(&self, webhook: Webhook, mut delivery: WebhookDelivery, body: Value) {
+        let body = serde_json::to_vec(&body).unwrap_or_default();
+        let mut backoff = self.initial_backoff;
+
+        loop {
+            delivery.attempts += 1;
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, delivery.event.as_str())
+                .header(DELIVERY_HEADER, &delivery.id)
+                .body(body.clone());
+            // Signed again on every attempt, so retries carry a fresh timestamp
+            if let Some(secret) = &webhook.secret {
+                let timestamp = Utc::now().timestamp();
+                request = request
+                    .header(TIMESTAMP_HEADER, timestamp)
+                    .header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+            }
+
+            let retry = match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    delivery.response_status = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.status = DeliveryStatus::Succeeded;
+                        delivery.error = None;
+                        self.record(&delivery);
+                        return;
+                    }
+                    delivery.error = Some(format!("Webhook answered {}", status));
+                    status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                }
+                Err(e) => {
+                    delivery.response_status = None;
+                    delivery.error = Some(e.to_string());
+                    true
+                }
+            };
+
+            if !retry || delivery.attempts >= MAX_ATTEMPTS {
+                tracing::warn!(
+                    "Giving up on {} delivery {} to {}: {}",
+                    delivery.event.as_str(),
+                    delivery.id,
+                    webhook.url,
+                    delivery.error.as_deref().unwrap_or("unknown error")
+                );
+                delivery.status = DeliveryStatus::Failed;
+                self.record(&delivery);
+                return;
+            }
+
+            self.record(&delivery);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    fn record(&self, delivery: &WebhookDelivery) {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let log = deliveries.entry(delivery.webhook_id.clone()).or_default();
+        match log.iter_mut().find(|logged| logged.id == delivery.id) {
+            Some(logged) => *logged = delivery.clone(),
+            None => {
+                log.push_back(delivery.clone());
+                while log.len() > MAX_LOGGED_DELIVERIES {
+                    log.pop_front();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use tempfile::TempDir;
+
+    fn store(dir: &TempDir) -> WebhookStore {
+        WebhookStore::new(dir.path().join(WEBHOOKS_FILE))
+    }
+
+    #[test]
+    fn test_sign_covers_the_timestamp() {
+        // HMAC-SHA256 of "1700000000.{}" keyed with "s3cret"
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(b"1700000000.{}");
+        assert_eq!(
+            sign("s3cret", 1_700_000_000, b"{}"),
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        );
+        assert_ne!(
+            sign("s3cret", 1_700_000_000, b"{}"),
+            sign("s3cret", 1_700_000_001, b"{}")
+        );
+    }
+
+    #[test]
+    fn test_store_crud() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        assert!(store.create(WebhookChanges::default()).is_err());
+        assert!(store
+            .create(WebhookChanges {
+                url: Some("ftp://example.com".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+
+        let webhook = store
+            .create(WebhookChanges {
+                url: Some("https://example.com/hook".to_string()),
+                events: Some(vec![WebhookEvent::TurnCompleted]),
+                secret: Some("s3cret".to_string()),
+                enabled: None,
+            })
+            .unwrap();
+        assert!(webhook.enabled);
+        assert!(webhook.wants(WebhookEvent::TurnCompleted));
+        assert!(!webhook.wants(WebhookEvent::SessionCreated));
+        assert!(WebhookInfo::from(&webhook).has_secret);
+
+        let updated = store
+            .update(
+                &webhook.id,
+                WebhookChanges {
+                    events: Some(vec![]),
+                    secret: Some(String::new()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+        assert!(updated.wants(WebhookEvent::SessionCreated));
+        assert!(updated.secret.is_none());
+        assert!(store
+            .update("missing", WebhookChanges::default())
+            .unwrap()
+            .is_none());
+
+        assert!(store.delete(&webhook.id).unwrap());
+        assert!(!store.delete(&webhook.id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_creates_are_all_kept() {
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(store(&dir));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    store
+                        .create(WebhookChanges {
+                            url: Some(format!("https://example.com/{}", i)),
+                            ..Default::default()
+                        })
+                        .unwrap()
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(store.list().unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed_and_retried() {
+        let received = Arc::new(Mutex::new(Vec::<(HeaderMap, Bytes)>::new()));
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: Bytes| async move {
+                    let mut received = received.lock().unwrap();
+                    received.push((headers, body));
+                    if received.len() == 1 {
+                        reqwest::StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        reqwest::StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let webhook = store
+            .create(WebhookChanges {
+                url: Some(format!("http://{}/hook", addr)),
+                secret: Some("s3cret".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut dispatcher = WebhookDispatcher::new(store);
+        dispatcher.initial_backoff = Duration::from_millis(10);
+        let dispatcher = Arc::new(dispatcher);
+
+        dispatcher.emit(
+            WebhookEvent::TurnCompleted,
+            serde_json::json!({"session_id": "abc"}),
+        );
+
+        let delivery = loop {
+            let deliveries = dispatcher.deliveries(&webhook.id);
+            if deliveries[0].status != DeliveryStatus::Pending {
+                break deliveries[0].clone();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(delivery.status, DeliveryStatus::Succeeded);
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.response_status, Some(200));
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[1];
+        assert_eq!(headers[EVENT_HEADER], "turn.completed");
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            headers[SIGNATURE_HEADER],
+            sign("s3cret", timestamp, body).as_str()
+        );
+        let payload: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["data"]["session_id"], "abc");
+        assert_eq!(payload["id"], delivery.id);
+    }
+}
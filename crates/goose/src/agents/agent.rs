@@ -98,6 +98,8 @@ pub struct Agent {
     pub(super) frontend_tools: Mutex<HashMap<String, FrontendTool>>,
     pub(super) frontend_instructions: Mutex<Option<String>>,
     pub(super) prompt_manager: Mutex<PromptManager>,
+    /// Per session permission mode; falls back to the configured GOOSE_MODE when unset
+    pub(super) goose_mode: Mutex<Option<GooseMode>>,
     pub(super) confirmation_tx: mpsc::Sender<(String, PermissionConfirmation)>,
     pub(super) confirmation_rx: Mutex<mpsc::Receiver<(String, PermissionConfirmation)>>,
     pub(super) tool_result_tx: mpsc::Sender<(String, ToolResult<Vec<Content>>)>,
@@ -173,6 +175,7 @@ impl Agent {
             frontend_tools: Mutex::new(HashMap::new()),
             frontend_instructions: Mutex::new(None),
             prompt_manager: Mutex::new(PromptManager::new()),
+            goose_mode: Mutex::new(None),
             confirmation_tx: confirm_tx,
             confirmation_rx: Mutex::new(confirm_rx),
             tool_result_tx: tool_tx,
@@ -277,7 +280,10 @@ impl Agent {
 
         let (tools, toolshim_tools, system_prompt) =
             self.prepare_tools_and_prompt(working_dir).await?;
-        let goose_mode = config.get_goose_mode().unwrap_or(GooseMode::Auto);
+        let goose_mode = match *self.goose_mode.lock().await {
+            Some(goose_mode) => goose_mode,
+            None => config.get_goose_mode().unwrap_or(GooseMode::Auto),
+        };
 
         self.tool_inspection_manager
             .update_permission_inspector_mode(goose_mode)
@@ -371,6 +377,10 @@ impl Agent {
         *scheduler_service = Some(scheduler);
     }
 
+    pub async fn set_goose_mode(&self, goose_mode: Option<GooseMode>) {
+        *self.goose_mode.lock().await = goose_mode;
+    }
+
     pub async fn disable_router_for_recipe(&self) {
         self.tool_route_manager.disable_router_for_recipe().await;
     }
@@ -474,6 +484,7 @@ impl Agent {
         }
 
         debug!("WAITING_TOOL_START: {}", tool_call.name);
+        let started = std::time::Instant::now();
         let result: ToolCallResult = if self
             .sub_recipe_manager
             .lock()
@@ -620,15 +631,15 @@ impl Agent {
 
         debug!("WAITING_TOOL_END: {}", tool_call.name);
 
+        let tool_name = tool_call.name.to_string();
         (
             request_id,
             Ok(ToolCallResult {
                 notification_stream: result.notification_stream,
-                result: Box::new(
-                    result
-                        .result
-                        .map(super::large_response_handler::process_tool_response),
-                ),
+                result: Box::new(result.result.map(move |result| {
+                    crate::metrics::record_tool_call(&tool_name, result.is_ok(), started.elapsed());
+                    super::large_response_handler::process_tool_response(result)
+                })),
             }),
         )
     }
@@ -896,7 +907,12 @@ impl Agent {
                     )
                 );
 
-                match compact_messages(self.provider().await?.as_ref(), &conversation_to_compact, is_manual_compact).await {
+                let compaction = compact_messages(self.provider().await?.as_ref(), &conversation_to_compact, is_manual_compact).await;
+                crate::metrics::record_compaction(
+                    if is_manual_compact { "manual" } else { "auto" },
+                    compaction.is_ok(),
+                );
+                match compaction {
                     Ok((compacted_conversation, summarization_usage)) => {
                         SessionManager::replace_conversation(&session_config.id, &compacted_conversation).await?;
                         Self::update_session_metrics(&session_config, &summarization_usage, true).await?;
@@ -1236,7 +1252,9 @@ impl Agent {
                                 )
                             );
 
-                            match compact_messages(self.provider().await?.as_ref(), &conversation, false).await {
+                            let compaction = compact_messages(self.provider().await?.as_ref(), &conversation, false).await;
+                            crate::metrics::record_compaction("context_limit", compaction.is_ok());
+                            match compaction {
                                 Ok((compacted_conversation, usage)) => {
                                     SessionManager::replace_conversation(&session_config.id, &compacted_conversation).await?;
                                     Self::update_session_metrics(&session_config, &usage, true).await?;
@@ -20,7 +20,7 @@ use std::time::Duration;
 use tempfile::{tempdir, TempDir};
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
 use tokio::task;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
@@ -94,6 +94,8 @@ impl Extension {
 /// Manages goose extensions / MCP clients and their interactions
 pub struct ExtensionManager {
     extensions: Mutex<HashMap<String, Extension>>,
+    /// Extensions that are known but not started yet; they start the first time a client is needed
+    deferred: Mutex<Vec<ExtensionConfig>>,
     context: Mutex<PlatformExtensionContext>,
     provider: SharedProvider,
 }
@@ -258,6 +260,7 @@ impl ExtensionManager {
     pub fn new(provider: SharedProvider) -> Self {
         Self {
             extensions: Mutex::new(HashMap::new()),
+            deferred: Mutex::new(Vec::new()),
             context: Mutex::new(PlatformExtensionContext {
                 session_id: None,
                 extension_manager: None,
@@ -281,14 +284,54 @@ impl ExtensionManager {
     }
 
     pub async fn supports_resources(&self) -> bool {
-        self.extensions
-            .lock()
+        self.running()
             .await
             .values()
             .any(|ext| ext.supports_resources())
     }
 
     pub async fn add_extension(&self, config: ExtensionConfig) -> ExtensionResult<()> {
+        let key = normalize(config.key());
+        self.deferred
+            .lock()
+            .await
+            .retain(|deferred| normalize(deferred.key()) != key);
+        self.start_extension(config).await
+    }
+
+    /// Registers extensions without starting them. They are started together the first time
+    /// anything needs an extension client, which keeps restoring an agent cheap.
+    pub async fn defer_extensions(&self, configs: Vec<ExtensionConfig>) {
+        self.deferred.lock().await.extend(configs);
+    }
+
+    async fn start_deferred_extensions(&self) {
+        // Held while starting so concurrent callers wait for the full set of extensions
+        let mut deferred = self.deferred.lock().await;
+        if deferred.is_empty() {
+            return;
+        }
+
+        let configs = std::mem::take(&mut *deferred);
+        let results = future::join_all(configs.into_iter().map(|config| async move {
+            let name = config.name();
+            (name, self.start_extension(config).await)
+        }))
+        .await;
+        for (name, result) in results {
+            if let Err(e) = result {
+                warn!("Failed to start extension {}: {}", name, e);
+            }
+        }
+    }
+
+    /// The running extensions, after starting any deferred ones.
+    async fn running(&self) -> MutexGuard<'_, HashMap<String, Extension>> {
+        self.start_deferred_extensions().await;
+        self.extensions.lock().await
+    }
+
+    async fn start_extension(&self, config: ExtensionConfig) -> ExtensionResult<()> {
         let config_name = config.key().to_string();
         let sanitized_name = normalize(config_name.clone());
         let mut temp_dir = None;
@@ -600,8 +643,7 @@ impl ExtensionManager {
 
     /// Get extensions info
     pub async fn get_extensions_info(&self) -> Vec<ExtensionInfo> {
-        self.extensions
-            .lock()
+        self.running()
             .await
             .iter()
             .map(|(name, ext)| {
@@ -617,12 +659,16 @@ impl ExtensionManager {
     /// Get aggregated usage statistics
     pub async fn remove_extension(&self, name: &str) -> ExtensionResult<()> {
         let sanitized_name = normalize(name.to_string());
+        self.deferred
+            .lock()
+            .await
+            .retain(|config| normalize(config.key()) != sanitized_name);
         self.extensions.lock().await.remove(&sanitized_name);
         Ok(())
     }
 
     pub async fn get_extension_and_tool_counts(&self) -> (usize, usize) {
-        let enabled_extensions_count = self.extensions.lock().await.len();
+        let enabled_extensions_count = self.running().await.len();
 
         let total_tools = self
             .get_prefixed_tools(None)
@@ -634,16 +680,27 @@ impl ExtensionManager {
     }
 
     pub async fn list_extensions(&self) -> ExtensionResult<Vec<String>> {
-        Ok(self.extensions.lock().await.keys().cloned().collect())
+        let mut names: Vec<String> = self.extensions.lock().await.keys().cloned().collect();
+        names.extend(
+            self.deferred
+                .lock()
+                .await
+                .iter()
+                .map(|config| normalize(config.key())),
+        );
+        Ok(names)
     }
 
     pub async fn get_extension_configs(&self) -> Vec<ExtensionConfig> {
-        self.extensions
+        let mut configs: Vec<ExtensionConfig> = self
+            .extensions
             .lock()
             .await
             .values()
             .map(|ext| ext.config.clone())
-            .collect()
+            .collect();
+        configs.extend(self.deferred.lock().await.iter().cloned());
+        configs
     }
 
     /// Get all tools from all clients with proper prefixing
@@ -653,8 +710,7 @@ impl ExtensionManager {
     ) -> ExtensionResult<Vec<Tool>> {
         // Filter clients based on the provided extension_name or include all if None
         let filtered_clients: Vec<_> = self
-            .extensions
-            .lock()
+            .running()
             .await
             .iter()
             .filter(|(name, _ext)| {
@@ -733,8 +789,7 @@ impl ExtensionManager {
 
     /// Find and return a reference to the appropriate client for a tool call
     async fn get_client_for_tool(&self, prefixed_name: &str) -> Option<(String, McpClientBox)> {
-        self.extensions
-            .lock()
+        self.running()
             .await
             .iter()
             .find(|(key, _)| prefixed_name.starts_with(*key))
@@ -769,7 +824,7 @@ impl ExtensionManager {
         // currently it will return the first match and skip any others
 
         // Collect extension names first to avoid holding the lock during iteration
-        let extension_names: Vec<String> = self.extensions.lock().await.keys().cloned().collect();
+        let extension_names: Vec<String> = self.running().await.keys().cloned().collect();
 
         for extension_name in extension_names {
             let result = self
@@ -783,8 +838,7 @@ impl ExtensionManager {
 
         // None of the extensions had the resource so we raise an error
         let available_extensions = self
-            .extensions
-            .lock()
+            .running()
             .await
             .keys()
             .map(|s| s.as_str())
@@ -809,8 +863,7 @@ impl ExtensionManager {
         cancellation_token: CancellationToken,
     ) -> Result<Vec<Content>, ErrorData> {
         let available_extensions = self
-            .extensions
-            .lock()
+            .running()
             .await
             .keys()
             .map(|s| s.as_str())
@@ -907,8 +960,7 @@ impl ExtensionManager {
                 let mut futures = FuturesUnordered::new();
 
                 // Create futures for each resource_capable_extension
-                self.extensions
-                    .lock()
+                self.running()
                     .await
                     .iter()
                     .filter(|(_name, ext)| ext.supports_resources())
@@ -975,7 +1027,7 @@ impl ExtensionManager {
             })?
             .to_string();
 
-        if let Some(extension) = self.extensions.lock().await.get(&client_name) {
+        if let Some(extension) = self.running().await.get(&client_name) {
             if !extension.config.is_tool_available(&tool_name) {
                 return Err(ErrorData::new(
                     ErrorCode::RESOURCE_NOT_FOUND,
@@ -1049,7 +1101,7 @@ impl ExtensionManager {
     ) -> Result<HashMap<String, Vec<Prompt>>, ErrorData> {
         let mut futures = FuturesUnordered::new();
 
-        let names: Vec<_> = self.extensions.lock().await.keys().cloned().collect();
+        let names: Vec<_> = self.running().await.keys().cloned().collect();
         for extension_name in names {
             let token = cancellation_token.clone();
             futures.push(async move {
@@ -1142,8 +1194,7 @@ impl ExtensionManager {
         }
 
         // Get currently enabled extensions that can be disabled
-        let enabled_extensions: Vec<String> =
-            self.extensions.lock().await.keys().cloned().collect();
+        let enabled_extensions: Vec<String> = self.list_extensions().await.unwrap_or_default();
 
         // Build output string
         if !disabled_extensions.is_empty() {
@@ -1172,8 +1223,7 @@ impl ExtensionManager {
     }
 
     async fn get_server_client(&self, name: impl Into<String>) -> Option<McpClientBox> {
-        self.extensions
-            .lock()
+        self.running()
             .await
             .get(&name.into())
             .map(|ext| ext.get_client())
@@ -1183,7 +1233,7 @@ impl ExtensionManager {
         let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let mut content = format!("<info-msg>\nDatetime: {}\n", timestamp);
 
-        let extensions = self.extensions.lock().await;
+        let extensions = self.running().await;
         for (name, extension) in extensions.iter() {
             if let ExtensionConfig::Platform { .. } = &extension.config {
                 let client = extension.get_client();
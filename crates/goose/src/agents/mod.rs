@@ -17,6 +17,7 @@ mod router_tool_selector;
 mod router_tools;
 mod schedule_tool;
 pub(crate) mod skills_extension;
+pub mod snapshot;
 pub mod sub_recipe_manager;
 pub mod subagent_execution_tool;
 pub mod subagent_handler;
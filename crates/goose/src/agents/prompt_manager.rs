@@ -218,9 +218,16 @@ impl PromptManager {
         }
     }
 
-    /// Add an additional instruction to the system prompt
+    /// Add an additional instruction to the system prompt. Instructions that are already
+    /// present are skipped, so re-applying a recipe to a restored agent doesn't repeat them.
     pub fn add_system_prompt_extra(&mut self, instruction: String) {
-        self.system_prompt_extras.push(instruction);
+        if !self.system_prompt_extras.contains(&instruction) {
+            self.system_prompt_extras.push(instruction);
+        }
+    }
+
+    pub fn system_prompt_extras(&self) -> &[String] {
+        &self.system_prompt_extras
     }
 
     /// Override the system prompt with custom text
@@ -228,6 +235,10 @@ impl PromptManager {
         self.system_prompt_override = Some(template);
     }
 
+    pub fn system_prompt_override(&self) -> Option<&str> {
+        self.system_prompt_override.as_deref()
+    }
+
     pub fn builder<'a>(&'a self, model_name: &str) -> SystemPromptBuilder<'a, Self> {
         SystemPromptBuilder {
             model_name: model_name.to_string(),
@@ -301,6 +312,19 @@ mod tests {
         assert!(result.contains("Thirdinstruction"));
     }
 
+    #[test]
+    fn test_duplicate_extras_are_added_once() {
+        let mut manager = PromptManager::new();
+        manager.add_system_prompt_extra("Use the recipe".to_string());
+        manager.add_system_prompt_extra("Use the recipe".to_string());
+        manager.add_system_prompt_extra("Be brief".to_string());
+
+        assert_eq!(
+            manager.system_prompt_extras(),
+            &["Use the recipe".to_string(), "Be brief".to_string()]
+        );
+    }
+
     #[test]
     fn test_build_system_prompt_preserves_legitimate_unicode_in_extras() {
         let mut manager = PromptManager::new();
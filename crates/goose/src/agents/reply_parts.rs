@@ -202,6 +202,9 @@ impl Agent {
         let toolshim_tools = toolshim_tools.to_owned();
         let provider = provider.clone();
 
+        let provider_name = provider.get_name().to_string();
+        let started = std::time::Instant::now();
+
         // Capture errors during stream creation and return them as part of the stream
         // so they can be handled by the existing error handling logic in the agent
         let stream_result = if provider.supports_streaming() {
@@ -236,6 +239,12 @@ impl Agent {
         let mut stream = match stream_result {
             Ok(s) => s,
             Err(e) => {
+                crate::metrics::record_provider_request(
+                    &provider_name,
+                    &config.model_name,
+                    started.elapsed(),
+                    Some(&e),
+                );
                 // Return a stream that immediately yields the error
                 // This allows the error to be caught by existing error handling in agent.rs
                 return Ok(Box::pin(try_stream! {
@@ -245,10 +254,19 @@ impl Agent {
         };
 
         Ok(Box::pin(try_stream! {
-            while let Some(Ok((mut message, usage))) = stream.next().await {
+            let mut error = None;
+            while let Some(item) = stream.next().await {
+                let (mut message, usage) = match item {
+                    Ok(item) => item,
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                };
                 // Store the model information in the global store
                 if let Some(usage) = usage.as_ref() {
                     crate::providers::base::set_current_model(&usage.model);
+                    crate::metrics::record_token_usage(&provider_name, &config.model_name, &usage.usage);
                 }
 
                 // Post-process / structure the response only if tool interpretation is enabled
@@ -258,6 +276,12 @@ impl Agent {
 
                 yield (message, usage);
             }
+            crate::metrics::record_provider_request(
+                &provider_name,
+                &config.model_name,
+                started.elapsed(),
+                error.as_ref(),
+            );
         }))
     }
 
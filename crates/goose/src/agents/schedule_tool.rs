@@ -106,6 +106,11 @@ impl Agent {
                 )
             })?;
 
+        let timezone = arguments
+            .get("timezone")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
         // Get the execution_mode parameter, defaulting to "background" if not provided
         let execution_mode = arguments
             .get("execution_mode")
@@ -157,11 +162,17 @@ impl Agent {
             id: job_id.clone(),
             source: recipe_path.to_string(),
             cron: cron_expression.to_string(),
+            trigger: crate::scheduler_trigger::ScheduleTrigger::Cron,
+            timezone,
             last_run: None,
             currently_running: false,
             paused: false,
             current_session_id: None,
             process_start_time: None,
+            retry: Default::default(),
+            concurrency: Default::default(),
+            catch_up: Default::default(),
+            notifications: Vec::new(),
         };
 
         match scheduler.add_scheduled_job(job, true).await {
@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tracing::warn;
+
+use crate::agents::extension::{Envs, ExtensionConfig};
+use crate::agents::final_output_tool::FinalOutputTool;
+use crate::agents::types::FrontendTool;
+use crate::agents::Agent;
+use crate::config::extensions::get_extension_by_name;
+use crate::config::GooseMode;
+use crate::model::ModelConfig;
+use crate::providers::create;
+use crate::recipe::{Recipe, Response, SubRecipe};
+use crate::session::SessionManager;
+
+/// Everything needed to rebuild a session's agent after goosed restarts or the agent is
+/// evicted from the agent pool. Todo state is not part of it since it already lives in the
+/// session's extension data, and neither are the values of extension `envs`, which can hold
+/// secrets; only their names are kept, and the values are looked up again on restore.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentSnapshot {
+    #[serde(default)]
+    pub provider_name: Option<String>,
+    #[serde(default)]
+    pub model_config: Option<ModelConfig>,
+    #[serde(default)]
+    pub extensions: Vec<ExtensionConfig>,
+    /// Names of the `envs` of each extension, by extension name
+    #[serde(default)]
+    pub extension_env_keys: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub frontend_tools: Vec<Tool>,
+    #[serde(default)]
+    pub frontend_instructions: Option<String>,
+    #[serde(default)]
+    pub sub_recipes: Vec<SubRecipe>,
+    #[serde(default)]
+    pub final_output: Option<Response>,
+    #[serde(default)]
+    pub system_prompt_extras: Vec<String>,
+    #[serde(default)]
+    pub system_prompt_override: Option<String>,
+    #[serde(default)]
+    pub goose_mode: Option<GooseMode>,
+}
+
+static SNAPSHOT_LOCKS: Lazy<std::sync::Mutex<HashMap<String, Weak<Mutex<()>>>>> =
+    Lazy::new(Default::default);
+
+/// Held while a session's snapshot is written or read, so a slow write can't land after a
+/// newer one and a read can't miss a write in progress. Other sessions aren't held up.
+pub struct SnapshotLock {
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl SnapshotLock {
+    pub async fn acquire(session_id: &str) -> Self {
+        let lock = {
+            let mut locks = SNAPSHOT_LOCKS.lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(session_id).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(Mutex::new(()));
+                    locks.insert(session_id.to_string(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        Self {
+            _guard: lock.lock_owned().await,
+        }
+    }
+}
+
+fn envs_mut(config: &mut ExtensionConfig) -> Option<&mut Envs> {
+    match config {
+        ExtensionConfig::Sse { envs, .. }
+        | ExtensionConfig::Stdio { envs, .. }
+        | ExtensionConfig::StreamableHttp { envs, .. } => Some(envs),
+        _ => None,
+    }
+}
+
+fn without_envs(mut config: ExtensionConfig) -> ExtensionConfig {
+    if let Some(envs) = envs_mut(&mut config) {
+        *envs = Envs::default();
+    }
+    config
+}
+
+fn env_values(mut config: Option<ExtensionConfig>) -> HashMap<String, String> {
+    config
+        .as_mut()
+        .and_then(envs_mut)
+        .map(|envs| envs.get_env())
+        .unwrap_or_default()
+}
+
+/// Puts back the env values left out of a snapshot, from the extension of the same name in
+/// the session's recipe, or else in the user's config. Fails if any value can't be found
+/// rather than start the extension without it.
+fn with_resolved_envs(
+    extensions: Vec<ExtensionConfig>,
+    env_keys: &HashMap<String, Vec<String>>,
+    recipe: Option<&Recipe>,
+) -> Result<Vec<ExtensionConfig>> {
+    extensions
+        .into_iter()
+        .map(|mut config| {
+            let name = config.name();
+            let mut values = env_values(get_extension_by_name(&name));
+            values.extend(env_values(
+                recipe
+                    .and_then(|recipe| recipe.extensions.as_ref())
+                    .and_then(|extensions| extensions.iter().find(|e| e.name() == name))
+                    .cloned(),
+            ));
+            let keys = env_keys.get(&name).map(Vec::as_slice).unwrap_or_default();
+            if let Some(missing) = keys.iter().find(|key| !values.contains_key(*key)) {
+                bail!("No value for {} of extension {}", missing, name);
+            }
+            if let Some(envs) = envs_mut(&mut config) {
+                *envs = Envs::new(values);
+            }
+            Ok(config)
+        })
+        .collect()
+}
+
+impl Agent {
+    pub async fn snapshot(&self) -> AgentSnapshot {
+        let (provider_name, model_config) = match self.provider().await {
+            Ok(provider) => (
+                Some(provider.get_name().to_string()),
+                Some(provider.get_model_config()),
+            ),
+            Err(_) => (None, None),
+        };
+
+        let mut frontend_tools: Vec<Tool> = self
+            .frontend_tools
+            .lock()
+            .await
+            .values()
+            .map(|frontend_tool| frontend_tool.tool.clone())
+            .collect();
+        frontend_tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut sub_recipes: Vec<SubRecipe> = self
+            .sub_recipe_manager
+            .lock()
+            .await
+            .sub_recipes
+            .values()
+            .cloned()
+            .collect();
+        sub_recipes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let (system_prompt_extras, system_prompt_override) = {
+            let prompt_manager = self.prompt_manager.lock().await;
+            (
+                prompt_manager.system_prompt_extras().to_vec(),
+                prompt_manager.system_prompt_override().map(str::to_string),
+            )
+        };
+
+        let mut extension_env_keys = HashMap::new();
+        let mut extensions = self.extension_manager.get_extension_configs().await;
+        for config in &mut extensions {
+            let name = config.name();
+            if let Some(envs) = envs_mut(config) {
+                let mut keys: Vec<String> = envs.get_env().into_keys().collect();
+                if !keys.is_empty() {
+                    keys.sort();
+                    extension_env_keys.insert(name, keys);
+                }
+            }
+        }
+
+        AgentSnapshot {
+            provider_name,
+            model_config,
+            extensions: extensions.into_iter().map(without_envs).collect(),
+            extension_env_keys,
+            frontend_tools,
+            frontend_instructions: self.frontend_instructions.lock().await.clone(),
+            sub_recipes,
+            final_output: self
+                .final_output_tool
+                .lock()
+                .await
+                .as_ref()
+                .map(|tool| tool.response.clone()),
+            system_prompt_extras,
+            system_prompt_override,
+            goose_mode: *self.goose_mode.lock().await,
+        }
+    }
+
+    /// Persists the agent's current state to the session so it can be restored later.
+    pub async fn save_snapshot(&self, session_id: &str) -> Result<()> {
+        let _lock = SnapshotLock::acquire(session_id).await;
+        let snapshot = self.snapshot().await;
+        SessionManager::save_agent_snapshot(session_id, &snapshot).await
+    }
+
+    /// Rebuilds the agent from a snapshot. Extensions are registered but only started when
+    /// first needed, so resuming a session doesn't wait on extension processes.
+    ///
+    /// Fails without changing the agent when the env values of an extension can't be found
+    /// again, so the caller can start over with a fresh agent.
+    pub async fn restore_snapshot(&self, snapshot: AgentSnapshot, session_id: &str) -> Result<()> {
+        let recipe = SessionManager::get_session(session_id, false)
+            .await
+            .ok()
+            .and_then(|session| session.recipe);
+        let extensions = with_resolved_envs(
+            snapshot.extensions,
+            &snapshot.extension_env_keys,
+            recipe.as_ref(),
+        )?;
+
+        if let (Some(provider_name), Some(model_config)) =
+            (snapshot.provider_name, snapshot.model_config)
+        {
+            match create(&provider_name, model_config).await {
+                Ok(provider) => {
+                    if let Err(e) = self.update_provider(provider, session_id).await {
+                        warn!("Failed to restore provider for {}: {}", session_id, e);
+                    }
+                }
+                Err(e) => warn!(
+                    "Failed to create provider {} for {}: {}",
+                    provider_name, session_id, e
+                ),
+            }
+        }
+
+        self.extension_manager.defer_extensions(extensions).await;
+
+        if !snapshot.frontend_tools.is_empty() {
+            let frontend_tools: HashMap<String, FrontendTool> = snapshot
+                .frontend_tools
+                .into_iter()
+                .map(|tool| {
+                    let name = tool.name.to_string();
+                    (name.clone(), FrontendTool { name, tool })
+                })
+                .collect();
+            *self.frontend_tools.lock().await = frontend_tools;
+            *self.frontend_instructions.lock().await = snapshot.frontend_instructions;
+        }
+
+        if !snapshot.sub_recipes.is_empty() {
+            self.add_sub_recipes(snapshot.sub_recipes).await;
+        }
+
+        // The final output prompt is already among the restored extras
+        if let Some(response) = snapshot.final_output {
+            *self.final_output_tool.lock().await = Some(FinalOutputTool::new(response));
+        }
+
+        {
+            let mut prompt_manager = self.prompt_manager.lock().await;
+            for extra in snapshot.system_prompt_extras {
+                prompt_manager.add_system_prompt_extra(extra);
+            }
+            if let Some(system_prompt_override) = snapshot.system_prompt_override {
+                prompt_manager.set_system_prompt_override(system_prompt_override);
+            }
+        }
+
+        self.set_goose_mode(snapshot.goose_mode).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+    use std::sync::Arc;
+
+    fn frontend_extension() -> ExtensionConfig {
+        ExtensionConfig::Frontend {
+            name: "frontend".to_string(),
+            description: "frontend tools".to_string(),
+            tools: vec![Tool::new(
+                "open_tab".to_string(),
+                "Open a browser tab".to_string(),
+                Arc::new(object!({"type": "object"})),
+            )],
+            instructions: Some("Use the browser".to_string()),
+            bundled: None,
+            available_tools: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip_restores_recipe_state() {
+        let agent = Agent::new();
+        agent.add_extension(frontend_extension()).await.unwrap();
+        agent
+            .extend_system_prompt("Follow the recipe".to_string())
+            .await;
+        agent
+            .add_final_output_tool(Response {
+                json_schema: Some(serde_json::json!({"type": "object"})),
+            })
+            .await;
+        agent.set_goose_mode(Some(GooseMode::Approve)).await;
+
+        let snapshot = agent.snapshot().await;
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: AgentSnapshot = serde_json::from_str(&json).unwrap();
+
+        let restored = Agent::new();
+        restored
+            .restore_snapshot(snapshot, "not-a-session")
+            .await
+            .unwrap();
+
+        assert!(restored.is_frontend_tool("open_tab").await);
+        assert_eq!(
+            restored.frontend_instructions.lock().await.as_deref(),
+            Some("Use the browser")
+        );
+        assert!(restored.final_output_tool.lock().await.is_some());
+        assert_eq!(*restored.goose_mode.lock().await, Some(GooseMode::Approve));
+        assert_eq!(
+            restored.prompt_manager.lock().await.system_prompt_extras(),
+            agent.prompt_manager.lock().await.system_prompt_extras()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restored_extensions_are_deferred() {
+        let agent = Agent::new();
+        let extension = ExtensionConfig::Builtin {
+            name: "developer".to_string(),
+            display_name: None,
+            description: "developer".to_string(),
+            timeout: None,
+            bundled: None,
+            available_tools: vec![],
+        };
+        agent
+            .restore_snapshot(
+                AgentSnapshot {
+                    extensions: vec![extension],
+                    ..Default::default()
+                },
+                "not-a-session",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(agent.list_extensions().await, vec!["developer".to_string()]);
+        assert_eq!(agent.snapshot().await.extensions.len(), 1);
+
+        agent.remove_extension("developer").await.unwrap();
+        assert!(agent.list_extensions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_leaves_out_env_values() {
+        let agent = Agent::new();
+        let extension = ExtensionConfig::Stdio {
+            name: "not-configured-anywhere".to_string(),
+            description: "needs a token".to_string(),
+            cmd: "server".to_string(),
+            args: vec![],
+            envs: Envs::new(HashMap::from([(
+                "API_TOKEN".to_string(),
+                "hunter2".to_string(),
+            )])),
+            env_keys: vec!["OTHER_TOKEN".to_string()],
+            timeout: None,
+            bundled: None,
+            available_tools: vec![],
+        };
+        agent
+            .extension_manager
+            .defer_extensions(vec![extension.clone()])
+            .await;
+
+        let snapshot = agent.snapshot().await;
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(json.contains("OTHER_TOKEN"));
+        assert_eq!(
+            snapshot.extension_env_keys["not-configured-anywhere"],
+            vec!["API_TOKEN".to_string()]
+        );
+
+        // The value can't be found again, so the restore fails and leaves the agent alone
+        let restored = Agent::new();
+        assert!(restored
+            .restore_snapshot(snapshot.clone(), "not-a-session")
+            .await
+            .is_err());
+        assert!(restored.list_extensions().await.is_empty());
+
+        // A recipe that carried the value brings it back
+        let recipe = Recipe::builder()
+            .title("needs a token")
+            .description("test")
+            .instructions("test")
+            .extensions(vec![extension.clone()])
+            .build()
+            .unwrap();
+        let extensions = with_resolved_envs(
+            snapshot.extensions,
+            &snapshot.extension_env_keys,
+            Some(&recipe),
+        )
+        .unwrap();
+        assert_eq!(
+            env_values(extensions.into_iter().next())["API_TOKEN"],
+            "hunter2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_locks_are_per_session() {
+        let held = SnapshotLock::acquire("s1").await;
+        // Another session isn't held up, the same one is
+        let _other = SnapshotLock::acquire("s2").await;
+        let same = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            SnapshotLock::acquire("s1"),
+        )
+        .await;
+        assert!(same.is_err());
+        drop(held);
+        let _same = SnapshotLock::acquire("s1").await;
+    }
+}
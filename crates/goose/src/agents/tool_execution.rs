@@ -85,6 +85,7 @@ impl Agent {
                 let mut rx = self.confirmation_rx.lock().await;
                 while let Some((req_id, confirmation)) = rx.recv().await {
                     if req_id == request.id {
+                        crate::metrics::record_permission_prompt(&confirmation.permission);
                         // Log user decision if this was a security alert
                         if let Some(finding_id) = get_security_finding_id_from_results(&request.id, inspection_results) {
                             tracing::info!(
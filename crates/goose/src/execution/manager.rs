@@ -1,15 +1,18 @@
 use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::snapshot::SnapshotLock;
 use crate::agents::Agent;
 use crate::config::paths::Paths;
 use crate::config::Config;
 use crate::scheduler::Scheduler;
 use crate::scheduler_trait::SchedulerTrait;
+use crate::session::SessionManager;
 use anyhow::Result;
 use lru::LruCache;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use tokio::sync::{OnceCell, RwLock};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 const DEFAULT_MAX_SESSION: usize = 100;
 
@@ -17,6 +20,9 @@ static AGENT_MANAGER: OnceCell<Arc<AgentManager>> = OnceCell::const_new();
 
 pub struct AgentManager {
     sessions: Arc<RwLock<LruCache<String, Arc<Agent>>>>,
+    /// Agents evicted from the pool whose snapshot is still being saved. They are taken back
+    /// as they are if their session comes back meanwhile, rather than loading a stale snapshot.
+    evicting: std::sync::Mutex<HashMap<String, Arc<Agent>>>,
     scheduler: Arc<dyn SchedulerTrait>,
     default_provider: Arc<RwLock<Option<Arc<dyn crate::providers::base::Provider>>>>,
 }
@@ -43,6 +49,7 @@ impl AgentManager {
 
         let manager = Self {
             sessions: Arc::new(RwLock::new(LruCache::new(capacity))),
+            evicting: std::sync::Mutex::new(HashMap::new()),
             scheduler,
             default_provider: Arc::new(RwLock::new(None)),
         };
@@ -80,29 +87,83 @@ impl AgentManager {
             }
         }
 
+        let revived = self.evicting.lock().unwrap().get(&session_id).cloned();
+        let agent = match revived {
+            Some(agent) => agent,
+            None => self.create_agent(&session_id).await?,
+        };
+
+        let evicted = {
+            let mut sessions = self.sessions.write().await;
+            if let Some(existing) = sessions.get(&session_id) {
+                return Ok(Arc::clone(existing));
+            }
+            let evicted = sessions.push(session_id.clone(), agent.clone());
+            crate::metrics::set_active_agents(sessions.len());
+            // Recorded before the pool lets go, so a lookup never misses the agent in both
+            if let Some((evicted_id, evicted)) = &evicted {
+                self.evicting
+                    .lock()
+                    .unwrap()
+                    .insert(evicted_id.clone(), Arc::clone(evicted));
+            }
+            evicted
+        };
+        // Keep the evicted agent's latest state so it comes back warm on next access
+        if let Some((evicted_id, evicted)) = evicted {
+            if let Err(e) = evicted.save_snapshot(&evicted_id).await {
+                warn!(
+                    "Failed to save snapshot for evicted session {}: {}",
+                    evicted_id, e
+                );
+            }
+            let mut evicting = self.evicting.lock().unwrap();
+            if evicting
+                .get(&evicted_id)
+                .is_some_and(|agent| Arc::ptr_eq(agent, &evicted))
+            {
+                evicting.remove(&evicted_id);
+            }
+        }
+        Ok(agent)
+    }
+
+    async fn create_agent(&self, session_id: &str) -> Result<Arc<Agent>> {
         let agent = Arc::new(Agent::new());
         agent.set_scheduler(Arc::clone(&self.scheduler)).await;
         agent
             .extension_manager
             .set_context(PlatformExtensionContext {
-                session_id: Some(session_id.clone()),
+                session_id: Some(session_id.to_string()),
                 extension_manager: Some(Arc::downgrade(&agent.extension_manager)),
                 tool_route_manager: Some(Arc::downgrade(&agent.tool_route_manager)),
             })
             .await;
         if let Some(provider) = &*self.default_provider.read().await {
             agent
-                .update_provider(Arc::clone(provider), &session_id)
+                .update_provider(Arc::clone(provider), session_id)
                 .await?;
         }
 
-        let mut sessions = self.sessions.write().await;
-        if let Some(existing) = sessions.get(&session_id) {
-            Ok(Arc::clone(existing))
-        } else {
-            sessions.put(session_id, agent.clone());
-            Ok(agent)
+        // Waits for a snapshot save of this session that is in progress
+        let snapshot = {
+            let _lock = SnapshotLock::acquire(session_id).await;
+            SessionManager::get_agent_snapshot(session_id).await
+        };
+        match snapshot {
+            Ok(Some(snapshot)) => {
+                debug!("Restoring agent for session {} from snapshot", session_id);
+                if let Err(e) = agent.restore_snapshot(snapshot, session_id).await {
+                    warn!(
+                        "Could not restore agent for {}, starting a fresh one: {}",
+                        session_id, e
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load agent snapshot for {}: {}", session_id, e),
         }
+        Ok(agent)
     }
 
     pub async fn remove_session(&self, session_id: &str) -> Result<()> {
@@ -110,10 +171,27 @@ impl AgentManager {
         sessions
             .pop(session_id)
             .ok_or_else(|| anyhow::anyhow!("Session {} not found", session_id))?;
+        crate::metrics::set_active_agents(sessions.len());
         info!("Removed session {}", session_id);
         Ok(())
     }
 
+    /// Saves a snapshot of every pooled agent, e.g. before shutting down.
+    pub async fn save_snapshots(&self) {
+        let agents: Vec<_> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .map(|(session_id, agent)| (session_id.clone(), Arc::clone(agent)))
+            .collect();
+        for (session_id, agent) in agents {
+            if let Err(e) = agent.save_snapshot(&session_id).await {
+                warn!("Failed to save snapshot for session {}: {}", session_id, e);
+            }
+        }
+    }
+
     pub async fn has_session(&self, session_id: &str) -> bool {
         self.sessions.read().await.contains(session_id)
     }
@@ -128,6 +206,7 @@ mod tests {
     use serial_test::serial;
     use std::sync::Arc;
 
+    use crate::agents::snapshot::SnapshotLock;
     use crate::execution::{manager::AgentManager, SessionExecutionMode};
 
     #[test]
@@ -336,6 +415,42 @@ mod tests {
         assert!(manager.has_session(&session101).await);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_saving_an_evicted_agent_does_not_block_other_sessions() {
+        let manager = Arc::new(AgentManager::new(Some(2)).await.unwrap());
+        let first = uuid::Uuid::new_v4().to_string();
+        let second = uuid::Uuid::new_v4().to_string();
+        let third = uuid::Uuid::new_v4().to_string();
+        let first_agent = manager.get_or_create_agent(first.clone()).await.unwrap();
+        manager.get_or_create_agent(second.clone()).await.unwrap();
+
+        // A snapshot of the first session is in progress, so saving it on eviction waits
+        let held = SnapshotLock::acquire(&first).await;
+        let evicting = tokio::spawn({
+            let manager = Arc::clone(&manager);
+            let third = third.clone();
+            async move { manager.get_or_create_agent(third).await }
+        });
+        while !manager.has_session(&third).await {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let lookup = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            manager.get_or_create_agent(second.clone()),
+        )
+        .await;
+        assert!(lookup.is_ok());
+
+        // The evicted agent comes back as it is while its snapshot is still being saved
+        let revived = manager.get_or_create_agent(first.clone()).await.unwrap();
+        assert!(Arc::ptr_eq(&first_agent, &revived));
+
+        drop(held);
+        evicting.await.unwrap().unwrap();
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_remove_nonexistent_session_error() {
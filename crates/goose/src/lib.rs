@@ -7,6 +7,7 @@ pub mod execution;
 pub mod hints;
 pub mod logging;
 pub mod mcp_utils;
+pub mod metrics;
 pub mod model;
 pub mod oauth;
 pub mod permission;
@@ -16,7 +17,11 @@ pub mod providers;
 pub mod recipe;
 pub mod recipe_deeplink;
 pub mod scheduler;
+pub mod scheduler_expression;
+pub mod scheduler_runs;
 pub mod scheduler_trait;
+pub mod scheduler_trigger;
+pub mod scheduler_workers;
 pub mod security;
 pub mod session;
 pub mod session_context;
@@ -0,0 +1,338 @@
+//! Process wide Prometheus metrics. goosed exposes them on `/metrics`; everything else only
+//! records into them.
+
+use crate::permission::Permission;
+use crate::providers::base::Usage;
+use crate::providers::errors::ProviderError;
+use crate::scheduler_runs::JobRunStatus;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Distinct values kept per label before further values are reported as `other`, so a
+/// misbehaving provider or extension can't blow up the number of series.
+const MAX_LABEL_VALUES: usize = 50;
+const OVERFLOW_LABEL: &str = "other";
+
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+const RUN_BUCKETS: &[f64] = &[1.0, 10.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0];
+
+struct Metrics {
+    registry: Registry,
+    labels: Mutex<HashMap<&'static str, HashSet<String>>>,
+    provider_requests: IntCounterVec,
+    provider_request_duration: HistogramVec,
+    provider_tokens: IntCounterVec,
+    tool_calls: IntCounterVec,
+    tool_call_duration: HistogramVec,
+    permission_prompts: IntCounterVec,
+    compactions: IntCounterVec,
+    active_agents: IntGauge,
+    scheduled_runs: IntCounterVec,
+    scheduled_run_duration: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("goose".to_string()), None)?;
+
+        let provider_requests = IntCounterVec::new(
+            Opts::new(
+                "provider_requests_total",
+                "LLM requests by provider, model and outcome",
+            ),
+            &["provider", "model", "outcome"],
+        )?;
+        let provider_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "provider_request_duration_seconds",
+                "Time until an LLM response completed",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+            &["provider", "model"],
+        )?;
+        let provider_tokens = IntCounterVec::new(
+            Opts::new(
+                "provider_tokens_total",
+                "Tokens sent to and received from LLMs",
+            ),
+            &["provider", "model", "direction"],
+        )?;
+        let tool_calls = IntCounterVec::new(
+            Opts::new(
+                "tool_calls_total",
+                "Tool calls by extension, tool and outcome",
+            ),
+            &["extension", "tool", "outcome"],
+        )?;
+        let tool_call_duration = HistogramVec::new(
+            HistogramOpts::new("tool_call_duration_seconds", "Time taken by tool calls")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["extension", "tool"],
+        )?;
+        let permission_prompts = IntCounterVec::new(
+            Opts::new(
+                "permission_prompts_total",
+                "Tool permission prompts by the user's decision",
+            ),
+            &["decision"],
+        )?;
+        let compactions = IntCounterVec::new(
+            Opts::new("compactions_total", "Conversation compactions"),
+            &["trigger", "outcome"],
+        )?;
+        let active_agents = IntGauge::new("active_agents", "Agents held by the agent manager")?;
+        let scheduled_runs = IntCounterVec::new(
+            Opts::new("scheduled_runs_total", "Scheduled job runs by final status"),
+            &["status"],
+        )?;
+        let scheduled_run_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "scheduled_run_duration_seconds",
+                "Time taken by scheduled job runs",
+            )
+            .buckets(RUN_BUCKETS.to_vec()),
+            &["status"],
+        )?;
+
+        registry.register(Box::new(provider_requests.clone()))?;
+        registry.register(Box::new(provider_request_duration.clone()))?;
+        registry.register(Box::new(provider_tokens.clone()))?;
+        registry.register(Box::new(tool_calls.clone()))?;
+        registry.register(Box::new(tool_call_duration.clone()))?;
+        registry.register(Box::new(permission_prompts.clone()))?;
+        registry.register(Box::new(compactions.clone()))?;
+        registry.register(Box::new(active_agents.clone()))?;
+        registry.register(Box::new(scheduled_runs.clone()))?;
+        registry.register(Box::new(scheduled_run_duration.clone()))?;
+
+        Ok(Self {
+            registry,
+            labels: Mutex::new(HashMap::new()),
+            provider_requests,
+            provider_request_duration,
+            provider_tokens,
+            tool_calls,
+            tool_call_duration,
+            permission_prompts,
+            compactions,
+            active_agents,
+            scheduled_runs,
+            scheduled_run_duration,
+        })
+    }
+
+    fn bounded(&self, label: &'static str, value: &str) -> String {
+        let mut labels = self.labels.lock().unwrap();
+        let seen = labels.entry(label).or_default();
+        if seen.contains(value) {
+            return value.to_string();
+        }
+        if seen.len() >= MAX_LABEL_VALUES {
+            return OVERFLOW_LABEL.to_string();
+        }
+        seen.insert(value.to_string());
+        value.to_string()
+    }
+}
+
+fn metrics() -> Option<&'static Metrics> {
+    static METRICS: OnceLock<Option<Metrics>> = OnceLock::new();
+    METRICS
+        .get_or_init(|| match Metrics::new() {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                tracing::warn!("Failed to set up metrics: {}", e);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// Tools are exposed as `<extension>__<tool>`; platform tools have no prefix.
+fn split_tool_name(name: &str) -> (&str, &str) {
+    name.split_once("__").unwrap_or(("platform", name))
+}
+
+pub fn record_provider_request(
+    provider: &str,
+    model: &str,
+    duration: Duration,
+    error: Option<&ProviderError>,
+) {
+    let Some(metrics) = metrics() else { return };
+    let provider = metrics.bounded("provider", provider);
+    let model = metrics.bounded("model", model);
+    let outcome = error.map_or("ok", ProviderError::telemetry_type);
+    metrics
+        .provider_requests
+        .with_label_values(&[&provider, &model, outcome])
+        .inc();
+    metrics
+        .provider_request_duration
+        .with_label_values(&[&provider, &model])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_token_usage(provider: &str, model: &str, usage: &Usage) {
+    let Some(metrics) = metrics() else { return };
+    let provider = metrics.bounded("provider", provider);
+    let model = metrics.bounded("model", model);
+    for (direction, tokens) in [
+        ("input", usage.input_tokens),
+        ("output", usage.output_tokens),
+    ] {
+        if let Some(tokens) = tokens.filter(|tokens| *tokens > 0) {
+            metrics
+                .provider_tokens
+                .with_label_values(&[&provider, &model, direction])
+                .inc_by(tokens as u64);
+        }
+    }
+}
+
+pub fn record_tool_call(tool_name: &str, success: bool, duration: Duration) {
+    let Some(metrics) = metrics() else { return };
+    let (extension, tool) = split_tool_name(tool_name);
+    let extension = metrics.bounded("extension", extension);
+    let tool = metrics.bounded("tool", tool);
+    let outcome = if success { "ok" } else { "error" };
+    metrics
+        .tool_calls
+        .with_label_values(&[&extension, &tool, outcome])
+        .inc();
+    metrics
+        .tool_call_duration
+        .with_label_values(&[&extension, &tool])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_permission_prompt(decision: &Permission) {
+    let Some(metrics) = metrics() else { return };
+    let decision = match decision {
+        Permission::AlwaysAllow => "always_allow",
+        Permission::AllowOnce => "allow_once",
+        Permission::Cancel => "cancel",
+        Permission::DenyOnce => "deny",
+    };
+    metrics
+        .permission_prompts
+        .with_label_values(&[decision])
+        .inc();
+}
+
+/// `trigger` is one of `manual`, `auto` or `context_limit`.
+pub fn record_compaction(trigger: &'static str, success: bool) {
+    let Some(metrics) = metrics() else { return };
+    let outcome = if success { "ok" } else { "error" };
+    metrics
+        .compactions
+        .with_label_values(&[trigger, outcome])
+        .inc();
+}
+
+pub fn set_active_agents(count: usize) {
+    if let Some(metrics) = metrics() {
+        metrics.active_agents.set(count as i64);
+    }
+}
+
+pub fn record_scheduled_run(status: JobRunStatus, duration: Duration) {
+    let Some(metrics) = metrics() else { return };
+    let status = status.to_string();
+    metrics.scheduled_runs.with_label_values(&[&status]).inc();
+    metrics
+        .scheduled_run_duration
+        .with_label_values(&[&status])
+        .observe(duration.as_secs_f64());
+}
+
+/// All metrics in the Prometheus text exposition format.
+pub fn gather() -> String {
+    let Some(metrics) = metrics() else {
+        return String::new();
+    };
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&metrics.registry.gather(), &mut buffer) {
+        tracing::warn!("Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Content type of [`gather`]'s output.
+pub fn content_type() -> String {
+    TextEncoder::new().format_type().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_tool_name() {
+        assert_eq!(
+            split_tool_name("developer__text_editor"),
+            ("developer", "text_editor")
+        );
+        assert_eq!(
+            split_tool_name("platform_tool"),
+            ("platform", "platform_tool")
+        );
+    }
+
+    #[test]
+    fn test_labels_are_bounded() {
+        let metrics = Metrics::new().unwrap();
+        for i in 0..MAX_LABEL_VALUES {
+            assert_eq!(
+                metrics.bounded("model", &format!("m{}", i)),
+                format!("m{}", i)
+            );
+        }
+        assert_eq!(metrics.bounded("model", "one-too-many"), OVERFLOW_LABEL);
+        assert_eq!(metrics.bounded("model", "m0"), "m0");
+        assert_eq!(metrics.bounded("provider", "openai"), "openai");
+    }
+
+    #[test]
+    fn test_recorded_metrics_are_exposed() {
+        record_provider_request("openai", "gpt-4o", Duration::from_millis(300), None);
+        record_provider_request(
+            "openai",
+            "gpt-4o",
+            Duration::from_secs(1),
+            Some(&ProviderError::RateLimitExceeded {
+                details: "slow down".to_string(),
+                retry_delay: None,
+            }),
+        );
+        record_token_usage("openai", "gpt-4o", &Usage::new(Some(120), Some(30), None));
+        record_tool_call("developer__shell", true, Duration::from_millis(50));
+        record_permission_prompt(&Permission::AllowOnce);
+        record_compaction("auto", true);
+        set_active_agents(3);
+        record_scheduled_run(JobRunStatus::Failed, Duration::from_secs(5));
+
+        let output = gather();
+        for expected in [
+            r#"goose_provider_requests_total{model="gpt-4o",outcome="ok",provider="openai"}"#,
+            r#"goose_provider_requests_total{model="gpt-4o",outcome="rate_limit",provider="openai"}"#,
+            r#"goose_provider_tokens_total{direction="input",model="gpt-4o",provider="openai"} 120"#,
+            r#"goose_tool_calls_total{extension="developer",outcome="ok",tool="shell"}"#,
+            r#"goose_permission_prompts_total{decision="allow_once"}"#,
+            r#"goose_compactions_total{outcome="ok",trigger="auto"}"#,
+            "goose_active_agents 3",
+            r#"goose_scheduled_runs_total{status="failed"}"#,
+        ] {
+            assert!(
+                output.contains(expected),
+                "missing {} in\n{}",
+                expected,
+                output
+            );
+        }
+    }
+}
@@ -6,10 +6,25 @@ use crate::permission::permission_judge::PermissionCheckResult;
 use crate::tool_inspection::{InspectionAction, InspectionResult, ToolInspector};
 use anyhow::Result;
 use async_trait::async_trait;
+use rmcp::model::CallToolRequestParam;
 use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Tool calls that set this argument to `true` do something that can't be undone, such as
+/// the developer extension's `git` tool resetting a branch or force pushing. They are never
+/// approved automatically, whatever the mode or the tool's permission level.
+pub const DESTRUCTIVE_ARGUMENT: &str = "destructive";
+
+fn is_marked_destructive(tool_call: &CallToolRequestParam) -> bool {
+    tool_call
+        .arguments
+        .as_ref()
+        .and_then(|arguments| arguments.get(DESTRUCTIVE_ARGUMENT))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
 /// Permission Inspector that handles tool permission checking
 pub struct PermissionInspector {
     mode: Arc<Mutex<GooseMode>>,
@@ -138,6 +153,7 @@ impl ToolInspector for PermissionInspector {
         for request in tool_requests {
             if let Ok(tool_call) = &request.tool_call {
                 let tool_name = &tool_call.name;
+                let destructive = is_marked_destructive(tool_call);
 
                 let action = match *mode {
                     GooseMode::Chat => continue,
@@ -172,6 +188,17 @@ impl ToolInspector for PermissionInspector {
                     }
                 };
 
+                // Destructive calls are never auto-approved, but a denied tool stays denied
+                let action = match action {
+                    InspectionAction::Allow if destructive => {
+                        InspectionAction::RequireApproval(Some(
+                            "This operation is destructive and always requires approval"
+                                .to_string(),
+                        ))
+                    }
+                    action => action,
+                };
+
                 let reason = match &action {
                     InspectionAction::Allow => {
                         if *mode == GooseMode::Auto {
@@ -186,7 +213,9 @@ impl ToolInspector for PermissionInspector {
                     }
                     InspectionAction::Deny => "User permission denies this tool".to_string(),
                     InspectionAction::RequireApproval(_) => {
-                        if tool_name == MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE {
+                        if destructive {
+                            "Destructive operation requires user approval".to_string()
+                        } else if tool_name == MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE {
                             "Extension management requires user approval".to_string()
                         } else {
                             "Tool requires user approval".to_string()
@@ -208,3 +237,65 @@ impl ToolInspector for PermissionInspector {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+    use tempfile::TempDir;
+
+    fn git_request(id: &str, arguments: rmcp::model::JsonObject) -> ToolRequest {
+        ToolRequest {
+            id: id.to_string(),
+            tool_call: Ok(CallToolRequestParam {
+                name: "developer__git".into(),
+                arguments: Some(arguments),
+            }),
+            thought_signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_destructive_calls_always_require_approval() {
+        let dir = TempDir::new().unwrap();
+        let mut permission_manager = PermissionManager::new(dir.path().join("permission.yaml"));
+        permission_manager.update_user_permission("developer__git", PermissionLevel::AlwaysAllow);
+
+        let inspector = PermissionInspector::with_permission_manager(
+            GooseMode::Auto,
+            HashSet::new(),
+            HashSet::new(),
+            Arc::new(Mutex::new(permission_manager)),
+        );
+        let requests = vec![
+            git_request("status", object!({ "command": "status" })),
+            git_request(
+                "reset",
+                object!({ "command": "reset", "mode": "hard", "destructive": true }),
+            ),
+        ];
+
+        for mode in [GooseMode::Auto, GooseMode::Approve, GooseMode::SmartApprove] {
+            inspector.update_mode(mode).await;
+            let results = inspector.inspect(&requests, &[]).await.unwrap();
+            assert_eq!(results[0].action, InspectionAction::Allow);
+            assert!(matches!(
+                results[1].action,
+                InspectionAction::RequireApproval(Some(_))
+            ));
+        }
+
+        // A tool the user never allows stays denied rather than asking
+        inspector
+            .permission_manager
+            .lock()
+            .await
+            .update_user_permission("developer__git", PermissionLevel::NeverAllow);
+        inspector.update_mode(GooseMode::Approve).await;
+        let results = inspector.inspect(&requests, &[]).await.unwrap();
+        assert_eq!(results[1].action, InspectionAction::Deny);
+
+        inspector.update_mode(GooseMode::Chat).await;
+        assert!(inspector.inspect(&requests, &[]).await.unwrap().is_empty());
+    }
+}
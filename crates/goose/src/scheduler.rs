@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -6,7 +6,9 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use futures::future::BoxFuture;
+use rmcp::model::Role;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::{job::JobId, Job, JobScheduler as TokioJobScheduler};
@@ -19,13 +21,37 @@ use crate::config::Config;
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
 use crate::providers::create;
+use crate::recipe::build_recipe::build_recipe_from_template;
 use crate::recipe::Recipe;
+use crate::scheduler_expression::{fire_times_between, parse_schedule_expression, parse_timezone};
+use crate::scheduler_runs::{
+    notify_failure, publish_finished_run, CatchUpPolicy, ConcurrencyPolicy, FailureNotification,
+    JobRun, JobRunStatus, RetryPolicy, RunHistory,
+};
 use crate::scheduler_trait::SchedulerTrait;
+use crate::scheduler_trigger::{
+    changed_paths, snapshot_watch_path, ScheduleTrigger, CHANGED_FILES_PARAM,
+};
+use crate::scheduler_workers::{WorkItem, WorkerPool};
 use crate::session::session_manager::SessionType;
 use crate::session::{Session, SessionManager};
 
-type RunningTasksMap = HashMap<String, CancellationToken>;
-type JobsMap = HashMap<String, (JobId, ScheduledJob)>;
+/// Cancellation tokens of the active runs of each job, keyed by run id. Jobs with the
+/// `Allow` concurrency policy can have several.
+type RunningTasksMap = HashMap<String, HashMap<String, CancellationToken>>;
+/// Parameters of runs waiting for the current run of a `Queue` job to finish.
+type QueuedRunsMap = HashMap<String, VecDeque<HashMap<String, String>>>;
+type WatchTasksMap = HashMap<String, CancellationToken>;
+/// When each cron job last fired, used to tell late ticks after a sleep from on-time ones.
+type CronTicksMap = HashMap<String, DateTime<Utc>>;
+/// Cron jobs are registered with the tokio scheduler; other triggers have no `JobId`.
+type JobsMap = HashMap<String, (Option<JobId>, ScheduledJob)>;
+
+const FILE_WATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const MAX_QUEUED_RUNS: usize = 10;
+/// A cron tick arriving this long after its fire time means the machine was asleep.
+const LATE_TICK_GRACE: chrono::Duration = chrono::Duration::seconds(60);
+const MAX_CATCH_UP_RUNS: usize = 10;
 
 pub fn get_default_scheduler_storage_path() -> Result<PathBuf, io::Error> {
     let data_dir = Paths::data_dir();
@@ -49,6 +75,7 @@ pub enum SchedulerError {
     AgentSetupError(String),
     PersistError(String),
     CronParseError(String),
+    InvalidTrigger(String),
     SchedulerInternalError(String),
     AnyhowError(anyhow::Error),
 }
@@ -63,6 +90,7 @@ impl std::fmt::Display for SchedulerError {
             SchedulerError::AgentSetupError(e) => write!(f, "Agent setup error: {}", e),
             SchedulerError::PersistError(e) => write!(f, "Failed to persist schedules: {}", e),
             SchedulerError::CronParseError(e) => write!(f, "Invalid cron string: {}", e),
+            SchedulerError::InvalidTrigger(e) => write!(f, "Invalid trigger: {}", e),
             SchedulerError::SchedulerInternalError(e) => {
                 write!(f, "Scheduler internal error: {}", e)
             }
@@ -103,7 +131,13 @@ impl From<anyhow::Error> for SchedulerError {
 pub struct ScheduledJob {
     pub id: String,
     pub source: String,
+    /// Cron expression, only used when `trigger` is `Cron`
     pub cron: String,
+    #[serde(default)]
+    pub trigger: ScheduleTrigger,
+    /// IANA timezone the cron expression is evaluated in; local time when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub last_run: Option<DateTime<Utc>>,
     #[serde(default)]
     pub currently_running: bool,
@@ -113,6 +147,15 @@ pub struct ScheduledJob {
     pub current_session_id: Option<String>,
     #[serde(default)]
     pub process_start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub concurrency: ConcurrencyPolicy,
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+    /// Sent when a run fails after all of its retries
+    #[serde(default)]
+    pub notifications: Vec<FailureNotification>,
 }
 
 async fn persist_jobs(
@@ -134,6 +177,10 @@ pub struct Scheduler {
     jobs: Arc<Mutex<JobsMap>>,
     storage_path: PathBuf,
     running_tasks: Arc<Mutex<RunningTasksMap>>,
+    queued_runs: Arc<Mutex<QueuedRunsMap>>,
+    cron_ticks: Arc<Mutex<CronTicksMap>>,
+    watch_tasks: Arc<Mutex<WatchTasksMap>>,
+    history: RunHistory,
 }
 
 impl Scheduler {
@@ -144,12 +191,20 @@ impl Scheduler {
 
         let jobs = Arc::new(Mutex::new(HashMap::new()));
         let running_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let queued_runs = Arc::new(Mutex::new(HashMap::new()));
+        let cron_ticks = Arc::new(Mutex::new(HashMap::new()));
+        let watch_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let history = RunHistory::for_storage_path(&storage_path);
 
         let arc_self = Arc::new(Self {
             tokio_scheduler: internal_scheduler,
             jobs,
             storage_path,
             running_tasks,
+            queued_runs,
+            cron_ticks,
+            watch_tasks,
+            history,
         });
 
         arc_self.load_jobs_from_storage().await;
@@ -162,108 +217,120 @@ impl Scheduler {
         Ok(arc_self)
     }
 
+    fn runner(&self) -> JobRunner {
+        JobRunner {
+            jobs: self.jobs.clone(),
+            storage_path: self.storage_path.clone(),
+            running_tasks: self.running_tasks.clone(),
+            queued_runs: self.queued_runs.clone(),
+            cron_ticks: self.cron_ticks.clone(),
+            history: self.history.clone(),
+        }
+    }
+
     fn create_cron_task(&self, job: ScheduledJob) -> Result<Job, SchedulerError> {
-        let job_for_task = job.clone();
-        let jobs_arc = self.jobs.clone();
-        let storage_path = self.storage_path.clone();
-        let running_tasks_arc = self.running_tasks.clone();
-
-        let cron_parts: Vec<&str> = job.cron.split_whitespace().collect();
-        let cron = match cron_parts.len() {
-            5 => {
-                tracing::warn!(
-                    "Job '{}' has legacy 5-field cron '{}', converting to 6-field",
-                    job.id,
-                    job.cron
-                );
-                format!("0 {}", job.cron)
-            }
-            6 => job.cron.clone(),
-            _ => {
-                return Err(SchedulerError::CronParseError(format!(
-                    "Invalid cron expression '{}': expected 5 or 6 fields, got {}",
-                    job.cron,
-                    cron_parts.len()
-                )))
-            }
-        };
+        let cron = parse_schedule_expression(&job.cron).map_err(SchedulerError::CronParseError)?;
+        let runner = self.runner();
 
-        let local_tz = Local::now().timezone();
-
-        Job::new_async_tz(&cron, local_tz, move |_uuid, _l| {
-            tracing::info!("Cron task triggered for job '{}'", job_for_task.id);
-            let task_job_id = job_for_task.id.clone();
-            let current_jobs_arc = jobs_arc.clone();
-            let local_storage_path = storage_path.clone();
-            let job_to_execute = job_for_task.clone();
-            let running_tasks = running_tasks_arc.clone();
-
-            Box::pin(async move {
-                let should_execute = {
-                    let jobs_guard = current_jobs_arc.lock().await;
-                    jobs_guard
-                        .get(&task_job_id)
-                        .map(|(_, j)| !j.paused)
-                        .unwrap_or(false)
-                };
-
-                if !should_execute {
-                    return;
-                }
+        match &job.timezone {
+            Some(timezone) => {
+                let tz = parse_timezone(timezone).map_err(SchedulerError::CronParseError)?;
+                cron_task(&cron, tz, runner, job.id)
+            }
+            None => cron_task(&cron, Local::now().timezone(), runner, job.id),
+        }
+    }
 
-                let current_time = Utc::now();
-                {
-                    let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.last_run = Some(current_time);
-                        job.currently_running = true;
-                        job.process_start_time = Some(current_time);
-                    }
-                }
+    /// Checks the trigger configuration, including that a chained job does not end up
+    /// triggering itself.
+    async fn validate_trigger(&self, job: &ScheduledJob) -> Result<(), SchedulerError> {
+        job.trigger
+            .validate()
+            .map_err(SchedulerError::InvalidTrigger)?;
+        if let Some(timezone) = &job.timezone {
+            parse_timezone(timezone).map_err(SchedulerError::CronParseError)?;
+        }
 
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job status: {}", e);
-                }
+        if let ScheduleTrigger::AfterJob {
+            job_id: upstream_id,
+            ..
+        } = &job.trigger
+        {
+            let jobs_guard = self.jobs.lock().await;
+            if !jobs_guard.contains_key(upstream_id) {
+                return Err(SchedulerError::InvalidTrigger(format!(
+                    "upstream job '{}' does not exist",
+                    upstream_id
+                )));
+            }
 
-                let cancel_token = CancellationToken::new();
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.insert(task_job_id.clone(), cancel_token.clone());
+            let mut visited = HashSet::new();
+            let mut current = upstream_id.clone();
+            loop {
+                if current == job.id || !visited.insert(current.clone()) {
+                    return Err(SchedulerError::InvalidTrigger(format!(
+                        "running '{}' after '{}' would create a cycle",
+                        job.id, upstream_id
+                    )));
                 }
-
-                let result = execute_job(
-                    job_to_execute,
-                    current_jobs_arc.clone(),
-                    task_job_id.clone(),
-                    cancel_token.clone(),
-                )
-                .await;
-
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.remove(&task_job_id);
+                match jobs_guard.get(&current).map(|(_, j)| &j.trigger) {
+                    Some(ScheduleTrigger::AfterJob { job_id, .. }) => current = job_id.clone(),
+                    _ => break,
                 }
+            }
+        }
+        Ok(())
+    }
 
+    /// Starts listening for the job's trigger. Cron jobs are registered with the tokio
+    /// scheduler and file watches get a polling task; webhook and chained jobs are started
+    /// on demand by `trigger_job` and `JobRunner`.
+    async fn activate_trigger(&self, job: &ScheduledJob) -> Result<Option<JobId>, SchedulerError> {
+        match &job.trigger {
+            ScheduleTrigger::Cron => {
+                let cron_task = self.create_cron_task(job.clone())?;
+                self.cron_ticks
+                    .lock()
+                    .await
+                    .insert(job.id.clone(), Utc::now());
+                let job_uuid = self
+                    .tokio_scheduler
+                    .add(cron_task)
+                    .await
+                    .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+                Ok(Some(job_uuid))
+            }
+            ScheduleTrigger::FileWatch { path } => {
+                let cancel_token = CancellationToken::new();
                 {
-                    let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.currently_running = false;
-                        job.current_session_id = None;
-                        job.process_start_time = None;
+                    let mut watch_tasks = self.watch_tasks.lock().await;
+                    if let Some(previous) = watch_tasks.insert(job.id.clone(), cancel_token.clone())
+                    {
+                        previous.cancel();
                     }
                 }
+                spawn_file_watch(self.runner(), job.id.clone(), path.clone(), cancel_token);
+                Ok(None)
+            }
+            ScheduleTrigger::Webhook { .. } | ScheduleTrigger::AfterJob { .. } => Ok(None),
+        }
+    }
 
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job completion: {}", e);
-                }
-
-                match result {
-                    Ok(_) => tracing::info!("Job '{}' completed", task_job_id),
-                    Err(e) => tracing::error!("Job '{}' failed: {}", task_job_id, e),
-                }
-            })
-        })
-        .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+    async fn deactivate_trigger(
+        &self,
+        id: &str,
+        job_uuid: Option<JobId>,
+    ) -> Result<(), SchedulerError> {
+        if let Some(job_uuid) = job_uuid {
+            self.tokio_scheduler
+                .remove(&job_uuid)
+                .await
+                .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        }
+        if let Some(cancel_token) = self.watch_tasks.lock().await.remove(id) {
+            cancel_token.cancel();
+        }
+        Ok(())
     }
 
     pub async fn add_scheduled_job(
@@ -278,7 +345,13 @@ impl Scheduler {
             }
         }
 
+        self.validate_trigger(&original_job_spec).await?;
+
         let mut stored_job = original_job_spec;
+        if stored_job.trigger.is_cron() {
+            stored_job.cron = parse_schedule_expression(&stored_job.cron)
+                .map_err(SchedulerError::CronParseError)?;
+        }
         if make_copy {
             let original_recipe_path = Path::new(&stored_job.source);
             if !original_recipe_path.is_file() {
@@ -303,13 +376,7 @@ impl Scheduler {
             stored_job.process_start_time = None;
         }
 
-        let cron_task = self.create_cron_task(stored_job.clone())?;
-
-        let job_uuid = self
-            .tokio_scheduler
-            .add(cron_task)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        let job_uuid = self.activate_trigger(&stored_job).await?;
 
         {
             let mut jobs_guard = self.jobs.lock().await;
@@ -345,11 +412,17 @@ impl Scheduler {
                         id: job_id,
                         source: recipe_path_str,
                         cron,
+                        trigger: ScheduleTrigger::Cron,
+                        timezone: None,
                         last_run: None,
                         currently_running: false,
                         paused: false,
                         current_session_id: None,
                         process_start_time: None,
+                        retry: RetryPolicy::default(),
+                        concurrency: ConcurrencyPolicy::default(),
+                        catch_up: CatchUpPolicy::default(),
+                        notifications: Vec::new(),
                     };
                     self.add_scheduled_job(job, false).await
                 }
@@ -422,23 +495,11 @@ impl Scheduler {
                 continue;
             }
 
-            let cron_task = match self.create_cron_task(job_to_load.clone()) {
-                Ok(task) => task,
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to create cron task for job '{}': {}. Skipping.",
-                        job_to_load.id,
-                        e
-                    );
-                    continue;
-                }
-            };
-
-            let job_uuid = match self.tokio_scheduler.add(cron_task).await {
+            let job_uuid = match self.activate_trigger(&job_to_load).await {
                 Ok(uuid) => uuid,
                 Err(e) => {
                     tracing::error!(
-                        "Failed to add job '{}' to scheduler: {}. Skipping.",
+                        "Failed to activate trigger for job '{}': {}. Skipping.",
                         job_to_load.id,
                         e
                     );
@@ -446,8 +507,15 @@ impl Scheduler {
                 }
             };
 
-            let mut jobs_guard = self.jobs.lock().await;
-            jobs_guard.insert(job_to_load.id.clone(), (job_uuid, job_to_load));
+            let missed = missed_while_stopped(&job_to_load);
+            let job_id = job_to_load.id.clone();
+            {
+                let mut jobs_guard = self.jobs.lock().await;
+                jobs_guard.insert(job_id.clone(), (job_uuid, job_to_load));
+            }
+            if missed > 0 {
+                tokio::spawn(self.runner().catch_up(job_id, missed, false));
+            }
         }
     }
 
@@ -460,6 +528,7 @@ impl Scheduler {
             .collect()
     }
 
+    /// Removes a job. Jobs chained to run after it have to be removed or retriggered first.
     pub async fn remove_scheduled_job(
         &self,
         id: &str,
@@ -467,16 +536,33 @@ impl Scheduler {
     ) -> Result<(), SchedulerError> {
         let (job_uuid, recipe_path) = {
             let mut jobs_guard = self.jobs.lock().await;
+            let mut dependents: Vec<&str> = jobs_guard
+                .values()
+                .filter(|(_, job)| {
+                    matches!(&job.trigger, ScheduleTrigger::AfterJob { job_id, .. } if job_id == id)
+                })
+                .map(|(_, job)| job.id.as_str())
+                .collect();
+            if !dependents.is_empty() {
+                dependents.sort();
+                return Err(SchedulerError::AnyhowError(anyhow!(
+                    "Cannot remove schedule '{}', {} run after it",
+                    id,
+                    dependents.join(", ")
+                )));
+            }
             match jobs_guard.remove(id) {
                 Some((uuid, job)) => (uuid, job.source.clone()),
                 None => return Err(SchedulerError::JobNotFound(id.to_string())),
             }
         };
 
-        self.tokio_scheduler
-            .remove(&job_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        self.deactivate_trigger(id, job_uuid).await?;
+        self.queued_runs.lock().await.remove(id);
+        self.cron_ticks.lock().await.remove(id);
+        if let Err(e) = self.history.remove(id) {
+            tracing::warn!("Failed to remove run history for job '{}': {}", id, e);
+        }
 
         if remove_recipe {
             let path = Path::new(&recipe_path);
@@ -510,66 +596,49 @@ impl Scheduler {
         Ok(schedule_sessions)
     }
 
-    pub async fn run_now(&self, sched_id: &str) -> Result<String, SchedulerError> {
-        let job_to_run = {
-            let mut jobs_guard = self.jobs.lock().await;
-            match jobs_guard.get_mut(sched_id) {
-                Some((_, job)) => {
-                    if job.currently_running {
-                        return Err(SchedulerError::AnyhowError(anyhow!(
-                            "Job '{}' is already running",
-                            sched_id
-                        )));
-                    }
-                    job.currently_running = true;
-                    job.process_start_time = Some(Utc::now());
-                    job.clone()
-                }
-                None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
-            }
-        };
-
-        persist_jobs(&self.storage_path, &self.jobs).await?;
-
-        let cancel_token = CancellationToken::new();
-        {
-            let mut tasks = self.running_tasks.lock().await;
-            tasks.insert(sched_id.to_string(), cancel_token.clone());
+    /// Recorded runs of a job, newest first.
+    pub async fn runs(&self, sched_id: &str, limit: usize) -> Result<Vec<JobRun>, SchedulerError> {
+        if !self.jobs.lock().await.contains_key(sched_id) {
+            return Err(SchedulerError::JobNotFound(sched_id.to_string()));
         }
+        Ok(self.history.list(sched_id, limit)?)
+    }
 
-        let result = execute_job(
-            job_to_run,
-            self.jobs.clone(),
-            sched_id.to_string(),
-            cancel_token.clone(),
-        )
-        .await;
-
-        {
-            let mut tasks = self.running_tasks.lock().await;
-            tasks.remove(sched_id);
-        }
+    pub async fn run_now(&self, sched_id: &str) -> Result<String, SchedulerError> {
+        self.runner().run(sched_id, HashMap::new(), true).await
+    }
 
+    /// Starts a job in the background on behalf of an external trigger such as a webhook,
+    /// passing `params` to the recipe.
+    pub async fn trigger_job(
+        &self,
+        sched_id: &str,
+        params: HashMap<String, String>,
+    ) -> Result<(), SchedulerError> {
         {
-            let mut jobs_guard = self.jobs.lock().await;
-            if let Some((_, job)) = jobs_guard.get_mut(sched_id) {
-                job.currently_running = false;
-                job.current_session_id = None;
-                job.process_start_time = None;
-                job.last_run = Some(Utc::now());
+            let jobs_guard = self.jobs.lock().await;
+            match jobs_guard.get(sched_id) {
+                Some((_, job)) if job.paused => {
+                    return Err(SchedulerError::AnyhowError(anyhow!(
+                        "Schedule '{}' is paused",
+                        sched_id
+                    )));
+                }
+                Some((_, job))
+                    if job.currently_running && job.concurrency == ConcurrencyPolicy::Skip =>
+                {
+                    return Err(SchedulerError::AnyhowError(anyhow!(
+                        "Job '{}' is already running",
+                        sched_id
+                    )));
+                }
+                Some(_) => {}
+                None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
             }
         }
 
-        persist_jobs(&self.storage_path, &self.jobs).await?;
-
-        match result {
-            Ok(session_id) => Ok(session_id),
-            Err(e) => Err(SchedulerError::AnyhowError(anyhow!(
-                "Job '{}' failed: {}",
-                sched_id,
-                e
-            ))),
-        }
+        tokio::spawn(self.runner().run_triggered(sched_id.to_string(), params));
+        Ok(())
     }
 
     pub async fn pause_schedule(&self, sched_id: &str) -> Result<(), SchedulerError> {
@@ -613,33 +682,32 @@ impl Scheduler {
             let mut jobs_guard = self.jobs.lock().await;
             match jobs_guard.get_mut(sched_id) {
                 Some((uuid, job)) => {
+                    if !job.trigger.is_cron() {
+                        return Err(SchedulerError::InvalidTrigger(format!(
+                            "Schedule '{}' is triggered by {}, not cron",
+                            sched_id, job.trigger
+                        )));
+                    }
                     if job.currently_running {
                         return Err(SchedulerError::AnyhowError(anyhow!(
                             "Cannot update running schedule '{}'",
                             sched_id
                         )));
                     }
+                    let new_cron = parse_schedule_expression(&new_cron)
+                        .map_err(SchedulerError::CronParseError)?;
                     if new_cron == job.cron {
                         return Ok(());
                     }
-                    job.cron = new_cron.clone();
+                    job.cron = new_cron;
                     (*uuid, job.clone())
                 }
                 None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
             }
         };
 
-        self.tokio_scheduler
-            .remove(&old_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
-
-        let cron_task = self.create_cron_task(updated_job)?;
-        let new_uuid = self
-            .tokio_scheduler
-            .add(cron_task)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        self.deactivate_trigger(sched_id, old_uuid).await?;
+        let new_uuid = self.activate_trigger(&updated_job).await?;
 
         {
             let mut jobs_guard = self.jobs.lock().await;
@@ -668,8 +736,10 @@ impl Scheduler {
 
         {
             let tasks = self.running_tasks.lock().await;
-            if let Some(token) = tasks.get(sched_id) {
-                token.cancel();
+            if let Some(runs) = tasks.get(sched_id) {
+                for token in runs.values() {
+                    token.cancel();
+                }
             }
         }
 
@@ -694,11 +764,423 @@ impl Scheduler {
     }
 }
 
+/// Shared state needed to run jobs, cloned into cron tasks, file watchers and chained runs.
+#[derive(Clone)]
+struct JobRunner {
+    jobs: Arc<Mutex<JobsMap>>,
+    storage_path: PathBuf,
+    running_tasks: Arc<Mutex<RunningTasksMap>>,
+    queued_runs: Arc<Mutex<QueuedRunsMap>>,
+    cron_ticks: Arc<Mutex<CronTicksMap>>,
+    history: RunHistory,
+}
+
+impl JobRunner {
+    /// Handles a cron tick. A tick that arrives well after its fire time means runs were
+    /// missed while the machine was asleep, and the job's catch-up policy decides what
+    /// happens to them.
+    fn run_cron_tick(self, job_id: String) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let now = Utc::now();
+            let previous_tick = self.cron_ticks.lock().await.insert(job_id.clone(), now);
+            let job = match self.jobs.lock().await.get(&job_id) {
+                Some((_, job)) if !job.paused => job.clone(),
+                _ => return,
+            };
+
+            let Some(previous_tick) = previous_tick else {
+                self.run_triggered(job_id, HashMap::new()).await;
+                return;
+            };
+            let fire_times = |after: DateTime<Utc>, until: DateTime<Utc>| {
+                fire_times_between(
+                    &job.cron,
+                    job.timezone.as_deref(),
+                    after,
+                    until,
+                    MAX_CATCH_UP_RUNS,
+                )
+                .map(|times| times.len())
+                .unwrap_or_default()
+            };
+            // Fire times older than the grace period were missed; a later one is on time
+            let missed = fire_times(previous_tick, now - LATE_TICK_GRACE);
+            let on_time = fire_times(previous_tick.max(now - LATE_TICK_GRACE), now) > 0;
+
+            if missed == 0 {
+                self.run_triggered(job_id, HashMap::new()).await;
+            } else {
+                self.catch_up(job_id, missed, on_time).await;
+            }
+        })
+    }
+
+    /// Applies the job's catch-up policy to `missed` fire times that did not run. `on_time`
+    /// is set when a fire time within the grace period is due as well, which runs whatever
+    /// the policy, though a single catch-up run stands in for it.
+    async fn catch_up(self, job_id: String, missed: usize, on_time: bool) {
+        let policy = match self.jobs.lock().await.get(&job_id) {
+            Some((_, job)) => job.catch_up,
+            None => return,
+        };
+        tracing::info!(
+            "Job '{}' missed {} scheduled run(s), catch-up policy is {}",
+            job_id,
+            missed,
+            policy
+        );
+
+        match policy {
+            CatchUpPolicy::Skip => {
+                self.record_skipped(
+                    &job_id,
+                    &format!(
+                        "missed {} scheduled run(s) while goose was stopped or the machine was asleep",
+                        missed
+                    ),
+                );
+                if on_time {
+                    self.run_triggered(job_id, HashMap::new()).await;
+                }
+            }
+            CatchUpPolicy::RunOnce => self.run_triggered(job_id, HashMap::new()).await,
+            CatchUpPolicy::RunAll => {
+                for _ in 0..missed + usize::from(on_time) {
+                    self.clone()
+                        .run_triggered(job_id.clone(), HashMap::new())
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Runs a job for a trigger. Paused jobs are skipped; if the job is still running from an
+    /// earlier trigger its concurrency policy decides whether this run is skipped, queued or
+    /// started alongside. Returns a boxed future because chained and queued jobs are started
+    /// from inside a run.
+    fn run_triggered(
+        self,
+        job_id: String,
+        params: HashMap<String, String>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let policy = {
+                let jobs_guard = self.jobs.lock().await;
+                match jobs_guard.get(&job_id) {
+                    Some((_, job)) if job.paused => return,
+                    Some((_, job)) if job.currently_running => Some(job.concurrency),
+                    Some(_) => None,
+                    None => return,
+                }
+            };
+
+            match policy {
+                Some(ConcurrencyPolicy::Skip) => {
+                    tracing::warn!("Job '{}' is still running, skipping this trigger", job_id);
+                    self.record_skipped(&job_id, "previous run still in progress");
+                    return;
+                }
+                Some(ConcurrencyPolicy::Queue) => {
+                    let mut queued = self.queued_runs.lock().await;
+                    let queue = queued.entry(job_id.clone()).or_default();
+                    if queue.len() >= MAX_QUEUED_RUNS {
+                        drop(queued);
+                        tracing::warn!("Run queue for job '{}' is full, skipping", job_id);
+                        self.record_skipped(&job_id, "run queue is full");
+                    } else {
+                        queue.push_back(params);
+                        tracing::info!(
+                            "Job '{}' is still running, queued this trigger ({} waiting)",
+                            job_id,
+                            queue.len()
+                        );
+                    }
+                    return;
+                }
+                Some(ConcurrencyPolicy::Allow) | None => {}
+            }
+
+            match self.run(&job_id, params, false).await {
+                Ok(_) => tracing::info!("Job '{}' completed", job_id),
+                Err(e) => tracing::error!("{}", e),
+            }
+        })
+    }
+
+    fn record_skipped(&self, job_id: &str, reason: &str) {
+        let mut run = JobRun::new(job_id, &uuid::Uuid::new_v4().to_string(), 0);
+        run.finish(JobRunStatus::Skipped, Some(reason.to_string()));
+        if let Err(e) = self.history.append(&run) {
+            tracing::error!("Failed to record skipped run for job '{}': {}", job_id, e);
+        }
+    }
+
+    /// Runs a job, retrying failed attempts according to its retry policy. Every attempt is
+    /// recorded in the run history. The job's last run is when a triggered run started, and
+    /// when a `manual` one, started with `run_now`, finished.
+    async fn run(
+        &self,
+        job_id: &str,
+        params: HashMap<String, String>,
+        manual: bool,
+    ) -> Result<String, SchedulerError> {
+        let job_to_run = {
+            let mut jobs_guard = self.jobs.lock().await;
+            match jobs_guard.get_mut(job_id) {
+                Some((_, job)) => {
+                    if job.currently_running && job.concurrency != ConcurrencyPolicy::Allow {
+                        return Err(SchedulerError::AnyhowError(anyhow!(
+                            "Job '{}' is already running",
+                            job_id
+                        )));
+                    }
+                    let current_time = Utc::now();
+                    if !manual {
+                        job.last_run = Some(current_time);
+                    }
+                    job.currently_running = true;
+                    job.process_start_time = Some(current_time);
+                    job.clone()
+                }
+                None => return Err(SchedulerError::JobNotFound(job_id.to_string())),
+            }
+        };
+
+        if let Err(e) = persist_jobs(&self.storage_path, &self.jobs).await {
+            tracing::error!("Failed to persist job status: {}", e);
+        }
+
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let cancel_token = CancellationToken::new();
+        {
+            let mut tasks = self.running_tasks.lock().await;
+            tasks
+                .entry(job_id.to_string())
+                .or_default()
+                .insert(run_id.clone(), cancel_token.clone());
+        }
+
+        let mut attempt = 1;
+        let (result, final_run) = loop {
+            let mut run = JobRun::new(job_id, &run_id, attempt);
+            let result = execute_job(
+                job_to_run.clone(),
+                self.jobs.clone(),
+                job_id.to_string(),
+                cancel_token.clone(),
+                params.clone(),
+                &mut run,
+            )
+            .await;
+
+            if let Some(session_id) = &run.session_id {
+                if let Ok(session) = SessionManager::get_session(session_id, false).await {
+                    run.input_tokens = session.accumulated_input_tokens.or(session.input_tokens);
+                    run.output_tokens = session.accumulated_output_tokens.or(session.output_tokens);
+                    run.total_tokens = session.accumulated_total_tokens.or(session.total_tokens);
+                }
+            }
+            match &result {
+                _ if cancel_token.is_cancelled() => run.finish(JobRunStatus::Cancelled, None),
+                Ok(_) => run.finish(JobRunStatus::Succeeded, None),
+                Err(e) => run.finish(JobRunStatus::Failed, Some(e.to_string())),
+            }
+            if let Err(e) = self.history.append(&run) {
+                tracing::error!("Failed to record run of job '{}': {}", job_id, e);
+            }
+
+            if run.status != JobRunStatus::Failed || attempt > job_to_run.retry.max_retries {
+                break (result, run);
+            }
+
+            let delay = job_to_run.retry.delay_after(attempt);
+            tracing::warn!(
+                "Job '{}' failed on attempt {}, retrying in {:?}",
+                job_id,
+                attempt,
+                delay
+            );
+            tokio::select! {
+                _ = cancel_token.cancelled() => break (result, run),
+                _ = tokio::time::sleep(delay) => {}
+            }
+            attempt += 1;
+        };
+
+        let still_running = {
+            let mut tasks = self.running_tasks.lock().await;
+            let runs = tasks.entry(job_id.to_string()).or_default();
+            runs.remove(&run_id);
+            let still_running = !runs.is_empty();
+            if !still_running {
+                tasks.remove(job_id);
+            }
+            still_running
+        };
+
+        {
+            let mut jobs_guard = self.jobs.lock().await;
+            if let Some((_, job)) = jobs_guard.get_mut(job_id) {
+                if manual {
+                    job.last_run = Some(Utc::now());
+                }
+                if !still_running {
+                    job.currently_running = false;
+                    job.current_session_id = None;
+                    job.process_start_time = None;
+                }
+            }
+        }
+
+        if let Err(e) = persist_jobs(&self.storage_path, &self.jobs).await {
+            tracing::error!("Failed to persist job completion: {}", e);
+        }
+
+        publish_finished_run(&final_run);
+        if final_run.status == JobRunStatus::Failed && !job_to_run.notifications.is_empty() {
+            let notifications = job_to_run.notifications.clone();
+            tokio::spawn(async move { notify_failure(&notifications, &final_run).await });
+        }
+
+        self.start_chained_jobs(job_id, result.is_ok()).await;
+        if !still_running {
+            self.start_queued_run(job_id).await;
+        }
+
+        result.map_err(|e| SchedulerError::AnyhowError(anyhow!("Job '{}' failed: {}", job_id, e)))
+    }
+
+    async fn start_queued_run(&self, job_id: &str) {
+        let params = {
+            let mut queued = self.queued_runs.lock().await;
+            let params = queued.get_mut(job_id).and_then(|queue| queue.pop_front());
+            if queued.get(job_id).is_some_and(|queue| queue.is_empty()) {
+                queued.remove(job_id);
+            }
+            params
+        };
+
+        if let Some(params) = params {
+            tracing::info!("Starting queued run of job '{}'", job_id);
+            tokio::spawn(self.clone().run_triggered(job_id.to_string(), params));
+        }
+    }
+
+    async fn start_chained_jobs(&self, upstream_id: &str, succeeded: bool) {
+        let downstream: Vec<String> = {
+            let jobs_guard = self.jobs.lock().await;
+            jobs_guard
+                .values()
+                .filter_map(|(_, job)| match &job.trigger {
+                    ScheduleTrigger::AfterJob { job_id, on }
+                        if job_id == upstream_id && on.matches(succeeded) =>
+                    {
+                        Some(job.id.clone())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        for job_id in downstream {
+            tracing::info!("Starting job '{}' after '{}' finished", job_id, upstream_id);
+            tokio::spawn(self.clone().run_triggered(job_id, HashMap::new()));
+        }
+    }
+}
+
+fn cron_task<TZ: TimeZone>(
+    cron: &str,
+    timezone: TZ,
+    runner: JobRunner,
+    job_id: String,
+) -> Result<Job, SchedulerError> {
+    Job::new_async_tz(cron, timezone, move |_uuid, _l| {
+        tracing::info!("Cron task triggered for job '{}'", job_id);
+        runner.clone().run_cron_tick(job_id.clone())
+    })
+    .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+}
+
+/// Number of fire times of a cron job between its last run and now, for jobs loaded at
+/// startup. Jobs that never ran have nothing to catch up on.
+fn missed_while_stopped(job: &ScheduledJob) -> usize {
+    let last_run = match job.last_run {
+        Some(last_run) if job.trigger.is_cron() && !job.paused => last_run,
+        _ => return 0,
+    };
+    match fire_times_between(
+        &job.cron,
+        job.timezone.as_deref(),
+        last_run,
+        Utc::now(),
+        MAX_CATCH_UP_RUNS,
+    ) {
+        Ok(missed) => missed.len(),
+        Err(e) => {
+            tracing::warn!("Could not check missed runs of job '{}': {}", job.id, e);
+            0
+        }
+    }
+}
+
+/// Polls the files matching `path` and runs the job with the changed paths whenever they
+/// differ from the previous poll. Changes made while the job runs are picked up afterwards.
+fn spawn_file_watch(
+    runner: JobRunner,
+    job_id: String,
+    path: String,
+    cancel_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let snapshot_path = path.clone();
+        let mut snapshot = tokio::task::spawn_blocking(move || snapshot_watch_path(&snapshot_path))
+            .await
+            .unwrap_or_default();
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = tokio::time::sleep(FILE_WATCH_POLL_INTERVAL) => {}
+            }
+
+            let snapshot_path = path.clone();
+            let current = match tokio::task::spawn_blocking(move || {
+                snapshot_watch_path(&snapshot_path)
+            })
+            .await
+            {
+                Ok(current) => current,
+                Err(e) => {
+                    tracing::error!("File watch for job '{}' failed: {}", job_id, e);
+                    continue;
+                }
+            };
+
+            let changed = changed_paths(&snapshot, &current);
+            snapshot = current;
+            if changed.is_empty() {
+                continue;
+            }
+
+            tracing::info!(
+                "File watch for job '{}' detected {} changed file(s)",
+                job_id,
+                changed.len()
+            );
+            let params = HashMap::from([(CHANGED_FILES_PARAM.to_string(), changed.join("\n"))]);
+            runner.clone().run_triggered(job_id.clone(), params).await;
+        }
+    });
+}
+
 async fn execute_job(
     job: ScheduledJob,
     jobs: Arc<Mutex<JobsMap>>,
     job_id: String,
     cancel_token: CancellationToken,
+    params: HashMap<String, String>,
+    run: &mut JobRun,
 ) -> Result<String> {
     if job.source.is_empty() {
         return Ok(job.id.to_string());
@@ -707,7 +1189,7 @@ async fn execute_job(
     let recipe_path = Path::new(&job.source);
     let recipe_content = fs::read_to_string(recipe_path)?;
 
-    let recipe: Recipe = {
+    let recipe: Recipe = if params.is_empty() {
         let extension = recipe_path
             .extension()
             .and_then(|s| s.to_str())
@@ -718,8 +1200,86 @@ async fn execute_job(
             "json" | "jsonl" => serde_json::from_str(&recipe_content)?,
             _ => serde_yaml::from_str(&recipe_content)?,
         }
+    } else {
+        let recipe_dir = recipe_path.parent().unwrap_or_else(|| Path::new("."));
+        build_recipe_from_template(
+            recipe_content,
+            recipe_dir,
+            params.into_iter().collect(),
+            None::<fn(&str, &str) -> Result<String, anyhow::Error>>,
+        )?
     };
 
+    let session = SessionManager::create_session(
+        std::env::current_dir()?,
+        format!("Scheduled job: {}", job.id),
+        SessionType::Scheduled,
+    )
+    .await?;
+    run.session_id = Some(session.id.clone());
+
+    {
+        let mut jobs_guard = jobs.lock().await;
+        if let Some((_, job_def)) = jobs_guard.get_mut(job_id.as_str()) {
+            job_def.current_session_id = Some(session.id.clone());
+        }
+    }
+
+    let result = if WorkerPool::enabled() {
+        let item = WorkItem {
+            run_id: run.run_id.clone(),
+            job_id: job.id.clone(),
+            attempt: run.attempt,
+            session_id: session.id.clone(),
+            recipe: recipe.clone(),
+        };
+        match WorkerPool::global().dispatch(item, cancel_token).await {
+            Ok(result) => {
+                run.worker = Some(result.worker_id);
+                match result.error {
+                    Some(error) => Err(anyhow!("Run failed on worker: {}", error)),
+                    None => Ok(result.output),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    } else {
+        run_recipe(
+            &recipe,
+            &session.id,
+            Some(job.id.clone()),
+            cancel_token,
+            |_| {},
+        )
+        .await
+    };
+
+    SessionManager::update_session(&session.id)
+        .schedule_id(Some(job.id.clone()))
+        .recipe(Some(recipe))
+        .apply()
+        .await?;
+
+    if let Some(output) = result? {
+        run.set_output(&output);
+    }
+    Ok(session.id)
+}
+
+/// Runs a recipe with a fresh agent in an existing session, using the configured provider.
+/// `on_message` sees every message the agent produces. Returns the text of the last
+/// assistant message.
+///
+/// An error in the agent's stream fails the run, where scheduled jobs used to stop quietly
+/// and count as done, so the run is recorded as failed and retried. Whatever the agent said
+/// before the error stays in the session.
+pub async fn run_recipe(
+    recipe: &Recipe,
+    session_id: &str,
+    schedule_id: Option<String>,
+    cancel_token: CancellationToken,
+    mut on_message: impl FnMut(&Message) + Send,
+) -> Result<Option<String>> {
     let agent = Agent::new();
 
     let config = Config::global();
@@ -735,38 +1295,25 @@ async fn execute_job(
         }
     }
 
-    let session = SessionManager::create_session(
-        std::env::current_dir()?,
-        format!("Scheduled job: {}", job.id),
-        SessionType::Scheduled,
-    )
-    .await?;
-
-    agent.update_provider(agent_provider, &session.id).await?;
-
-    let mut jobs_guard = jobs.lock().await;
-    if let Some((_, job_def)) = jobs_guard.get_mut(job_id.as_str()) {
-        job_def.current_session_id = Some(session.id.clone());
-    }
+    agent.update_provider(agent_provider, session_id).await?;
 
     let prompt_text = recipe
         .prompt
         .as_ref()
         .or(recipe.instructions.as_ref())
-        .unwrap();
+        .ok_or_else(|| anyhow!("Recipe has neither a prompt nor instructions"))?;
 
     let user_message = Message::user().with_text(prompt_text);
     let mut conversation = Conversation::new_unvalidated(vec![user_message.clone()]);
 
     let session_config = SessionConfig {
-        id: session.id.clone(),
-        schedule_id: Some(job.id.clone()),
+        id: session_id.to_string(),
+        schedule_id,
         max_turns: None,
         retry_config: None,
     };
 
-    let session_id = session_config.id.clone();
-    let stream = crate::session_context::with_session_id(Some(session_id.clone()), async {
+    let stream = crate::session_context::with_session_id(Some(session_id.to_string()), async {
         agent
             .reply(user_message, session_config, Some(cancel_token))
             .await
@@ -775,12 +1322,14 @@ async fn execute_job(
 
     use futures::StreamExt;
     let mut stream = std::pin::pin!(stream);
+    let mut stream_error = None;
 
     while let Some(message_result) = stream.next().await {
         tokio::task::yield_now().await;
 
         match message_result {
             Ok(AgentEvent::Message(msg)) => {
+                on_message(&msg);
                 conversation.push(msg);
             }
             Ok(AgentEvent::HistoryReplaced(updated)) => {
@@ -789,17 +1338,22 @@ async fn execute_job(
             Ok(_) => {}
             Err(e) => {
                 tracing::error!("Error in agent stream: {}", e);
+                stream_error = Some(e);
                 break;
             }
         }
     }
 
-    SessionManager::update_session(&session.id)
-        .schedule_id(Some(job.id.clone()))
-        .recipe(Some(recipe))
-        .apply()
-        .await?;
-    Ok(session.id)
+    if let Some(e) = stream_error {
+        return Err(anyhow!("Agent stream failed: {}", e));
+    }
+
+    Ok(conversation
+        .messages()
+        .iter()
+        .rev()
+        .find(|message| message.role == Role::Assistant)
+        .map(|message| message.as_concat_text()))
 }
 
 #[async_trait]
@@ -844,6 +1398,14 @@ impl SchedulerTrait for Scheduler {
         self.run_now(id).await
     }
 
+    async fn trigger_job(
+        &self,
+        id: &str,
+        params: HashMap<String, String>,
+    ) -> Result<(), SchedulerError> {
+        self.trigger_job(id, params).await
+    }
+
     async fn sessions(
         &self,
         sched_id: &str,
@@ -852,6 +1414,10 @@ impl SchedulerTrait for Scheduler {
         self.sessions(sched_id, limit).await
     }
 
+    async fn runs(&self, sched_id: &str, limit: usize) -> Result<Vec<JobRun>, SchedulerError> {
+        self.runs(sched_id, limit).await
+    }
+
     async fn update_schedule(
         &self,
         sched_id: &str,
@@ -875,6 +1441,7 @@ impl SchedulerTrait for Scheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scheduler_trigger::ChainCondition;
     use tempfile::tempdir;
     use tokio::time::{sleep, Duration};
 
@@ -884,6 +1451,34 @@ mod tests {
         recipe_path
     }
 
+    fn triggered_job(id: &str, recipe_path: &Path, trigger: ScheduleTrigger) -> ScheduledJob {
+        ScheduledJob {
+            id: id.to_string(),
+            source: recipe_path.to_string_lossy().to_string(),
+            cron: String::new(),
+            trigger,
+            timezone: None,
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            current_session_id: None,
+            process_start_time: None,
+            retry: RetryPolicy::default(),
+            concurrency: ConcurrencyPolicy::default(),
+            catch_up: CatchUpPolicy::default(),
+            notifications: Vec::new(),
+        }
+    }
+
+    async fn last_run(scheduler: &Scheduler, id: &str) -> Option<DateTime<Utc>> {
+        scheduler
+            .list_scheduled_jobs()
+            .await
+            .into_iter()
+            .find(|job| job.id == id)
+            .and_then(|job| job.last_run)
+    }
+
     #[tokio::test]
     async fn test_job_runs_on_schedule() {
         let temp_dir = tempdir().unwrap();
@@ -895,11 +1490,17 @@ mod tests {
             id: "scheduled_job".to_string(),
             source: recipe_path.to_string_lossy().to_string(),
             cron: "* * * * * *".to_string(),
+            trigger: ScheduleTrigger::Cron,
+            timezone: None,
             last_run: None,
             currently_running: false,
             paused: false,
             current_session_id: None,
             process_start_time: None,
+            retry: RetryPolicy::default(),
+            concurrency: ConcurrencyPolicy::default(),
+            catch_up: CatchUpPolicy::default(),
+            notifications: Vec::new(),
         };
 
         scheduler.add_scheduled_job(job, true).await.unwrap();
@@ -920,11 +1521,17 @@ mod tests {
             id: "paused_job".to_string(),
             source: recipe_path.to_string_lossy().to_string(),
             cron: "* * * * * *".to_string(),
+            trigger: ScheduleTrigger::Cron,
+            timezone: None,
             last_run: None,
             currently_running: false,
             paused: false,
             current_session_id: None,
             process_start_time: None,
+            retry: RetryPolicy::default(),
+            concurrency: ConcurrencyPolicy::default(),
+            catch_up: CatchUpPolicy::default(),
+            notifications: Vec::new(),
         };
 
         scheduler.add_scheduled_job(job, true).await.unwrap();
@@ -934,4 +1541,324 @@ mod tests {
         let jobs = scheduler.list_scheduled_jobs().await;
         assert!(jobs[0].last_run.is_none(), "Paused job should not run");
     }
+
+    #[tokio::test]
+    async fn test_chained_jobs_follow_upstream_outcome() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        // The test recipe has no title, so every run of it fails
+        let recipe_path = create_test_recipe(temp_dir.path(), "chain");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        scheduler
+            .add_scheduled_job(
+                triggered_job("upstream", &recipe_path, ScheduleTrigger::new_webhook()),
+                false,
+            )
+            .await
+            .unwrap();
+        for (id, on) in [
+            ("on_failure", ChainCondition::Failure),
+            ("on_success", ChainCondition::Success),
+        ] {
+            let trigger = ScheduleTrigger::AfterJob {
+                job_id: "upstream".to_string(),
+                on,
+            };
+            scheduler
+                .add_scheduled_job(triggered_job(id, &recipe_path, trigger), false)
+                .await
+                .unwrap();
+        }
+
+        assert!(scheduler.run_now("upstream").await.is_err());
+        sleep(Duration::from_millis(500)).await;
+
+        assert!(last_run(&scheduler, "on_failure").await.is_some());
+        assert!(last_run(&scheduler, "on_success").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chain_rejects_missing_upstream_and_removed_upstream() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "cycle");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+        let after = |job_id: &str| ScheduleTrigger::AfterJob {
+            job_id: job_id.to_string(),
+            on: ChainCondition::Always,
+        };
+
+        let result = scheduler
+            .add_scheduled_job(triggered_job("a", &recipe_path, after("missing")), false)
+            .await;
+        assert!(matches!(result, Err(SchedulerError::InvalidTrigger(_))));
+
+        scheduler
+            .add_scheduled_job(
+                triggered_job("a", &recipe_path, ScheduleTrigger::new_webhook()),
+                false,
+            )
+            .await
+            .unwrap();
+        scheduler
+            .add_scheduled_job(triggered_job("b", &recipe_path, after("a")), false)
+            .await
+            .unwrap();
+        scheduler
+            .add_scheduled_job(triggered_job("c", &recipe_path, after("b")), false)
+            .await
+            .unwrap();
+
+        // Removing a job others run after would leave them waiting on nothing
+        let error = scheduler
+            .remove_scheduled_job("a", false)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("b run after it"), "{}", error);
+        assert_eq!(scheduler.list_scheduled_jobs().await.len(), 3);
+
+        scheduler.remove_scheduled_job("c", false).await.unwrap();
+        scheduler.remove_scheduled_job("b", false).await.unwrap();
+        scheduler.remove_scheduled_job("a", false).await.unwrap();
+        assert!(scheduler.list_scheduled_jobs().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_job_runs_in_background() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "hook");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        scheduler
+            .add_scheduled_job(
+                triggered_job("hook", &recipe_path, ScheduleTrigger::new_webhook()),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let params = HashMap::from([("name".to_string(), "goose".to_string())]);
+        scheduler.trigger_job("hook", params.clone()).await.unwrap();
+        sleep(Duration::from_millis(500)).await;
+        assert!(last_run(&scheduler, "hook").await.is_some());
+
+        scheduler.pause_schedule("hook").await.unwrap();
+        assert!(scheduler.trigger_job("hook", params).await.is_err());
+        assert!(matches!(
+            scheduler.trigger_job("missing", HashMap::new()).await,
+            Err(SchedulerError::JobNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_schedule_rejects_non_cron_jobs() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "watched");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+        let trigger = ScheduleTrigger::FileWatch {
+            path: format!("{}/*.csv", temp_dir.path().display()),
+        };
+
+        scheduler
+            .add_scheduled_job(triggered_job("watched", &recipe_path, trigger), false)
+            .await
+            .unwrap();
+
+        let result = scheduler
+            .update_schedule("watched", "0 * * * * *".to_string())
+            .await;
+        assert!(matches!(result, Err(SchedulerError::InvalidTrigger(_))));
+        scheduler
+            .remove_scheduled_job("watched", false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_runs_are_retried_and_recorded() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        // The test recipe has no title, so every attempt fails
+        let recipe_path = create_test_recipe(temp_dir.path(), "flaky");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        let mut job = triggered_job("flaky", &recipe_path, ScheduleTrigger::new_webhook());
+        job.retry = RetryPolicy {
+            max_retries: 2,
+            backoff_seconds: 0,
+        };
+        scheduler.add_scheduled_job(job, false).await.unwrap();
+
+        assert!(scheduler.run_now("flaky").await.is_err());
+
+        let runs = scheduler.runs("flaky", 10).await.unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(
+            runs.iter().map(|run| run.attempt).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+        assert!(runs.iter().all(|run| run.status == JobRunStatus::Failed
+            && run.error.is_some()
+            && run.run_id == runs[0].run_id));
+        assert_eq!(scheduler.runs("flaky", 1).await.unwrap().len(), 1);
+        assert!(matches!(
+            scheduler.runs("missing", 10).await,
+            Err(SchedulerError::JobNotFound(_))
+        ));
+
+        scheduler
+            .remove_scheduled_job("flaky", false)
+            .await
+            .unwrap();
+        assert!(scheduler.history.list("flaky", 10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_policy_for_running_job() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "busy");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        for (id, concurrency) in [
+            ("skip", ConcurrencyPolicy::Skip),
+            ("queue", ConcurrencyPolicy::Queue),
+        ] {
+            let mut job = triggered_job(id, &recipe_path, ScheduleTrigger::new_webhook());
+            job.concurrency = concurrency;
+            scheduler.add_scheduled_job(job, false).await.unwrap();
+            // Simulate a run that is still in progress
+            scheduler
+                .jobs
+                .lock()
+                .await
+                .get_mut(id)
+                .unwrap()
+                .1
+                .currently_running = true;
+        }
+
+        assert!(scheduler.trigger_job("skip", HashMap::new()).await.is_err());
+        scheduler
+            .runner()
+            .run_triggered("skip".to_string(), HashMap::new())
+            .await;
+        let runs = scheduler.runs("skip", 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status, JobRunStatus::Skipped);
+
+        scheduler
+            .trigger_job("queue", HashMap::new())
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(200)).await;
+        assert!(scheduler.runs("queue", 10).await.unwrap().is_empty());
+        assert_eq!(scheduler.queued_runs.lock().await["queue"].len(), 1);
+
+        scheduler
+            .jobs
+            .lock()
+            .await
+            .get_mut("queue")
+            .unwrap()
+            .1
+            .currently_running = false;
+        scheduler.runner().start_queued_run("queue").await;
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(scheduler.runs("queue", 10).await.unwrap().len(), 1);
+        assert!(!scheduler.queued_runs.lock().await.contains_key("queue"));
+    }
+
+    #[tokio::test]
+    async fn test_add_scheduled_job_normalizes_schedule_and_checks_timezone() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "weekdays");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        let mut job = triggered_job("weekdays", &recipe_path, ScheduleTrigger::Cron);
+        job.cron = "every weekday at 9am".to_string();
+        job.timezone = Some("Europe/Berlin".to_string());
+        scheduler
+            .add_scheduled_job(job.clone(), false)
+            .await
+            .unwrap();
+        let stored = scheduler.list_scheduled_jobs().await;
+        assert_eq!(stored[0].cron, "0 0 9 * * 1-5");
+
+        job.id = "mars".to_string();
+        job.timezone = Some("Mars/Olympus".to_string());
+        assert!(matches!(
+            scheduler.add_scheduled_job(job, false).await,
+            Err(SchedulerError::CronParseError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missed_runs_at_startup_follow_catch_up_policy() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "missed");
+
+        let jobs: Vec<ScheduledJob> = [
+            ("skip", CatchUpPolicy::Skip),
+            ("once", CatchUpPolicy::RunOnce),
+            ("all", CatchUpPolicy::RunAll),
+        ]
+        .into_iter()
+        .map(|(id, catch_up)| {
+            let mut job = triggered_job(id, &recipe_path, ScheduleTrigger::Cron);
+            job.cron = "0 0 * * * *".to_string();
+            job.timezone = Some("UTC".to_string());
+            job.last_run = Some(Utc::now() - chrono::Duration::hours(3));
+            job.catch_up = catch_up;
+            job
+        })
+        .collect();
+        fs::write(&storage_path, serde_json::to_string(&jobs).unwrap()).unwrap();
+
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+        sleep(Duration::from_millis(500)).await;
+
+        let skipped = scheduler.runs("skip", 10).await.unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].status, JobRunStatus::Skipped);
+        assert_eq!(scheduler.runs("once", 10).await.unwrap().len(), 1);
+        let all = scheduler.runs("all", 10).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|run| run.status == JobRunStatus::Failed));
+    }
+
+    #[tokio::test]
+    async fn test_late_tick_within_grace_still_runs() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedules.json");
+        let recipe_path = create_test_recipe(temp_dir.path(), "late");
+        let scheduler = Scheduler::new(storage_path).await.unwrap();
+
+        // Every minute always has a fire time within the grace period
+        let mut job = triggered_job("late", &recipe_path, ScheduleTrigger::Cron);
+        job.cron = "0 * * * * *".to_string();
+        job.catch_up = CatchUpPolicy::Skip;
+        scheduler
+            .jobs
+            .lock()
+            .await
+            .insert(job.id.clone(), (None, job));
+        scheduler
+            .cron_ticks
+            .lock()
+            .await
+            .insert("late".to_string(), Utc::now() - chrono::Duration::hours(3));
+
+        scheduler.runner().run_cron_tick("late".to_string()).await;
+
+        let runs = scheduler.runs("late", 10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().any(|run| run.status == JobRunStatus::Skipped));
+        assert!(runs.iter().any(|run| run.status == JobRunStatus::Failed));
+    }
 }
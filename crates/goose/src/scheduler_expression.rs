@@ -0,0 +1,359 @@
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+
+/// Number of upcoming fire times shown when validating a schedule.
+pub const DEFAULT_PREVIEW_COUNT: usize = 5;
+
+const SHORTHANDS: [(&str, &str); 7] = [
+    ("@yearly", "0 0 0 1 1 *"),
+    ("@annually", "0 0 0 1 1 *"),
+    ("@monthly", "0 0 0 1 * *"),
+    ("@weekly", "0 0 0 * * 0"),
+    ("@daily", "0 0 0 * * *"),
+    ("@midnight", "0 0 0 * * *"),
+    ("@hourly", "0 0 * * * *"),
+];
+
+const WEEKDAYS: [(&str, u32); 7] = [
+    ("sunday", 0),
+    ("monday", 1),
+    ("tuesday", 2),
+    ("wednesday", 3),
+    ("thursday", 4),
+    ("friday", 5),
+    ("saturday", 6),
+];
+
+/// Turns a cron expression or a natural-language schedule such as "every weekday at 9am" or
+/// "every 15 minutes" into the 6-field cron format (with seconds) the scheduler runs on.
+pub fn parse_schedule_expression(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("schedule cannot be empty".to_string());
+    }
+
+    let cron = if looks_like_cron(input) {
+        normalize_cron(input)?
+    } else {
+        parse_natural_language(input)?
+    };
+    compile(&cron)?;
+    Ok(cron)
+}
+
+/// Cron expressions start with a shorthand or a numeric field, phrases start with a word.
+fn looks_like_cron(input: &str) -> bool {
+    input
+        .chars()
+        .next()
+        .is_some_and(|c| c == '@' || c == '*' || c == '?' || c.is_ascii_digit())
+}
+
+fn normalize_cron(input: &str) -> Result<String, String> {
+    if input.starts_with('@') {
+        let lowered = input.to_lowercase();
+        return SHORTHANDS
+            .iter()
+            .find(|(name, _)| *name == lowered)
+            .map(|(_, cron)| cron.to_string())
+            .ok_or_else(|| format!("unknown cron shorthand '{}'", input));
+    }
+
+    let fields: Vec<&str> = input.split_whitespace().collect();
+    match fields.len() {
+        5 => Ok(format!("0 {}", fields.join(" "))),
+        6 => Ok(fields.join(" ")),
+        n => Err(format!(
+            "invalid cron expression '{}': expected 5 or 6 fields, got {}",
+            input, n
+        )),
+    }
+}
+
+fn compile(cron: &str) -> Result<Cron, String> {
+    // Same options tokio-cron-scheduler uses, so previews match what actually runs
+    Cron::new(cron)
+        .with_seconds_required()
+        .with_dom_and_dow()
+        .parse()
+        .map_err(|e| format!("invalid cron expression '{}': {}", cron, e))
+}
+
+fn parse_natural_language(input: &str) -> Result<String, String> {
+    let text = input.to_lowercase();
+    let (schedule, time) = match text.split_once(" at ") {
+        Some((schedule, time)) => (schedule.trim(), Some(time.trim())),
+        None => (text.trim(), None),
+    };
+    let (hour, minute) = match time {
+        Some(time) => parse_time_of_day(time)?,
+        None => (0, 0),
+    };
+
+    let schedule = schedule.strip_prefix("every ").unwrap_or(schedule);
+    let words: Vec<&str> = schedule
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty() && *word != "and")
+        .collect();
+
+    let interval_only = |cron: String| {
+        if time.is_some() {
+            Err(format!(
+                "'{}' runs on an interval and cannot have a time of day",
+                input
+            ))
+        } else {
+            Ok(cron)
+        }
+    };
+
+    match words.as_slice() {
+        ["second"] => interval_only("* * * * * *".to_string()),
+        ["minute"] => interval_only("0 * * * * *".to_string()),
+        ["hour"] | ["hourly"] => interval_only("0 0 * * * *".to_string()),
+        [count, unit] if count.chars().all(|c| c.is_ascii_digit()) => {
+            let count: u32 = count.parse().map_err(|_| unrecognized_schedule(input))?;
+            let cron = match *unit {
+                "second" | "seconds" | "sec" | "secs" => {
+                    format!("{} * * * * *", interval(count, 59, "seconds")?)
+                }
+                "minute" | "minutes" | "min" | "mins" => {
+                    format!("0 {} * * * *", interval(count, 59, "minutes")?)
+                }
+                "hour" | "hours" | "hr" | "hrs" => {
+                    format!("0 0 {} * * *", interval(count, 23, "hours")?)
+                }
+                _ => return Err(unrecognized_schedule(input)),
+            };
+            interval_only(cron)
+        }
+        ["day"] | ["daily"] => Ok(format!("0 {} {} * * *", minute, hour)),
+        ["weekday"] | ["weekdays"] => Ok(format!("0 {} {} * * 1-5", minute, hour)),
+        ["weekend"] | ["weekends"] => Ok(format!("0 {} {} * * 0,6", minute, hour)),
+        ["week"] | ["weekly"] => Ok(format!("0 {} {} * * 0", minute, hour)),
+        ["month"] | ["monthly"] => Ok(format!("0 {} {} 1 * *", minute, hour)),
+        ["month", "on", "the", day] | ["month", "on", day] => Ok(format!(
+            "0 {} {} {} * *",
+            minute,
+            hour,
+            parse_day_of_month(day)?
+        )),
+        days => {
+            let days = days
+                .iter()
+                .map(|day| parse_weekday(day).ok_or_else(|| unrecognized_schedule(input)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let days: Vec<String> = days.iter().map(u32::to_string).collect();
+            Ok(format!("0 {} {} * * {}", minute, hour, days.join(",")))
+        }
+    }
+}
+
+fn interval(count: u32, max: u32, unit: &str) -> Result<String, String> {
+    match count {
+        0 => Err(format!("interval of 0 {} is not allowed", unit)),
+        1 => Ok("*".to_string()),
+        n if n <= max => Ok(format!("*/{}", n)),
+        n => Err(format!(
+            "every {} {} is too long; use a larger unit instead",
+            n, unit
+        )),
+    }
+}
+
+fn unrecognized_schedule(input: &str) -> String {
+    format!(
+        "could not understand schedule '{}'. Try a cron expression or phrases like \
+         'every 15 minutes', 'every day at 9am', 'every weekday at 17:30' or 'every monday and friday at noon'",
+        input
+    )
+}
+
+fn parse_weekday(word: &str) -> Option<u32> {
+    let word = word.strip_suffix('s').unwrap_or(word);
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| word.len() >= 3 && name.starts_with(word))
+        .map(|(_, number)| *number)
+}
+
+fn parse_day_of_month(word: &str) -> Result<u32, String> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    match digits.parse::<u32>() {
+        Ok(day) if (1..=31).contains(&day) => Ok(day),
+        _ => Err(format!("invalid day of month '{}'", word)),
+    }
+}
+
+/// Parses times like "9am", "9:30 pm", "17:45", "noon" and "midnight" into (hour, minute).
+fn parse_time_of_day(time: &str) -> Result<(u32, u32), String> {
+    let compact: String = time.chars().filter(|c| !c.is_whitespace()).collect();
+    match compact.as_str() {
+        "noon" => return Ok((12, 0)),
+        "midnight" => return Ok((0, 0)),
+        _ => {}
+    }
+
+    let (clock, meridiem) = if let Some(clock) = compact.strip_suffix("am") {
+        (clock, Some(false))
+    } else if let Some(clock) = compact.strip_suffix("pm") {
+        (clock, Some(true))
+    } else {
+        (compact.as_str(), None)
+    };
+
+    let invalid = || format!("invalid time of day '{}'", time);
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (
+            hour.parse::<u32>().map_err(|_| invalid())?,
+            minute.parse::<u32>().map_err(|_| invalid())?,
+        ),
+        None => (clock.parse::<u32>().map_err(|_| invalid())?, 0),
+    };
+    if minute > 59 {
+        return Err(invalid());
+    }
+
+    let hour = match meridiem {
+        Some(pm) => {
+            if !(1..=12).contains(&hour) {
+                return Err(invalid());
+            }
+            match (hour, pm) {
+                (12, false) => 0,
+                (12, true) => 12,
+                (hour, true) => hour + 12,
+                (hour, false) => hour,
+            }
+        }
+        None if hour <= 23 => hour,
+        None => return Err(invalid()),
+    };
+    Ok((hour, minute))
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>().map_err(|_| {
+        format!(
+            "unknown timezone '{}', expected an IANA name such as 'Europe/Berlin'",
+            name
+        )
+    })
+}
+
+/// The next `count` times the schedule fires after `after`, in the job's timezone or local
+/// time when none is set.
+pub fn next_fire_times(
+    cron: &str,
+    timezone: Option<&str>,
+    after: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<DateTime<FixedOffset>>, String> {
+    let schedule = compile(cron)?;
+    Ok(match timezone {
+        Some(name) => upcoming(
+            &schedule,
+            after.with_timezone(&parse_timezone(name)?),
+            count,
+        ),
+        None => upcoming(&schedule, after.with_timezone(&Local), count),
+    })
+}
+
+fn upcoming<T: TimeZone>(
+    schedule: &Cron,
+    after: DateTime<T>,
+    count: usize,
+) -> Vec<DateTime<FixedOffset>> {
+    schedule
+        .iter_after(after)
+        .take(count)
+        .map(|time| time.fixed_offset())
+        .collect()
+}
+
+/// Fire times in `(after, until]`, at most `limit` of them. Used to find runs that were
+/// missed while goose was not running or the machine was asleep.
+pub fn fire_times_between(
+    cron: &str,
+    timezone: Option<&str>,
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+    limit: usize,
+) -> Result<Vec<DateTime<Utc>>, String> {
+    if until <= after {
+        return Ok(Vec::new());
+    }
+    let times = next_fire_times(cron, timezone, after, limit)?;
+    Ok(times
+        .into_iter()
+        .map(|time| time.with_timezone(&Utc))
+        .take_while(|time| *time <= until)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use test_case::test_case;
+
+    #[test_case("0 9 * * 1-5", "0 0 9 * * 1-5"; "five field cron")]
+    #[test_case("30 0 9 * * *", "30 0 9 * * *"; "six field cron")]
+    #[test_case("@hourly", "0 0 * * * *"; "shorthand")]
+    #[test_case("every weekday at 9am", "0 0 9 * * 1-5"; "weekdays")]
+    #[test_case("every 15 minutes", "0 */15 * * * *"; "minute interval")]
+    #[test_case("every minute", "0 * * * * *"; "every minute")]
+    #[test_case("every 2 hours", "0 0 */2 * * *"; "hour interval")]
+    #[test_case("every 30 seconds", "*/30 * * * * *"; "second interval")]
+    #[test_case("daily at 17:30", "0 30 17 * * *"; "daily")]
+    #[test_case("every day at 12am", "0 0 0 * * *"; "midnight meridiem")]
+    #[test_case("every monday and friday at 9:15 pm", "0 15 21 * * 1,5"; "weekday list")]
+    #[test_case("every tue, thu", "0 0 0 * * 2,4"; "abbreviated weekdays")]
+    #[test_case("Every Sunday at noon", "0 0 12 * * 0"; "case insensitive")]
+    #[test_case("every month on the 15th at 8am", "0 0 8 15 * *"; "day of month")]
+    #[test_case("every weekend", "0 0 0 * * 0,6"; "weekend default time")]
+    fn test_parse_schedule_expression(input: &str, expected: &str) {
+        assert_eq!(parse_schedule_expression(input).unwrap(), expected);
+    }
+
+    #[test_case("every 90 minutes"; "interval too long")]
+    #[test_case("every 15 minutes at 9am"; "interval with time")]
+    #[test_case("every day at 25:00"; "bad hour")]
+    #[test_case("every day at 13pm"; "bad meridiem")]
+    #[test_case("whenever you like"; "gibberish")]
+    #[test_case("@fortnightly"; "unknown shorthand")]
+    #[test_case("61 * * * *"; "out of range cron")]
+    fn test_parse_schedule_expression_errors(input: &str) {
+        assert!(parse_schedule_expression(input).is_err());
+    }
+
+    #[test]
+    fn test_next_fire_times_in_timezone() {
+        let after = Utc.with_ymd_and_hms(2024, 1, 5, 20, 0, 0).unwrap(); // a Friday
+        let times = next_fire_times("0 0 9 * * 1-5", Some("America/New_York"), after, 3).unwrap();
+        assert_eq!(times.len(), 3);
+        // Next weekday 9am in New York is Monday, 14:00 UTC in winter
+        assert_eq!(times[0].with_timezone(&Utc).day(), 8);
+        assert_eq!(times[0].with_timezone(&Utc).hour(), 14);
+        assert_eq!(times[0].hour(), 9);
+
+        assert!(next_fire_times("0 0 9 * * *", Some("Mars/Olympus"), after, 1).is_err());
+    }
+
+    #[test]
+    fn test_fire_times_between() {
+        let after = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+        let missed = fire_times_between("0 0 * * * *", Some("UTC"), after, until, 10).unwrap();
+        assert_eq!(missed.len(), 3);
+        assert_eq!(missed[2], until);
+
+        let capped = fire_times_between("0 0 * * * *", Some("UTC"), after, until, 2).unwrap();
+        assert_eq!(capped.len(), 2);
+        assert!(fire_times_between("0 0 * * * *", None, until, after, 10)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tokio::sync::broadcast;
+
+use crate::subprocess::configure_command_no_window;
+
+/// Runs kept per job; older entries are dropped when the history is appended to.
+pub const MAX_RUNS_PER_JOB: usize = 200;
+/// Runs a job's history file may hold past `MAX_RUNS_PER_JOB` before it is rewritten, so
+/// appending rarely has to rewrite the file.
+const RUNS_BEFORE_COMPACTING: usize = 50;
+/// Final output stored with a run is cut to this many characters.
+const MAX_RUN_OUTPUT_CHARS: usize = 4000;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The trigger fired while the job was already running and its concurrency policy
+    /// did not allow another run
+    Skipped,
+}
+
+impl fmt::Display for JobRunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            JobRunStatus::Succeeded => "succeeded",
+            JobRunStatus::Failed => "failed",
+            JobRunStatus::Cancelled => "cancelled",
+            JobRunStatus::Skipped => "skipped",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// One attempt at running a scheduled job. Retries of the same trigger share a `run_id`
+/// and are told apart by `attempt`.
+#[derive(Clone, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct JobRun {
+    pub run_id: String,
+    pub job_id: String,
+    pub attempt: u32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub status: JobRunStatus,
+    pub error: Option<String>,
+    pub session_id: Option<String>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub total_tokens: Option<i32>,
+    /// Text of the last assistant message
+    pub output: Option<String>,
+    /// Worker that ran the attempt, when runs are handed to `goosed worker` processes
+    #[serde(default)]
+    pub worker: Option<String>,
+}
+
+impl JobRun {
+    pub fn new(job_id: &str, run_id: &str, attempt: u32) -> Self {
+        let now = Utc::now();
+        JobRun {
+            run_id: run_id.to_string(),
+            job_id: job_id.to_string(),
+            attempt,
+            started_at: now,
+            finished_at: now,
+            status: JobRunStatus::Succeeded,
+            error: None,
+            session_id: None,
+            input_tokens: None,
+            output_tokens: None,
+            total_tokens: None,
+            output: None,
+            worker: None,
+        }
+    }
+
+    pub fn set_output(&mut self, output: &str) {
+        self.output = Some(output.chars().take(MAX_RUN_OUTPUT_CHARS).collect());
+    }
+
+    pub fn finish(&mut self, status: JobRunStatus, error: Option<String>) {
+        self.finished_at = Utc::now();
+        self.status = status;
+        self.error = error;
+        let duration = (self.finished_at - self.started_at)
+            .to_std()
+            .unwrap_or_default();
+        crate::metrics::record_scheduled_run(status, duration);
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Extra attempts after a failed run; 0 disables retries
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further attempt
+    pub backoff_seconds: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            backoff_seconds: 60,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after the given failed attempt (1-based), capped at an hour.
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(self.backoff_seconds.saturating_mul(factor)).min(MAX_RETRY_DELAY)
+    }
+}
+
+/// What to do when a job is triggered while a previous run is still going.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyPolicy {
+    /// Drop the new trigger and record a skipped run
+    #[default]
+    Skip,
+    /// Run the new trigger once the current run finishes
+    Queue,
+    /// Start the new run right away, alongside the current one
+    Allow,
+}
+
+impl fmt::Display for ConcurrencyPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = match self {
+            ConcurrencyPolicy::Skip => "skip",
+            ConcurrencyPolicy::Queue => "queue",
+            ConcurrencyPolicy::Allow => "allow",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl FromStr for ConcurrencyPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(ConcurrencyPolicy::Skip),
+            "queue" => Ok(ConcurrencyPolicy::Queue),
+            "allow" => Ok(ConcurrencyPolicy::Allow),
+            _ => Err(format!(
+                "unknown concurrency policy '{}'. Expected skip, queue or allow",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do about cron fire times that were missed because goose was not running or the
+/// machine was asleep.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Record the missed runs as skipped and wait for the next fire time
+    #[default]
+    Skip,
+    /// Run once, however many fire times were missed
+    RunOnce,
+    /// Run once for every missed fire time, up to a limit
+    RunAll,
+}
+
+impl fmt::Display for CatchUpPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run-once",
+            CatchUpPolicy::RunAll => "run-all",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl FromStr for CatchUpPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace('_', "-").as_str() {
+            "skip" => Ok(CatchUpPolicy::Skip),
+            "run-once" => Ok(CatchUpPolicy::RunOnce),
+            "run-all" => Ok(CatchUpPolicy::RunAll),
+            _ => Err(format!(
+                "unknown catch-up policy '{}'. Expected skip, run-once or run-all",
+                s
+            )),
+        }
+    }
+}
+
+/// Where to report a run that failed after all of its retries.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FailureNotification {
+    Desktop,
+    /// Shell command run with GOOSE_SCHEDULE_ID, GOOSE_RUN_ID, GOOSE_RUN_ERROR and
+    /// GOOSE_SESSION_ID set
+    Command {
+        command: String,
+    },
+    /// URL that receives the failed run as a JSON POST
+    Webhook {
+        url: String,
+    },
+}
+
+impl fmt::Display for FailureNotification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureNotification::Desktop => write!(f, "desktop"),
+            FailureNotification::Command { command } => write!(f, "command:{}", command),
+            FailureNotification::Webhook { url } => write!(f, "webhook:{}", url),
+        }
+    }
+}
+
+/// Parses the `--notify` syntax used by the CLI: `desktop`, `command:<cmd>` or `webhook:<url>`.
+impl FromStr for FailureNotification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "desktop" => Ok(FailureNotification::Desktop),
+            Some(("command", command)) if !command.trim().is_empty() => {
+                Ok(FailureNotification::Command {
+                    command: command.to_string(),
+                })
+            }
+            Some(("webhook", url)) => {
+                let parsed =
+                    url::Url::parse(url).map_err(|e| format!("invalid webhook URL: {}", e))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err("webhook URL must use http or https".to_string());
+                }
+                Ok(FailureNotification::Webhook {
+                    url: url.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "unknown notification '{}'. Expected desktop, command:<cmd> or webhook:<url>",
+                s
+            )),
+        }
+    }
+}
+
+fn finished_runs() -> &'static broadcast::Sender<JobRun> {
+    static FINISHED_RUNS: OnceLock<broadcast::Sender<JobRun>> = OnceLock::new();
+    FINISHED_RUNS.get_or_init(|| broadcast::channel(64).0)
+}
+
+/// Receives the last attempt of every run once it ended, after any retries.
+pub fn subscribe_finished_runs() -> broadcast::Receiver<JobRun> {
+    finished_runs().subscribe()
+}
+
+pub(crate) fn publish_finished_run(run: &JobRun) {
+    // Nobody listening is fine
+    let _ = finished_runs().send(run.clone());
+}
+
+/// Sends every notification for a failed run. Delivery problems are logged and otherwise
+/// ignored so they never affect the job itself.
+pub async fn notify_failure(notifications: &[FailureNotification], run: &JobRun) {
+    for notification in notifications {
+        let result = match notification {
+            FailureNotification::Desktop => send_desktop_notification(run).await,
+            FailureNotification::Command { command } => {
+                run_notification_command(command, run).await
+            }
+            FailureNotification::Webhook { url } => post_notification_webhook(url, run).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(
+                "Failed to send {} notification for job '{}': {}",
+                notification,
+                run.job_id,
+                e
+            );
+        }
+    }
+}
+
+fn notification_message(run: &JobRun) -> String {
+    format!(
+        "Job '{}' failed after {} attempt(s): {}",
+        run.job_id,
+        run.attempt,
+        run.error.as_deref().unwrap_or("unknown error")
+    )
+}
+
+async fn send_desktop_notification(run: &JobRun) -> anyhow::Result<()> {
+    let title = "goose scheduled job failed";
+    let message = notification_message(run);
+
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            message.replace('"', "'"),
+            title
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "[void][System.Reflection.Assembly]::LoadWithPartialName('System.Windows.Forms'); \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Warning; $n.Visible = $true; \
+             $n.ShowBalloonTip(10000, '{}', '{}', 'Error'); Start-Sleep -Seconds 10; $n.Dispose()",
+            title,
+            message.replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args([title, &message]);
+        command
+    };
+    configure_command_no_window(&mut command);
+
+    let status = tokio::time::timeout(NOTIFICATION_TIMEOUT, command.status()).await??;
+    if !status.success() {
+        anyhow::bail!("notification command exited with {}", status);
+    }
+    Ok(())
+}
+
+async fn run_notification_command(command_line: &str, run: &JobRun) -> anyhow::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", command_line]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", command_line]);
+        command
+    };
+    command
+        .env("GOOSE_SCHEDULE_ID", &run.job_id)
+        .env("GOOSE_RUN_ID", &run.run_id)
+        .env("GOOSE_RUN_ERROR", run.error.as_deref().unwrap_or_default())
+        .env(
+            "GOOSE_SESSION_ID",
+            run.session_id.as_deref().unwrap_or_default(),
+        )
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    configure_command_no_window(&mut command);
+
+    let output = tokio::time::timeout(NOTIFICATION_TIMEOUT, command.output()).await??;
+    if !output.status.success() {
+        anyhow::bail!(
+            "command exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+async fn post_notification_webhook(url: &str, run: &JobRun) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(NOTIFICATION_TIMEOUT)
+        .build()?;
+    let body = serde_json::json!({
+        "event": "schedule.run_failed",
+        "message": notification_message(run),
+        "run": run,
+    });
+    client
+        .post(url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Run history stored as one JSON Lines file per job, oldest run first.
+#[derive(Clone, Debug)]
+pub struct RunHistory {
+    dir: PathBuf,
+    /// Runs in each job's file, counted when the job is first appended to so later appends
+    /// don't need to read the file
+    counts: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl RunHistory {
+    /// Keeps the history next to the scheduler's job storage file.
+    pub fn for_storage_path(storage_path: &Path) -> Self {
+        let parent = storage_path.parent().unwrap_or_else(|| Path::new("."));
+        RunHistory {
+            dir: parent.join("schedule_runs"),
+            counts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The job's history file, named after the job id with every byte but ASCII letters,
+    /// digits, `-` and `_` percent-encoded, so distinct ids never share a file.
+    fn path_for(&self, job_id: &str) -> PathBuf {
+        let mut file_name = String::with_capacity(job_id.len());
+        for byte in job_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                file_name.push(byte as char);
+            } else {
+                file_name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        self.dir.join(format!("{}.jsonl", file_name))
+    }
+
+    pub fn append(&self, run: &JobRun) -> io::Result<()> {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let count = match counts.get(&run.job_id) {
+            Some(count) => *count,
+            None => self.read_all(&run.job_id)?.len(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(&run.job_id);
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "{}", serde_json::to_string(run)?)?;
+        drop(file);
+
+        let mut count = count + 1;
+        if count > MAX_RUNS_PER_JOB + RUNS_BEFORE_COMPACTING {
+            let runs = self.read_all(&run.job_id)?;
+            let keep = &runs[runs.len().saturating_sub(MAX_RUNS_PER_JOB)..];
+            let mut data = String::new();
+            for run in keep {
+                data.push_str(&serde_json::to_string(run)?);
+                data.push('\n');
+            }
+            fs::write(&path, data)?;
+            count = keep.len();
+        }
+        counts.insert(run.job_id.clone(), count);
+        Ok(())
+    }
+
+    fn read_all(&self, job_id: &str) -> io::Result<Vec<JobRun>> {
+        let file = match fs::File::open(self.path_for(job_id)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut runs = Vec::new();
+        for line in io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(run) => runs.push(run),
+                Err(e) => tracing::warn!("Skipping unreadable run record for '{}': {}", job_id, e),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// The most recent runs of a job, newest first.
+    pub fn list(&self, job_id: &str, limit: usize) -> io::Result<Vec<JobRun>> {
+        let mut runs = self.read_all(job_id)?;
+        runs.reverse();
+        runs.truncate(limit.min(MAX_RUNS_PER_JOB));
+        Ok(runs)
+    }
+
+    pub fn remove(&self, job_id: &str) -> io::Result<()> {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts.remove(job_id);
+        match fs::remove_file(self.path_for(job_id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_retry_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            backoff_seconds: 30,
+        };
+        assert_eq!(policy.delay_after(1), Duration::from_secs(30));
+        assert_eq!(policy.delay_after(2), Duration::from_secs(60));
+        assert_eq!(policy.delay_after(3), Duration::from_secs(120));
+        assert_eq!(policy.delay_after(40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_parse_notifications() {
+        assert_eq!(
+            "desktop".parse::<FailureNotification>().unwrap(),
+            FailureNotification::Desktop
+        );
+        assert_eq!(
+            "command:echo failed"
+                .parse::<FailureNotification>()
+                .unwrap(),
+            FailureNotification::Command {
+                command: "echo failed".to_string()
+            }
+        );
+        assert_eq!(
+            "webhook:https://example.com/hook"
+                .parse::<FailureNotification>()
+                .unwrap(),
+            FailureNotification::Webhook {
+                url: "https://example.com/hook".to_string()
+            }
+        );
+        assert!("webhook:ftp://example.com"
+            .parse::<FailureNotification>()
+            .is_err());
+        assert!("command:".parse::<FailureNotification>().is_err());
+        assert!("email".parse::<FailureNotification>().is_err());
+    }
+
+    #[test]
+    fn test_history_is_newest_first_and_bounded() {
+        let dir = tempdir().unwrap();
+        let history = RunHistory::for_storage_path(&dir.path().join("schedules.json"));
+        assert!(history.list("job/with slash", 10).unwrap().is_empty());
+
+        for attempt in 1..=(MAX_RUNS_PER_JOB + RUNS_BEFORE_COMPACTING + 5) as u32 {
+            let mut run = JobRun::new("job/with slash", "run", attempt);
+            run.finish(JobRunStatus::Failed, Some("boom".to_string()));
+            history.append(&run).unwrap();
+        }
+
+        let runs = history.list("job/with slash", usize::MAX).unwrap();
+        assert_eq!(runs.len(), MAX_RUNS_PER_JOB);
+        assert_eq!(
+            runs[0].attempt,
+            (MAX_RUNS_PER_JOB + RUNS_BEFORE_COMPACTING + 5) as u32
+        );
+        assert_eq!(history.list("job/with slash", 3).unwrap().len(), 3);
+
+        // Cut back to MAX_RUNS_PER_JOB once past the slack, then appended to four more times
+        let path = history.path_for("job/with slash");
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, MAX_RUNS_PER_JOB + 4);
+
+        history.remove("job/with slash").unwrap();
+        assert!(history.list("job/with slash", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_files_are_distinct_per_job() {
+        let dir = tempdir().unwrap();
+        let history = RunHistory::for_storage_path(&dir.path().join("schedules.json"));
+        assert_ne!(history.path_for("a/b"), history.path_for("a_b"));
+        assert_ne!(history.path_for("a.b"), history.path_for("a%2Eb"));
+        assert_eq!(
+            history.path_for("../x").file_name().unwrap(),
+            "%2E%2E%2Fx.jsonl"
+        );
+
+        history.append(&JobRun::new("a/b", "run", 1)).unwrap();
+        assert_eq!(history.list("a/b", 10).unwrap().len(), 1);
+        assert!(history.list("a_b", 10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_command_notification_receives_run_details() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let mut run = JobRun::new("nightly", "run-1", 2);
+        run.finish(JobRunStatus::Failed, Some("provider error".to_string()));
+
+        let command = format!(
+            "echo \"$GOOSE_SCHEDULE_ID $GOOSE_RUN_ID $GOOSE_RUN_ERROR\" > {}",
+            out.display()
+        );
+        notify_failure(&[FailureNotification::Command { command }], &run).await;
+
+        assert_eq!(
+            fs::read_to_string(out).unwrap().trim(),
+            "nightly run-1 provider error"
+        );
+    }
+}
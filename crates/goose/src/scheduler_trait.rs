@@ -1,8 +1,10 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::scheduler::{ScheduledJob, SchedulerError};
+use crate::scheduler_runs::JobRun;
 use crate::session::Session;
 
 #[async_trait]
@@ -26,11 +28,17 @@ pub trait SchedulerTrait: Send + Sync {
     async fn pause_schedule(&self, id: &str) -> Result<(), SchedulerError>;
     async fn unpause_schedule(&self, id: &str) -> Result<(), SchedulerError>;
     async fn run_now(&self, id: &str) -> Result<String, SchedulerError>;
+    async fn trigger_job(
+        &self,
+        id: &str,
+        params: HashMap<String, String>,
+    ) -> Result<(), SchedulerError>;
     async fn sessions(
         &self,
         sched_id: &str,
         limit: usize,
     ) -> Result<Vec<(String, Session)>, SchedulerError>;
+    async fn runs(&self, sched_id: &str, limit: usize) -> Result<Vec<JobRun>, SchedulerError>;
     async fn update_schedule(&self, sched_id: &str, new_cron: String)
         -> Result<(), SchedulerError>;
     async fn kill_running_job(&self, sched_id: &str) -> Result<(), SchedulerError>;
@@ -0,0 +1,330 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Parameter passed to file watch runs, listing the changed paths one per line.
+pub const CHANGED_FILES_PARAM: &str = "changed_files";
+
+const WEBHOOK_TOKEN_LENGTH: usize = 32;
+const MIN_WEBHOOK_TOKEN_LENGTH: usize = 16;
+
+/// What starts a scheduled job. Jobs created before triggers existed have no `trigger` field
+/// and default to `Cron`, which fires on the job's `cron` expression.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    #[default]
+    Cron,
+    /// Runs when a file matching the glob is created, modified or removed
+    FileWatch { path: String },
+    /// Runs when the webhook endpoint is called with this token; the JSON body becomes the
+    /// recipe parameters
+    Webhook { token: String },
+    /// Runs after another job finishes
+    AfterJob {
+        job_id: String,
+        #[serde(default)]
+        on: ChainCondition,
+    },
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainCondition {
+    #[default]
+    Success,
+    Failure,
+    Always,
+}
+
+impl ChainCondition {
+    pub fn matches(&self, succeeded: bool) -> bool {
+        match self {
+            ChainCondition::Success => succeeded,
+            ChainCondition::Failure => !succeeded,
+            ChainCondition::Always => true,
+        }
+    }
+}
+
+impl ScheduleTrigger {
+    pub fn is_cron(&self) -> bool {
+        matches!(self, ScheduleTrigger::Cron)
+    }
+
+    pub fn new_webhook() -> Self {
+        let token = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(WEBHOOK_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        ScheduleTrigger::Webhook { token }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduleTrigger::Cron => Ok(()),
+            ScheduleTrigger::FileWatch { path } => {
+                if path.trim().is_empty() {
+                    return Err("file watch path cannot be empty".to_string());
+                }
+                glob::Pattern::new(&expand_watch_path(path))
+                    .map(|_| ())
+                    .map_err(|e| format!("invalid file watch glob '{}': {}", path, e))
+            }
+            ScheduleTrigger::Webhook { token } => {
+                if token.len() < MIN_WEBHOOK_TOKEN_LENGTH {
+                    Err(format!(
+                        "webhook token must be at least {} characters",
+                        MIN_WEBHOOK_TOKEN_LENGTH
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            ScheduleTrigger::AfterJob { job_id, .. } => {
+                if job_id.trim().is_empty() {
+                    Err("upstream job id cannot be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Constant-time comparison so the token cannot be guessed byte by byte from response times.
+    pub fn verify_webhook_token(&self, candidate: &str) -> bool {
+        match self {
+            ScheduleTrigger::Webhook { token } => {
+                token.len() == candidate.len()
+                    && token
+                        .bytes()
+                        .zip(candidate.bytes())
+                        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                        == 0
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleTrigger::Cron => write!(f, "cron"),
+            ScheduleTrigger::FileWatch { path } => write!(f, "watch:{}", path),
+            ScheduleTrigger::Webhook { .. } => write!(f, "webhook"),
+            ScheduleTrigger::AfterJob { job_id, on } => {
+                let on = match on {
+                    ChainCondition::Success => "success",
+                    ChainCondition::Failure => "failure",
+                    ChainCondition::Always => "always",
+                };
+                write!(f, "after:{}:{}", job_id, on)
+            }
+        }
+    }
+}
+
+/// Parses the `--trigger` syntax used by the CLI:
+/// `watch:<glob>`, `webhook[:<token>]`, `after:<job-id>[:success|failure|always]`.
+impl FromStr for ScheduleTrigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = match s.split_once(':') {
+            Some((kind, rest)) => (kind, Some(rest)),
+            None => (s, None),
+        };
+        let trigger = match (kind, rest) {
+            ("cron", None) => ScheduleTrigger::Cron,
+            ("watch", Some(path)) => ScheduleTrigger::FileWatch {
+                path: path.to_string(),
+            },
+            ("webhook", None) => ScheduleTrigger::new_webhook(),
+            ("webhook", Some(token)) => ScheduleTrigger::Webhook {
+                token: token.to_string(),
+            },
+            ("after", Some(rest)) => {
+                let (job_id, on) = match rest.rsplit_once(':') {
+                    Some((job_id, "success")) => (job_id, ChainCondition::Success),
+                    Some((job_id, "failure")) => (job_id, ChainCondition::Failure),
+                    Some((job_id, "always")) => (job_id, ChainCondition::Always),
+                    _ => (rest, ChainCondition::Success),
+                };
+                ScheduleTrigger::AfterJob {
+                    job_id: job_id.to_string(),
+                    on,
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "unknown trigger '{}'. Expected watch:<glob>, webhook[:<token>] or after:<job-id>[:success|failure|always]",
+                    s
+                ))
+            }
+        };
+        trigger.validate()?;
+        Ok(trigger)
+    }
+}
+
+pub fn expand_watch_path(path: &str) -> String {
+    shellexpand::tilde(path).into_owned()
+}
+
+/// Modification times of every file matching the watch glob.
+pub type WatchSnapshot = BTreeMap<String, Option<SystemTime>>;
+
+pub fn snapshot_watch_path(path: &str) -> WatchSnapshot {
+    let mut snapshot = WatchSnapshot::new();
+    let entries = match glob::glob(&expand_watch_path(path)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("Invalid file watch glob '{}': {}", path, e);
+            return snapshot;
+        }
+    };
+    for entry in entries.flatten() {
+        if entry.is_file() {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            snapshot.insert(entry.to_string_lossy().into_owned(), modified);
+        }
+    }
+    snapshot
+}
+
+/// Paths that were added, modified or removed between two snapshots, in sorted order.
+pub fn changed_paths(previous: &WatchSnapshot, current: &WatchSnapshot) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, modified)| previous.get(*path) != Some(*modified))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(
+        previous
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned(),
+    );
+    changed.sort();
+    changed
+}
+
+/// Converts a webhook JSON body into recipe parameters. Strings are passed through and other
+/// values are serialized as JSON.
+pub fn webhook_body_to_params(body: &serde_json::Value) -> Result<HashMap<String, String>, String> {
+    match body {
+        serde_json::Value::Null => Ok(HashMap::new()),
+        serde_json::Value::Object(map) => Ok(map
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect()),
+        _ => Err("webhook body must be a JSON object".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_triggers() {
+        assert_eq!(
+            "watch:/tmp/inbox/*.csv".parse::<ScheduleTrigger>().unwrap(),
+            ScheduleTrigger::FileWatch {
+                path: "/tmp/inbox/*.csv".to_string()
+            }
+        );
+        assert_eq!(
+            "after:nightly-build".parse::<ScheduleTrigger>().unwrap(),
+            ScheduleTrigger::AfterJob {
+                job_id: "nightly-build".to_string(),
+                on: ChainCondition::Success
+            }
+        );
+        assert_eq!(
+            "after:nightly-build:always"
+                .parse::<ScheduleTrigger>()
+                .unwrap(),
+            ScheduleTrigger::AfterJob {
+                job_id: "nightly-build".to_string(),
+                on: ChainCondition::Always
+            }
+        );
+
+        let webhook = "webhook".parse::<ScheduleTrigger>().unwrap();
+        match &webhook {
+            ScheduleTrigger::Webhook { token } => assert_eq!(token.len(), WEBHOOK_TOKEN_LENGTH),
+            other => panic!("expected webhook trigger, got {:?}", other),
+        }
+
+        assert!("webhook:short".parse::<ScheduleTrigger>().is_err());
+        assert!("watch:".parse::<ScheduleTrigger>().is_err());
+        assert!("sometimes".parse::<ScheduleTrigger>().is_err());
+    }
+
+    #[test]
+    fn test_legacy_job_defaults_to_cron() {
+        let trigger: ScheduleTrigger = serde_json::from_str(r#"{"type":"cron"}"#).unwrap();
+        assert!(trigger.is_cron());
+        assert_eq!(ScheduleTrigger::default(), ScheduleTrigger::Cron);
+    }
+
+    #[test]
+    fn test_verify_webhook_token() {
+        let trigger = ScheduleTrigger::Webhook {
+            token: "0123456789abcdef".to_string(),
+        };
+        assert!(trigger.verify_webhook_token("0123456789abcdef"));
+        assert!(!trigger.verify_webhook_token("0123456789abcdeX"));
+        assert!(!trigger.verify_webhook_token("0123"));
+        assert!(!ScheduleTrigger::Cron.verify_webhook_token("0123456789abcdef"));
+    }
+
+    #[test]
+    fn test_changed_paths() {
+        let dir = tempdir().unwrap();
+        let pattern = format!("{}/*.txt", dir.path().display());
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("ignored.md"), "x").unwrap();
+        let before = snapshot_watch_path(&pattern);
+        assert_eq!(before.len(), 1);
+        assert!(changed_paths(&before, &snapshot_watch_path(&pattern)).is_empty());
+
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        fs::remove_file(dir.path().join("a.txt")).unwrap();
+        let after = snapshot_watch_path(&pattern);
+        let changed = changed_paths(&before, &after);
+        assert_eq!(changed.len(), 2);
+        assert!(changed[0].ends_with("a.txt"));
+        assert!(changed[1].ends_with("b.txt"));
+    }
+
+    #[test]
+    fn test_webhook_body_to_params() {
+        let params =
+            webhook_body_to_params(&serde_json::json!({"repo": "goose", "pr": 42})).unwrap();
+        assert_eq!(params["repo"], "goose");
+        assert_eq!(params["pr"], "42");
+        assert!(webhook_body_to_params(&serde_json::Value::Null)
+            .unwrap()
+            .is_empty());
+        assert!(webhook_body_to_params(&serde_json::json!([1, 2])).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Hands scheduled runs to remote `goosed worker` processes instead of running them in the
+//! coordinator. Workers lease runs over HTTP and have to heartbeat before their lease
+//! expires; runs whose worker goes quiet are put back on the queue for another worker.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::Config;
+use crate::recipe::Recipe;
+
+/// Config key that makes the scheduler hand runs to workers instead of running them itself.
+pub const WORKERS_CONFIG_KEY: &str = "GOOSE_SCHEDULER_WORKERS";
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+const MAX_LEASE: Duration = Duration::from_secs(600);
+/// Lost leases tolerated before a run is failed, so a run that kills its worker can't take
+/// down the whole pool.
+const MAX_LEASE_EXPIRIES: u32 = 3;
+const LEASE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Workers not heard from for this long are dropped from the worker list.
+const WORKER_SEEN_WINDOW: chrono::Duration = chrono::Duration::minutes(10);
+
+/// A scheduled run handed to a worker.
+#[derive(Clone, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct WorkItem {
+    pub run_id: String,
+    pub job_id: String,
+    pub attempt: u32,
+    /// Session on the coordinator that receives the run's messages
+    pub session_id: String,
+    pub recipe: Recipe,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct WorkerInfo {
+    pub worker_id: String,
+    pub last_seen: DateTime<Utc>,
+    /// Run ids currently leased by the worker
+    pub running: Vec<String>,
+}
+
+/// A run handed to a worker by [`WorkerPool::lease`].
+#[derive(Clone, Debug)]
+pub struct LeasedRun {
+    pub item: WorkItem,
+    /// Set when an earlier worker lost the run, so its messages in the coordinator session
+    /// have to be cleared before this attempt adds its own
+    pub requeued: bool,
+}
+
+/// What a worker reported for a run.
+#[derive(Clone, Debug)]
+pub struct WorkResult {
+    pub worker_id: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+struct Lease {
+    worker_id: String,
+    expires_at: Instant,
+}
+
+struct Entry {
+    item: WorkItem,
+    done: oneshot::Sender<Result<WorkResult>>,
+    lease: Option<Lease>,
+    expiries: u32,
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Run ids waiting for a worker, oldest first
+    queue: VecDeque<String>,
+    entries: HashMap<String, Entry>,
+    workers: HashMap<String, DateTime<Utc>>,
+}
+
+#[derive(Default)]
+pub struct WorkerPool {
+    state: Mutex<PoolState>,
+}
+
+impl WorkerPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn global() -> &'static WorkerPool {
+        static POOL: OnceLock<WorkerPool> = OnceLock::new();
+        POOL.get_or_init(WorkerPool::new)
+    }
+
+    /// Whether scheduled runs go to workers, set with `GOOSE_SCHEDULER_WORKERS`.
+    pub fn enabled() -> bool {
+        Config::global()
+            .get_param::<bool>(WORKERS_CONFIG_KEY)
+            .unwrap_or(false)
+    }
+
+    /// Queues a run and waits until a worker reports its result. Cancelling the token, or
+    /// dropping the future, drops the run; the worker holding it finds out on its next
+    /// heartbeat.
+    pub async fn dispatch(
+        &self,
+        item: WorkItem,
+        cancel_token: CancellationToken,
+    ) -> Result<WorkResult> {
+        let run_id = item.run_id.clone();
+        let (done, mut result) = oneshot::channel();
+        let _withdraw = Withdraw {
+            pool: self,
+            run_id: &run_id,
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            state.queue.push_back(run_id.clone());
+            state.entries.insert(
+                run_id.clone(),
+                Entry {
+                    item,
+                    done,
+                    lease: None,
+                    expiries: 0,
+                },
+            );
+        }
+
+        loop {
+            tokio::select! {
+                outcome = &mut result => {
+                    return outcome.unwrap_or_else(|_| Err(anyhow!("Run {} was dropped", run_id)));
+                }
+                _ = cancel_token.cancelled() => bail!("Run {} was cancelled", run_id),
+                _ = tokio::time::sleep(LEASE_CHECK_INTERVAL) => self.expire_leases(Instant::now()),
+            }
+        }
+    }
+
+    /// Gives the oldest waiting run to the worker, if there is one.
+    pub fn lease(&self, worker_id: &str, lease: Duration) -> Option<LeasedRun> {
+        let now = Instant::now();
+        self.expire_leases(now);
+
+        let mut state = self.state.lock().unwrap();
+        state.workers.insert(worker_id.to_string(), Utc::now());
+        while let Some(run_id) = state.queue.pop_front() {
+            if let Some(entry) = state.entries.get_mut(&run_id) {
+                entry.lease = Some(Lease {
+                    worker_id: worker_id.to_string(),
+                    expires_at: now + lease.min(MAX_LEASE),
+                });
+                tracing::info!(
+                    "Leased run {} of job '{}' to worker {}",
+                    run_id,
+                    entry.item.job_id,
+                    worker_id
+                );
+                return Some(LeasedRun {
+                    item: entry.item.clone(),
+                    requeued: entry.expiries > 0,
+                });
+            }
+        }
+        None
+    }
+
+    /// Extends the worker's lease on a run and returns the coordinator session of the run.
+    /// Fails when the worker no longer holds the lease, because the run was cancelled or the
+    /// lease expired and the run went to someone else; the worker should then stop it.
+    pub fn heartbeat(&self, run_id: &str, worker_id: &str, lease: Duration) -> Result<String> {
+        let mut state = self.state.lock().unwrap();
+        state.workers.insert(worker_id.to_string(), Utc::now());
+        let entry = Self::leased_entry(&mut state, run_id, worker_id)?;
+        if let Some(current) = entry.lease.as_mut() {
+            current.expires_at = Instant::now() + lease.min(MAX_LEASE);
+        }
+        Ok(entry.item.session_id.clone())
+    }
+
+    /// Records the result of a run. Same lease rules as `heartbeat`.
+    pub fn complete(
+        &self,
+        run_id: &str,
+        worker_id: &str,
+        output: Option<String>,
+        error: Option<String>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.workers.insert(worker_id.to_string(), Utc::now());
+        Self::leased_entry(&mut state, run_id, worker_id)?;
+        if let Some(entry) = state.entries.remove(run_id) {
+            let _ = entry.done.send(Ok(WorkResult {
+                worker_id: worker_id.to_string(),
+                output,
+                error,
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn workers(&self) -> Vec<WorkerInfo> {
+        let mut state = self.state.lock().unwrap();
+        let cutoff = Utc::now() - WORKER_SEEN_WINDOW;
+        state.workers.retain(|_, last_seen| *last_seen >= cutoff);
+
+        let mut workers: Vec<WorkerInfo> = state
+            .workers
+            .iter()
+            .map(|(worker_id, last_seen)| WorkerInfo {
+                worker_id: worker_id.clone(),
+                last_seen: *last_seen,
+                running: state
+                    .entries
+                    .iter()
+                    .filter(|(_, entry)| {
+                        entry
+                            .lease
+                            .as_ref()
+                            .is_some_and(|lease| &lease.worker_id == worker_id)
+                    })
+                    .map(|(run_id, _)| run_id.clone())
+                    .collect(),
+            })
+            .collect();
+        workers.sort_by(|a, b| a.worker_id.cmp(&b.worker_id));
+        workers
+    }
+
+    /// Number of runs waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    fn leased_entry<'a>(
+        state: &'a mut PoolState,
+        run_id: &str,
+        worker_id: &str,
+    ) -> Result<&'a mut Entry> {
+        match state.entries.get_mut(run_id) {
+            Some(entry)
+                if entry
+                    .lease
+                    .as_ref()
+                    .is_some_and(|lease| lease.worker_id == worker_id) =>
+            {
+                Ok(entry)
+            }
+            _ => bail!(
+                "Worker {} does not hold a lease on run {}",
+                worker_id,
+                run_id
+            ),
+        }
+    }
+
+    fn remove(&self, run_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.remove(run_id);
+        state.queue.retain(|queued| queued != run_id);
+    }
+
+    /// Puts runs whose lease ran out back at the front of the queue, or fails them once they
+    /// lost too many workers.
+    fn expire_leases(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let expired: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry
+                    .lease
+                    .as_ref()
+                    .is_some_and(|lease| lease.expires_at <= now)
+            })
+            .map(|(run_id, _)| run_id.clone())
+            .collect();
+
+        for run_id in expired {
+            let Some(entry) = state.entries.get_mut(&run_id) else {
+                continue;
+            };
+            let worker_id = entry
+                .lease
+                .take()
+                .map(|lease| lease.worker_id)
+                .unwrap_or_default();
+            entry.expiries += 1;
+
+            if entry.expiries >= MAX_LEASE_EXPIRIES {
+                tracing::error!(
+                    "Run {} lost its worker {} times, giving up",
+                    run_id,
+                    entry.expiries
+                );
+                if let Some(entry) = state.entries.remove(&run_id) {
+                    let _ = entry.done.send(Err(anyhow!(
+                        "Worker {} stopped responding and the run was already retried {} times",
+                        worker_id,
+                        MAX_LEASE_EXPIRIES - 1
+                    )));
+                }
+            } else {
+                tracing::warn!(
+                    "Worker {} stopped heartbeating run {}, queueing it again",
+                    worker_id,
+                    run_id
+                );
+                entry.item.attempt += 1;
+                state.queue.push_front(run_id);
+            }
+        }
+    }
+}
+
+/// Takes a dispatched run out of the pool however `dispatch` ends, including when its
+/// future is dropped.
+struct Withdraw<'a> {
+    pool: &'a WorkerPool,
+    run_id: &'a str,
+}
+
+impl Drop for Withdraw<'_> {
+    fn drop(&mut self) {
+        self.pool.remove(self.run_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn item(run_id: &str) -> WorkItem {
+        WorkItem {
+            run_id: run_id.to_string(),
+            job_id: "nightly".to_string(),
+            attempt: 1,
+            session_id: format!("session-{}", run_id),
+            recipe: Recipe::builder()
+                .title("Nightly")
+                .description("Nightly report")
+                .instructions("Write the report")
+                .build()
+                .unwrap(),
+        }
+    }
+
+    async fn queued(pool: &WorkerPool, count: usize) {
+        while pool.queued() < count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lease_heartbeat_and_complete() {
+        let pool = Arc::new(WorkerPool::new());
+        let dispatched = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.dispatch(item("r1"), CancellationToken::new()).await }
+        });
+        queued(&pool, 1).await;
+
+        assert!(pool
+            .lease("w2", DEFAULT_LEASE)
+            .is_some_and(|leased| leased.item.run_id == "r1" && !leased.requeued));
+        assert!(pool.lease("w1", DEFAULT_LEASE).is_none());
+
+        assert_eq!(
+            pool.heartbeat("r1", "w2", DEFAULT_LEASE).unwrap(),
+            "session-r1"
+        );
+        assert!(pool.heartbeat("r1", "w1", DEFAULT_LEASE).is_err());
+        assert_eq!(pool.workers()[1].running, vec!["r1".to_string()]);
+
+        pool.complete("r1", "w2", Some("done".to_string()), None)
+            .unwrap();
+        let result = dispatched.await.unwrap().unwrap();
+        assert_eq!(result.worker_id, "w2");
+        assert_eq!(result.output.as_deref(), Some("done"));
+        assert!(pool.complete("r1", "w2", None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_is_retried_then_failed() {
+        let pool = Arc::new(WorkerPool::new());
+        let dispatched = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.dispatch(item("r1"), CancellationToken::new()).await }
+        });
+        queued(&pool, 1).await;
+
+        for attempt in 1..=MAX_LEASE_EXPIRIES {
+            let worker = format!("w{}", attempt);
+            let leased = pool.lease(&worker, Duration::from_secs(1)).unwrap();
+            assert_eq!(leased.item.attempt, attempt);
+            assert_eq!(leased.requeued, attempt > 1);
+            pool.expire_leases(Instant::now() + Duration::from_secs(2));
+            assert!(pool.heartbeat("r1", &worker, DEFAULT_LEASE).is_err());
+            if attempt < MAX_LEASE_EXPIRIES {
+                assert_eq!(pool.queued(), 1);
+            }
+        }
+
+        assert_eq!(pool.queued(), 0);
+        let error = dispatched.await.unwrap().unwrap_err();
+        assert!(error.to_string().contains("stopped responding"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_run_is_withdrawn() {
+        let pool = Arc::new(WorkerPool::new());
+        let cancel_token = CancellationToken::new();
+        let dispatched = tokio::spawn({
+            let pool = pool.clone();
+            let cancel_token = cancel_token.clone();
+            async move { pool.dispatch(item("r1"), cancel_token).await }
+        });
+        queued(&pool, 1).await;
+        assert!(pool.lease("w1", DEFAULT_LEASE).is_some());
+
+        cancel_token.cancel();
+        assert!(dispatched.await.unwrap().is_err());
+        assert!(pool.heartbeat("r1", "w1", DEFAULT_LEASE).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_dispatch_is_withdrawn() {
+        let pool = Arc::new(WorkerPool::new());
+        let dispatched = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.dispatch(item("r1"), CancellationToken::new()).await }
+        });
+        queued(&pool, 1).await;
+
+        dispatched.abort();
+        assert!(dispatched.await.unwrap_err().is_cancelled());
+        assert_eq!(pool.queued(), 0);
+        assert!(pool.lease("w1", DEFAULT_LEASE).is_none());
+    }
+}
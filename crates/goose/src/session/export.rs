@@ -0,0 +1,759 @@
+use crate::conversation::message::{ActionRequiredData, Message, MessageContent, ToolResponse};
+use crate::providers::formats::{anthropic, openai};
+use crate::providers::utils::ImageFormat;
+use crate::session::Session;
+use crate::utils::safe_truncate;
+use chrono::DateTime;
+use rmcp::model::{RawContent, ResourceContents, Role};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+const ATTRIBUTE_PREVIEW_CHARS: usize = 200;
+const SPAN_KIND_INTERNAL: i32 = 1;
+const STATUS_CODE_OK: i32 = 1;
+const STATUS_CODE_ERROR: i32 = 2;
+
+/// Formats a stored session can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The session as goose stores it, which `import_session` reads back
+    Json,
+    /// A standalone HTML transcript
+    Html,
+    /// One OpenAI chat fine-tuning record
+    OpenAiJsonl,
+    /// One Anthropic fine-tuning record
+    AnthropicJsonl,
+    /// OTLP/JSON traces with a span per turn and per tool call
+    Otlp,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json | ExportFormat::Otlp => "application/json",
+            ExportFormat::Html => "text/html; charset=utf-8",
+            ExportFormat::OpenAiJsonl | ExportFormat::AnthropicJsonl => "application/jsonl",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+            ExportFormat::OpenAiJsonl => "openai-jsonl",
+            ExportFormat::AnthropicJsonl => "anthropic-jsonl",
+            ExportFormat::Otlp => "otlp",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "html" => Ok(ExportFormat::Html),
+            "openai" | "openai-jsonl" => Ok(ExportFormat::OpenAiJsonl),
+            "anthropic" | "anthropic-jsonl" => Ok(ExportFormat::AnthropicJsonl),
+            "otlp" | "otel" => Ok(ExportFormat::Otlp),
+            other => Err(format!(
+                "unsupported export format '{}': expected json, html, openai-jsonl, anthropic-jsonl or otlp",
+                other
+            )),
+        }
+    }
+}
+
+/// Why a session could not be exported.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    /// Only the json format can represent a session without messages
+    #[error("Session has no messages")]
+    NoMessages,
+    #[error("Failed to serialize the session: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+fn messages(session: &Session) -> Result<&[Message], ExportError> {
+    match session.conversation.as_ref() {
+        Some(conversation) if !conversation.messages().is_empty() => {
+            Ok(conversation.messages().as_slice())
+        }
+        _ => Err(ExportError::NoMessages),
+    }
+}
+
+/// Renders a session as a standalone HTML transcript with a note above the conversation, e.g. to
+/// say it is a shared snapshot.
+pub fn export_html_with_note(session: &Session, note: &str) -> Result<String, ExportError> {
+    Ok(to_html(session, messages(session)?, Some(note)))
+}
+
+/// Renders a session loaded with its conversation in the given format.
+pub fn export_session(session: &Session, format: ExportFormat) -> Result<String, ExportError> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(session)?),
+        ExportFormat::Html => Ok(to_html(session, messages(session)?, None)),
+        ExportFormat::OpenAiJsonl => Ok(to_openai_jsonl(messages(session)?)?),
+        ExportFormat::AnthropicJsonl => Ok(to_anthropic_jsonl(messages(session)?)?),
+        ExportFormat::Otlp => Ok(serde_json::to_string(&to_otlp(
+            session,
+            messages(session)?,
+        ))?),
+    }
+}
+
+// Sessions don't store the system prompt, so fine-tuning records hold the conversation only.
+fn to_openai_jsonl(messages: &[Message]) -> serde_json::Result<String> {
+    let messages = openai::format_messages(messages, &ImageFormat::OpenAi);
+    serde_json::to_string(&json!({ "messages": messages }))
+}
+
+fn to_anthropic_jsonl(messages: &[Message]) -> serde_json::Result<String> {
+    let mut messages = anthropic::format_messages(messages);
+    // Prompt caching markers only make sense for live requests
+    for message in &mut messages {
+        if let Some(blocks) = message.get_mut("content").and_then(Value::as_array_mut) {
+            for block in blocks.iter_mut().filter_map(Value::as_object_mut) {
+                block.remove("cache_control");
+            }
+        }
+    }
+    serde_json::to_string(&json!({ "messages": messages }))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn image_to_html(data: &str, mime_type: &str) -> String {
+    if mime_type.starts_with("image/") {
+        format!(
+            "<img src=\"data:{};base64,{}\" alt=\"image\">",
+            escape_html(mime_type),
+            escape_html(data)
+        )
+    } else {
+        format!(
+            "<p class=\"notice\">Binary content ({}, {} bytes)</p>",
+            escape_html(mime_type),
+            data.len()
+        )
+    }
+}
+
+fn tool_response_to_html(response: &ToolResponse) -> String {
+    let contents = match &response.tool_result {
+        Ok(contents) => contents,
+        Err(e) => {
+            return format!(
+                "<details class=\"tool-result error\"><summary>Tool error</summary><pre>{}</pre></details>",
+                escape_html(&e.to_string())
+            );
+        }
+    };
+
+    let mut html = String::from("<details class=\"tool-result\"><summary>Tool result</summary>");
+    if contents.is_empty() {
+        html.push_str("<p class=\"notice\">No output</p>");
+    }
+    for content in contents {
+        match &content.raw {
+            RawContent::Text(text) => {
+                html.push_str(&format!("<pre>{}</pre>", escape_html(&text.text)));
+            }
+            RawContent::Image(image) => {
+                html.push_str(&image_to_html(&image.data, &image.mime_type));
+            }
+            RawContent::Resource(resource) => match &resource.resource {
+                ResourceContents::TextResourceContents { uri, text, .. } => {
+                    html.push_str(&format!(
+                        "<p class=\"notice\">{}</p><pre>{}</pre>",
+                        escape_html(uri),
+                        escape_html(text)
+                    ));
+                }
+                ResourceContents::BlobResourceContents {
+                    uri,
+                    mime_type,
+                    blob,
+                    ..
+                } => match mime_type {
+                    Some(mime_type) if mime_type.starts_with("image/") => {
+                        html.push_str(&image_to_html(blob, mime_type));
+                    }
+                    _ => html.push_str(&format!(
+                        "<p class=\"notice\">Binary file {} ({} bytes)</p>",
+                        escape_html(uri),
+                        blob.len()
+                    )),
+                },
+            },
+            RawContent::ResourceLink(link) => {
+                html.push_str(&format!(
+                    "<p class=\"notice\">Resource link: {}</p>",
+                    escape_html(&link.uri)
+                ));
+            }
+            RawContent::Audio(_) => {
+                html.push_str("<p class=\"notice\">Audio content is not included</p>");
+            }
+        }
+    }
+    html.push_str("</details>");
+    html
+}
+
+fn message_to_html(message: &Message) -> String {
+    let mut html = String::new();
+    for content in &message.content {
+        match content {
+            MessageContent::Text(text) => {
+                html.push_str(&format!(
+                    "<div class=\"text\">{}</div>",
+                    escape_html(&text.text)
+                ));
+            }
+            MessageContent::Image(image) => {
+                html.push_str(&image_to_html(&image.data, &image.mime_type));
+            }
+            MessageContent::ToolRequest(request) => match &request.tool_call {
+                Ok(call) => {
+                    let arguments = call
+                        .arguments
+                        .as_ref()
+                        .map(|args| serde_json::to_string_pretty(args).unwrap_or_default())
+                        .unwrap_or_else(|| "{}".to_string());
+                    html.push_str(&format!(
+                        "<details class=\"tool-call\"><summary>Tool call: <code>{}</code></summary><pre>{}</pre></details>",
+                        escape_html(&call.name),
+                        escape_html(&arguments)
+                    ));
+                }
+                Err(e) => {
+                    html.push_str(&format!(
+                        "<details class=\"tool-call error\"><summary>Invalid tool call</summary><pre>{}</pre></details>",
+                        escape_html(&e.to_string())
+                    ));
+                }
+            },
+            MessageContent::ToolResponse(response) => {
+                html.push_str(&tool_response_to_html(response));
+            }
+            MessageContent::Thinking(thinking) => {
+                html.push_str(&format!(
+                    "<details class=\"thinking\"><summary>Thinking</summary><pre>{}</pre></details>",
+                    escape_html(&thinking.thinking)
+                ));
+            }
+            MessageContent::RedactedThinking(_) => {
+                html.push_str("<p class=\"notice\">Thinking was redacted</p>");
+            }
+            MessageContent::SystemNotification(notification) => {
+                html.push_str(&format!(
+                    "<p class=\"notice\">{}</p>",
+                    escape_html(&notification.msg)
+                ));
+            }
+            MessageContent::ToolConfirmationRequest(request) => {
+                html.push_str(&format!(
+                    "<p class=\"notice\">Asked for confirmation to run <code>{}</code></p>",
+                    escape_html(&request.tool_name)
+                ));
+            }
+            MessageContent::ActionRequired(action) => {
+                let description = match &action.data {
+                    ActionRequiredData::ToolConfirmation { tool_name, .. } => {
+                        format!("Asked for confirmation to run {}", tool_name)
+                    }
+                    ActionRequiredData::Elicitation { message, .. } => message.clone(),
+                    ActionRequiredData::ElicitationResponse { .. } => {
+                        "Answered a request for information".to_string()
+                    }
+                };
+                html.push_str(&format!(
+                    "<p class=\"notice\">{}</p>",
+                    escape_html(&description)
+                ));
+            }
+            MessageContent::FrontendToolRequest(request) => {
+                let name = request
+                    .tool_call
+                    .as_ref()
+                    .map(|call| call.name.to_string())
+                    .unwrap_or_else(|_| "unknown tool".to_string());
+                html.push_str(&format!(
+                    "<p class=\"notice\">Frontend tool call: <code>{}</code></p>",
+                    escape_html(&name)
+                ));
+            }
+        }
+    }
+    html
+}
+
+fn role_label(message: &Message) -> &'static str {
+    let only_tool_responses = !message.content.is_empty()
+        && message
+            .content
+            .iter()
+            .all(|content| matches!(content, MessageContent::ToolResponse(_)));
+    match message.role {
+        _ if only_tool_responses => "tool",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2328;background:#fff}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:1.5rem}\
+header p{color:#59636e;margin:.25rem 0}\
+.message{border:1px solid #d0d7de;border-radius:8px;padding:.75rem 1rem;margin:1rem 0}\
+.message.user{background:#f6f8fa}\
+.message.tool{border-style:dashed}\
+.role{font-size:.75rem;font-weight:600;text-transform:uppercase;color:#59636e}\
+.role time{font-weight:400;margin-left:.5rem}\
+.text{white-space:pre-wrap;margin:.5rem 0}\
+details{margin:.5rem 0}\
+summary{cursor:pointer;color:#0969da}\
+pre{background:#f6f8fa;border-radius:6px;padding:.75rem;overflow-x:auto;white-space:pre-wrap}\
+.error summary{color:#cf222e}\
+.notice{color:#59636e;font-style:italic}\
+.note{background:#ddf4ff;border:1px solid #54aeff;border-radius:6px;padding:.5rem .75rem}\
+img{max-width:100%;border-radius:6px}";
+
+fn to_html(session: &Session, messages: &[Message], note: Option<&str>) -> String {
+    let mut body = String::new();
+    for message in messages.iter().filter(|m| m.is_user_visible()) {
+        let role = role_label(message);
+        let time = DateTime::from_timestamp(message.created, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        body.push_str(&format!(
+            "<section class=\"message {role}\"><div class=\"role\">{role}<time>{time}</time></div>{}</section>\n",
+            message_to_html(message)
+        ));
+    }
+
+    let title = escape_html(&session.name);
+    let mut details = Vec::new();
+    if let Some(model) = &session.model_config {
+        details.push(format!(
+            "Model {}",
+            escape_html(&match &session.provider_name {
+                Some(provider) => format!("{}/{}", provider, model.model_name),
+                None => model.model_name.clone(),
+            })
+        ));
+    }
+    if let Some(recipe) = &session.recipe {
+        details.push(format!("Recipe {}", escape_html(&recipe.title)));
+    }
+    let details = if details.is_empty() {
+        String::new()
+    } else {
+        format!("<p>{}</p>\n", details.join(" &middot; "))
+    };
+    let note = note
+        .map(|note| format!("<p class=\"note\">{}</p>\n", escape_html(note)))
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<p>Session {} &middot; {} &middot; {}</p>\n{details}{note}</header>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape_html(&session.id),
+        escape_html(&session.working_dir.display().to_string()),
+        session.created_at.format("%Y-%m-%d %H:%M UTC"),
+    )
+}
+
+fn hex_id(seed: &str, bytes: usize) -> String {
+    Sha256::digest(seed.as_bytes())[..bytes]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn unix_nanos(seconds: i64) -> String {
+    (seconds.max(0) as i128 * 1_000_000_000).to_string()
+}
+
+fn string_attribute(key: &str, value: impl Into<String>) -> Value {
+    json!({ "key": key, "value": { "stringValue": value.into() } })
+}
+
+fn int_attribute(key: &str, value: i64) -> Value {
+    json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn span(
+    trace_id: &str,
+    span_id: &str,
+    parent_span_id: &str,
+    name: &str,
+    start: i64,
+    end: i64,
+    attributes: Vec<Value>,
+) -> Value {
+    json!({
+        "traceId": trace_id,
+        "spanId": span_id,
+        "parentSpanId": parent_span_id,
+        "name": name,
+        "kind": SPAN_KIND_INTERNAL,
+        "startTimeUnixNano": unix_nanos(start),
+        "endTimeUnixNano": unix_nanos(end.max(start)),
+        "attributes": attributes,
+        "status": { "code": STATUS_CODE_OK },
+    })
+}
+
+fn starts_turn(message: &Message) -> bool {
+    message.role == Role::User
+        && message
+            .content
+            .iter()
+            .any(|content| matches!(content, MessageContent::Text(_)))
+}
+
+/// Rebuilds a trace for the session: a root span for the session, a span for every turn that
+/// starts with user text, and a span for every tool call from request to response. Message
+/// timestamps have one second resolution, so span timings are approximate.
+fn to_otlp(session: &Session, messages: &[Message]) -> Value {
+    let trace_id = hex_id(&session.id, 16);
+    let root_span_id = hex_id(&format!("{}/session", session.id), 8);
+    let last_created = messages.last().map(|m| m.created).unwrap_or_default();
+
+    let mut root_attributes = vec![
+        string_attribute("goose.session.id", session.id.clone()),
+        string_attribute("goose.session.name", session.name.clone()),
+        string_attribute(
+            "goose.session.working_dir",
+            session.working_dir.display().to_string(),
+        ),
+        int_attribute("goose.session.message_count", messages.len() as i64),
+    ];
+    if let Some(provider) = &session.provider_name {
+        root_attributes.push(string_attribute("gen_ai.system", provider.clone()));
+    }
+    if let Some(model_config) = &session.model_config {
+        root_attributes.push(string_attribute(
+            "gen_ai.request.model",
+            model_config.model_name.clone(),
+        ));
+    }
+    for (key, value) in [
+        (
+            "gen_ai.usage.input_tokens",
+            session.accumulated_input_tokens,
+        ),
+        (
+            "gen_ai.usage.output_tokens",
+            session.accumulated_output_tokens,
+        ),
+    ] {
+        if let Some(value) = value {
+            root_attributes.push(int_attribute(key, value as i64));
+        }
+    }
+
+    let mut spans = vec![span(
+        &trace_id,
+        &root_span_id,
+        "",
+        "goose.session",
+        session
+            .created_at
+            .timestamp()
+            .min(messages.first().map(|m| m.created).unwrap_or(i64::MAX)),
+        session.updated_at.timestamp().max(last_created),
+        root_attributes,
+    )];
+
+    let mut turn_starts: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| starts_turn(message))
+        .map(|(index, _)| index)
+        .collect();
+    if turn_starts.first() != Some(&0) {
+        turn_starts.insert(0, 0);
+    }
+
+    for (turn, start) in turn_starts.iter().enumerate() {
+        let end_index = turn_starts.get(turn + 1).copied().unwrap_or(messages.len());
+        let turn_messages = &messages[*start..end_index];
+        let turn_span_id = hex_id(&format!("{}/turn/{}", session.id, turn), 8);
+        let input = turn_messages[0].as_concat_text();
+
+        spans.push(span(
+            &trace_id,
+            &turn_span_id,
+            &root_span_id,
+            "goose.turn",
+            turn_messages[0].created,
+            turn_messages.last().map(|m| m.created).unwrap_or_default(),
+            vec![
+                int_attribute("goose.turn.index", turn as i64),
+                int_attribute("goose.turn.message_count", turn_messages.len() as i64),
+                string_attribute(
+                    "goose.turn.input",
+                    safe_truncate(&input, ATTRIBUTE_PREVIEW_CHARS),
+                ),
+            ],
+        ));
+
+        for message in turn_messages {
+            for request in message.content.iter().filter_map(|c| match c {
+                MessageContent::ToolRequest(request) => Some(request),
+                _ => None,
+            }) {
+                let response = turn_messages.iter().find_map(|m| {
+                    m.content.iter().find_map(|c| match c {
+                        MessageContent::ToolResponse(response) if response.id == request.id => {
+                            Some((m.created, response))
+                        }
+                        _ => None,
+                    })
+                });
+                let tool_name = request
+                    .tool_call
+                    .as_ref()
+                    .map(|call| call.name.to_string())
+                    .unwrap_or_else(|_| "unknown".to_string());
+                let error = match (&request.tool_call, response) {
+                    (Err(e), _) => Some(e.to_string()),
+                    (_, Some((_, response))) => {
+                        response.tool_result.as_ref().err().map(|e| e.to_string())
+                    }
+                    (_, None) => Some("no tool response recorded".to_string()),
+                };
+
+                let mut tool_span = span(
+                    &trace_id,
+                    &hex_id(&format!("{}/tool/{}", session.id, request.id), 8),
+                    &turn_span_id,
+                    &format!("execute_tool {}", tool_name),
+                    message.created,
+                    response.map_or(message.created, |(created, _)| created),
+                    vec![
+                        string_attribute("gen_ai.tool.name", tool_name.clone()),
+                        string_attribute("gen_ai.tool.call.id", request.id.clone()),
+                    ],
+                );
+                if let Some(message) = error {
+                    tool_span["status"] = json!({ "code": STATUS_CODE_ERROR, "message": message });
+                }
+                spans.push(tool_span);
+            }
+        }
+    }
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    string_attribute("service.name", "goose"),
+                    string_attribute("service.version", env!("CARGO_PKG_VERSION")),
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "goose.session.export" },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::Conversation;
+    use rmcp::model::{CallToolRequestParam, Content, ErrorCode, ErrorData};
+    use rmcp::object;
+
+    fn at(mut message: Message, created: i64) -> Message {
+        message.created = created;
+        message
+    }
+
+    fn session_with(messages: Vec<Message>) -> Session {
+        Session {
+            id: "20250101_1".to_string(),
+            name: "Fix <the> build".to_string(),
+            conversation: Some(Conversation::new_unvalidated(messages)),
+            ..Default::default()
+        }
+    }
+
+    fn tool_session() -> Session {
+        let shell = |command: &str| {
+            Ok(CallToolRequestParam {
+                name: "developer__shell".into(),
+                arguments: Some(object!({ "command": command })),
+            })
+        };
+        session_with(vec![
+            at(Message::user().with_text("list the files"), 100),
+            at(
+                Message::assistant()
+                    .with_text("Sure")
+                    .with_tool_request("call_1", shell("ls")),
+                101,
+            ),
+            at(
+                Message::user().with_tool_response("call_1", Ok(vec![Content::text("Cargo.toml")])),
+                104,
+            ),
+            at(Message::assistant().with_text("There is one file"), 105),
+            at(Message::user().with_text("delete it"), 200),
+            at(
+                Message::assistant().with_tool_request("call_2", shell("rm Cargo.toml")),
+                201,
+            ),
+            at(
+                Message::user().with_tool_response(
+                    "call_2",
+                    Err(ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        "permission denied",
+                        None,
+                    )),
+                ),
+                202,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_format_round_trips_through_display() {
+        for format in [
+            ExportFormat::Json,
+            ExportFormat::Html,
+            ExportFormat::OpenAiJsonl,
+            ExportFormat::AnthropicJsonl,
+            ExportFormat::Otlp,
+        ] {
+            assert_eq!(format.to_string().parse::<ExportFormat>(), Ok(format));
+        }
+        assert_eq!("OpenAI".parse(), Ok(ExportFormat::OpenAiJsonl));
+        assert!("pdf".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_html_note() {
+        let html = export_html_with_note(&tool_session(), "Shared <read-only> snapshot").unwrap();
+        assert!(html.contains("<p class=\"note\">Shared &lt;read-only&gt; snapshot</p>"));
+        assert!(matches!(
+            export_html_with_note(&Session::default(), "note"),
+            Err(ExportError::NoMessages)
+        ));
+    }
+
+    #[test]
+    fn test_html_escapes_text_and_collapses_tool_calls() {
+        let html = export_session(&tool_session(), ExportFormat::Html).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Fix &lt;the&gt; build</title>"));
+        assert!(html.contains("<summary>Tool call: <code>developer__shell</code></summary>"));
+        assert!(html.contains("<pre>Cargo.toml</pre>"));
+        assert!(html.contains("tool-result error"));
+    }
+
+    #[test]
+    fn test_html_renders_images_inline() {
+        let session = session_with(vec![Message::user()
+            .with_text("what is this?")
+            .with_image("aGVsbG8=", "image/png")]);
+        let html = export_session(&session, ExportFormat::Html).unwrap();
+
+        assert!(html.contains("<img src=\"data:image/png;base64,aGVsbG8=\""));
+    }
+
+    #[test]
+    fn test_fine_tuning_records_are_single_lines() {
+        let session = tool_session();
+
+        let openai = export_session(&session, ExportFormat::OpenAiJsonl).unwrap();
+        assert_eq!(openai.lines().count(), 1);
+        let record: Value = serde_json::from_str(&openai).unwrap();
+        let messages = record["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(
+            messages[1]["tool_calls"][0]["function"]["name"],
+            "developer__shell"
+        );
+        assert!(messages.iter().any(|m| m["role"] == "tool"));
+
+        let anthropic = export_session(&session, ExportFormat::AnthropicJsonl).unwrap();
+        assert_eq!(anthropic.lines().count(), 1);
+        assert!(!anthropic.contains("cache_control"));
+        let record: Value = serde_json::from_str(&anthropic).unwrap();
+        assert_eq!(record["messages"][1]["content"][1]["type"], "tool_use");
+    }
+
+    #[test]
+    fn test_otlp_nests_tool_spans_under_turns() {
+        let trace: Value =
+            serde_json::from_str(&export_session(&tool_session(), ExportFormat::Otlp).unwrap())
+                .unwrap();
+        let spans = trace["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+
+        let names: Vec<&str> = spans.iter().map(|s| s["name"].as_str().unwrap()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "goose.session",
+                "goose.turn",
+                "execute_tool developer__shell",
+                "goose.turn",
+                "execute_tool developer__shell",
+            ]
+        );
+
+        let root = &spans[0];
+        assert!(spans[1..].iter().all(|s| s["traceId"] == root["traceId"]));
+        assert_eq!(spans[1]["parentSpanId"], root["spanId"]);
+        assert_eq!(spans[2]["parentSpanId"], spans[1]["spanId"]);
+        assert_eq!(spans[2]["startTimeUnixNano"], "101000000000");
+        assert_eq!(spans[2]["endTimeUnixNano"], "104000000000");
+        assert_eq!(spans[2]["status"]["code"], STATUS_CODE_OK);
+        assert_eq!(spans[4]["status"]["code"], STATUS_CODE_ERROR);
+    }
+
+    #[test]
+    fn test_empty_session_only_exports_as_json() {
+        let session = session_with(vec![]);
+        assert!(export_session(&session, ExportFormat::Json).is_ok());
+        for format in [
+            ExportFormat::Html,
+            ExportFormat::OpenAiJsonl,
+            ExportFormat::AnthropicJsonl,
+            ExportFormat::Otlp,
+        ] {
+            assert!(matches!(
+                export_session(&session, format),
+                Err(ExportError::NoMessages)
+            ));
+        }
+    }
+}
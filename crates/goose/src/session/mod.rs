@@ -1,5 +1,6 @@
 mod chat_history_search;
 mod diagnostics;
+pub mod export;
 pub mod extension_data;
 mod legacy;
 pub mod session_manager;
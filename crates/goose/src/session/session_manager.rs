@@ -1,3 +1,4 @@
+use crate::agents::snapshot::AgentSnapshot;
 use crate::config::paths::Paths;
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
@@ -19,7 +20,7 @@ use tokio::sync::OnceCell;
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
-const CURRENT_SCHEMA_VERSION: i32 = 6;
+const CURRENT_SCHEMA_VERSION: i32 = 8;
 pub const SESSIONS_FOLDER: &str = "sessions";
 pub const DB_NAME: &str = "sessions.db";
 
@@ -95,6 +96,9 @@ pub struct Session {
     pub message_count: usize,
     pub provider_name: Option<String>,
     pub model_config: Option<ModelConfig>,
+    /// User that created the session through a named goosed API token, if any
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 pub struct SessionUpdateBuilder {
@@ -115,6 +119,7 @@ pub struct SessionUpdateBuilder {
     user_recipe_values: Option<Option<HashMap<String, String>>>,
     provider_name: Option<Option<String>>,
     model_config: Option<Option<ModelConfig>>,
+    owner: Option<Option<String>>,
 }
 
 #[derive(Serialize, ToSchema, Debug)]
@@ -144,6 +149,7 @@ impl SessionUpdateBuilder {
             user_recipe_values: None,
             provider_name: None,
             model_config: None,
+            owner: None,
         }
     }
 
@@ -238,6 +244,11 @@ impl SessionUpdateBuilder {
         self
     }
 
+    pub fn owner(mut self, owner: Option<String>) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
     pub async fn apply(self) -> Result<()> {
         SessionManager::apply_update(self).await
     }
@@ -321,6 +332,17 @@ impl SessionManager {
             .await
     }
 
+    pub async fn get_agent_snapshot(id: &str) -> Result<Option<AgentSnapshot>> {
+        Self::instance().await?.get_agent_snapshot(id).await
+    }
+
+    pub async fn save_agent_snapshot(id: &str, snapshot: &AgentSnapshot) -> Result<()> {
+        Self::instance()
+            .await?
+            .save_agent_snapshot(id, snapshot)
+            .await
+    }
+
     pub async fn truncate_conversation(session_id: &str, timestamp: i64) -> Result<()> {
         Self::instance()
             .await?
@@ -415,6 +437,7 @@ impl Default for Session {
             message_count: 0,
             provider_name: None,
             model_config: None,
+            owner: None,
         }
     }
 }
@@ -479,6 +502,7 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             message_count: row.try_get("message_count").unwrap_or(0) as usize,
             provider_name: row.try_get("provider_name").ok().flatten(),
             model_config,
+            owner: row.try_get("owner").ok().flatten(),
         })
     }
 }
@@ -568,7 +592,9 @@ impl SessionStorage {
                 recipe_json TEXT,
                 user_recipe_values_json TEXT,
                 provider_name TEXT,
-                model_config_json TEXT
+                model_config_json TEXT,
+                owner TEXT,
+                agent_snapshot_json TEXT
             )
         "#,
         )
@@ -843,6 +869,24 @@ impl SessionStorage {
                 .execute(&self.pool)
                 .await?;
             }
+            7 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN owner TEXT
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            8 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN agent_snapshot_json TEXT
+                "#,
+                )
+                .execute(&self.pool)
+                .await?;
+            }
             _ => {
                 anyhow::bail!("Unknown migration version: {}", version);
             }
@@ -898,7 +942,7 @@ impl SessionStorage {
                total_tokens, input_tokens, output_tokens,
                accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
                schedule_id, recipe_json, user_recipe_values_json,
-               provider_name, model_config_json
+               provider_name, model_config_json, owner
         FROM sessions
         WHERE id = ?
     "#,
@@ -961,6 +1005,7 @@ impl SessionStorage {
         add_update!(builder.user_recipe_values, "user_recipe_values_json");
         add_update!(builder.provider_name, "provider_name");
         add_update!(builder.model_config, "model_config_json");
+        add_update!(builder.owner, "owner");
 
         if updates.is_empty() {
             return Ok(());
@@ -1026,6 +1071,9 @@ impl SessionStorage {
                 .transpose()?;
             q = q.bind(model_config_json);
         }
+        if let Some(owner) = builder.owner {
+            q = q.bind(owner);
+        }
 
         let mut tx = self.pool.begin().await?;
         q = q.bind(&builder.session_id);
@@ -1141,7 +1189,7 @@ impl SessionStorage {
                    s.total_tokens, s.input_tokens, s.output_tokens,
                    s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
                    s.schedule_id, s.recipe_json, s.user_recipe_values_json,
-                   s.provider_name, s.model_config_json,
+                   s.provider_name, s.model_config_json, s.owner,
                    COUNT(m.id) as message_count
             FROM sessions s
             INNER JOIN messages m ON s.id = m.session_id
@@ -1266,7 +1314,8 @@ impl SessionStorage {
             .extension_data(original_session.extension_data)
             .schedule_id(original_session.schedule_id)
             .recipe(original_session.recipe)
-            .user_recipe_values(original_session.user_recipe_values);
+            .user_recipe_values(original_session.user_recipe_values)
+            .owner(original_session.owner);
 
         self.apply_update(builder).await?;
 
@@ -1278,6 +1327,28 @@ impl SessionStorage {
         self.get_session(&new_session.id, true).await
     }
 
+    async fn get_agent_snapshot(&self, session_id: &str) -> Result<Option<AgentSnapshot>> {
+        let json = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT agent_snapshot_json FROM sessions WHERE id = ?",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        json.map(|json| serde_json::from_str(&json).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn save_agent_snapshot(&self, session_id: &str, snapshot: &AgentSnapshot) -> Result<()> {
+        sqlx::query("UPDATE sessions SET agent_snapshot_json = ? WHERE id = ?")
+            .bind(serde_json::to_string(snapshot)?)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
         sqlx::query("DELETE FROM messages WHERE session_id = ? AND created_timestamp >= ?")
             .bind(session_id)
@@ -1492,6 +1563,93 @@ mod tests {
         assert_eq!(conversation.messages()[1].role, Role::Assistant);
     }
 
+    #[tokio::test]
+    async fn test_owner_is_kept_on_copy_but_not_on_import() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_owner.db");
+        let storage = Arc::new(SessionStorage::create(&db_path).await.unwrap());
+
+        let session = storage
+            .create_session(
+                PathBuf::from("/tmp/test"),
+                "Owned".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+        assert_eq!(session.owner, None);
+
+        storage
+            .add_message(&session.id, &Message::user().with_text("hello"))
+            .await
+            .unwrap();
+        storage
+            .apply_update(
+                SessionUpdateBuilder::new(session.id.clone()).owner(Some("alice".to_string())),
+            )
+            .await
+            .unwrap();
+        let owned = storage.get_session(&session.id, false).await.unwrap();
+        assert_eq!(owned.owner.as_deref(), Some("alice"));
+
+        let listed = storage.list_sessions().await.unwrap();
+        assert_eq!(listed[0].owner.as_deref(), Some("alice"));
+
+        let copy = storage
+            .copy_session(&session.id, "Copy".to_string())
+            .await
+            .unwrap();
+        assert_eq!(copy.owner.as_deref(), Some("alice"));
+
+        let exported = storage.export_session(&session.id).await.unwrap();
+        let imported = storage.import_session(&exported).await.unwrap();
+        assert_eq!(imported.owner, None);
+    }
+
+    #[tokio::test]
+    async fn test_agent_snapshot_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_snapshot.db");
+        let storage = Arc::new(SessionStorage::create(&db_path).await.unwrap());
+
+        let session = storage
+            .create_session(
+                PathBuf::from("/tmp/test"),
+                "Snapshot".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+        assert!(storage
+            .get_agent_snapshot(&session.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(storage
+            .get_agent_snapshot("missing")
+            .await
+            .unwrap()
+            .is_none());
+
+        let snapshot = AgentSnapshot {
+            provider_name: Some("openai".to_string()),
+            system_prompt_extras: vec!["Be brief".to_string()],
+            ..Default::default()
+        };
+        storage
+            .save_agent_snapshot(&session.id, &snapshot)
+            .await
+            .unwrap();
+
+        let loaded = storage
+            .get_agent_snapshot(&session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.provider_name.as_deref(), Some("openai"));
+        assert_eq!(loaded.system_prompt_extras, vec!["Be brief".to_string()]);
+    }
+
     #[tokio::test]
     async fn test_import_session_with_description_field() {
         const OLD_FORMAT_JSON: &str = r#"{
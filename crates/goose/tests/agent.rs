@@ -16,6 +16,7 @@ mod tests {
         use chrono::{DateTime, Utc};
         use goose::agents::platform_tools::PLATFORM_MANAGE_SCHEDULE_TOOL_NAME;
         use goose::scheduler::{ScheduledJob, SchedulerError};
+        use goose::scheduler_runs::JobRun;
         use goose::scheduler_trait::SchedulerTrait;
         use goose::session::Session;
         use std::path::PathBuf;
@@ -84,6 +85,14 @@ mod tests {
                 Ok("test_session_123".to_string())
             }
 
+            async fn trigger_job(
+                &self,
+                _id: &str,
+                _params: std::collections::HashMap<String, String>,
+            ) -> Result<(), SchedulerError> {
+                Ok(())
+            }
+
             async fn sessions(
                 &self,
                 _sched_id: &str,
@@ -92,6 +101,14 @@ mod tests {
                 Ok(vec![])
             }
 
+            async fn runs(
+                &self,
+                _sched_id: &str,
+                _limit: usize,
+            ) -> Result<Vec<JobRun>, SchedulerError> {
+                Ok(vec![])
+            }
+
             async fn update_schedule(
                 &self,
                 _sched_id: &str,
@@ -20,6 +20,7 @@ import {
   NotificationEvent,
 } from '../types/message';
 import { errorMessage } from '../utils/conversionUtils';
+import { getApiUrl } from '../config';
 
 const resultsCache = new Map<string, { messages: Message[]; session: Session }>();
 
@@ -405,9 +406,19 @@ export function useChatStream({
   }, [session]);
 
   const stopStreaming = useCallback(() => {
+    // Replies keep running on the server when the stream disconnects, so cancel explicitly
+    window.electron
+      .getSecretKey()
+      .then((secretKey) =>
+        fetch(getApiUrl(`/sessions/${encodeURIComponent(sessionId)}/cancel`), {
+          method: 'POST',
+          headers: { 'X-Secret-Key': secretKey },
+        })
+      )
+      .catch((error) => console.warn('Failed to cancel reply:', error));
     abortControllerRef.current?.abort();
     setChatState(ChatState.Idle);
-  }, []);
+  }, [sessionId]);
 
   const onMessageUpdate = useCallback(
     async (messageId: string, newContent: string, editType: 'fork' | 'edit' = 'fork') => {